@@ -1,8 +1,15 @@
+// Shaders are only compiled with the hot-reload feature, otherwise the
+// committed SPIR-V next to them is used
+#[cfg(feature = "hot-reload")]
 use anyhow::*;
+#[cfg(feature = "hot-reload")]
 use glob::glob;
+#[cfg(feature = "hot-reload")]
 use std::fs::{read_to_string, write};
+#[cfg(feature = "hot-reload")]
 use std::path::PathBuf;
 
+#[cfg(feature = "hot-reload")]
 struct ShaderData {
     src: String,
     src_path: PathBuf,
@@ -10,6 +17,7 @@ struct ShaderData {
     kind: shaderc::ShaderKind,
 }
 
+#[cfg(feature = "hot-reload")]
 impl ShaderData {
     pub fn load(src_path: PathBuf) -> Result<Self> {
         let extension = src_path
@@ -36,6 +44,10 @@ impl ShaderData {
     }
 }
 
+#[cfg(not(feature = "hot-reload"))]
+fn main() {}
+
+#[cfg(feature = "hot-reload")]
 fn main() -> Result<()> {
     // Collect all shaders recursively within /src/
     let mut shader_paths = [
@@ -70,7 +82,7 @@ fn main() -> Result<()> {
         let compiled = compiler.compile_into_spirv(
             &shader.src,
             shader.kind,
-            &shader.src_path.to_str().unwrap(),
+            shader.src_path.to_str().unwrap(),
             "main",
             None,
         )?;
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+
+use ludwig_world_3d::render::{
+    export::{
+        args::{ExportArgs, EXPORT_USAGE},
+        gltf::write_gltf,
+        obj::write_obj,
+        region::mesh_region,
+    },
+    low::textures::TERRAIN_TEXTURE,
+};
+use ludwig_world_3d::resources::resourcemanager::ResourceManager;
+use ludwig_world_3d::world::{
+    block::registry::BlockRegistry,
+    chunk::pos::WorldCoord,
+    structure::registry::StructureRegistry,
+};
+
+/// The atlas is written next to the models under this name
+const TEXTURE_FILE: &str = "terrain.png";
+
+/// Writes the chunks of a region of a generated world to OBJ and glTF files, without a window
+fn main() -> Result<()> {
+    let args = match ExportArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}\n\n{}", e, EXPORT_USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", EXPORT_USAGE);
+        return Ok(())
+    }
+
+    let resources = ResourceManager::from_env()?;
+    BlockRegistry::load(&resources)?.install()?;
+    StructureRegistry::load(&resources)?.install()?;
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let [x, z] = args.center;
+    let center = WorldCoord { x, y: 0, z }.to_chunk_coord();
+
+    let meshes = mesh_region(seed, center, args.radius);
+    let triangles: usize = meshes.iter().map(|mesh| mesh.indices.len() / 3).sum();
+
+    std::fs::create_dir_all(&args.out)
+        .with_context(|| format!("Could not create {}", args.out.display()))?;
+    let texture = args.out.join(TEXTURE_FILE);
+    resources.load_image(TERRAIN_TEXTURE)?
+        .save(&texture)
+        .with_context(|| format!("Could not write {}", texture.display()))?;
+
+    // The center is at the origin, so the model doesn't end up far away in an editor
+    let origin = [x as f32, 0.0, z as f32];
+    let name = format!("world-{}", seed);
+
+    if args.format.obj() {
+        let path = args.out.join(&name).with_extension("obj");
+        write_obj(&path, &meshes, origin, TEXTURE_FILE)?;
+        println!("Wrote {}", path.display());
+    }
+    if args.format.gltf() {
+        let path = args.out.join(&name).with_extension("gltf");
+        write_gltf(&path, &meshes, origin, TEXTURE_FILE)?;
+        println!("Wrote {}", path.display());
+    }
+
+    println!("Exported {} chunks with {} triangles around {} {} with seed {}", meshes.len(), triangles, x, z, seed);
+
+    Ok(())
+}
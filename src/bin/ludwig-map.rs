@@ -0,0 +1,113 @@
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use ludwig_world_3d::resources::resourcemanager::ResourceManager;
+use ludwig_world_3d::settings::args::{column, number, Options};
+use ludwig_world_3d::world::{
+    block::{registry::BlockRegistry, colors::BlockColors},
+    structure::registry::StructureRegistry,
+    topdown::render_seed,
+};
+
+const MAP_USAGE: &str = "Usage: ludwig-map [options]
+
+Generates the world around a point on the CPU and writes a map of it seen
+from above to map-<seed>.png, one pixel per block with north up.
+
+Options:
+    --seed <number>           Seed of the world, can be given more than once (default: random)
+    --center <x>,<z>          Block the map is centered on (default: 0,0)
+    --radius <blocks>         Blocks on every side of the center (default: 256)
+    --out <directory>         Where the maps are written (default: maps)
+    --help                    Show this message";
+
+/// The largest map is 8192 pixels wide
+const MAX_MAP_RADIUS: u32 = 4096;
+
+/// The command line arguments of the map renderer
+#[derive(Debug)]
+struct MapArgs {
+    help: bool,
+    seeds: Vec<u32>,
+    /// In blocks
+    center: [i64; 2],
+    /// In blocks
+    radius: u32,
+    out: PathBuf,
+}
+
+impl Default for MapArgs {
+    fn default() -> Self {
+        Self {
+            help: false,
+            seeds: vec![],
+            center: [0, 0],
+            radius: 256,
+            out: PathBuf::from("maps"),
+        }
+    }
+}
+
+impl MapArgs {
+    /// Parses the arguments without the program name, see Options
+    fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut parsed = MapArgs::default();
+        let mut options = Options::new(args);
+
+        for option in &mut options {
+            let (name, value) = option?;
+
+            match name.as_str() {
+                "--seed" => parsed.seeds.push(number(&name, &value)?),
+                "--center" => parsed.center = column(&name, &value)?,
+                "--radius" => {
+                    parsed.radius = number(&name, &value)?;
+                    if parsed.radius == 0 || parsed.radius > MAX_MAP_RADIUS {
+                        bail!("The radius has to be between 1 and {}", MAX_MAP_RADIUS);
+                    }
+                }
+                "--out" => parsed.out = PathBuf::from(value),
+                _ => bail!("Unknown option '{}'", name),
+            }
+        }
+        parsed.help = options.help;
+
+        Ok(parsed)
+    }
+}
+
+/// Writes maps of generated worlds seen from above, on the CPU without a window
+fn main() -> Result<()> {
+    let args = match MapArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}\n\n{}", e, MAP_USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", MAP_USAGE);
+        return Ok(())
+    }
+
+    let resources = ResourceManager::from_env()?;
+    BlockRegistry::load(&resources)?.install()?;
+    StructureRegistry::load(&resources)?.install()?;
+    let colors = BlockColors::load(&resources)?;
+
+    std::fs::create_dir_all(&args.out)
+        .with_context(|| format!("Could not create {}", args.out.display()))?;
+
+    let seeds = if args.seeds.is_empty() { vec![rand::random()] } else { args.seeds };
+    for seed in seeds {
+        let start = Instant::now();
+        let map = render_seed(seed, &colors, args.center, args.radius);
+
+        let path = args.out.join(format!("map-{}.png", seed));
+        map.save(&path).with_context(|| format!("Could not write {}", path.display()))?;
+        println!("Wrote {} in {:.2}s", path.display(), start.elapsed().as_secs_f32());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use std::io::BufRead;
+use std::sync::mpsc::{channel, Receiver};
+
+use ludwig_world_3d::resources::resourcemanager::ResourceManager;
+use ludwig_world_3d::server::{
+    args::{ServerArgs, SERVER_USAGE},
+    network::Listener,
+    server::{Server, ServerKind},
+};
+use ludwig_world_3d::world::{
+    block::{registry::BlockRegistry, colors::BlockColors},
+    level::{clean_world_name, world_directory},
+    structure::registry::StructureRegistry,
+    world::World,
+};
+
+/// Hosts a world for players that join over the network, without a window
+fn main() -> Result<()> {
+    let args = match ServerArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}\n\n{}", e, SERVER_USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", SERVER_USAGE);
+        return Ok(())
+    }
+
+    // The blocks have to be the same as those of the players
+    let resources = ResourceManager::from_env()?;
+    BlockRegistry::load(&resources)?.install()?;
+    BlockColors::load(&resources)?.install()?;
+    StructureRegistry::load(&resources)?.install()?;
+
+    let name = match clean_world_name(&args.world) {
+        name if name.is_empty() => "server".to_string(),
+        name => name,
+    };
+    let world = World::open(world_directory(&name), args.seed.unwrap_or_else(rand::random))?;
+
+    let (listener, connections) = Listener::bind(args.port)?;
+    println!("Hosting world '{}' with seed {} on port {}", name, world.seed, listener.port);
+
+    let mut server = Server::new(world, connections, ServerKind::Dedicated);
+    server.max_view_distance = args.view_distance;
+    server.operators = args.operators.into_iter().collect();
+    server.set_console(read_console());
+    server.run();
+
+    Ok(())
+}
+
+/// The lines typed in the terminal
+fn read_console() -> Receiver<String> {
+    let (sender, lines) = channel();
+
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let sent = line.map(|line| sender.send(line).is_ok()).unwrap_or(false);
+            if !sent {
+                break
+            }
+        }
+    });
+
+    lines
+}
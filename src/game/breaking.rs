@@ -0,0 +1,147 @@
+use crate::game::{
+    clock::TICKS_PER_SECOND,
+    player::item::ToolKind,
+};
+use crate::world::{
+    raycast::RaycastHit,
+    block::blocks::{Block, get_block},
+    chunk::pos::WorldCoord,
+};
+
+/// What the server has to be told after aiming, it times the breaking too
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakChange {
+    Started(WorldCoord),
+    Stopped,
+}
+
+/// Breaking a block takes time that depends on its hardness and the tool that
+/// is used. Progress is made in ticks, so it takes as long at every frame rate.
+pub struct BlockBreaking {
+    target: Option<RaycastHit>,
+    /// From 0 to 1, the block breaks at 1
+    progress: f32,
+}
+
+impl BlockBreaking {
+    pub fn new() -> Self {
+        Self {
+            target: None,
+            progress: 0.0,
+        }
+    }
+
+    /// Called every frame with the block that is looked at and whether the break
+    /// action is held. Letting go or looking at another block starts over.
+    pub fn aim(&mut self, hit: Option<RaycastHit>, held: bool) -> Option<BreakChange> {
+        let hit = hit.filter(|_| held);
+
+        let same = match (&self.target, &hit) {
+            (Some(target), Some(hit)) => target.coord == hit.coord && target.block == hit.block,
+            _ => false,
+        };
+
+        let change = match (same, &self.target, &hit) {
+            (true, _, _) => None,
+            (false, _, Some(hit)) => Some(BreakChange::Started(hit.coord)),
+            (false, Some(_), None) => Some(BreakChange::Stopped),
+            (false, None, None) => None,
+        };
+
+        if !same {
+            self.progress = 0.0;
+        }
+        self.target = hit;
+
+        change
+    }
+
+    /// Makes one tick of progress. Returns the block once it breaks.
+    pub fn tick(&mut self, tool: Option<ToolKind>) -> Option<RaycastHit> {
+        let target = self.target?;
+
+        self.progress += progress_per_tick(get_block(target.block), tool);
+        if self.progress < 1.0 {
+            return None
+        }
+
+        self.target = None;
+        self.progress = 0.0;
+
+        Some(target)
+    }
+
+    /// The block that is being broken and how far along it is, from 0 to 1
+    pub fn progress(&self) -> Option<(RaycastHit, f32)> {
+        self.target.filter(|_| self.progress > 0.0).map(|target| (target, self.progress))
+    }
+}
+
+/// How much closer to breaking a block gets every tick
+pub fn progress_per_tick(block: &Block, tool: Option<ToolKind>) -> f32 {
+    if block.hardness < 0.0 {
+        return 0.0
+    }
+    if block.hardness == 0.0 {
+        return 1.0
+    }
+
+    let speed = match (block.tool, tool) {
+        (Some(wanted), Some(tool)) if wanted == tool => ToolKind::SPEED,
+        _ => 1.0,
+    };
+
+    speed / (block.hardness * TICKS_PER_SECOND as f32)
+}
+
+/// How many ticks breaking a block takes, None if it can't be broken
+pub fn ticks_to_break(block: &Block, tool: Option<ToolKind>) -> Option<u64> {
+    let progress = progress_per_tick(block, tool);
+    if progress <= 0.0 {
+        return None
+    }
+
+    Some((1.0 / progress).ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{block::blocks::{BlockID, Blocks}, testing::setup};
+
+    fn hit(x: i64, block: Blocks) -> RaycastHit {
+        RaycastHit { coord: WorldCoord { x, y: 10, z: 0 }, block: block as BlockID, normal: [0, 1, 0], distance: 1.0 }
+    }
+
+    #[test]
+    fn the_server_is_told_when_breaking_starts_and_stops() {
+        setup();
+        let mut breaking = BlockBreaking::new();
+        let stone = hit(0, Blocks::STONE);
+
+        assert_eq!(breaking.aim(Some(stone), true), Some(BreakChange::Started(stone.coord)));
+        assert_eq!(breaking.aim(Some(stone), true), None);
+        assert_eq!(breaking.aim(Some(hit(1, Blocks::STONE)), true), Some(BreakChange::Started(WorldCoord { x: 1, y: 10, z: 0 })));
+        assert_eq!(breaking.aim(Some(stone), false), Some(BreakChange::Stopped));
+        assert_eq!(breaking.aim(None, false), None);
+    }
+
+    #[test]
+    fn blocks_break_after_their_ticks() {
+        setup();
+        let dirt = hit(0, Blocks::DIRT);
+
+        for tool in [None, Some(ToolKind::Shovel), Some(ToolKind::Pickaxe)] {
+            let ticks = ticks_to_break(get_block(dirt.block), tool).unwrap();
+            let mut breaking = BlockBreaking::new();
+            breaking.aim(Some(dirt), true);
+
+            assert!((1..ticks).all(|_| breaking.tick(tool).is_none()));
+            assert_eq!(breaking.tick(tool).map(|hit| hit.coord), Some(dirt.coord));
+        }
+
+        let with_shovel = ticks_to_break(get_block(dirt.block), Some(ToolKind::Shovel)).unwrap();
+        assert!(with_shovel < ticks_to_break(get_block(dirt.block), None).unwrap());
+        assert_eq!(ticks_to_break(get_block(Blocks::AIR as BlockID), None), None);
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+/// How many times per second the world is updated
+pub const TICKS_PER_SECOND: u32 = 20;
+
+/// The clock never catches up on more than this many ticks at once,
+/// so a long freeze doesn't stall the game even longer
+const MAX_CATCH_UP: u32 = 10;
+
+/// Turns real time into a fixed number of ticks per second,
+/// independent of the frame rate.
+pub struct TickClock {
+    last: Instant,
+    /// Time that passed but was not enough for a full tick yet
+    accumulated: Duration,
+    tick_length: Duration,
+
+    /// Ticks since the clock was created
+    pub ticks: u64,
+}
+
+impl TickClock {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            accumulated: Duration::from_secs(0),
+            tick_length: Duration::from_secs(1) / TICKS_PER_SECOND,
+
+            ticks: 0,
+        }
+    }
+
+    /// The number of ticks that have to be run since the last call
+    pub fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        self.accumulated += now - self.last;
+        self.last = now;
+
+        let mut ticks = 0;
+        while self.accumulated >= self.tick_length {
+            self.accumulated -= self.tick_length;
+            ticks += 1;
+        }
+
+        if ticks > MAX_CATCH_UP {
+            ticks = MAX_CATCH_UP;
+        }
+
+        self.ticks += ticks as u64;
+        ticks
+    }
+
+    /// Forgets the time that passed since the last tick, for when the
+    /// game was paused. Otherwise it would try to catch up.
+    pub fn resume(&mut self) {
+        self.last = Instant::now();
+        self.accumulated = Duration::from_secs(0);
+    }
+
+    /// How far the current tick is, between 0 and 1. For
+    /// interpolating between the last two ticks while rendering.
+    pub fn partial_tick(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.tick_length.as_secs_f32()
+    }
+}
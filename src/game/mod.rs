@@ -1,2 +1,4 @@
 pub mod player;
-pub mod state;
\ No newline at end of file
+pub mod clock;
+pub mod state;
+pub mod breaking;
\ No newline at end of file
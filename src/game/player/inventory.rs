@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::player::item::{Item, ItemStack};
+
+/// The first slots of the inventory are the hotbar
+pub const HOTBAR_SLOTS: usize = 9;
+pub const INVENTORY_SLOTS: usize = 36;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    /// Blocks have to be broken before they can be placed
+    Survival,
+    /// Every block can be placed as often as wanted
+    Creative,
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            GameMode::Survival => GameMode::Creative,
+            GameMode::Creative => GameMode::Survival,
+        }
+    }
+}
+
+/// The items a player carries, of which one hotbar slot is selected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    #[serde(with = "slots")]
+    slots: [Option<ItemStack>; INVENTORY_SLOTS],
+    selected: usize,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            slots: [None; INVENTORY_SLOTS],
+            selected: 0,
+        }
+    }
+
+    pub fn slot(&self, slot: usize) -> Option<ItemStack> {
+        self.slots[slot]
+    }
+
+    /// Empty stacks leave the slot empty
+    pub fn set_slot(&mut self, slot: usize, stack: Option<ItemStack>) {
+        self.slots[slot] = stack.filter(|stack| stack.count > 0);
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.slots.swap(a, b);
+    }
+
+    /// The selected hotbar slot
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, slot: usize) {
+        self.selected = slot.min(HOTBAR_SLOTS - 1);
+    }
+
+    /// Moves the selection through the hotbar, wrapping around at the ends
+    pub fn scroll(&mut self, steps: i32) {
+        self.selected = (self.selected as i32 + steps).rem_euclid(HOTBAR_SLOTS as i32) as usize;
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// Removes one item from the selected slot and returns it
+    pub fn take_selected(&mut self) -> Option<Item> {
+        let stack = self.slots[self.selected]?;
+        self.set_slot(self.selected, Some(ItemStack::new(stack.item, stack.count - 1)));
+
+        Some(stack.item)
+    }
+
+    /// Adds items to stacks of the same block first, then to empty slots with
+    /// the hotbar first. Returns how many did not fit.
+    pub fn add(&mut self, stack: ItemStack) -> u32 {
+        let max = stack.item.max_stack();
+        let mut left = stack.count;
+
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.item == stack.item && slot.count < max {
+                let moved = left.min(max - slot.count);
+                slot.count += moved;
+                left -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if left == 0 {
+                break
+            }
+
+            let moved = left.min(max);
+            *slot = Some(ItemStack::new(stack.item, moved));
+            left -= moved;
+        }
+
+        left
+    }
+}
+
+/// Serde only knows arrays of up to 32 elements, the slots are a list instead
+mod slots {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{ItemStack, INVENTORY_SLOTS};
+
+    pub fn serialize<S: Serializer>(slots: &[Option<ItemStack>; INVENTORY_SLOTS], serializer: S) -> Result<S::Ok, S::Error> {
+        slots[..].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[Option<ItemStack>; INVENTORY_SLOTS], D::Error> {
+        let list = Vec::<Option<ItemStack>>::deserialize(deserializer)?;
+        if list.len() != INVENTORY_SLOTS {
+            return Err(D::Error::invalid_length(list.len(), &"one entry for every slot"))
+        }
+
+        let mut slots = [None; INVENTORY_SLOTS];
+        slots.copy_from_slice(&list);
+
+        Ok(slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::player::{item::ToolKind, player::Player};
+    use crate::world::{block::blocks::{BlockID, Blocks}, testing::setup};
+
+    fn blocks(block: Blocks, count: u32) -> ItemStack {
+        ItemStack::new(Item::Block(block as BlockID), count)
+    }
+
+    #[test]
+    fn items_go_on_stacks_of_the_same_item_first() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(4, Some(blocks(Blocks::DIRT, 60)));
+        inventory.set_slot(20, Some(blocks(Blocks::DIRT, 10)));
+        inventory.set_slot(0, Some(blocks(Blocks::STONE, 1)));
+
+        assert_eq!(inventory.add(blocks(Blocks::DIRT, 10)), 0);
+
+        assert_eq!(inventory.slot(4), Some(blocks(Blocks::DIRT, 64)));
+        assert_eq!(inventory.slot(20), Some(blocks(Blocks::DIRT, 16)));
+        assert_eq!(inventory.slot(0), Some(blocks(Blocks::STONE, 1)));
+        assert_eq!(inventory.slot(1), None);
+    }
+
+    #[test]
+    fn large_stacks_are_split_over_empty_slots() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(1, Some(blocks(Blocks::STONE, 1)));
+
+        assert_eq!(inventory.add(blocks(Blocks::DIRT, 150)), 0);
+        assert_eq!(inventory.slot(0), Some(blocks(Blocks::DIRT, 64)));
+        assert_eq!(inventory.slot(2), Some(blocks(Blocks::DIRT, 64)));
+        assert_eq!(inventory.slot(3), Some(blocks(Blocks::DIRT, 22)));
+
+        // Tools don't stack, each one takes a slot
+        let pickaxes = ItemStack::new(Item::Tool(ToolKind::Pickaxe), 2);
+        assert_eq!(inventory.add(pickaxes), 0);
+        assert_eq!(inventory.slot(4), Some(ItemStack::new(Item::Tool(ToolKind::Pickaxe), 1)));
+        assert_eq!(inventory.slot(5), Some(ItemStack::new(Item::Tool(ToolKind::Pickaxe), 1)));
+    }
+
+    #[test]
+    fn taking_the_last_item_empties_the_slot() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, Some(blocks(Blocks::SAND, 2)));
+
+        assert_eq!(inventory.take_selected(), Some(Item::Block(Blocks::SAND as BlockID)));
+        assert_eq!(inventory.selected_stack(), Some(blocks(Blocks::SAND, 1)));
+        assert_eq!(inventory.take_selected(), Some(Item::Block(Blocks::SAND as BlockID)));
+        assert_eq!(inventory.selected_stack(), None);
+        assert_eq!(inventory.take_selected(), None);
+
+        inventory.set_slot(3, Some(blocks(Blocks::SAND, 0)));
+        assert_eq!(inventory.slot(3), None);
+    }
+
+    #[test]
+    fn the_selection_stays_in_the_hotbar() {
+        let mut inventory = Inventory::new();
+
+        inventory.select(4);
+        assert_eq!(inventory.selected(), 4);
+        inventory.select(INVENTORY_SLOTS - 1);
+        assert_eq!(inventory.selected(), HOTBAR_SLOTS - 1);
+
+        inventory.scroll(1);
+        assert_eq!(inventory.selected(), 0);
+        inventory.scroll(-1);
+        assert_eq!(inventory.selected(), HOTBAR_SLOTS - 1);
+        inventory.scroll(-(HOTBAR_SLOTS as i32) * 3 - 2);
+        assert_eq!(inventory.selected(), HOTBAR_SLOTS - 3);
+
+        inventory.set_slot(HOTBAR_SLOTS - 3, Some(blocks(Blocks::GRAVEL, 5)));
+        assert_eq!(inventory.selected_stack(), Some(blocks(Blocks::GRAVEL, 5)));
+    }
+
+    #[test]
+    fn picking_up_gives_back_what_did_not_fit() {
+        setup();
+        let mut player = Player::new((0.0, 0.0, 0.0).into());
+        player.inventory = Inventory::new();
+        for slot in 0..INVENTORY_SLOTS - 1 {
+            player.inventory.set_slot(slot, Some(blocks(Blocks::STONE, 64)));
+        }
+        player.inventory.set_slot(INVENTORY_SLOTS - 1, Some(blocks(Blocks::DIRT, 50)));
+
+        assert_eq!(player.pick_up(blocks(Blocks::DIRT, 20)), 6);
+        assert_eq!(player.inventory.slot(INVENTORY_SLOTS - 1), Some(blocks(Blocks::DIRT, 64)));
+        assert_eq!(player.pick_up(blocks(Blocks::GRAVEL, 3)), 3);
+        assert_eq!(player.pick_up(ItemStack::new(Item::Tool(ToolKind::Axe), 1)), 1);
+    }
+}
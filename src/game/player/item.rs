@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use crate::world::block::{
+    blocks::{BlockID, Blocks},
+    registry::registry,
+};
+
+/// Tools break the blocks they are made for faster
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolKind {
+    Pickaxe,
+    Shovel,
+    Axe,
+}
+
+impl ToolKind {
+    pub const ALL: [ToolKind; 3] = [ToolKind::Pickaxe, ToolKind::Shovel, ToolKind::Axe];
+
+    /// How many times faster than by hand a tool breaks the blocks it is made for
+    pub const SPEED: f32 = 4.0;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolKind::Pickaxe => "pickaxe",
+            ToolKind::Shovel => "shovel",
+            ToolKind::Axe => "axe",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ToolKind::ALL.iter().copied().find(|tool| tool.name() == name)
+    }
+}
+
+/// Anything that can be in an inventory slot
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Item {
+    Block(BlockID),
+    Tool(ToolKind),
+}
+
+impl Item {
+    /// Tools don't stack
+    pub fn max_stack(&self) -> u32 {
+        match self {
+            Item::Block(_) => 64,
+            Item::Tool(_) => 1,
+        }
+    }
+
+    /// The name the item is saved with. Blocks have the name from the block
+    /// definitions, so their ids can change between resource packs.
+    pub fn name(&self) -> String {
+        match self {
+            Item::Block(block) => registry().get(*block).name.clone(),
+            Item::Tool(tool) => tool.name().to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match ToolKind::from_name(name) {
+            Some(tool) => Some(Item::Tool(tool)),
+            None => registry().id(name).map(Item::Block),
+        }
+    }
+
+    /// Every item, for creative mode
+    pub fn all() -> Vec<Item> {
+        registry()
+            .ids()
+            .filter(|id| *id != Blocks::AIR as BlockID)
+            .map(Item::Block)
+            .chain(ToolKind::ALL.iter().copied().map(Item::Tool))
+            .collect()
+    }
+
+    pub fn block(&self) -> Option<BlockID> {
+        match self {
+            Item::Block(block) => Some(*block),
+            Item::Tool(_) => None,
+        }
+    }
+
+    pub fn tool(&self) -> Option<ToolKind> {
+        match self {
+            Item::Tool(tool) => Some(*tool),
+            Item::Block(_) => None,
+        }
+    }
+}
+
+/// A number of the same item
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: Item, count: u32) -> Self {
+        Self {
+            item,
+            count,
+        }
+    }
+}
@@ -1 +1,4 @@
-pub mod player;
\ No newline at end of file
+pub mod player;
+pub mod inventory;
+pub mod item;
+pub mod remote;
\ No newline at end of file
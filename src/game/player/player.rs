@@ -1,13 +1,242 @@
+use anyhow::{Context, Result};
 use cgmath::{Point3};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
+use crate::game::player::{
+    inventory::{Inventory, GameMode, HOTBAR_SLOTS, INVENTORY_SLOTS},
+    item::{Item, ItemStack},
+};
+use crate::world::block::blocks::BlockID;
+
+/// The file in a world directory with the player of a singleplayer world,
+/// before players had names
+pub const PLAYER_FILE: &str = "player.toml";
+/// The directory in a world directory with a file for every player that joined it
+pub const PLAYER_DIRECTORY: &str = "players";
+/// The name of the player in singleplayer
+pub const LOCAL_PLAYER: &str = "player";
+/// Names are also file names, so they are kept short and simple
+pub const MAX_NAME_LENGTH: usize = 16;
+/// How far away blocks can be reached, in blocks
+pub const REACH: f32 = 8.0;
+
+#[derive(Clone)]
 pub struct Player {
     pub position: Point3<f32>,
+    /// Where the player looks, in degrees
+    pub yaw: f32,
+    pub pitch: f32,
+
+    pub game_mode: GameMode,
+    pub inventory: Inventory,
+}
+
+/// What a player does in the inventory screen or with the hotbar keys. The client
+/// applies it right away and sends it to the server, which has the real inventory.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InventoryAction {
+    Select(usize),
+    Swap(usize, usize),
+    /// Puts a stack from the creative palette in the selected slot
+    Pick(ItemStack),
+    SetGameMode(GameMode),
 }
 
 impl Player {
     pub fn null_player() -> Self {
-        Self {
-            position: (0.0, 0.0, 0.0).into(),
+        Player::new((0.0, 0.0, 0.0).into())
+    }
+
+    pub fn new(position: Point3<f32>) -> Self {
+        let mut player = Self {
+            position,
+            yaw: 45.0,
+            pitch: 0.0,
+
+            game_mode: GameMode::Creative,
+            inventory: Inventory::new(),
+        };
+        player.fill_hotbar();
+
+        player
+    }
+
+    /// Puts the first items in the hotbar, so there is something
+    /// to build with in creative mode
+    pub fn fill_hotbar(&mut self) {
+        for (slot, item) in (0..HOTBAR_SLOTS).zip(Item::all()) {
+            if self.inventory.slot(slot).is_none() {
+                self.inventory.set_slot(slot, Some(ItemStack::new(item, 1)));
+            }
+        }
+    }
+
+    /// The item in the selected hotbar slot
+    pub fn held_item(&self) -> Option<Item> {
+        self.inventory.selected_stack().map(|stack| stack.item)
+    }
+
+    /// The block to place, if a block is held. Creative mode never runs out.
+    pub fn take_block(&mut self) -> Option<BlockID> {
+        let block = self.held_item()?.block()?;
+
+        if self.game_mode == GameMode::Survival {
+            self.inventory.take_selected();
+        }
+
+        Some(block)
+    }
+
+    /// One of the held item, to throw away. Creative mode never runs out.
+    pub fn throw_item(&mut self) -> Option<ItemStack> {
+        let item = self.held_item()?;
+
+        if self.game_mode == GameMode::Survival {
+            self.inventory.take_selected();
+        }
+
+        Some(ItemStack::new(item, 1))
+    }
+
+    /// Puts items that were picked up in the inventory. Returns how many did not fit.
+    pub fn pick_up(&mut self, stack: ItemStack) -> u32 {
+        self.inventory.add(stack)
+    }
+
+    /// Returns false if the action is not allowed, such as picking items in survival mode
+    pub fn apply(&mut self, action: InventoryAction) -> bool {
+        match action {
+            InventoryAction::Select(slot) => self.inventory.select(slot),
+            InventoryAction::Swap(a, b) if a < INVENTORY_SLOTS && b < INVENTORY_SLOTS => self.inventory.swap(a, b),
+            InventoryAction::Pick(stack) if self.game_mode == GameMode::Creative => {
+                let selected = self.inventory.selected();
+                self.inventory.set_slot(selected, Some(stack));
+            }
+            InventoryAction::SetGameMode(mode) => self.game_mode = mode,
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Reads the player with the given name from a world directory. Returns None if
+    /// the player never joined the world.
+    pub fn load(directory: &Path, name: &str) -> Result<Option<Self>> {
+        let mut path = player_file(directory, name);
+
+        // Singleplayer worlds from before names kept their player in player.toml
+        if !path.is_file() && name == LOCAL_PLAYER {
+            path = directory.join(PLAYER_FILE);
         }
+
+        if !path.is_file() {
+            return Ok(None)
+        }
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        let data: PlayerData = toml::from_str(&source)
+            .with_context(|| format!("{} is not a valid player file", path.display()))?;
+
+        let mut inventory = Inventory::new();
+        inventory.select(data.selected);
+
+        for slot in data.inventory {
+            // Items are saved by name, a resource pack can leave a block out
+            let item = match Item::from_name(&slot.item) {
+                Some(item) if slot.slot < INVENTORY_SLOTS => item,
+                _ => {
+                    println!("Dropping {} {} from slot {} of the inventory", slot.count, slot.item, slot.slot);
+                    continue
+                }
+            };
+
+            inventory.set_slot(slot.slot, Some(ItemStack::new(item, slot.count)));
+        }
+
+        Ok(Some(Self {
+            position: data.position.into(),
+            yaw: data.yaw,
+            pitch: data.pitch,
+
+            game_mode: data.game_mode,
+            inventory,
+        }))
     }
-}
\ No newline at end of file
+
+    pub fn save(&self, directory: &Path, name: &str) -> Result<()> {
+        let data = PlayerData {
+            position: self.position.into(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            game_mode: self.game_mode,
+            selected: self.inventory.selected(),
+            inventory: (0..INVENTORY_SLOTS)
+                .filter_map(|slot| self.inventory.slot(slot).map(|stack| SlotData {
+                    slot,
+                    item: stack.item.name(),
+                    count: stack.count,
+                }))
+                .collect(),
+        };
+
+        let path = player_file(directory, name);
+        let temporary = path.with_extension("toml.tmp");
+
+        std::fs::create_dir_all(directory.join(PLAYER_DIRECTORY))
+            .with_context(|| format!("Could not create the player directory of {}", directory.display()))?;
+        std::fs::write(&temporary, toml::to_string(&data)?)
+            .with_context(|| format!("Could not write {}", temporary.display()))?;
+        std::fs::rename(&temporary, &path)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+
+        // The player is in the players directory now
+        if name == LOCAL_PLAYER {
+            let legacy = directory.join(PLAYER_FILE);
+            if legacy.is_file() {
+                std::fs::remove_file(&legacy)
+                    .with_context(|| format!("Could not remove {}", legacy.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Where a player is saved in a world directory
+pub fn player_file(directory: &Path, name: &str) -> PathBuf {
+    directory.join(PLAYER_DIRECTORY).join(format!("{}.toml", name))
+}
+
+/// A name can be used by a player if it is not empty, not too long and
+/// only has letters, digits, - and _
+pub fn valid_player_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The player as it is saved in player.toml
+#[derive(Serialize, Deserialize)]
+struct PlayerData {
+    position: [f32; 3],
+    #[serde(default)]
+    yaw: f32,
+    #[serde(default)]
+    pitch: f32,
+    game_mode: GameMode,
+    #[serde(default)]
+    selected: usize,
+    #[serde(default)]
+    inventory: Vec<SlotData>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SlotData {
+    slot: usize,
+    /// Inventories used to hold only blocks
+    #[serde(alias = "block")]
+    item: String,
+    count: u32,
+}
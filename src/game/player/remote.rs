@@ -0,0 +1,108 @@
+use cgmath::{Point3, Vector3};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::game::clock::TICKS_PER_SECOND;
+use crate::render::low::textures::TextureTile;
+use crate::server::protocol::PlayerState;
+use crate::world::entity::entity::Model;
+
+/// How far the eyes of a player are above its feet, in blocks
+pub const EYE_HEIGHT: f32 = 1.6;
+const BODY_SIZE: [f32; 3] = [0.6, 1.2, 0.4];
+const HEAD_SIZE: f32 = 0.5;
+
+/// Another player on the server. It is drawn between the last two positions
+/// that arrived, so it moves smoothly even though positions come once a tick.
+struct RemotePlayer {
+    /// Where it was drawn when the last position arrived
+    from: (Point3<f32>, f32),
+    to: (Point3<f32>, f32),
+    received: Instant,
+}
+
+impl RemotePlayer {
+    /// Position and yaw, the time since the last position arrived
+    /// of the way between the last two
+    fn interpolated(&self, now: Instant) -> (Point3<f32>, f32) {
+        let tick = Duration::from_secs(1) / TICKS_PER_SECOND;
+        let t = ((now - self.received).as_secs_f32() / tick.as_secs_f32()).min(1.0);
+
+        let (from, from_yaw) = self.from;
+        let (to, to_yaw) = self.to;
+        // The short way around
+        let turn = (to_yaw - from_yaw + 180.0).rem_euclid(360.0) - 180.0;
+
+        (from + (to - from) * t, from_yaw + turn * t)
+    }
+}
+
+/// The other players the server tells a client about
+pub struct RemotePlayers {
+    players: HashMap<String, RemotePlayer>,
+}
+
+impl RemotePlayers {
+    pub fn new() -> Self {
+        Self {
+            players: HashMap::new(),
+        }
+    }
+
+    /// Players that are not in the list are out of view or left
+    pub fn update(&mut self, states: Vec<PlayerState>) {
+        let now = Instant::now();
+        let mut players = HashMap::new();
+
+        for state in states {
+            let to = (state.position, state.yaw);
+            let from = match self.players.get(&state.name) {
+                Some(player) => player.interpolated(now),
+                None => to,
+            };
+
+            players.insert(state.name, RemotePlayer { from, to, received: now });
+        }
+
+        self.players = players;
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.players.keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// A body and a head for every player: the feet, the yaw of an entity and the model
+    pub fn models(&self) -> Vec<(Point3<f32>, f32, Model)> {
+        let now = Instant::now();
+        let mut models = vec![];
+
+        for player in self.players.values() {
+            let (eyes, yaw) = player.interpolated(now);
+            let feet = eyes - Vector3::new(0.0, EYE_HEIGHT, 0.0);
+            // Entities face the other way around than the camera, see Entity::heading
+            let yaw = -yaw - 90.0;
+
+            let wool = TextureTile::new(0, 4);
+            models.push((feet, yaw, Model {
+                size: BODY_SIZE.into(),
+                textures: [wool; 6],
+            }));
+
+            let side = TextureTile::new(6, 7);
+            models.push((feet + Vector3::new(0.0, BODY_SIZE[1], 0.0), yaw, Model {
+                size: Vector3::new(HEAD_SIZE, HEAD_SIZE, HEAD_SIZE),
+                textures: [TextureTile::new(8, 7), side, side, side, TextureTile::new(6, 6), side],
+            }));
+        }
+
+        models
+    }
+}
@@ -0,0 +1,55 @@
+use super::{State, Transition, menu::Menu, mainmenu::{MainMenuState, MENU_TIME}};
+use crate::render::{
+    low::context::Context,
+    drawables::{Drawable, sky::SkyDrawable},
+    ui::ui::{Rect, TEXT_SIZE, TEXT_COLOR},
+};
+use crate::world::time::WorldTime;
+use crate::input::action::Action;
+
+/// Shown when a server could not be joined, the player had to leave it or
+/// a world could not be opened
+pub struct DisconnectedState {
+    menu: Menu,
+    reason: String,
+    sky: SkyDrawable,
+}
+
+impl DisconnectedState {
+    pub fn new(reason: &str) -> Self {
+        Self::with_title("Disconnected", reason)
+    }
+
+    pub fn with_title(title: &str, reason: &str) -> Self {
+        Self {
+            menu: Menu::new(title, vec!["Back to title".to_string()]),
+            reason: reason.to_string(),
+            sky: SkyDrawable,
+        }
+    }
+}
+
+impl State for DisconnectedState {
+    fn update(&mut self, context: &mut Context, _encoder: &mut wgpu::CommandEncoder) -> Transition {
+        context.renderer.update_sky(&WorldTime::new(MENU_TIME), 0.0);
+
+        let ui = &mut context.ui;
+        let screen = ui.screen();
+        ui.text_centered(Rect::new(0.0, Menu::top(ui) - 60.0, screen.w, 40.0), TEXT_SIZE, &self.reason, TEXT_COLOR);
+
+        let back = context.input.pressed(Action::Pause);
+        match self.menu.update(&context.input, &mut context.ui) {
+            Some(_) => Transition::Switch(Box::new(MainMenuState::new())),
+            None if back => Transition::Switch(Box::new(MainMenuState::new())),
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![&self.sky]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+    }
+}
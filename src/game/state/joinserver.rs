@@ -0,0 +1,101 @@
+use super::{State, Transition, menu::{Menu, BUTTON_WIDTH, BUTTON_HEIGHT, SPACING}, loading::LoadingState, mainmenu::MENU_TIME};
+use crate::render::{
+    low::context::Context,
+    drawables::{Drawable, sky::SkyDrawable},
+    ui::ui::{Rect, TEXT_SIZE, HINT_COLOR, TEXT_COLOR},
+};
+use crate::world::time::WorldTime;
+use crate::game::player::player::{valid_player_name, MAX_NAME_LENGTH};
+use crate::input::action::Action;
+
+/// Asks for a name and the address of a server to join. Both are
+/// remembered in the settings for the next time.
+pub struct JoinServerState {
+    menu: Menu,
+    name: String,
+    address: String,
+    error: Option<String>,
+    sky: SkyDrawable,
+}
+
+impl JoinServerState {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new("Multiplayer", vec!["Join server".to_string(), "Back".to_string()]),
+            name: String::new(),
+            address: String::new(),
+            error: None,
+            sky: SkyDrawable,
+        }
+    }
+
+    fn join(&mut self, context: &mut Context) -> Transition {
+        let name = self.name.trim().to_string();
+        let address = self.address.trim().to_string();
+
+        if !valid_player_name(&name) {
+            self.error = Some(format!("A name has 1 to {} letters, digits or _", MAX_NAME_LENGTH));
+            return Transition::None
+        }
+        if address.is_empty() {
+            self.error = Some("Which server?".to_string());
+            return Transition::None
+        }
+
+        context.settings.name = name.clone();
+        context.settings.server = address.clone();
+        context.save_settings();
+
+        Transition::Switch(Box::new(LoadingState::connect(address, name)))
+    }
+}
+
+impl State for JoinServerState {
+    fn update(&mut self, context: &mut Context, _encoder: &mut wgpu::CommandEncoder) -> Transition {
+        context.renderer.update_sky(&WorldTime::new(MENU_TIME), 0.0);
+
+        if context.input.pressed(Action::Pause) {
+            return Transition::Pop
+        }
+
+        // The name and the address go above the buttons
+        let ui = &mut context.ui;
+        let top = Menu::top(ui);
+        let screen = ui.screen();
+        let x = (screen.w - BUTTON_WIDTH) / 2.0;
+        let name_field = Rect::new(x, top - 2.0 * (BUTTON_HEIGHT + SPACING) - 20.0, BUTTON_WIDTH, BUTTON_HEIGHT);
+        let address_field = Rect::new(x, top - BUTTON_HEIGHT - SPACING - 20.0, BUTTON_WIDTH, BUTTON_HEIGHT);
+
+        let mut entered = ui.text_input(name_field, "player name", &mut self.name);
+        if self.name.is_empty() {
+            ui.text(name_field.x + 6.0, name_field.y + 8.0, TEXT_SIZE, "Name", HINT_COLOR);
+        }
+        entered |= ui.text_input(address_field, "server address", &mut self.address);
+        if self.address.is_empty() {
+            ui.text(address_field.x + 6.0, address_field.y + 8.0, TEXT_SIZE, "Server address", HINT_COLOR);
+        }
+
+        if let Some(error) = &self.error {
+            let y = top + 2.0 * (BUTTON_HEIGHT + SPACING) + SPACING;
+            ui.text_centered(Rect::new(0.0, y, screen.w, BUTTON_HEIGHT), TEXT_SIZE, error, TEXT_COLOR);
+        }
+
+        match if entered { Some(0) } else { self.menu.update(&context.input, &mut context.ui) } {
+            Some(0) => self.join(context),
+            Some(_) => Transition::Pop,
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![&self.sky]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+
+        self.name = context.settings.name.clone();
+        self.address = context.settings.server.clone();
+        self.error = None;
+    }
+}
@@ -0,0 +1,130 @@
+use super::{State, Transition, mainstate::MainState, disconnected::DisconnectedState};
+use crate::render::{
+    low::context::Context,
+    drawables::{Drawable, sky::SkyDrawable},
+    ui::ui::{Rect, TEXT_SIZE, TEXT_COLOR, WIDGET_COLOR, ACCENT_COLOR},
+};
+use crate::world::{
+    level::world_directory,
+    world::World,
+};
+use crate::server::network;
+
+/// How many received chunks are meshed every frame while loading
+const CHUNKS_PER_FRAME: usize = 8;
+
+/// What is being loaded
+enum Target {
+    World { name: String, seed: u32 },
+    Server { address: String, name: String },
+}
+
+/// Opens a world or joins a server and shows progress while the server sends
+/// the chunks around the player, then hands over to the game.
+pub struct LoadingState {
+    target: Target,
+    game: Option<MainState>,
+    sky: SkyDrawable,
+}
+
+impl LoadingState {
+    /// The seed is only used if the world doesn't exist yet
+    pub fn new(name: String, seed: u32) -> Self {
+        Self {
+            target: Target::World { name, seed },
+            game: None,
+            sky: SkyDrawable,
+        }
+    }
+
+    /// Joins a server as the player with the name
+    pub fn connect(address: String, name: String) -> Self {
+        Self {
+            target: Target::Server { address, name },
+            game: None,
+            sky: SkyDrawable,
+        }
+    }
+}
+
+impl State for LoadingState {
+    fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) -> Transition {
+        if self.game.is_none() {
+            let view_distance = context.settings.render_distance;
+
+            let game = match &self.target {
+                Target::World { name, seed } => match World::open(world_directory(name), *seed) {
+                    Ok(world) => {
+                        println!("Loading world '{}'", name);
+                        MainState::new(&mut context.renderer, world, view_distance)
+                    }
+                    Err(e) => {
+                        println!("Could not open world '{}': {:?}", name, e);
+                        // A broken level file is not replaced by a new world, the player has to fix or remove it
+                        let reason = format!("{:#}", e);
+                        return Transition::Switch(Box::new(DisconnectedState::with_title("Could not open world", &reason)))
+                    }
+                },
+                Target::Server { address, name } => match network::connect(address) {
+                    Ok(connection) => {
+                        println!("Joining {} as {}", address, name);
+                        MainState::join(&mut context.renderer, connection, name, view_distance)
+                    }
+                    Err(e) => {
+                        println!("Could not connect to {}: {:?}", address, e);
+                        return Transition::Switch(Box::new(DisconnectedState::new(&format!("{:#}", e))))
+                    }
+                },
+            };
+
+            self.game = Some(game);
+        }
+
+        let game = self.game.as_mut().unwrap();
+        game.load_chunks(context, encoder, CHUNKS_PER_FRAME);
+
+        // The server can refuse the player, for example when the name is taken
+        if let Some(reason) = game.disconnected() {
+            game.exit(context);
+            self.game = None;
+            return Transition::Switch(Box::new(DisconnectedState::new(&reason)))
+        }
+
+        let progress = game.loading_progress();
+
+        let ui = &mut context.ui;
+        let bar = Rect::centered(ui.screen(), 400.0, 24.0);
+        ui.text_centered(
+            Rect::new(bar.x, bar.y - 48.0, bar.w, 40.0),
+            TEXT_SIZE,
+            &match &self.target {
+                Target::World { name, .. } => format!("Loading world '{}'... {}%", name, (progress * 100.0) as u32),
+                Target::Server { address, .. } => format!("Joining {}... {}%", address, (progress * 100.0) as u32),
+            },
+            TEXT_COLOR,
+        );
+        ui.rect(bar, WIDGET_COLOR);
+        ui.rect(Rect::new(bar.x, bar.y, bar.w * progress, bar.h), ACCENT_COLOR);
+
+        if progress >= 1.0 {
+            return Transition::Replace(Box::new(self.game.take().unwrap()))
+        }
+
+        Transition::None
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![&self.sky]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+    }
+
+    fn exit(&mut self, context: &mut Context) {
+        // The game was not handed over, so the game is closing while loading
+        if let Some(game) = &mut self.game {
+            game.exit(context);
+        }
+    }
+}
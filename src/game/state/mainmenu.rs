@@ -0,0 +1,46 @@
+use super::{State, Transition, menu::Menu, worldselect::WorldSelectState, joinserver::JoinServerState, settings::SettingsState};
+use crate::render::{
+    low::context::Context,
+    drawables::{Drawable, sky::SkyDrawable},
+};
+use crate::world::time::WorldTime;
+
+/// The sky behind the menus is always in the morning
+pub const MENU_TIME: u64 = 3000;
+
+/// The first thing that is shown when the game starts
+pub struct MainMenuState {
+    menu: Menu,
+    sky: SkyDrawable,
+}
+
+impl MainMenuState {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new("Ludwig World 3D", vec!["Play".to_string(), "Multiplayer".to_string(), "Settings".to_string(), "Quit".to_string()]),
+            sky: SkyDrawable,
+        }
+    }
+}
+
+impl State for MainMenuState {
+    fn update(&mut self, context: &mut Context, _encoder: &mut wgpu::CommandEncoder) -> Transition {
+        context.renderer.update_sky(&WorldTime::new(MENU_TIME), 0.0);
+
+        match self.menu.update(&context.input, &mut context.ui) {
+            Some(0) => Transition::Push(Box::new(WorldSelectState::new())),
+            Some(1) => Transition::Push(Box::new(JoinServerState::new())),
+            Some(2) => Transition::Push(Box::new(SettingsState::new())),
+            Some(_) => Transition::Quit,
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![&self.sky]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+    }
+}
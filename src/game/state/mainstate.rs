@@ -1,49 +1,532 @@
-use super::State;
+use super::{State, Transition, pause::PauseState, disconnected::DisconnectedState};
 use crate::render::{
     low::{
         context::Context,
         renderer::Renderer,
     },
-    drawables::{Drawable},
+    drawables::{Drawable, sky::SkyDrawable, breaking::BreakingDrawable, entity::EntityDrawable, selection::SelectionDrawable, minimap::MinimapDrawable},
+    ui::{ui::{Ui, Rect}, debug::compass, inventory::{draw_hotbar, inventory_screen}, chat::Chat},
 };
 use crate::world::{
-    chunk::{chunkmanager::ChunkManager, pos::{WorldCoord}},
+    chunk::{chunkmanager::ChunkManager, pos::{WorldCoord}, codec::decode},
+    constants::CHUNKSIZE,
     world::World,
+    time::WorldTime,
+    raycast::{raycast, RaycastHit},
+    block::blocks::get_block,
+    constants::WORLDHEIGHT,
+    entity::entity::Entity,
 };
+use crate::game::{
+    clock::TickClock,
+    breaking::{BlockBreaking, BreakChange},
+    player::{inventory::GameMode, player::{Player, InventoryAction, LOCAL_PLAYER, REACH}, remote::RemotePlayers},
+};
+use crate::server::{
+    integrated::IntegratedServer,
+    connection::ClientConnection,
+    protocol::{ClientMessage, ServerMessage},
+    command::{Commands, split},
+};
+use crate::settings::settings::{MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE};
+use crate::input::action::Action;
+use cgmath::MetricSpace;
+
+/// Chunks meshed per frame while playing, more would make the game stutter
+const CHUNKS_PER_FRAME: usize = 2;
+/// How far away corners can be selected for editing, in blocks
+const SELECT_REACH: f32 = 64.0;
+
+const CROSSHAIR_SIZE: f32 = 16.0;
+const CROSSHAIR_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
 
+/// Playing in a world. This is the client: it shows what the server sends and
+/// tells the server what the player does. Singleplayer runs an integrated server.
 pub struct MainState {
     chm:  ChunkManager,
-    world: World,
+    /// None when playing on a server over the network
+    server: Option<IntegratedServer>,
+    connection: ClientConnection,
+    /// Why the server sent the player away
+    disconnected: Option<String>,
+
+    /// The player as the server sent it last, with the inventory actions done since
+    player: Player,
+    seed: u32,
+    time: WorldTime,
+    /// The entities around the player as the server sent them last tick
+    entities: Vec<Entity>,
+    players: RemotePlayers,
+    chat: Chat,
+    /// For completing commands, they run on the server
+    commands: Commands,
+    /// In chunks
+    view_distance: u32,
+
+    /// Runs alongside the server clock, for breaking blocks and moving smoothly between ticks
+    clock: TickClock,
+    sky: SkyDrawable,
+    /// The inventory screen is open, the cursor is free for it
+    inventory_open: bool,
+    breaking: BlockBreaking,
+    cracks: BreakingDrawable,
+    entity_drawable: EntityDrawable,
+    selection: SelectionDrawable,
+    minimap: MinimapDrawable,
 }
 
-impl State for MainState {
-    fn new(renderer: &mut Renderer) -> Self {
-        let mut chm = ChunkManager::new(5);
-        let world = World::new(69);
+impl MainState {
+    /// Starts a server for the world and joins it. The view distance is in chunks.
+    pub fn new(renderer: &mut Renderer, world: World, view_distance: u32) -> Self {
+        let (seed, time) = (world.seed, world.time);
+        let (server, connection) = IntegratedServer::start(world);
+
+        let mut state = MainState::join(renderer, connection, LOCAL_PLAYER, view_distance);
+        state.server = Some(server);
+        state.seed = seed;
+        state.time = time;
+
+        state
+    }
+
+    /// Joins a server with a connection to it, see network::connect. The
+    /// camera is put where the player is once the server answered.
+    pub fn join(renderer: &mut Renderer, mut connection: ClientConnection, name: &str, view_distance: u32) -> Self {
+        set_fog(renderer, view_distance);
+        connection.send(ClientMessage::Join { name: name.to_string(), view_distance });
 
-        chm.set_camera_location(WorldCoord {x: -1, y: 0, z: 0}, renderer);
-        
         Self {
-            chm,
-            world,
+            chm: ChunkManager::new(),
+            server: None,
+            connection,
+            disconnected: None,
+
+            player: Player::null_player(),
+            seed: 0,
+            time: WorldTime::new(0),
+            entities: vec![],
+            players: RemotePlayers::new(),
+            chat: Chat::new(),
+            commands: Commands::builtin(),
+            view_distance,
+
+            clock: TickClock::new(),
+            sky: SkyDrawable,
+            inventory_open: false,
+            breaking: BlockBreaking::new(),
+            cracks: BreakingDrawable::new(),
+            entity_drawable: EntityDrawable::new(&renderer.device),
+            selection: SelectionDrawable::new(),
+            minimap: MinimapDrawable::new(renderer),
+        }
+    }
+
+    /// Handles what the server sent, meshes received chunks and uploads their meshes
+    pub fn load_chunks(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder, limit: usize) {
+        self.receive(&mut context.renderer);
+        self.chm.mesh_queue(limit);
+        self.chm.update(context, encoder);
+    }
+
+    /// Why the game can't go on: the server sent the player away or the connection was lost
+    pub fn disconnected(&self) -> Option<String> {
+        match &self.disconnected {
+            Some(reason) => Some(reason.clone()),
+            None if self.connection.is_closed() => Some("Lost the connection to the server".to_string()),
+            None => None,
+        }
+    }
+
+    /// How many of the chunks in view are received and meshed, from 0 to 1
+    pub fn loading_progress(&self) -> f32 {
+        let stats = self.chm.stats();
+        let expected = (2 * self.view_distance as usize).pow(2);
+
+        (stats.loaded.saturating_sub(stats.queued) as f32 / expected as f32).min(1.0)
+    }
+
+    fn receive(&mut self, renderer: &mut Renderer) {
+        for message in self.connection.receive() {
+            match message {
+                ServerMessage::Welcome { seed, position, yaw, pitch } => {
+                    self.seed = seed;
+                    renderer.camera.view.position = position;
+                    renderer.camera.view.set_angles(yaw, pitch);
+                }
+                ServerMessage::Chunk(bytes) => match decode(&bytes) {
+                    Ok(data) => self.chm.insert_chunk(data.chunk, renderer),
+                    Err(e) => self.broken_chunk(e),
+                },
+                ServerMessage::Chunks(chunks) => match chunks.iter().map(|bytes| decode(bytes).map(|data| data.chunk)).collect() {
+                    Ok(chunks) => self.chm.replace_chunks(chunks, renderer),
+                    Err(e) => self.broken_chunk(e),
+                },
+                ServerMessage::UnloadChunk(pos) => self.chm.unload_chunk(&pos, renderer),
+                ServerMessage::BlockChange { coord, block } => {
+                    self.chm.set_block(coord, block);
+                }
+                ServerMessage::Time(ticks) => self.time = WorldTime::new(ticks),
+                ServerMessage::Inventory { game_mode, inventory } => {
+                    self.player.game_mode = game_mode;
+                    self.player.inventory = *inventory;
+                }
+                ServerMessage::Entities(entities) => self.entities = entities,
+                ServerMessage::Players(players) => self.players.update(players),
+                ServerMessage::Chat(line) => self.chat.add(line),
+                ServerMessage::Selection { first, second } => self.selection.set(renderer, first, second),
+                ServerMessage::Teleport(position) => {
+                    renderer.camera.view.position = position;
+                    self.player.position = position;
+                }
+                ServerMessage::Disconnect(reason) => self.disconnected = Some(reason),
+            }
+        }
+    }
+
+    /// The game can't go on without the chunk, so the player leaves the server
+    fn broken_chunk(&mut self, error: anyhow::Error) {
+        println!("Received a broken chunk: {:?}", error);
+        self.disconnected = Some(format!("The server sent a broken chunk: {:#}", error));
+    }
+
+    /// Sends a chat line, or runs a command. Most commands run on the server,
+    /// the render distance is a setting of the game.
+    fn send_line(&mut self, context: &mut Context, line: String) {
+        if !line.starts_with('/') {
+            return self.connection.send(ClientMessage::Chat(line))
+        }
+
+        match split(&line) {
+            ("rd", words) => match words.as_slice() {
+                [distance] => match distance.parse::<u32>() {
+                    Ok(distance) => {
+                        context.settings.render_distance = distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+                        context.save_settings();
+                        self.chat.add(format!("Render distance set to {} chunks", context.settings.render_distance));
+                    }
+                    Err(_) => self.chat.add(format!("'{}' is not a number of chunks", distance)),
+                },
+                _ => self.chat.add(format!("Use /rd <chunks>, from {} to {}", MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE)),
+            },
+            _ => self.connection.send(ClientMessage::Command(line)),
+        }
+    }
+
+    /// What the debug overlay shows about the world and the camera
+    fn debug_lines(&self, context: &mut Context) {
+        let view = &context.renderer.camera.view;
+        let position = view.position;
+        let direction = view.direction();
+        let (yaw, pitch) = view.angles();
+
+        let coord = WorldCoord::from_point(position);
+        let chunk = coord.to_chunk_coord();
+        let local = coord.to_chunk_local();
+
+        let stats = self.chm.stats();
+        let target = raycast(self.chm.chunks(), position, direction, REACH);
+        let nearby = self.entities.iter().filter(|entity| entity.center().distance2(position) <= 16.0 * 16.0).count();
+
+        let lines = vec![
+            format!("Seed {}, day {}, time {}", self.seed, self.time.day(), self.time.time_of_day()),
+            String::new(),
+            format!("XYZ: {:.2} / {:.2} / {:.2}", position.x, position.y, position.z),
+            format!("Block: {} {} {}", coord.x, coord.y, coord.z),
+            format!("Chunk: {} {} {} in {} {}", local.x, local.y, local.z, chunk.x, chunk.z),
+            format!("Facing: {} ({:.1} / {:.1})", compass(direction.x, direction.z), yaw.rem_euclid(360.0), pitch),
+            String::new(),
+            format!("Chunks: {} loaded, {} meshed, {} drawn, {} queued", stats.loaded, stats.meshed, stats.drawn, stats.queued),
+            format!("Vertices: {}, indices: {}", stats.vertices, stats.indices),
+            format!("Meshing: {} us per chunk", stats.meshing_time),
+            format!("Entities: {} in view, {} within 16 blocks, {} other players", self.entities.len(), nearby, self.players.len()),
+            String::new(),
+            match target {
+                Some(hit) => format!(
+                    "Targeted block: {} at {} {} {}, face {:?}, {:.1} away",
+                    get_block(hit.block).name, hit.coord.x, hit.coord.y, hit.coord.z, hit.normal, hit.distance,
+                ),
+                None => "Targeted block: none".to_string(),
+            },
+        ];
+
+        for line in lines {
+            context.debug.line(line);
+        }
+    }
+
+    /// The player is wherever the camera is
+    fn follow_camera(&mut self, context: &Context) {
+        let view = &context.renderer.camera.view;
+        let (yaw, pitch) = view.angles();
+
+        let player = &mut self.player;
+        player.position = view.position;
+        player.yaw = yaw;
+        player.pitch = pitch;
+    }
+
+    /// Changes the inventory right away, so the player doesn't have to wait for the server
+    fn inventory_action(&mut self, action: InventoryAction) {
+        if self.player.apply(action) {
+            self.connection.send(ClientMessage::Inventory(action));
+        }
+    }
+
+    /// Hotbar selection and breaking and placing the targeted block
+    fn interact(&mut self, context: &mut Context) {
+        let input = &context.input;
+
+        for action in Action::ALL.iter() {
+            if let Some(slot) = action.hotbar_slot() {
+                if input.pressed(*action) {
+                    self.inventory_action(InventoryAction::Select(slot));
+                }
+            }
+        }
+        for (action, steps) in [(Action::HotbarNext, 1), (Action::HotbarPrevious, -1)].iter() {
+            if input.pressed(*action) {
+                let mut inventory = self.player.inventory.clone();
+                inventory.scroll(*steps);
+                self.inventory_action(InventoryAction::Select(inventory.selected()));
+            }
+        }
+
+        let view = &context.renderer.camera.view;
+        let target = raycast(self.chm.chunks(), view.position, view.direction(), REACH);
+        let camera = WorldCoord::from_point(view.position);
+
+        // Blocks break at once in creative mode, in survival mode it takes
+        // time while the button is held, see tick
+        match self.player.game_mode {
+            GameMode::Creative => {
+                let change = self.breaking.aim(None, false);
+                self.send_break_change(change);
+                if let (true, Some(hit)) = (input.pressed(Action::BreakBlock), target) {
+                    self.connection.send(ClientMessage::BreakBlock(hit.coord));
+                }
+            }
+            GameMode::Survival => {
+                let change = self.breaking.aim(target, input.held(Action::BreakBlock));
+                self.send_break_change(change);
+            }
+        }
+
+        // Corners for the edit commands, the server checks if the player may edit
+        for (action, command) in [(Action::SelectFirst, "pos1"), (Action::SelectSecond, "pos2")].iter() {
+            if input.pressed(*action) {
+                match raycast(self.chm.chunks(), view.position, view.direction(), SELECT_REACH) {
+                    Some(hit) => {
+                        let line = format!("/{} {} {} {}", command, hit.coord.x, hit.coord.y, hit.coord.z);
+                        self.connection.send(ClientMessage::Command(line));
+                    }
+                    None => self.chat.add("No block to select there".to_string()),
+                }
+            }
+        }
+
+        if input.pressed(Action::DropItem) {
+            self.connection.send(ClientMessage::ThrowItem(view.direction()));
+        }
+
+        if let (true, Some(hit)) = (input.pressed(Action::PlaceBlock), target) {
+            let holds_block = self.player.held_item().and_then(|item| item.block()).is_some();
+
+            if let (true, Some(coord)) = (holds_block, self.placement(hit, camera)) {
+                self.connection.send(ClientMessage::PlaceBlock(coord));
+            }
+        }
+    }
+
+    /// Runs at the same rate as the server: tells it where the player is and
+    /// when the block that is being broken is done
+    fn tick(&mut self) {
+        let player = &self.player;
+        self.connection.send_unreliable(ClientMessage::Move { position: player.position, yaw: player.yaw, pitch: player.pitch });
+
+        let tool = self.player.held_item().and_then(|item| item.tool());
+        if let Some(hit) = self.breaking.tick(tool) {
+            self.connection.send(ClientMessage::BreakBlock(hit.coord));
         }
     }
 
+    /// The server times breaking too, so it is told when it starts and stops
+    fn send_break_change(&mut self, change: Option<BreakChange>) {
+        match change {
+            Some(BreakChange::Started(coord)) => self.connection.send(ClientMessage::StartBreaking(coord)),
+            Some(BreakChange::Stopped) => self.connection.send(ClientMessage::AbortBreaking),
+            None => {}
+        }
+    }
+
+    /// Where a block goes when placed against the targeted face. Never
+    /// inside the camera or in another block.
+    fn placement(&self, target: RaycastHit, camera: WorldCoord) -> Option<WorldCoord> {
+        let coord = WorldCoord {
+            x: target.coord.x + target.normal[0],
+            y: target.coord.y + target.normal[1],
+            z: target.coord.z + target.normal[2],
+        };
+
+        let free = matches!(self.chm.get_block_at_coord(coord), Some(block) if !get_block(block).solid);
+        let inside = coord.y < 0 || coord.y >= WORLDHEIGHT as i64;
+
+        if target.normal == [0; 3] || !free || inside || coord == camera {
+            return None
+        }
+
+        Some(coord)
+    }
+}
+
+/// Chunks are loaded in a square around the camera, fog hides the edge of it
+fn set_fog(renderer: &mut Renderer, view_distance: u32) {
+    renderer.set_view_distance(((view_distance - 1) * CHUNKSIZE as u32) as f32);
+}
+
+impl State for MainState {
     fn draw(&self) -> Vec<&dyn Drawable> {
         let mut objs = Vec::<&dyn Drawable>::new();
 
+        // The sky goes behind everything else
+        objs.push(&self.sky);
+
         // Draw all chunks
-        for (_, chunk) in &self.chm.chunk_buffers {
+        for chunk in self.chm.chunk_buffers.values() {
             objs.push(chunk);
         }
 
+        objs.push(&self.entity_drawable);
+
+        // On top of the chunks
+        objs.push(&self.cracks);
+        objs.push(&self.selection);
+
+        // Over the world, the UI is drawn over it in its own pass
+        objs.push(&self.minimap);
+
         objs
     }
 
-    fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) {
-        self.chm.load_queue(&self.world, &mut context.renderer);
-        self.chm.update(context, encoder);
+    fn draw_ui(&self, ui: &mut Ui) {
+        if self.inventory_open {
+            return
+        }
 
-        self.chm.set_camera_location(WorldCoord::from_point(context.renderer.camera.view.position), &mut context.renderer);
+        draw_hotbar(ui, &self.player);
+
+        let screen = ui.screen();
+        ui.rect(Rect::centered(screen, CROSSHAIR_SIZE, 2.0), CROSSHAIR_COLOR);
+        ui.rect(Rect::centered(screen, 2.0, CROSSHAIR_SIZE), CROSSHAIR_COLOR);
     }
-}
\ No newline at end of file
+
+    fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) -> Transition {
+        if let Some(reason) = self.disconnected() {
+            println!("Disconnected: {}", reason);
+            return Transition::Switch(Box::new(DisconnectedState::new(&reason)))
+        }
+
+        self.follow_camera(context);
+
+        let typing = self.chat.is_open();
+        if typing {
+            let (commands, players) = (&self.commands, self.players.names());
+            if let Some(line) = self.chat.update(&mut context.ui, |line| commands.complete(line, &players)) {
+                self.send_line(context, line);
+            }
+            if !self.chat.is_open() {
+                context.capture_cursor(true);
+            }
+        } else if self.inventory_open {
+            if let Some(action) = inventory_screen(&mut context.ui, &self.player) {
+                self.inventory_action(action);
+            }
+
+            if context.input.pressed(Action::Inventory) || context.input.pressed(Action::Pause) {
+                self.inventory_open = false;
+                context.capture_cursor(true);
+            }
+        } else if context.input.pressed(Action::Inventory) {
+            self.inventory_open = true;
+            context.capture_cursor(false);
+        } else if context.input.pressed(Action::ToggleMinimap) {
+            self.minimap.visible = !self.minimap.visible;
+        } else if context.input.pressed(Action::Chat) {
+            self.chat.open(&mut context.ui, "");
+            context.capture_cursor(false);
+        } else if context.input.pressed(Action::Command) {
+            self.chat.open(&mut context.ui, "/");
+            context.capture_cursor(false);
+        } else if context.input.pressed(Action::Pause) {
+            context.renderer.camera.controller.reset();
+            // Only singleplayer worlds stop, the server saves when it is paused
+            if self.server.is_some() {
+                self.connection.send(ClientMessage::Pause(true));
+            }
+
+            return Transition::Push(Box::new(PauseState::new()))
+        }
+
+        // Only draws the lines, typing was handled above
+        if !typing {
+            self.chat.update(&mut context.ui, |_| vec![]);
+        }
+
+        // The camera only moves while the mouse is used for looking around
+        if context.cursor_captured() && !self.inventory_open && !self.chat.is_open() {
+            context.renderer.camera.controller.process_input(&context.input);
+            self.interact(context);
+        } else {
+            context.renderer.camera.controller.reset();
+            let change = self.breaking.aim(None, false);
+            self.send_break_change(change);
+        }
+
+        for _ in 0..self.clock.advance() {
+            self.tick();
+        }
+
+        context.renderer.update_sky(&self.time, self.clock.partial_tick());
+
+        let cracks = self.breaking.progress().map(|(hit, progress)| (hit.coord, hit.normal, progress));
+        self.cracks.set(&mut context.renderer, cracks);
+
+        // The render distance can be changed in the settings menu
+        let view_distance = context.settings.render_distance;
+        if view_distance != self.view_distance {
+            self.view_distance = view_distance;
+            self.connection.send(ClientMessage::ViewDistance(view_distance));
+            set_fog(&mut context.renderer, view_distance);
+        }
+
+        self.load_chunks(context, encoder, CHUNKS_PER_FRAME);
+        self.minimap.update(&mut context.renderer, self.chm.chunks());
+        let partial_tick = self.clock.partial_tick();
+        let entities = self.entities.iter().map(|entity| (entity.interpolated_position(partial_tick), entity.yaw, entity.model()));
+        self.entity_drawable.upload(&context.renderer, encoder, entities.chain(self.players.models()));
+
+        if context.debug.visible {
+            self.debug_lines(context);
+        }
+
+        Transition::None
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(!self.inventory_open);
+        // Don't catch up on the time spent in menus
+        self.clock.resume();
+        if self.server.is_some() {
+            self.connection.send(ClientMessage::Pause(false));
+        }
+    }
+
+    fn exit(&mut self, context: &mut Context) {
+        // Waits for the server to save the world
+        self.connection.send(ClientMessage::Leave);
+        if let Some(server) = &mut self.server {
+            server.stop();
+        }
+
+        self.chm.unload_all(&mut context.renderer);
+    }
+}
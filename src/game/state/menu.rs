@@ -0,0 +1,77 @@
+use crate::input::{action::Action, inputmanager::InputManager};
+use crate::render::ui::ui::{Rect, Ui, TEXT_COLOR};
+
+const TITLE_SIZE: f32 = 40.0;
+pub const BUTTON_WIDTH: f32 = 360.0;
+pub const BUTTON_HEIGHT: f32 = 40.0;
+pub const SPACING: f32 = 8.0;
+
+/// A list of entries of which one is selected, shared by all menus.
+/// Entries are chosen by clicking them or with the menu actions.
+pub struct Menu {
+    pub title: String,
+    pub entries: Vec<String>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: &str, entries: Vec<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            entries,
+            selected: 0,
+        }
+    }
+
+    /// Where the first entry goes, everything above it is free for the title
+    pub fn top(ui: &Ui) -> f32 {
+        ui.screen().h * 0.35
+    }
+
+    /// Draws a title above the entries
+    pub fn title(ui: &mut Ui, title: &str) {
+        let screen = ui.screen();
+        ui.text_centered(Rect::new(0.0, screen.h * 0.15, screen.w, TITLE_SIZE * 1.5), TITLE_SIZE, title, TEXT_COLOR);
+    }
+
+    /// Draws the menu and moves the selection. Returns
+    /// the index of the entry that was chosen, if any.
+    pub fn update(&mut self, input: &InputManager, ui: &mut Ui) -> Option<usize> {
+        let screen = ui.screen();
+        Menu::title(ui, &self.title);
+
+        if self.entries.is_empty() {
+            return None
+        }
+
+        if input.pressed(Action::MenuUp) {
+            self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+        }
+        if input.pressed(Action::MenuDown) {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+
+        let mut chosen = None;
+        let mut y = Menu::top(ui);
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let rect = Rect::new((screen.w - BUTTON_WIDTH) / 2.0, y, BUTTON_WIDTH, BUTTON_HEIGHT);
+
+            // Only when the mouse moves, so it doesn't undo selecting with the keyboard
+            if ui.mouse_moved() && ui.hovered(rect) {
+                self.selected = i;
+            }
+            if ui.selectable(rect, entry, i == self.selected) {
+                chosen = Some(i);
+            }
+
+            y += BUTTON_HEIGHT + SPACING;
+        }
+
+        if input.pressed(Action::MenuSelect) {
+            return Some(self.selected)
+        }
+
+        chosen
+    }
+}
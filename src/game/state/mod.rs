@@ -1,17 +1,64 @@
+use winit::event::WindowEvent;
+
 use crate::render::{
-    low::{
-        context::Context,
-        renderer::Renderer,
-    },
+    low::context::Context,
     drawables::Drawable,
+    ui::ui::Ui,
 };
 
+/// What should happen to the state stack after an update
+pub enum Transition {
+    None,
+    /// Put a state on top of this one
+    Push(Box<dyn State>),
+    /// Remove this state, the one below it continues
+    Pop,
+    /// Swap this state for another one
+    Replace(Box<dyn State>),
+    /// Remove all states and start over with this one
+    Switch(Box<dyn State>),
+    Quit,
+}
+
 pub trait State {
-    fn new(renderer: &mut Renderer) -> Self;
-    /// Update all state
-    fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder);
+    /// Update all state. Only the top state decides what happens to the stack,
+    /// the transitions of states below it are ignored.
+    fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) -> Transition;
     /// Draw all state such as chunks, ui, players, mobs.
     fn draw(&self) -> Vec<&dyn Drawable>;
+    /// Draws UI that doesn't take input, such as the HUD. Unlike update this is
+    /// also called while the state is below an overlay. Menus and other widgets
+    /// that are used go in update, through context.ui.
+    fn draw_ui(&self, _ui: &mut Ui) {}
+
+    /// Window events go to the top state first. Returns true if the event was used.
+    fn input(&mut self, _context: &mut Context, _event: &WindowEvent) -> bool {
+        false
+    }
+    /// Called when the state becomes the top of the stack, both when it is
+    /// pushed and when the state above it is removed
+    fn enter(&mut self, _context: &mut Context) {}
+    /// Called once when the state is removed or the game closes, for saving
+    /// and giving back what the state took from the renderer
+    fn exit(&mut self, _context: &mut Context) {}
+
+    /// Overlays are drawn on top of the states below them
+    fn is_overlay(&self) -> bool {
+        false
+    }
+    /// Whether the states below keep updating while this state is on top
+    fn updates_below(&self) -> bool {
+        false
+    }
 }
 
-pub mod mainstate;
\ No newline at end of file
+pub mod statestack;
+pub mod mainstate;
+pub mod menu;
+pub mod mainmenu;
+pub mod worldselect;
+pub mod loading;
+pub mod joinserver;
+pub mod disconnected;
+pub mod pause;
+pub mod settings;
\ No newline at end of file
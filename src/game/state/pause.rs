@@ -0,0 +1,52 @@
+use super::{State, Transition, menu::Menu, mainmenu::MainMenuState, settings::SettingsState};
+use crate::render::{
+    low::context::Context,
+    drawables::Drawable,
+    ui::ui::PANEL_COLOR,
+};
+use crate::input::action::Action;
+
+/// The pause menu, shown over the game. The world doesn't tick while it is open.
+pub struct PauseState {
+    menu: Menu,
+}
+
+impl PauseState {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new("Paused", vec!["Resume".to_string(), "Settings".to_string(), "Save and quit to title".to_string()]),
+        }
+    }
+}
+
+impl State for PauseState {
+    fn update(&mut self, context: &mut Context, _encoder: &mut wgpu::CommandEncoder) -> Transition {
+        if context.input.pressed(Action::Pause) {
+            return Transition::Pop
+        }
+
+        // Darken the game behind the menu
+        let screen = context.ui.screen();
+        context.ui.rect(screen, PANEL_COLOR);
+
+        match self.menu.update(&context.input, &mut context.ui) {
+            Some(0) => Transition::Pop,
+            Some(1) => Transition::Push(Box::new(SettingsState::new())),
+            // The game saves when it is removed
+            Some(_) => Transition::Switch(Box::new(MainMenuState::new())),
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,83 @@
+use super::{State, Transition, menu::{Menu, BUTTON_WIDTH, BUTTON_HEIGHT, SPACING}};
+use crate::render::{
+    low::context::Context,
+    drawables::Drawable,
+    ui::ui::{Rect, PANEL_COLOR},
+};
+use crate::settings::settings::{MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE, MIN_FOV, MAX_FOV, MIN_CAMERA_SPEED, MAX_CAMERA_SPEED};
+use crate::input::action::Action;
+
+/// Changes the settings while the game runs. They are applied right
+/// away and saved when the menu is closed.
+pub struct SettingsState;
+
+impl SettingsState {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl State for SettingsState {
+    fn update(&mut self, context: &mut Context, _encoder: &mut wgpu::CommandEncoder) -> Transition {
+        let ui = &mut context.ui;
+        let screen = ui.screen();
+        ui.rect(screen, PANEL_COLOR);
+        Menu::title(ui, "Settings");
+
+        let settings = &mut context.settings;
+        let mut changed = false;
+        let mut y = Menu::top(ui);
+        let mut next = || {
+            let rect = Rect::new((screen.w - BUTTON_WIDTH) / 2.0, y, BUTTON_WIDTH, BUTTON_HEIGHT);
+            y += BUTTON_HEIGHT + SPACING;
+            rect
+        };
+
+        let mut render_distance = settings.render_distance as f32;
+        let text = format!("Render distance: {} chunks", settings.render_distance);
+        if ui.slider(next(), &text, &mut render_distance, MIN_RENDER_DISTANCE as f32, MAX_RENDER_DISTANCE as f32) {
+            settings.render_distance = render_distance.round() as u32;
+        }
+
+        let text = format!("Field of view: {:.0}", settings.fov);
+        changed |= ui.slider(next(), &text, &mut settings.fov, MIN_FOV, MAX_FOV);
+
+        let text = format!("Camera speed: {:.0}", settings.camera_speed);
+        changed |= ui.slider(next(), &text, &mut settings.camera_speed, MIN_CAMERA_SPEED, MAX_CAMERA_SPEED);
+
+        if ui.button(next(), &format!("VSync: {}", if settings.vsync { "on" } else { "off" })) {
+            settings.vsync = !settings.vsync;
+            changed = true;
+        }
+        if ui.button(next(), &format!("Shadows: {}", settings.shadows.name())) {
+            settings.shadows = settings.shadows.next();
+            changed = true;
+        }
+
+        let done = ui.button(next(), "Done");
+
+        // The game picks up the render distance itself
+        if changed {
+            context.apply_settings();
+        }
+
+        if done || context.input.pressed(Action::Pause) {
+            context.save_settings();
+            return Transition::Pop
+        }
+
+        Transition::None
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
\ No newline at end of file
@@ -0,0 +1,129 @@
+use winit::event::WindowEvent;
+
+use super::{State, Transition};
+use crate::render::{
+    low::context::Context,
+    drawables::Drawable,
+    ui::ui::Ui,
+};
+
+/// The states of the game, the last one is on top. The top state gets the
+/// input, states below it are drawn and updated as long as the ones
+/// above them are overlays that allow it.
+pub struct StateStack {
+    states: Vec<Box<dyn State>>,
+    quit: bool,
+}
+
+impl StateStack {
+    pub fn new() -> Self {
+        Self {
+            states: vec![],
+            quit: false,
+        }
+    }
+
+    /// False once a state asked to quit or the last state was removed
+    pub fn running(&self) -> bool {
+        !self.quit && !self.states.is_empty()
+    }
+
+    /// How many states are on the stack
+    pub fn depth(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn push(&mut self, mut state: Box<dyn State>, context: &mut Context) {
+        state.enter(context);
+        self.states.push(state);
+    }
+
+    pub fn pop(&mut self, context: &mut Context) {
+        if let Some(mut state) = self.states.pop() {
+            state.exit(context);
+        }
+
+        if let Some(state) = self.states.last_mut() {
+            state.enter(context);
+        }
+    }
+
+    pub fn apply(&mut self, transition: Transition, context: &mut Context) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(state) => self.push(state, context),
+            Transition::Pop => self.pop(context),
+            Transition::Replace(state) => {
+                if let Some(mut old) = self.states.pop() {
+                    old.exit(context);
+                }
+                self.push(state, context);
+            }
+            Transition::Switch(state) => {
+                self.exit(context);
+                self.push(state, context);
+            }
+            Transition::Quit => self.quit = true,
+        }
+    }
+
+    /// Updates the top state and the states below it that are allowed to update
+    pub fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) {
+        let mut transition = Transition::None;
+
+        for i in (0..self.states.len()).rev() {
+            // The UI of a state goes over the UI of the states below it
+            context.ui.set_layer(i);
+            let result = self.states[i].update(context, encoder);
+
+            if i == self.states.len() - 1 {
+                transition = result;
+            }
+
+            if !self.states[i].updates_below() {
+                break
+            }
+        }
+
+        self.apply(transition, context);
+    }
+
+    /// The lowest state that can be seen, every state above it is an overlay
+    fn first_visible(&self) -> usize {
+        let mut first = self.states.len().saturating_sub(1);
+        while first > 0 && self.states[first].is_overlay() {
+            first -= 1;
+        }
+        first
+    }
+
+    /// Draws the top state and every state below it that is visible through overlays
+    pub fn draw(&self) -> Vec<&dyn Drawable> {
+        self.states[self.first_visible()..]
+            .iter()
+            .flat_map(|state| state.draw())
+            .collect()
+    }
+
+    /// Draws the HUD of every visible state, each in its own layer
+    pub fn draw_ui(&self, ui: &mut Ui) {
+        for i in self.first_visible()..self.states.len() {
+            ui.set_layer(i);
+            self.states[i].draw_ui(ui);
+        }
+    }
+
+    pub fn input(&mut self, context: &mut Context, event: &WindowEvent) -> bool {
+        match self.states.last_mut() {
+            Some(state) => state.input(context, event),
+            None => false,
+        }
+    }
+
+    /// Removes all states, top first
+    pub fn exit(&mut self, context: &mut Context) {
+        while let Some(mut state) = self.states.pop() {
+            state.exit(context);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use super::{State, Transition, menu::Menu, loading::LoadingState, mainmenu::MENU_TIME};
+use crate::render::{
+    low::context::Context,
+    drawables::{Drawable, sky::SkyDrawable},
+    ui::ui::{Rect, TEXT_SIZE, HINT_COLOR},
+};
+use crate::world::{
+    level::{list_worlds, new_world_name, clean_world_name},
+    time::WorldTime,
+};
+use crate::input::action::Action;
+
+/// Lists the saved worlds and can create a new one
+pub struct WorldSelectState {
+    menu: Menu,
+    worlds: Vec<String>,
+    /// Name for the new world, a free name is picked if it is left empty
+    new_name: String,
+    sky: SkyDrawable,
+}
+
+impl WorldSelectState {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new("Select world", vec![]),
+            worlds: vec![],
+            new_name: String::new(),
+            sky: SkyDrawable,
+        }
+    }
+}
+
+impl State for WorldSelectState {
+    fn update(&mut self, context: &mut Context, _encoder: &mut wgpu::CommandEncoder) -> Transition {
+        context.renderer.update_sky(&WorldTime::new(MENU_TIME), 0.0);
+
+        if context.input.pressed(Action::Pause) {
+            return Transition::Pop
+        }
+
+        // The name of a new world is typed above the list
+        let ui = &mut context.ui;
+        let top = Menu::top(ui);
+        let screen = ui.screen();
+        let field = Rect::new((screen.w - 360.0) / 2.0, top - 60.0, 360.0, 40.0);
+
+        let entered = ui.text_input(field, "new world name", &mut self.new_name);
+        if self.new_name.is_empty() {
+            ui.text(field.x + 6.0, field.y + 8.0, TEXT_SIZE, "Name of a new world", HINT_COLOR);
+        }
+
+        let new_world = if entered { Some(self.worlds.len()) } else { None };
+
+        match new_world.or_else(|| self.menu.update(&context.input, &mut context.ui)) {
+            Some(i) if i < self.worlds.len() => {
+                Transition::Switch(Box::new(LoadingState::new(self.worlds[i].clone(), context.settings.new_world_seed())))
+            }
+            Some(i) if i == self.worlds.len() => {
+                let name = match clean_world_name(&self.new_name) {
+                    name if name.is_empty() => new_world_name(),
+                    name => name,
+                };
+                Transition::Switch(Box::new(LoadingState::new(name, context.settings.new_world_seed())))
+            }
+            Some(_) => Transition::Pop,
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self) -> Vec<&dyn Drawable> {
+        vec![&self.sky]
+    }
+
+    fn enter(&mut self, context: &mut Context) {
+        context.capture_cursor(false);
+
+        // Worlds can be added or removed while the game runs
+        self.worlds = list_worlds();
+
+        let mut entries = self.worlds.clone();
+        entries.push("New world".to_string());
+        entries.push("Back".to_string());
+
+        self.menu.entries = entries;
+        self.menu.selected = 0;
+    }
+}
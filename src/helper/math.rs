@@ -0,0 +1,5 @@
+/// Smooth Hermite interpolation between 0 and 1 when x goes from edge0 to edge1, like GLSL's smoothstep
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
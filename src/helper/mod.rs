@@ -1 +1,2 @@
-pub mod noise;
\ No newline at end of file
+pub mod noise;
+pub mod math;
\ No newline at end of file
@@ -19,6 +19,21 @@ impl NoiseGenerator {
         }
     }
 
+    /// Noise with fewer or more octaves and its own frequency, for
+    /// features that are larger or smaller than the hills
+    pub fn with(seed: u32, octaves: usize, frequency: f64) -> Self {
+        let noise = Fbm::new()
+            .set_seed(seed)
+            .set_octaves(octaves)
+            .set_frequency(frequency)
+            .set_lacunarity(2.0)
+            .set_persistence(0.5);
+
+        Self {
+            noise
+        }
+    }
+
     pub fn get(&self, x: f64, y: f64) -> f64 {
         self.noise.get([x, y])
     }
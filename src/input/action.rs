@@ -0,0 +1,141 @@
+/// Everything the player can do with a key or button. Game code asks
+/// the InputManager about actions, never about keys.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Sneak,
+    ZoomIn,
+    ZoomOut,
+    BreakBlock,
+    PlaceBlock,
+    ToggleDebug,
+    Pause,
+    ReloadResources,
+    CycleShadows,
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+    HotbarNext,
+    HotbarPrevious,
+    Hotbar1,
+    Hotbar2,
+    Hotbar3,
+    Hotbar4,
+    Hotbar5,
+    Hotbar6,
+    Hotbar7,
+    Hotbar8,
+    Hotbar9,
+    Inventory,
+    DropItem,
+    Chat,
+    Command,
+    SelectFirst,
+    SelectSecond,
+    ToggleMinimap,
+}
+
+impl Action {
+    pub const ALL: [Action; 35] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Sneak,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::BreakBlock,
+        Action::PlaceBlock,
+        Action::ToggleDebug,
+        Action::Pause,
+        Action::ReloadResources,
+        Action::CycleShadows,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuSelect,
+        Action::HotbarNext,
+        Action::HotbarPrevious,
+        Action::Hotbar1,
+        Action::Hotbar2,
+        Action::Hotbar3,
+        Action::Hotbar4,
+        Action::Hotbar5,
+        Action::Hotbar6,
+        Action::Hotbar7,
+        Action::Hotbar8,
+        Action::Hotbar9,
+        Action::Inventory,
+        Action::DropItem,
+        Action::Chat,
+        Action::Command,
+        Action::SelectFirst,
+        Action::SelectSecond,
+        Action::ToggleMinimap,
+    ];
+
+    /// The name used in the bindings file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBackward => "move_backward",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Jump => "jump",
+            Action::Sneak => "sneak",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::BreakBlock => "break_block",
+            Action::PlaceBlock => "place_block",
+            Action::ToggleDebug => "toggle_debug",
+            Action::Pause => "pause",
+            Action::ReloadResources => "reload_resources",
+            Action::CycleShadows => "cycle_shadows",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::MenuSelect => "menu_select",
+            Action::HotbarNext => "hotbar_next",
+            Action::HotbarPrevious => "hotbar_previous",
+            Action::Hotbar1 => "hotbar_1",
+            Action::Hotbar2 => "hotbar_2",
+            Action::Hotbar3 => "hotbar_3",
+            Action::Hotbar4 => "hotbar_4",
+            Action::Hotbar5 => "hotbar_5",
+            Action::Hotbar6 => "hotbar_6",
+            Action::Hotbar7 => "hotbar_7",
+            Action::Hotbar8 => "hotbar_8",
+            Action::Hotbar9 => "hotbar_9",
+            Action::Inventory => "inventory",
+            Action::DropItem => "drop_item",
+            Action::Chat => "chat",
+            Action::Command => "command",
+            Action::SelectFirst => "select_first",
+            Action::SelectSecond => "select_second",
+            Action::ToggleMinimap => "toggle_minimap",
+        }
+    }
+
+    /// The hotbar slot an action selects directly
+    pub fn hotbar_slot(&self) -> Option<usize> {
+        match self {
+            Action::Hotbar1 => Some(0),
+            Action::Hotbar2 => Some(1),
+            Action::Hotbar3 => Some(2),
+            Action::Hotbar4 => Some(3),
+            Action::Hotbar5 => Some(4),
+            Action::Hotbar6 => Some(5),
+            Action::Hotbar7 => Some(6),
+            Action::Hotbar8 => Some(7),
+            Action::Hotbar9 => Some(8),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde::de::IntoDeserializer;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::input::action::Action;
+
+/// Where the key bindings are stored, relative to the current directory
+pub const BINDINGS_FILE: &str = "bindings.toml";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+}
+
+/// Something on the keyboard or mouse an action can be bound to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// A key by what is printed on it, this follows the keyboard layout
+    Key(VirtualKeyCode),
+    /// A key by where it is on the keyboard, as a scancode. The same position
+    /// works on QWERTY, AZERTY and Dvorak keyboards.
+    Physical(u32),
+    Mouse(MouseButton),
+    Scroll(ScrollDirection),
+}
+
+impl Binding {
+    /// The key at the position of `key` on a US QWERTY keyboard
+    pub fn physical(key: VirtualKeyCode) -> Self {
+        Binding::Physical(qwerty_scancode(key).expect("Key has no known position"))
+    }
+
+    /// Parses the format used in the bindings file, for example "key:Space",
+    /// "physical:W", "scancode:17", "mouse:Left" or "scroll:Up"
+    pub fn parse(binding: &str) -> Result<Self> {
+        let (kind, name) = match binding.find(':') {
+            Some(i) => (&binding[..i], &binding[i + 1..]),
+            None => bail!("Binding '{}' should look like 'key:Space' or 'mouse:Left'", binding),
+        };
+
+        Ok(match kind {
+            "key" => Binding::Key(parse_key(name)?),
+            "physical" => Binding::Physical(
+                qwerty_scancode(parse_key(name)?)
+                    .with_context(|| format!("The position of key '{}' is not known, use a scancode", name))?
+            ),
+            "scancode" => Binding::Physical(
+                name.parse().with_context(|| format!("Invalid scancode '{}'", name))?
+            ),
+            "mouse" => Binding::Mouse(match name {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                other => MouseButton::Other(
+                    other.parse().with_context(|| format!("Unknown mouse button '{}'", other))?
+                ),
+            }),
+            "scroll" => Binding::Scroll(match name {
+                "Up" => ScrollDirection::Up,
+                "Down" => ScrollDirection::Down,
+                other => bail!("Unknown scroll direction '{}'", other),
+            }),
+            other => bail!("Unknown kind of binding '{}' in '{}'", other, binding),
+        })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "key:{:?}", key),
+            Binding::Physical(scancode) => match qwerty_key(*scancode) {
+                Some(key) => write!(f, "physical:{:?}", key),
+                None => write!(f, "scancode:{}", scancode),
+            },
+            Binding::Mouse(MouseButton::Other(button)) => write!(f, "mouse:{}", button),
+            Binding::Mouse(button) => write!(f, "mouse:{:?}", button),
+            Binding::Scroll(direction) => write!(f, "scroll:{:?}", direction),
+        }
+    }
+}
+
+fn parse_key(name: &str) -> Result<VirtualKeyCode> {
+    VirtualKeyCode::deserialize(name.into_deserializer())
+        .map_err(|_: serde::de::value::Error| anyhow::anyhow!("Unknown key '{}'", name))
+}
+
+/// How the mouse turns the camera
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+pub struct MouseSettings {
+    /// 1 is the default speed
+    pub sensitivity: f32,
+    /// Moving the mouse up looks down
+    pub invert_y: bool,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 1.0,
+            invert_y: false,
+        }
+    }
+}
+
+/// The bindings file, actions are written by name
+#[derive(Serialize, Deserialize)]
+struct BindingsFile {
+    #[serde(default)]
+    mouse: MouseSettings,
+    bindings: BTreeMap<String, Vec<String>>,
+}
+
+/// Which keys and buttons trigger which action
+#[derive(Clone)]
+pub struct Bindings {
+    actions: HashMap<Action, Vec<Binding>>,
+    pub mouse: MouseSettings,
+}
+
+impl Bindings {
+    pub fn defaults() -> Self {
+        use VirtualKeyCode as Key;
+
+        let mut actions = HashMap::new();
+
+        // Movement keys are bound by position, so they are in the same place on every layout
+        actions.insert(Action::MoveForward, vec![Binding::physical(Key::W), Binding::Key(Key::Up)]);
+        actions.insert(Action::MoveBackward, vec![Binding::physical(Key::S), Binding::Key(Key::Down)]);
+        actions.insert(Action::MoveLeft, vec![Binding::physical(Key::A), Binding::Key(Key::Left)]);
+        actions.insert(Action::MoveRight, vec![Binding::physical(Key::D), Binding::Key(Key::Right)]);
+        actions.insert(Action::Jump, vec![Binding::Key(Key::Space)]);
+        actions.insert(Action::Sneak, vec![Binding::Key(Key::LShift)]);
+        // The scroll wheel goes through the hotbar
+        actions.insert(Action::ZoomIn, vec![Binding::Key(Key::PageUp)]);
+        actions.insert(Action::ZoomOut, vec![Binding::Key(Key::PageDown)]);
+        actions.insert(Action::BreakBlock, vec![Binding::Mouse(MouseButton::Left)]);
+        actions.insert(Action::PlaceBlock, vec![Binding::Mouse(MouseButton::Right)]);
+        actions.insert(Action::ToggleDebug, vec![Binding::Key(Key::F3)]);
+        actions.insert(Action::Pause, vec![Binding::Key(Key::Escape)]);
+        actions.insert(Action::ReloadResources, vec![Binding::Key(Key::F5)]);
+        actions.insert(Action::CycleShadows, vec![Binding::Key(Key::F7)]);
+        actions.insert(Action::MenuUp, vec![Binding::Key(Key::Up)]);
+        actions.insert(Action::MenuDown, vec![Binding::Key(Key::Down)]);
+        actions.insert(Action::MenuSelect, vec![Binding::Key(Key::Return)]);
+        actions.insert(Action::HotbarNext, vec![Binding::Scroll(ScrollDirection::Down)]);
+        actions.insert(Action::HotbarPrevious, vec![Binding::Scroll(ScrollDirection::Up)]);
+        actions.insert(Action::Hotbar1, vec![Binding::physical(Key::Key1)]);
+        actions.insert(Action::Hotbar2, vec![Binding::physical(Key::Key2)]);
+        actions.insert(Action::Hotbar3, vec![Binding::physical(Key::Key3)]);
+        actions.insert(Action::Hotbar4, vec![Binding::physical(Key::Key4)]);
+        actions.insert(Action::Hotbar5, vec![Binding::physical(Key::Key5)]);
+        actions.insert(Action::Hotbar6, vec![Binding::physical(Key::Key6)]);
+        actions.insert(Action::Hotbar7, vec![Binding::physical(Key::Key7)]);
+        actions.insert(Action::Hotbar8, vec![Binding::physical(Key::Key8)]);
+        actions.insert(Action::Hotbar9, vec![Binding::physical(Key::Key9)]);
+        actions.insert(Action::Inventory, vec![Binding::physical(Key::E)]);
+        actions.insert(Action::DropItem, vec![Binding::physical(Key::Q)]);
+        actions.insert(Action::Chat, vec![Binding::physical(Key::T)]);
+        actions.insert(Action::Command, vec![Binding::Key(Key::Slash), Binding::Key(Key::Grave)]);
+        actions.insert(Action::SelectFirst, vec![Binding::physical(Key::LBracket)]);
+        actions.insert(Action::SelectSecond, vec![Binding::physical(Key::RBracket)]);
+        actions.insert(Action::ToggleMinimap, vec![Binding::physical(Key::M)]);
+
+        Self {
+            actions,
+            mouse: MouseSettings::default(),
+        }
+    }
+
+    /// Reads the bindings from a file. Actions that are missing
+    /// from the file keep their default bindings.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        let file: BindingsFile = toml::from_str(&source)
+            .with_context(|| format!("{} is not a valid bindings file", path.display()))?;
+
+        let mut bindings = Bindings::defaults();
+        bindings.mouse = file.mouse;
+
+        for (name, inputs) in file.bindings {
+            // Actions can be renamed or removed between versions, that should not throw away the rest
+            let action = match Action::from_name(&name) {
+                Some(action) => action,
+                None => {
+                    println!("Ignoring unknown action '{}' in {}", name, path.display());
+                    continue
+                }
+            };
+
+            let inputs = inputs
+                .iter()
+                .map(|input| Binding::parse(input))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Invalid binding for '{}' in {}", name, path.display()))?;
+
+            bindings.actions.insert(action, inputs);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = BindingsFile {
+            mouse: self.mouse,
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (action.name().to_string(), self.get(*action).iter().map(|b| b.to_string()).collect()))
+                .collect(),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, toml::to_string(&file)?)
+            .with_context(|| format!("Could not write {}", path.display()))
+    }
+
+    /// Loads the bindings file, or writes one with the defaults so it can be edited
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        if path.is_file() {
+            return Bindings::load(path)
+        }
+
+        let bindings = Bindings::defaults();
+        bindings.save(path)?;
+
+        Ok(bindings)
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.actions.get(&action).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+
+    /// All actions that are triggered by a binding
+    pub fn actions_for(&self, binding: Binding) -> impl Iterator<Item = Action> + '_ {
+        self.actions
+            .iter()
+            .filter(move |(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+}
+
+/// Scancodes of the keys of a US QWERTY keyboard
+#[cfg(not(target_os = "macos"))]
+const QWERTY_SCANCODES: &[(VirtualKeyCode, u32)] = {
+    use VirtualKeyCode::*;
+    &[
+        (Escape, 1), (Key1, 2), (Key2, 3), (Key3, 4), (Key4, 5), (Key5, 6), (Key6, 7), (Key7, 8),
+        (Key8, 9), (Key9, 10), (Key0, 11), (Minus, 12), (Equals, 13), (Back, 14), (Tab, 15),
+        (Q, 16), (W, 17), (E, 18), (R, 19), (T, 20), (Y, 21), (U, 22), (I, 23), (O, 24), (P, 25),
+        (LBracket, 26), (RBracket, 27), (Return, 28), (LControl, 29),
+        (A, 30), (S, 31), (D, 32), (F, 33), (G, 34), (H, 35), (J, 36), (K, 37), (L, 38),
+        (Semicolon, 39), (Apostrophe, 40), (Grave, 41), (LShift, 42), (Backslash, 43),
+        (Z, 44), (X, 45), (C, 46), (V, 47), (B, 48), (N, 49), (M, 50),
+        (Comma, 51), (Period, 52), (Slash, 53), (RShift, 54), (LAlt, 56), (Space, 57),
+    ]
+};
+
+/// Key codes of the keys of a US QWERTY keyboard
+#[cfg(target_os = "macos")]
+const QWERTY_SCANCODES: &[(VirtualKeyCode, u32)] = {
+    use VirtualKeyCode::*;
+    &[
+        (A, 0), (S, 1), (D, 2), (F, 3), (H, 4), (G, 5), (Z, 6), (X, 7), (C, 8), (V, 9), (B, 11),
+        (Q, 12), (W, 13), (E, 14), (R, 15), (Y, 16), (T, 17), (Key1, 18), (Key2, 19), (Key3, 20),
+        (Key4, 21), (Key6, 22), (Key5, 23), (Equals, 24), (Key9, 25), (Key7, 26), (Minus, 27),
+        (Key8, 28), (Key0, 29), (RBracket, 30), (O, 31), (U, 32), (LBracket, 33), (I, 34), (P, 35),
+        (Return, 36), (L, 37), (J, 38), (Apostrophe, 39), (K, 40), (Semicolon, 41), (Backslash, 42),
+        (Comma, 43), (Slash, 44), (N, 45), (M, 46), (Period, 47), (Tab, 48), (Space, 49), (Grave, 50),
+        (Back, 51), (Escape, 53), (LShift, 56), (LAlt, 58), (LControl, 59),
+    ]
+};
+
+fn qwerty_scancode(key: VirtualKeyCode) -> Option<u32> {
+    QWERTY_SCANCODES.iter().find(|(k, _)| *k == key).map(|(_, scancode)| *scancode)
+}
+
+fn qwerty_key(scancode: u32) -> Option<VirtualKeyCode> {
+    QWERTY_SCANCODES.iter().find(|(_, s)| *s == scancode).map(|(key, _)| *key)
+}
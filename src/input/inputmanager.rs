@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use winit::event::{DeviceEvent, ElementState, KeyboardInput, MouseScrollDelta, WindowEvent};
+
+use crate::input::{
+    action::Action,
+    binding::{Binding, Bindings, ScrollDirection},
+};
+
+/// Keeps track of which actions are held, and which were pressed or released
+/// since the last frame. Events go in through the event functions, game code
+/// only asks about actions.
+pub struct InputManager {
+    pub bindings: Bindings,
+
+    /// Keys and buttons that are down right now
+    held_inputs: HashSet<Binding>,
+    pressed: HashSet<Action>,
+    released: HashSet<Action>,
+
+    mouse_delta: (f64, f64),
+}
+
+impl InputManager {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+
+            held_inputs: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+
+            mouse_delta: (0.0, 0.0),
+        }
+    }
+
+    /// Returns true if the event was used
+    pub fn window_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput { scancode, state, virtual_keycode, .. },
+                ..
+            } => {
+                // A key can be bound by name and by position
+                let mut inputs = vec![Binding::Physical(*scancode)];
+                if let Some(key) = virtual_keycode {
+                    inputs.push(Binding::Key(*key));
+                }
+
+                for input in inputs {
+                    self.set_input(input, *state == ElementState::Pressed);
+                }
+                true
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.set_input(Binding::Mouse(*button), *state == ElementState::Pressed);
+                true
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let amount = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                    MouseScrollDelta::PixelDelta(position) => position.y,
+                };
+
+                // A scroll is pressed and released at once
+                let direction = if amount > 0.0 { ScrollDirection::Up } else { ScrollDirection::Down };
+                if amount != 0.0 {
+                    self.set_input(Binding::Scroll(direction), true);
+                    self.set_input(Binding::Scroll(direction), false);
+                }
+                true
+            }
+            // Keys that are released while the window is not focused are never seen
+            WindowEvent::Focused(false) => {
+                for input in self.held_inputs.clone() {
+                    self.set_input(input, false);
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    pub fn device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.mouse_delta.0 += delta.0;
+            self.mouse_delta.1 += delta.1;
+        }
+    }
+
+    fn set_input(&mut self, input: Binding, down: bool) {
+        if down {
+            // Held keys repeat, those are not new presses
+            if !self.held_inputs.insert(input) {
+                return
+            }
+
+            for action in self.bindings.actions_for(input) {
+                // Only if no other binding of the action was held already
+                let others_held = self.bindings.get(action).iter().any(|b| *b != input && self.held_inputs.contains(b));
+                if !others_held {
+                    self.pressed.insert(action);
+                }
+            }
+        } else {
+            if !self.held_inputs.remove(&input) {
+                return
+            }
+
+            for action in self.bindings.actions_for(input) {
+                if !self.held(action) {
+                    self.released.insert(action);
+                }
+            }
+        }
+    }
+
+    /// Whether the action started since the last frame
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether any binding of the action is down
+    pub fn held(&self, action: Action) -> bool {
+        self.bindings.get(action).iter().any(|b| self.held_inputs.contains(b))
+    }
+
+    /// Whether the action stopped since the last frame
+    #[allow(dead_code)]
+    pub fn released(&self, action: Action) -> bool {
+        self.released.contains(&action)
+    }
+
+    /// How far the mouse moved since the last frame
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    /// Forgets what was pressed and released this frame, called after everything is updated
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = (0.0, 0.0);
+    }
+}
@@ -0,0 +1,5 @@
+//! Maps keys, mouse buttons and the scroll wheel to actions
+
+pub mod action;
+pub mod binding;
+pub mod inputmanager;
\ No newline at end of file
@@ -0,0 +1,12 @@
+// Modules are named after their main type (chunk::chunk::Chunk) and
+// constructors are `new` even where there is nothing to pass
+#![allow(clippy::module_inception, clippy::new_without_default)]
+
+pub mod render;
+pub mod world;
+pub mod helper;
+pub mod game;
+pub mod resources;
+pub mod input;
+pub mod settings;
+pub mod server;
\ No newline at end of file
@@ -1,14 +1,80 @@
-mod render;
-mod world;
-mod helper;
-mod game;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
 
-use crate::render::low::context::Context;
-use crate::game::state::{mainstate::MainState, State};
+use ludwig_world_3d::render::low::context::Context;
+use ludwig_world_3d::game::state::{State, mainmenu::MainMenuState, loading::LoadingState};
+use ludwig_world_3d::resources::resourcemanager::ResourceManager;
+use ludwig_world_3d::world::{block::{registry::BlockRegistry, colors::BlockColors}, level::{clean_world_name, new_world_name}, structure::registry::StructureRegistry};
+use ludwig_world_3d::input::binding::{Bindings, BINDINGS_FILE};
+use ludwig_world_3d::settings::{
+    settings::{Settings, SETTINGS_FILE, config_directory},
+    args::{Args, USAGE},
+};
 
-fn main() {    
-    let mut context = Context::new(String::from("Ludwig World 3D"), [1200, 800]);
-    let state = MainState::new(&mut context.renderer);
+fn main() -> Result<()> {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return Ok(())
+    }
 
-    context.run(state);
+    let config = config_directory();
+    let settings_path = args.settings.clone().unwrap_or_else(|| config.join(SETTINGS_FILE));
+
+    let mut settings = Settings::load_or_create(&settings_path).unwrap_or_else(|e| {
+        println!("Using the default settings: {:?}", e);
+        Settings::default()
+    });
+    args.apply(&mut settings);
+    // Printed so a bug report can include the exact settings
+    println!("Settings from {}: {:?}", settings_path.display(), settings);
+
+    let resources = ResourceManager::from_env()?;
+    BlockRegistry::load(&resources)?.install()?;
+    BlockColors::load(&resources)?.install()?;
+    StructureRegistry::load(&resources)?.install()?;
+
+    let bindings = Bindings::load_or_create(&bindings_path(&config)).unwrap_or_else(|e| {
+        println!("Using the default key bindings: {:?}", e);
+        Bindings::defaults()
+    });
+
+    let initial: Box<dyn State> = match (&args.world, &args.connect) {
+        (_, Some(address)) => Box::new(LoadingState::connect(address.clone(), settings.name.clone())),
+        (Some(name), None) => {
+            let name = match clean_world_name(name) {
+                name if name.is_empty() => new_world_name(),
+                name => name,
+            };
+            Box::new(LoadingState::new(name, settings.new_world_seed()))
+        }
+        (None, None) => Box::new(MainMenuState::new()),
+    };
+
+    let context = Context::new(String::from("Ludwig World 3D"), resources, bindings, settings, settings_path)?;
+    context.run(initial);
+
+    Ok(())
+}
+
+/// Bindings used to be kept in the current directory, those are moved to the config directory
+fn bindings_path(config: &Path) -> PathBuf {
+    let path = config.join(BINDINGS_FILE);
+    let legacy = Path::new(BINDINGS_FILE);
+
+    if !path.exists() && legacy.is_file() {
+        let moved = std::fs::create_dir_all(config).and_then(|_| std::fs::rename(legacy, &path));
+        if let Err(e) = moved {
+            println!("Could not move {} to {}: {:?}", legacy.display(), path.display(), e);
+            return legacy.to_path_buf()
+        }
+    }
+
+    path
 }
\ No newline at end of file
@@ -1,9 +1,8 @@
 use cgmath::*;
-use winit::event::*;
-use winit::dpi::LogicalPosition;
 use std::f32::consts::FRAC_PI_2;
 
 use crate::render::low::uniforms::{Uniform, CameraUniform};
+use crate::input::{action::Action, inputmanager::InputManager};
 
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
@@ -13,34 +12,35 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
+/// How far the camera turns for every unit the mouse moves, at a sensitivity of 1
+const RADIANS_PER_MOUSE_UNIT: f32 = 0.003;
+
 pub struct Camera {
     pub view: CameraView,
     pub controller: CameraController,
     pub projection: Projection,
     pub uniform: Uniform<CameraUniform>,
-
-    pub mouse_pressed: bool,
 }
 
 impl Camera {
-    pub fn new(device: &wgpu::Device, width: u32, height: u32, startpos: Point3<f32>) -> Self {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, startpos: Point3<f32>, fov: Deg<f32>, speed: f32) -> Self {
         let view = CameraView {
             position: startpos,
-            pitch: cgmath::Rad(0.785398163),
-            yaw: cgmath::Rad(0.785398163),
+            pitch: cgmath::Rad(std::f32::consts::FRAC_PI_4),
+            yaw: cgmath::Rad(std::f32::consts::FRAC_PI_4),
         };
-        let controller = CameraController::new(8.0, 0.8);
+        let controller = CameraController::new(speed, 0.8);
 
         let projection = Projection {
             aspect: width as f32 / height as f32,
-            fovy: cgmath::Rad(0.785398163),
+            fovy: fov.into(),
             znear: 0.1,
             zfar: 100.0,
         };
 
         // Camera uniform setup
         let uniform_data = CameraUniform::new();
-        let uniform = Uniform::new(&device, uniform_data, 0, 0); // At binding 0
+        let uniform = Uniform::new(device, uniform_data, 0, 0); // At binding 0
 
         
         Self {
@@ -48,48 +48,43 @@ impl Camera {
             controller,
             projection,
             uniform,
-
-            mouse_pressed: false,
         }
     }
 
     pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
-        return self.projection.calc_matrix() * self.view.calc_matrix();
+        self.projection.calc_matrix() * self.view.calc_matrix()
     }
 
-    pub fn update(&mut self, queue: &wgpu::Queue) {
-        self.uniform.data.update_view_proj(self.build_view_projection_matrix());
-        self.uniform.update(queue);
+    /// The distances of the near and far plane
+    pub fn depth_range(&self) -> (f32, f32) {
+        (self.projection.znear, self.projection.zfar)
     }
 
-    pub fn input(&mut self, event: &DeviceEvent) -> bool {
-        match event {
-            DeviceEvent::Key(
-                KeyboardInput {
-                    virtual_keycode: Some(key),
-                    state,
-                    ..
-                }
-            ) => self.controller.process_keyboard(*key, *state),
-            DeviceEvent::MouseWheel { delta, .. } => {
-                self.controller.process_scroll(delta);
-                true
-            }
-            DeviceEvent::Button {
-                button: 1, // Left Mouse Button
-                state,
-            } => {
-                self.mouse_pressed = *state == ElementState::Pressed;
-                true
-            }
-            DeviceEvent::MouseMotion { delta } => {
-                if self.mouse_pressed {
-                    self.controller.process_mouse(delta.0, delta.1);
-                }
-                true
-            }
-            _ => false,
+    /// The corners of the part of the view frustum between the near and far
+    /// distance, in world space
+    pub fn frustum_corners(&self, near: f32, far: f32) -> [Point3<f32>; 8] {
+        let projection = perspective(self.projection.fovy, self.projection.aspect, near, far);
+        let inverse = (projection * self.view.calc_matrix()).invert().unwrap_or(Matrix4::identity());
+
+        let mut corners = [Point3::new(0.0, 0.0, 0.0); 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            // The corners of the OpenGL clip space cube
+            let ndc = Vector4::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+                1.0,
+            );
+            let world = inverse * ndc;
+            *corner = Point3::from_vec(world.truncate() / world.w);
         }
+
+        corners
+    }
+
+    pub fn update(&mut self, queue: &wgpu::Queue) {
+        self.uniform.data.update_view_proj(self.build_view_projection_matrix());
+        self.uniform.update(queue);
     }
 }
 
@@ -105,6 +100,14 @@ impl Projection {
     pub fn resize(&mut self, width: u32, height: u32) {
         self.aspect = width as f32 / height as f32;
     }
+
+    pub fn set_zfar(&mut self, zfar: f32) {
+        self.zfar = zfar;
+    }
+
+    pub fn set_fovy(&mut self, fovy: Deg<f32>) {
+        self.fovy = fovy.into();
+    }
     
     pub fn calc_matrix(&self) -> Matrix4<f32> {
         OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
@@ -121,14 +124,29 @@ impl CameraView {
     pub fn calc_matrix(&self) -> cgmath::Matrix4<f32> {
         Matrix4::look_at_dir(
             self.position,
-            Vector3::new(
-                self.yaw.0.cos(),
-                self.pitch.0.sin(),
-                self.yaw.0.sin(),
-            ).normalize(),
+            self.direction(),
             Vector3::unit_y(),
         )
     }
+
+    /// The direction the camera looks in, normalized
+    pub fn direction(&self) -> Vector3<f32> {
+        Vector3::new(
+            self.yaw.0.cos(),
+            self.pitch.0.sin(),
+            self.yaw.0.sin(),
+        ).normalize()
+    }
+
+    /// Yaw and pitch in degrees
+    pub fn angles(&self) -> (f32, f32) {
+        (Deg::from(self.yaw).0, Deg::from(self.pitch).0)
+    }
+
+    pub fn set_angles(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = Deg(yaw).into();
+        self.pitch = Deg(pitch).into();
+    }
 }
 
 pub struct CameraController {
@@ -162,51 +180,53 @@ impl CameraController {
         }
     }
 
-    pub fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
-        let amount = if state == ElementState::Pressed { 1.0 } else { 0.0 };
-        match key {
-            VirtualKeyCode::W | VirtualKeyCode::Up => {
-                self.amount_forward = amount;
-                true
-            }
-            VirtualKeyCode::S | VirtualKeyCode::Down => {
-                self.amount_backward = amount;
-                true
-            }
-            VirtualKeyCode::A | VirtualKeyCode::Left => {
-                self.amount_left = amount;
-                true
-            }
-            VirtualKeyCode::D | VirtualKeyCode::Right => {
-                self.amount_right = amount;
-                true
-            }
-            VirtualKeyCode::Space => {
-                self.amount_up = amount;
-                true
-            }
-            VirtualKeyCode::LShift => {
-                self.amount_down = amount;
-                true
-            }
-            _ => false,
+    /// In blocks per second
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Moves according to the actions that are held
+    pub fn process_input(&mut self, input: &InputManager) {
+        let held = |action| if input.held(action) { 1.0 } else { 0.0 };
+
+        self.amount_forward = held(Action::MoveForward);
+        self.amount_backward = held(Action::MoveBackward);
+        self.amount_left = held(Action::MoveLeft);
+        self.amount_right = held(Action::MoveRight);
+        self.amount_up = held(Action::Jump);
+        self.amount_down = held(Action::Sneak);
+
+        let mouse = &input.bindings.mouse;
+        let (dx, dy) = input.mouse_delta();
+        let dy = if mouse.invert_y { -dy } else { dy };
+        self.process_mouse(dx * mouse.sensitivity as f64, dy * mouse.sensitivity as f64);
+
+        // A step of the scroll wheel is about 100 pixels
+        if input.pressed(Action::ZoomIn) {
+            self.scroll += 100.0;
+        }
+        if input.pressed(Action::ZoomOut) {
+            self.scroll -= 100.0;
         }
     }
 
+    /// Stops all movement, for when the camera should not respond to input
+    pub fn reset(&mut self) {
+        self.amount_forward = 0.0;
+        self.amount_backward = 0.0;
+        self.amount_left = 0.0;
+        self.amount_right = 0.0;
+        self.amount_up = 0.0;
+        self.amount_down = 0.0;
+        self.rotate_horizontal = 0.0;
+        self.rotate_vertical = 0.0;
+        self.scroll = 0.0;
+    }
+
+    /// Adds up mouse movement until the camera is updated
     pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
-        self.rotate_horizontal = mouse_dx as f32;
-        self.rotate_vertical = mouse_dy as f32;
-    }
-
-    pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
-        self.scroll = -match delta {
-            // I'm assuming a line is about 100 pixels
-            MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
-            MouseScrollDelta::PixelDelta(LogicalPosition {
-                y: scroll,
-                ..
-            }) => *scroll as f32,
-        };
+        self.rotate_horizontal += mouse_dx as f32;
+        self.rotate_vertical += mouse_dy as f32;
     }
 
     pub fn update_camera(&mut self, camera: &mut CameraView, dt: std::time::Duration) {
@@ -232,13 +252,12 @@ impl CameraController {
         // modify the y coordinate directly.
         camera.position.y += (self.amount_up - self.amount_down) * self.speed * dt;
 
-        // Rotate
-        camera.yaw += Rad(self.rotate_horizontal) * self.sensitivity * dt;
-        camera.pitch += Rad(-self.rotate_vertical) * self.sensitivity * dt;
+        // Rotate. The mouse movement is a distance already, so it is not
+        // scaled by dt. That would make turning depend on the frame rate.
+        camera.yaw += Rad(self.rotate_horizontal * RADIANS_PER_MOUSE_UNIT);
+        camera.pitch += Rad(-self.rotate_vertical * RADIANS_PER_MOUSE_UNIT);
 
-        // If process_mouse isn't called every frame, these values
-        // will not get set to zero, and the camera will rotate
-        // when moving in a non cardinal direction.
+        // The movement has been used up
         self.rotate_horizontal = 0.0;
         self.rotate_vertical = 0.0;
 
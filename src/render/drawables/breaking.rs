@@ -0,0 +1,75 @@
+use crate::render::low::{
+    renderer::Renderer,
+    textures::TextureTile,
+    uniforms::{Uniform, BreakingUniform},
+};
+use crate::world::chunk::pos::WorldCoord;
+
+use super::Drawable;
+
+/// The crack textures are a row of stages in the block atlas, from barely to almost broken
+pub const CRACK_STAGES: u32 = 10;
+const CRACK_ROW: u32 = 15;
+
+/// The uniform for the cracks, it is kept by the renderer
+pub fn breaking_uniform(device: &wgpu::Device) -> Uniform<BreakingUniform> {
+    Uniform::new(device, BreakingUniform::new(), 0, 1)
+}
+
+/// Cracks on the face of the block that is being broken. There is only
+/// one block being broken at a time, its face is in renderer.breaking.
+pub struct BreakingDrawable {
+    visible: bool,
+}
+
+impl BreakingDrawable {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+        }
+    }
+
+    /// Shows the cracks for `progress` between 0 and 1 on the face of `block`
+    /// that `normal` points out of, or hides them
+    pub fn set(&mut self, renderer: &mut Renderer, breaking: Option<(WorldCoord, [i64; 3], f32)>) {
+        self.visible = breaking.is_some();
+
+        if let Some((block, normal, progress)) = breaking {
+            let stage = ((progress * CRACK_STAGES as f32) as u32).min(CRACK_STAGES - 1);
+            let layer = TextureTile::new(stage, CRACK_ROW).index();
+
+            renderer.breaking.data = BreakingUniform {
+                block: [block.x as f32, block.y as f32, block.z as f32, layer as f32],
+                normal: [normal[0] as f32, normal[1] as f32, normal[2] as f32, 0.0],
+            };
+            renderer.breaking.update(&renderer.queue);
+        }
+    }
+}
+
+impl Drawable for BreakingDrawable {
+    fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.decal_pipeline(
+            renderer.shader("breaking.vert", wgpu::include_spirv!("../low/shaders/breaking.vert.spv")),
+            renderer.shader("breaking.frag", wgpu::include_spirv!("../low/shaders/breaking.frag.spv")),
+            &[
+                &renderer.camera.uniform.uniform_bind_group_layout, // set = 0
+                &renderer.breaking.uniform_bind_group_layout, // set = 1
+                &renderer.textures.block_texture_bind_group_layout, // set = 2
+            ],
+        )
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
+        if !self.visible {
+            return
+        }
+
+        pass.set_pipeline(renderer.get_pipeline::<Self>());
+        pass.set_bind_group(renderer.camera.uniform.index, &renderer.camera.uniform.uniform_bind_group, &[]); // Camera
+        pass.set_bind_group(renderer.breaking.index, &renderer.breaking.uniform_bind_group, &[]); // Breaking face
+        pass.set_bind_group(2, renderer.textures.get_block_bind_group(), &[]); // Block textures
+
+        pass.draw(0..6, 0..1);
+    }
+}
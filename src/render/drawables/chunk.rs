@@ -2,7 +2,7 @@ use crate::render::{
     low::{
         buffer::DynamicBuffer,
         renderer::Renderer,
-        vertex,
+        vertex::ChunkVertex,
     },
     meshing::chunkmeshing::ChunkMesh,
 };
@@ -10,7 +10,7 @@ use crate::world::chunk::pos::ChunkPos;
 use super::Drawable;
 
 pub struct ChunkDrawable {
-    vertex_buffer: DynamicBuffer<vertex::Vertex>,
+    vertex_buffer: DynamicBuffer<ChunkVertex>,
     index_buffer: DynamicBuffer<u32>,
     pos: ChunkPos,
 }
@@ -36,30 +36,41 @@ impl ChunkDrawable {
         }
     }
 
+    /// Uploads the mesh. The packed vertices are copied to the gpu as they are
     pub fn from_chunk_mesh(&mut self, mesh: &ChunkMesh, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
         self.vertex_buffer.insert_back(
             device, 
             encoder,
-            &mesh.to_vertex_array().to_vertices(),
+            mesh.vertices(),
         );
 
         self.index_buffer.insert_back(
             device, 
             encoder,
-            &mesh.to_vertex_array().to_indices(),
+            mesh.indices(),
         );
     }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_buffer.len
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.index_buffer.len
+    }
 }
 
 impl Drawable for ChunkDrawable {
     fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
         renderer.default_pipeline(
-            wgpu::include_spirv!("../low/shaders/chunk.vert.spv"),
-            wgpu::include_spirv!("../low/shaders/chunk.frag.spv"), 
+            renderer.shader("chunk.vert", wgpu::include_spirv!("../low/shaders/chunk.vert.spv")),
+            renderer.shader("chunk.frag", wgpu::include_spirv!("../low/shaders/chunk.frag.spv")),
+            &[ChunkVertex::desc()],
             &[
                 &renderer.camera.uniform.uniform_bind_group_layout, // set = 0
-                &renderer.textures.texture_bind_group_layout, // set = 1
+                &renderer.textures.block_texture_bind_group_layout, // set = 1
                 &renderer.chunkpos_uniform.uniform_bind_group_layout, // set = 2
+                &renderer.shadows.lighting_bind_group_layout, // set = 3
             ],
         )
     }
@@ -67,15 +78,47 @@ impl Drawable for ChunkDrawable {
     fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
         pass.set_pipeline(renderer.get_pipeline::<Self>());
         pass.set_bind_group(renderer.camera.uniform.index, &renderer.camera.uniform.uniform_bind_group, &[]); // Camera
-        pass.set_bind_group(1, renderer.textures.get_bind_group(), &[]); // Texture
+        pass.set_bind_group(1, renderer.textures.get_block_bind_group(), &[]); // Texture
         
         // Set correct chunkpos uniform
         let a = renderer.chunkpos_uniform.offset.get(&self.pos).unwrap() * wgpu::BIND_BUFFER_ALIGNMENT as u32;
         pass.set_bind_group(renderer.chunkpos_uniform.index, &renderer.chunkpos_uniform.uniform_bind_group, &[a]);
+        pass.set_bind_group(3, &renderer.shadows.lighting_bind_group, &[]); // Sky light, fog and shadows
 
         // Draw
         pass.set_vertex_buffer(0, self.vertex_buffer.get_buffer().slice(..));
         pass.set_index_buffer(self.index_buffer.get_buffer().slice(..), wgpu::IndexFormat::Uint32);
         pass.draw_indexed(0..self.index_buffer.len as u32, 0, 0..1);
     }
-}
\ No newline at end of file
+
+    /// Uses the same buffers as the normal pass, only the depth is drawn
+    fn draw_shadow<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer, cascade: u32) {
+        pass.set_pipeline(renderer.get_pipeline::<ChunkShadow>());
+
+        let cascade_offset = renderer.shadows.cascades.offset[&cascade] * wgpu::BIND_BUFFER_ALIGNMENT as u32;
+        pass.set_bind_group(0, &renderer.shadows.cascades.uniform_bind_group, &[cascade_offset]);
+
+        let a = renderer.chunkpos_uniform.offset.get(&self.pos).unwrap() * wgpu::BIND_BUFFER_ALIGNMENT as u32;
+        pass.set_bind_group(1, &renderer.chunkpos_uniform.uniform_bind_group, &[a]);
+
+        pass.set_vertex_buffer(0, self.vertex_buffer.get_buffer().slice(..));
+        pass.set_index_buffer(self.index_buffer.get_buffer().slice(..), wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..self.index_buffer.len as u32, 0, 0..1);
+    }
+}
+
+/// The depth only pipeline chunks are drawn into the shadow maps with
+pub struct ChunkShadow;
+
+impl ChunkShadow {
+    pub fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.depth_only_pipeline(
+            renderer.shader("shadow.vert", wgpu::include_spirv!("../low/shaders/shadow.vert.spv")),
+            &[ChunkVertex::desc()],
+            &[
+                &renderer.shadows.cascades.uniform_bind_group_layout, // set = 0
+                &renderer.chunkpos_uniform.uniform_bind_group_layout, // set = 1
+            ],
+        )
+    }
+}
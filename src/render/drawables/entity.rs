@@ -0,0 +1,85 @@
+use cgmath::Point3;
+
+use crate::render::low::{
+    buffer::DynamicBuffer,
+    renderer::Renderer,
+    vertex::EntityInstance,
+};
+use crate::world::entity::entity::Model;
+
+use super::Drawable;
+
+/// Vertices of the cube every entity is drawn with, see entity.vert
+const CUBE_VERTICES: u32 = 36;
+
+/// Every visible entity and player, drawn as instances of one textured cube
+pub struct EntityDrawable {
+    instance_buffer: DynamicBuffer<EntityInstance>,
+}
+
+impl EntityDrawable {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            instance_buffer: DynamicBuffer::new(64, device, wgpu::BufferUsage::VERTEX),
+        }
+    }
+
+    /// Replaces the instances with the boxes of this frame: where their feet are,
+    /// their yaw and how they look
+    pub fn upload(
+        &mut self,
+        renderer: &Renderer,
+        encoder: &mut wgpu::CommandEncoder,
+        models: impl Iterator<Item = (Point3<f32>, f32, Model)>,
+    ) {
+        let instances: Vec<EntityInstance> = models
+            .map(|(position, yaw, model)| {
+                let mut textures = [0; 6];
+                for (layer, tile) in textures.iter_mut().zip(model.textures.iter()) {
+                    *layer = tile.index();
+                }
+
+                EntityInstance {
+                    position: position.into(),
+                    size: model.size.into(),
+                    yaw,
+                    textures,
+                }
+            })
+            .collect();
+
+        self.instance_buffer.len = 0;
+        if !instances.is_empty() {
+            self.instance_buffer.insert_back(&renderer.device, encoder, &instances);
+        }
+    }
+}
+
+impl Drawable for EntityDrawable {
+    fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.default_pipeline(
+            renderer.shader("entity.vert", wgpu::include_spirv!("../low/shaders/entity.vert.spv")),
+            renderer.shader("entity.frag", wgpu::include_spirv!("../low/shaders/entity.frag.spv")),
+            &[EntityInstance::desc()],
+            &[
+                &renderer.camera.uniform.uniform_bind_group_layout, // set = 0
+                &renderer.textures.block_texture_bind_group_layout, // set = 1
+                &renderer.shadows.lighting_bind_group_layout, // set = 2
+            ],
+        )
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
+        if self.instance_buffer.len == 0 {
+            return
+        }
+
+        pass.set_pipeline(renderer.get_pipeline::<Self>());
+        pass.set_bind_group(renderer.camera.uniform.index, &renderer.camera.uniform.uniform_bind_group, &[]); // Camera
+        pass.set_bind_group(1, renderer.textures.get_block_bind_group(), &[]); // Block textures
+        pass.set_bind_group(2, &renderer.shadows.lighting_bind_group, &[]); // Sky light and fog
+
+        pass.set_vertex_buffer(0, self.instance_buffer.get_buffer().slice(..));
+        pass.draw(0..CUBE_VERTICES, 0..self.instance_buffer.len as u32);
+    }
+}
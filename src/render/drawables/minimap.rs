@@ -0,0 +1,141 @@
+use cgmath::{InnerSpace, Vector2};
+use std::time::{Duration, Instant};
+
+use crate::render::low::{
+    renderer::Renderer,
+    textures::Texture,
+    uniforms::{Uniform, MinimapUniform},
+};
+use crate::world::{
+    block::colors::block_colors,
+    chunk::{chunkstore::ChunkStore, pos::WorldCoord},
+    topdown::render_map,
+};
+
+use super::Drawable;
+
+/// Blocks the minimap shows on every side of the player
+pub const MINIMAP_RADIUS: u32 = 64;
+/// Width and height on the screen in pixels
+const MINIMAP_SIZE: f32 = 192.0;
+/// From the top right corner of the screen in pixels
+const MINIMAP_MARGIN: f32 = 12.0;
+/// The map is rendered at most this often while the player moves
+const MOVING_REFRESH: Duration = Duration::from_millis(100);
+/// and this often when the player stands still, so changed blocks show up
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// The uniform for the minimap, it is kept by the renderer
+pub fn minimap_uniform(device: &wgpu::Device) -> Uniform<MinimapUniform> {
+    Uniform::new(device, MinimapUniform::new(), 0, 0)
+}
+
+/// The loaded chunks around the player seen from above, in the top right
+/// corner of the screen. Colored the same way as the maps of ludwig-map,
+/// see topdown.
+pub struct MinimapDrawable {
+    texture: Texture,
+    bind_group: wgpu::BindGroup,
+    /// The block at the center when the map was rendered last
+    center: Option<[i64; 2]>,
+    rendered: Instant,
+    pub visible: bool,
+}
+
+impl MinimapDrawable {
+    pub fn new(renderer: &Renderer) -> Self {
+        let size = MINIMAP_RADIUS * 2;
+        let empty = image::DynamicImage::new_rgba8(size, size);
+        let texture = Texture::from_image(&renderer.device, &renderer.queue, &empty, Some("Minimap"));
+
+        let bind_group = renderer.device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                layout: &renderer.textures.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                    }
+                ],
+                label: Some("minimap_bind_group"),
+            }
+        );
+
+        Self {
+            texture,
+            bind_group,
+            center: None,
+            rendered: Instant::now(),
+            visible: true,
+        }
+    }
+
+    /// Renders the map again around the camera when it moved or some time passed,
+    /// and keeps the map in its corner when the window is resized
+    pub fn update(&mut self, renderer: &mut Renderer, chunks: &ChunkStore) {
+        if !self.visible {
+            return
+        }
+
+        let position = WorldCoord::from_point(renderer.camera.view.position);
+        let center = [position.x, position.z];
+        let elapsed = self.rendered.elapsed();
+        let moved = self.center != Some(center);
+
+        if (moved && elapsed >= MOVING_REFRESH) || elapsed >= REFRESH || self.center.is_none() {
+            let radius = MINIMAP_RADIUS as i64;
+            let size = MINIMAP_RADIUS * 2;
+            let map = render_map(chunks, block_colors(), [center[0] - radius, center[1] - radius], [size, size]);
+
+            self.texture.write(&renderer.queue, &map);
+            self.center = Some(center);
+            self.rendered = Instant::now();
+        }
+
+        let (width, height) = (renderer.size.width.max(1) as f32, renderer.size.height.max(1) as f32);
+        let direction = renderer.camera.view.direction();
+        // Straight up or down there is no heading
+        let heading = Vector2::new(direction.x, direction.z);
+        let heading = if heading.magnitude2() > 0.0 { heading.normalize() } else { heading };
+
+        renderer.minimap.data = MinimapUniform {
+            rect: [
+                1.0 - 2.0 * (MINIMAP_MARGIN + MINIMAP_SIZE) / width,
+                1.0 - 2.0 * MINIMAP_MARGIN / height,
+                1.0 - 2.0 * MINIMAP_MARGIN / width,
+                1.0 - 2.0 * (MINIMAP_MARGIN + MINIMAP_SIZE) / height,
+            ],
+            heading: [heading.x, heading.y, (MINIMAP_RADIUS * 2) as f32, 0.0],
+        };
+        renderer.minimap.update(&renderer.queue);
+    }
+}
+
+impl Drawable for MinimapDrawable {
+    fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.hud_pipeline(
+            renderer.shader("minimap.vert", wgpu::include_spirv!("../low/shaders/minimap.vert.spv")),
+            renderer.shader("minimap.frag", wgpu::include_spirv!("../low/shaders/minimap.frag.spv")),
+            &[
+                &renderer.minimap.uniform_bind_group_layout, // set = 0
+                &renderer.textures.texture_bind_group_layout, // set = 1
+            ],
+        )
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
+        if !self.visible {
+            return
+        }
+
+        pass.set_pipeline(renderer.get_pipeline::<Self>());
+        pass.set_bind_group(renderer.minimap.index, &renderer.minimap.uniform_bind_group, &[]); // Position
+        pass.set_bind_group(1, &self.bind_group, &[]); // Map
+
+        pass.draw(0..6, 0..1);
+    }
+}
@@ -1,5 +1,11 @@
 pub mod chunk;
 pub mod texture_vertex;
+pub mod sky;
+pub mod ui;
+pub mod breaking;
+pub mod entity;
+pub mod selection;
+pub mod minimap;
 
 use wgpu::{RenderPipeline, RenderPass};
 
@@ -12,4 +18,8 @@ pub trait Drawable {
         where Self: Sized;
         
     fn draw<'a>(&'a self, pass: &mut RenderPass<'a>, renderer: &'a Renderer);
+
+    /// Draws the depth of the drawable into the shadow map of a cascade.
+    /// Drawables that don't cast shadows don't implement this.
+    fn draw_shadow<'a>(&'a self, _pass: &mut RenderPass<'a>, _renderer: &'a Renderer, _cascade: u32) {}
 }
\ No newline at end of file
@@ -0,0 +1,75 @@
+use crate::render::low::{
+    renderer::Renderer,
+    uniforms::{Uniform, SelectionUniform},
+};
+use crate::world::chunk::pos::WorldCoord;
+
+use super::Drawable;
+
+/// Two vertices for each of the twelve edges of a box, see selection.vert
+const BOX_VERTICES: u32 = 24;
+
+/// The uniform for the selection, it is kept by the renderer
+pub fn selection_uniform(device: &wgpu::Device) -> Uniform<SelectionUniform> {
+    Uniform::new(device, SelectionUniform::new(), 0, 1)
+}
+
+/// The edges of the selected corner blocks and of the box between them.
+/// The corners are in renderer.selection.
+pub struct SelectionDrawable {
+    /// Which boxes are drawn: 0 is the box between the corners, 1 and 2 the corners
+    boxes: std::ops::Range<u32>,
+}
+
+impl SelectionDrawable {
+    pub fn new() -> Self {
+        Self {
+            boxes: 0..0,
+        }
+    }
+
+    /// Shows the corners that are selected, the box between them once both are
+    pub fn set(&mut self, renderer: &mut Renderer, first: Option<WorldCoord>, second: Option<WorldCoord>) {
+        self.boxes = match (first, second) {
+            (Some(_), Some(_)) => 0..3,
+            (Some(_), None) => 1..2,
+            (None, Some(_)) => 2..3,
+            (None, None) => 0..0,
+        };
+
+        let corner = |coord: Option<WorldCoord>| match coord {
+            Some(coord) => [coord.x as f32, coord.y as f32, coord.z as f32, 0.0],
+            None => [0.0; 4],
+        };
+        renderer.selection.data = SelectionUniform {
+            first: corner(first),
+            second: corner(second),
+        };
+        renderer.selection.update(&renderer.queue);
+    }
+}
+
+impl Drawable for SelectionDrawable {
+    fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.line_pipeline(
+            renderer.shader("selection.vert", wgpu::include_spirv!("../low/shaders/selection.vert.spv")),
+            renderer.shader("selection.frag", wgpu::include_spirv!("../low/shaders/selection.frag.spv")),
+            &[
+                &renderer.camera.uniform.uniform_bind_group_layout, // set = 0
+                &renderer.selection.uniform_bind_group_layout, // set = 1
+            ],
+        )
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
+        if self.boxes.is_empty() {
+            return
+        }
+
+        pass.set_pipeline(renderer.get_pipeline::<Self>());
+        pass.set_bind_group(renderer.camera.uniform.index, &renderer.camera.uniform.uniform_bind_group, &[]); // Camera
+        pass.set_bind_group(renderer.selection.index, &renderer.selection.uniform_bind_group, &[]); // Corners
+
+        pass.draw(0..BOX_VERTICES, self.boxes.clone());
+    }
+}
\ No newline at end of file
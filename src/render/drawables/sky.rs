@@ -0,0 +1,35 @@
+use crate::render::low::renderer::Renderer;
+use super::Drawable;
+
+/// The sky gradient with the sun and moon. Covers the whole screen and
+/// should be drawn before anything else.
+pub struct SkyDrawable;
+
+impl Drawable for SkyDrawable {
+    fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.pipeline(
+            renderer.shader("sky.vert", wgpu::include_spirv!("../low/shaders/sky.vert.spv")),
+            renderer.shader("sky.frag", wgpu::include_spirv!("../low/shaders/sky.frag.spv")),
+            &[],
+            &[
+                &renderer.sky.uniform.uniform_bind_group_layout, // set = 0
+            ],
+            // Everything that is drawn later is in front of the sky
+            wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            },
+        )
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
+        pass.set_pipeline(renderer.get_pipeline::<Self>());
+        pass.set_bind_group(0, &renderer.sky.uniform.uniform_bind_group, &[]); // Sky
+
+        pass.draw(0..3, 0..1);
+    }
+}
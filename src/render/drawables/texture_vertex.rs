@@ -55,8 +55,9 @@ impl TextureVertex {
 impl Drawable for TextureVertex {
     fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
         renderer.default_pipeline(
-            wgpu::include_spirv!("../low/shaders/texture_vertex.vert.spv"),
-            wgpu::include_spirv!("../low/shaders/texture_vertex.frag.spv"), 
+            renderer.shader("texture_vertex.vert", wgpu::include_spirv!("../low/shaders/texture_vertex.vert.spv")),
+            renderer.shader("texture_vertex.frag", wgpu::include_spirv!("../low/shaders/texture_vertex.frag.spv")),
+            &[vertex::Vertex::desc()],
             &[
                 &renderer.camera.uniform.uniform_bind_group_layout, // set = 0
                 &renderer.textures.texture_bind_group_layout, // set = 1
@@ -0,0 +1,152 @@
+use crate::render::low::{
+    renderer::Renderer,
+    buffer::DynamicBuffer,
+    textures::Texture,
+    uniforms::{Uniform, UiUniform},
+    vertex::UiVertex,
+};
+
+use super::Drawable;
+
+/// The GPU side of the UI: the font atlas and the mesh of the current frame.
+/// Drawn without depth test after everything else, see Renderer::render_ui.
+pub struct UiDrawable {
+    vertex_buffer: DynamicBuffer<UiVertex>,
+    index_buffer: DynamicBuffer<u32>,
+    pub projection: Uniform<UiUniform>,
+
+    pub font_bind_group_layout: wgpu::BindGroupLayout,
+    font_bind_group: Option<wgpu::BindGroup>,
+}
+
+impl UiDrawable {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let font_bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float {filterable: true,},
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler {
+                            comparison: false,
+                            filtering: true,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("font_bind_group_layout"),
+            }
+        );
+
+        Self {
+            vertex_buffer: DynamicBuffer::new(4000, device, wgpu::BufferUsage::VERTEX),
+            index_buffer: DynamicBuffer::new(6000, device, wgpu::BufferUsage::INDEX),
+            projection: Uniform::new(device, UiUniform::new(1.0, 1.0), 0, 0),
+
+            font_bind_group_layout,
+            font_bind_group: None,
+        }
+    }
+
+    /// Uploads a rasterized font, see Font::rasterize
+    pub fn set_font_atlas(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, atlas: image::RgbaImage) {
+        let texture = Texture::from_image(device, queue, &image::DynamicImage::ImageRgba8(atlas), Some("Font atlas"));
+
+        // Text is scaled from the size it was rasterized at, so it is filtered
+        let sampler = device.create_sampler(
+            &wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }
+        );
+
+        self.font_bind_group = Some(device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                layout: &self.font_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    }
+                ],
+                label: Some("font_bind_group"),
+            }
+        ));
+    }
+
+    /// Replaces the mesh with the one of this frame
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        vertices: &[UiVertex],
+        indices: &[u32],
+        screen: [f32; 2],
+    ) {
+        self.projection.data = UiUniform::new(screen[0], screen[1]);
+        self.projection.update(queue);
+
+        self.vertex_buffer.len = 0;
+        self.index_buffer.len = 0;
+
+        if !indices.is_empty() {
+            self.vertex_buffer.insert_back(device, encoder, vertices);
+            self.index_buffer.insert_back(device, encoder, indices);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index_buffer.len == 0
+    }
+}
+
+impl Drawable for UiDrawable {
+    fn create_pipeline(renderer: &Renderer) -> wgpu::RenderPipeline {
+        renderer.overlay_pipeline(
+            renderer.shader("ui.vert", wgpu::include_spirv!("../low/shaders/ui.vert.spv")),
+            renderer.shader("ui.frag", wgpu::include_spirv!("../low/shaders/ui.frag.spv")),
+            &[UiVertex::desc()],
+            &[
+                &renderer.ui.projection.uniform_bind_group_layout, // set = 0
+                &renderer.ui.font_bind_group_layout, // set = 1
+                &renderer.textures.block_texture_bind_group_layout, // set = 2
+            ],
+        )
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, renderer: &'a Renderer) {
+        let font = match &self.font_bind_group {
+            Some(font) => font,
+            None => return,
+        };
+
+        pass.set_pipeline(renderer.get_pipeline::<Self>());
+        pass.set_bind_group(0, &self.projection.uniform_bind_group, &[]); // Projection
+        pass.set_bind_group(1, font, &[]); // Font
+        pass.set_bind_group(2, renderer.textures.get_block_bind_group(), &[]); // Block textures
+
+        pass.set_vertex_buffer(0, self.vertex_buffer.get_buffer().slice(..));
+        pass.set_index_buffer(self.index_buffer.get_buffer().slice(..), wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..self.index_buffer.len as u32, 0, 0..1);
+    }
+}
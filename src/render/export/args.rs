@@ -0,0 +1,98 @@
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+
+use crate::settings::args::{column, number, Options};
+
+pub const EXPORT_USAGE: &str = "Usage: ludwig-export [options]
+
+Generates the chunks around a point and writes their meshes to model files,
+with the terrain texture next to them. No window is opened.
+
+Options:
+    --seed <number>           Seed of the world (default: random)
+    --center <x>,<z>          Block the region is centered on (default: 0,0)
+    --radius <chunks>         Chunks on every side of the center chunk (default: 2)
+    --format <obj|gltf|both>  Which files to write (default: both)
+    --out <directory>         Where the files are written (default: export)
+    --help                    Show this message";
+
+/// More than this takes minutes and makes files no program opens quickly
+pub const MAX_EXPORT_RADIUS: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Obj,
+    Gltf,
+    Both,
+}
+
+impl ExportFormat {
+    pub fn obj(&self) -> bool {
+        *self != ExportFormat::Gltf
+    }
+
+    pub fn gltf(&self) -> bool {
+        *self != ExportFormat::Obj
+    }
+}
+
+/// The command line arguments of the exporter
+#[derive(Debug)]
+pub struct ExportArgs {
+    pub help: bool,
+    pub seed: Option<u32>,
+    /// In blocks
+    pub center: [i64; 2],
+    pub radius: u32,
+    pub format: ExportFormat,
+    pub out: PathBuf,
+}
+
+impl Default for ExportArgs {
+    fn default() -> Self {
+        Self {
+            help: false,
+            seed: None,
+            center: [0, 0],
+            radius: 2,
+            format: ExportFormat::Both,
+            out: PathBuf::from("export"),
+        }
+    }
+}
+
+impl ExportArgs {
+    /// Parses the arguments without the program name, see Options
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut parsed = ExportArgs::default();
+        let mut options = Options::new(args);
+
+        for option in &mut options {
+            let (name, value) = option?;
+
+            match name.as_str() {
+                "--seed" => parsed.seed = Some(number(&name, &value)?),
+                "--center" => parsed.center = column(&name, &value)?,
+                "--radius" => {
+                    parsed.radius = number(&name, &value)?;
+                    if parsed.radius > MAX_EXPORT_RADIUS {
+                        bail!("The radius can be at most {}", MAX_EXPORT_RADIUS);
+                    }
+                }
+                "--format" => {
+                    parsed.format = match value.to_lowercase().as_str() {
+                        "obj" => ExportFormat::Obj,
+                        "gltf" => ExportFormat::Gltf,
+                        "both" => ExportFormat::Both,
+                        _ => bail!("Unknown format '{}', expected obj, gltf or both", value),
+                    }
+                }
+                "--out" => parsed.out = PathBuf::from(value),
+                _ => bail!("Unknown option '{}'", name),
+            }
+        }
+        parsed.help = options.help;
+
+        Ok(parsed)
+    }
+}
\ No newline at end of file
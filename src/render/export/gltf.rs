@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::render::export::region::ExportMesh;
+
+// Constants of the glTF 2.0 specification
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const NEAREST: u32 = 9728;
+const CLAMP_TO_EDGE: u32 = 33071;
+
+/// Writes the meshes to a glTF 2.0 file with every chunk as a node, and its
+/// buffer to a .bin file next to it. The material uses `texture` without
+/// filtering, so blocks look the same as in the game. Nodes are placed in
+/// blocks from `origin`, y is up.
+pub fn write_gltf(path: &Path, meshes: &[ExportMesh], origin: [f32; 3], texture: &str) -> Result<()> {
+    let buffer_path = path.with_extension("bin");
+    let buffer_name = buffer_path.file_name().unwrap().to_string_lossy().into_owned();
+
+    let mut buffer: Vec<u8> = vec![];
+    let mut views = vec![];
+    let mut accessors = vec![];
+    let mut gltf_meshes = vec![];
+    let mut nodes = vec![];
+
+    for mesh in meshes {
+        let count = mesh.positions.len();
+
+        // Position needs its bounds, the others don't
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &mesh.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+
+        let position = accessors.len();
+        let attributes = [
+            (mesh.positions.iter().flatten().copied().collect::<Vec<f32>>(), "VEC3"),
+            (mesh.normals.iter().flatten().copied().collect(), "VEC3"),
+            (mesh.uvs.iter().flatten().copied().collect(), "VEC2"),
+        ];
+
+        for (i, (values, kind)) in attributes.iter().enumerate() {
+            let offset = buffer.len();
+            buffer.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+
+            views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+                offset, buffer.len() - offset, ARRAY_BUFFER,
+            ));
+            let bounds = if i == 0 {
+                format!(r#","min":{},"max":{}"#, numbers(&min), numbers(&max))
+            } else {
+                String::new()
+            };
+            accessors.push(format!(
+                r#"{{"bufferView":{},"componentType":{},"count":{},"type":"{}"{}}}"#,
+                views.len() - 1, FLOAT, count, kind, bounds,
+            ));
+        }
+
+        let offset = buffer.len();
+        buffer.extend(mesh.indices.iter().flat_map(|index| index.to_le_bytes()));
+        views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            offset, buffer.len() - offset, ELEMENT_ARRAY_BUFFER,
+        ));
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            views.len() - 1, UNSIGNED_INT, mesh.indices.len(),
+        ));
+
+        let name = format!("chunk_{}_{}", mesh.pos.x, mesh.pos.z);
+        gltf_meshes.push(format!(
+            r#"{{"name":{},"primitives":[{{"attributes":{{"POSITION":{},"NORMAL":{},"TEXCOORD_0":{}}},"indices":{},"material":0}}]}}"#,
+            string(&name), position, position + 1, position + 2, position + 3,
+        ));
+
+        let corner = mesh.corner();
+        let translation = [corner[0] - origin[0], corner[1] - origin[1], corner[2] - origin[2]];
+        nodes.push(format!(
+            r#"{{"name":{},"mesh":{},"translation":{}}}"#,
+            string(&name), gltf_meshes.len() - 1, numbers(&translation),
+        ));
+    }
+
+    let scene: Vec<String> = (0..nodes.len()).map(|node| node.to_string()).collect();
+    let json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"Ludwig World"}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[{}]}}],"#,
+            r#""nodes":[{}],"meshes":[{}],"accessors":[{}],"bufferViews":[{}],"#,
+            r#""buffers":[{{"uri":{},"byteLength":{}}}],"#,
+            r#""materials":[{{"name":"terrain","alphaMode":"MASK","pbrMetallicRoughness":{{"baseColorTexture":{{"index":0}},"metallicFactor":0,"roughnessFactor":1}}}}],"#,
+            r#""textures":[{{"sampler":0,"source":0}}],"images":[{{"uri":{}}}],"#,
+            r#""samplers":[{{"magFilter":{},"minFilter":{},"wrapS":{},"wrapT":{}}}]}}"#,
+            "\n",
+        ),
+        scene.join(","), nodes.join(","), gltf_meshes.join(","), accessors.join(","), views.join(","),
+        string(&buffer_name), buffer.len(),
+        string(texture),
+        NEAREST, NEAREST, CLAMP_TO_EDGE, CLAMP_TO_EDGE,
+    );
+
+    std::fs::write(&buffer_path, &buffer)
+        .with_context(|| format!("Could not write {}", buffer_path.display()))?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Could not write {}", path.display()))
+}
+
+fn numbers(values: &[f32]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// A JSON string
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::pos::ChunkPos;
+    use serde_json::Value;
+
+    /// Writes the meshes and reads back the JSON and the buffer
+    fn gltf(meshes: &[ExportMesh]) -> (Value, Vec<u8>) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("chunks.gltf");
+        write_gltf(&path, meshes, [0.0; 3], "terrain.png").unwrap();
+
+        let json = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        (json, std::fs::read(path.with_extension("bin")).unwrap())
+    }
+
+    fn meshes() -> Vec<ExportMesh> {
+        vec![ExportMesh::square(ChunkPos::new(0, 0, 0), 3.0), ExportMesh::square(ChunkPos::new(2, 0, 1), 5.0)]
+    }
+
+    #[test]
+    fn accessors_count_the_vertices_and_indices() {
+        let (json, _) = gltf(&meshes());
+
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+        for mesh in json["meshes"].as_array().unwrap() {
+            let primitive = &mesh["primitives"][0];
+            let count = |attribute: &Value| json["accessors"][attribute.as_u64().unwrap() as usize]["count"].as_u64().unwrap();
+
+            assert_eq!(count(&primitive["attributes"]["POSITION"]), 4);
+            assert_eq!(count(&primitive["attributes"]["NORMAL"]), 4);
+            assert_eq!(count(&primitive["attributes"]["TEXCOORD_0"]), 4);
+            assert_eq!(count(&primitive["indices"]), 6);
+        }
+    }
+
+    #[test]
+    fn buffer_views_fit_their_accessors() {
+        let (json, buffer) = gltf(&meshes());
+        let size = |kind: &str| match kind {
+            "VEC3" => 12,
+            "VEC2" => 8,
+            _ => 4,
+        };
+
+        let mut end = 0;
+        for accessor in json["accessors"].as_array().unwrap() {
+            let view = &json["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+            let length = view["byteLength"].as_u64().unwrap();
+
+            assert_eq!(length, accessor["count"].as_u64().unwrap() * size(accessor["type"].as_str().unwrap()));
+            assert_eq!(view["byteOffset"].as_u64().unwrap(), end);
+            end += length;
+        }
+        assert_eq!(end, buffer.len() as u64);
+        assert_eq!(json["buffers"][0]["byteLength"].as_u64().unwrap(), buffer.len() as u64);
+    }
+
+    #[test]
+    fn positions_have_their_bounds() {
+        let (json, buffer) = gltf(&meshes());
+
+        let floats = |value: &Value| -> Vec<f64> { value.as_array().unwrap().iter().map(|value| value.as_f64().unwrap()).collect() };
+
+        let position = &json["accessors"][4];
+        assert_eq!(floats(&position["min"]), vec![0.0, 5.0, 0.0]);
+        assert_eq!(floats(&position["max"]), vec![1.0, 5.0, 1.0]);
+
+        // The first position of the second mesh, read back from the buffer
+        let offset = json["bufferViews"][4]["byteOffset"].as_u64().unwrap() as usize;
+        let y = f32::from_le_bytes([buffer[offset + 4], buffer[offset + 5], buffer[offset + 6], buffer[offset + 7]]);
+        assert_eq!(y, 5.0);
+
+        let size = crate::world::constants::CHUNKSIZE as f64;
+        assert_eq!(floats(&json["nodes"][1]["translation"]), vec![2.0 * size, 0.0, size]);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod args;
+pub mod region;
+pub mod obj;
+pub mod gltf;
\ No newline at end of file
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::render::export::region::ExportMesh;
+
+/// The material every face uses, the terrain atlas
+const MATERIAL: &str = "terrain";
+
+/// Writes the meshes to a Wavefront OBJ file with every chunk as an object, and
+/// a material library next to it that uses `texture`. Positions are in blocks
+/// from `origin`, y is up.
+pub fn write_obj(path: &Path, meshes: &[ExportMesh], origin: [f32; 3], texture: &str) -> Result<()> {
+    let library = path.with_extension("mtl");
+    let library_name = library.file_name().unwrap().to_string_lossy().into_owned();
+
+    std::fs::write(&library, format!(
+        "newmtl {}\nKa 1 1 1\nKd 1 1 1\nKs 0 0 0\nd 1\nillum 1\nmap_Kd {}\nmap_d {}\n",
+        MATERIAL, texture, texture,
+    )).with_context(|| format!("Could not write {}", library.display()))?;
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Could not write {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    write_meshes(&mut out, meshes, origin, &library_name)
+        .with_context(|| format!("Could not write {}", path.display()))
+}
+
+fn write_meshes(out: &mut impl Write, meshes: &[ExportMesh], origin: [f32; 3], library: &str) -> std::io::Result<()> {
+    writeln!(out, "# Ludwig World chunks")?;
+    writeln!(out, "mtllib {}", library)?;
+
+    // Indices count from 1 for the whole file. Every vertex has its own
+    // position, texture coordinate and normal, so they have the same index.
+    let mut first = 1;
+
+    for mesh in meshes {
+        let corner = mesh.corner();
+        let offset = [corner[0] - origin[0], corner[1] - origin[1], corner[2] - origin[2]];
+
+        writeln!(out, "o chunk_{}_{}", mesh.pos.x, mesh.pos.z)?;
+        writeln!(out, "usemtl {}", MATERIAL)?;
+
+        for p in &mesh.positions {
+            writeln!(out, "v {} {} {}", p[0] + offset[0], p[1] + offset[1], p[2] + offset[2])?;
+        }
+        // OBJ counts v up from the bottom of the texture
+        for uv in &mesh.uvs {
+            writeln!(out, "vt {} {}", uv[0], 1.0 - uv[1])?;
+        }
+        for n in &mesh.normals {
+            writeln!(out, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [triangle[0] + first, triangle[1] + first, triangle[2] + first];
+            writeln!(out, "f {}/{}/{} {}/{}/{} {}/{}/{}", a, a, a, b, b, b, c, c, c)?;
+        }
+
+        first += mesh.positions.len() as u32;
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::pos::ChunkPos;
+
+    fn obj(meshes: &[ExportMesh], origin: [f32; 3]) -> Vec<String> {
+        let mut out = vec![];
+        write_meshes(&mut out, meshes, origin, "test.mtl").unwrap();
+        String::from_utf8(out).unwrap().lines().map(|line| line.to_string()).collect()
+    }
+
+    fn starting(lines: &[String], kind: &str) -> Vec<Vec<String>> {
+        lines.iter()
+            .filter(|line| line.split_whitespace().next() == Some(kind))
+            .map(|line| line.split_whitespace().skip(1).map(|part| part.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn every_vertex_and_triangle_is_written() {
+        let lines = obj(&[ExportMesh::square(ChunkPos::new(0, 0, 0), 3.0), ExportMesh::square(ChunkPos::new(1, 0, 0), 5.0)], [0.0; 3]);
+
+        assert_eq!(starting(&lines, "o").len(), 2);
+        assert_eq!(starting(&lines, "v").len(), 8);
+        assert_eq!(starting(&lines, "vt").len(), 8);
+        assert_eq!(starting(&lines, "vn").len(), 8);
+        assert_eq!(starting(&lines, "f").len(), 4);
+        assert!(lines.contains(&"mtllib test.mtl".to_string()));
+    }
+
+    #[test]
+    fn indices_count_from_one_over_the_whole_file() {
+        let lines = obj(&[ExportMesh::square(ChunkPos::new(0, 0, 0), 3.0), ExportMesh::square(ChunkPos::new(1, 0, 0), 5.0)], [0.0; 3]);
+
+        let indices: Vec<u32> = starting(&lines, "f")
+            .iter()
+            .flatten()
+            .map(|corner| {
+                let parts: Vec<&str> = corner.split('/').collect();
+                assert!(parts.iter().all(|part| *part == parts[0]));
+                parts[0].parse().unwrap()
+            })
+            .collect();
+
+        assert_eq!(indices, vec![1, 3, 2, 1, 4, 3, 5, 7, 6, 5, 8, 7]);
+    }
+
+    #[test]
+    fn positions_are_from_the_origin_and_texture_is_flipped() {
+        let lines = obj(&[ExportMesh::square(ChunkPos::new(1, 0, -1), 3.0)], [1.0, 2.0, 0.0]);
+
+        let size = crate::world::constants::CHUNKSIZE as f32;
+        let first: Vec<f32> = starting(&lines, "v")[0].iter().map(|value| value.parse().unwrap()).collect();
+        assert_eq!(first, vec![size - 1.0, 1.0, -size]);
+
+        let uv: Vec<f32> = starting(&lines, "vt")[2].iter().map(|value| value.parse().unwrap()).collect();
+        assert_eq!(uv, vec![0.25, 0.5]);
+    }
+}
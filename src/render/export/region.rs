@@ -0,0 +1,130 @@
+use crate::render::low::textures::{TEXTURE_ATLAS_COLUMNS, TEXTURE_HEIGHT, TEXTURE_IMAGE_HEIGHT};
+use crate::render::meshing::chunkmeshing::ChunkMesh;
+use crate::world::{
+    chunk::{chunkstore::ChunkStore, pos::ChunkPos},
+    constants::CHUNKSIZE,
+    map::Map,
+};
+
+const NORMALS: [[f32; 3]; 6] = [
+    [0.0, 0.0, -1.0], // Front
+    [1.0, 0.0, 0.0],  // Left
+    [0.0, 0.0, 1.0],  // Back
+    [-1.0, 0.0, 0.0], // Right
+    [0.0, 1.0, 0.0],  // Top
+    [0.0, -1.0, 0.0], // Bottom
+];
+
+/// The mesh of a chunk unpacked into what model files store. Positions are
+/// in blocks from the lowest corner of the chunk.
+pub struct ExportMesh {
+    pub pos: ChunkPos,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// In the terrain atlas, from its top left corner
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl ExportMesh {
+    /// Unpacks the vertices the same way chunk.vert does
+    pub fn from_chunk_mesh(pos: ChunkPos, mesh: &ChunkMesh) -> Self {
+        let vertices = mesh.vertices();
+        let rows = TEXTURE_IMAGE_HEIGHT / TEXTURE_HEIGHT;
+
+        let mut export = Self {
+            pos,
+            positions: Vec::with_capacity(vertices.len()),
+            normals: Vec::with_capacity(vertices.len()),
+            uvs: Vec::with_capacity(vertices.len()),
+            indices: mesh.indices().to_vec(),
+        };
+
+        // The mesher adds a face as four vertices, their texture coordinates
+        // are where they are on the face, like in the shader
+        for face in vertices.chunks(4) {
+            let face_uvs: Vec<[f32; 2]> = face.iter().map(|vertex| {
+                let [x, y, z] = vertex.position();
+                let (x, y, z) = (x as f32, y as f32, z as f32);
+
+                match vertex.normal() {
+                    0 => [-x, -y],
+                    1 => [-z, -y],
+                    2 => [x, -y],
+                    3 => [z, -y],
+                    4 => [-x, -z],
+                    _ => [-x, z],
+                }
+            }).collect();
+
+            let min_u = face_uvs.iter().map(|uv| uv[0]).fold(f32::MAX, f32::min);
+            let min_v = face_uvs.iter().map(|uv| uv[1]).fold(f32::MAX, f32::min);
+
+            for (vertex, uv) in face.iter().zip(face_uvs) {
+                let [x, y, z] = vertex.position();
+                let tile = vertex.texture();
+                let (column, row) = (tile % TEXTURE_ATLAS_COLUMNS, tile / TEXTURE_ATLAS_COLUMNS);
+
+                export.positions.push([x as f32, y as f32, z as f32]);
+                export.normals.push(NORMALS[vertex.normal() as usize % 6]);
+                export.uvs.push([
+                    (column as f32 + uv[0] - min_u) / TEXTURE_ATLAS_COLUMNS as f32,
+                    (row as f32 + uv[1] - min_v) / rows as f32,
+                ]);
+            }
+        }
+
+        export
+    }
+
+    /// The lowest corner of the chunk in blocks
+    pub fn corner(&self) -> [f32; 3] {
+        [(self.pos.x * CHUNKSIZE as i32) as f32, 0.0, (self.pos.z * CHUNKSIZE as i32) as f32]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+#[cfg(test)]
+impl ExportMesh {
+    /// A square of one block lying flat at `height`, facing up
+    pub fn square(pos: ChunkPos, height: f32) -> Self {
+        Self {
+            pos,
+            positions: vec![[0.0, height, 0.0], [1.0, height, 0.0], [1.0, height, 1.0], [0.0, height, 1.0]],
+            normals: vec![NORMALS[4]; 4],
+            uvs: vec![[0.0, 0.0], [0.25, 0.0], [0.25, 0.5], [0.0, 0.5]],
+            indices: vec![0, 2, 1, 0, 3, 2],
+        }
+    }
+}
+
+/// Generates the square of chunks `radius` chunks around `center` and meshes
+/// them, without a window. Chunks outside the square count as air, so its
+/// sides are closed.
+pub fn mesh_region(seed: u32, center: ChunkPos, radius: u32) -> Vec<ExportMesh> {
+    let map = Map::new(seed);
+    let mut chunks = ChunkStore::new();
+    let radius = radius as i32;
+
+    for x in -radius..=radius {
+        for z in -radius..=radius {
+            chunks.insert(map.generate_chunk(ChunkPos::new(center.x + x, 0, center.z + z)));
+        }
+    }
+
+    let mut positions: Vec<ChunkPos> = chunks.positions().collect();
+    positions.sort_by_key(|pos| (pos.z, pos.x));
+
+    positions
+        .into_iter()
+        .map(|pos| {
+            let mut mesh = ChunkMesh::new();
+            mesh.create_simple_mesh(chunks.get(pos).unwrap(), &chunks);
+            ExportMesh::from_chunk_mesh(pos, &mesh)
+        })
+        .filter(|mesh| !mesh.is_empty())
+        .collect()
+}
@@ -38,47 +38,6 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> DynamicBuffer<T> {
         }
     }
 
-    // Will insert starting at the 'at'th type T
-    fn insert_at(
-        &mut self,
-        device: &wgpu::Device,
-        encoder: &mut wgpu::CommandEncoder,
-        data: &[T],
-        at: usize,
-    ) {
-        self.insert_at_byte(device, encoder, data, at * std::mem::size_of::<T>())
-    }
-
-    // Insert at byte. Will replace anything, also through
-    // object borders
-    fn insert_at_byte(
-        &mut self,
-        device: &wgpu::Device,
-        encoder: &mut wgpu::CommandEncoder,
-        data: &[T],
-        at_byte: usize,
-    ) {
-        if data.len() + self.len > self.size {
-            self.resize((data.len() + self.len) * 2, device, encoder); // Double size if needed
-        }
-
-        let to_add_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("To add Buffer"),
-            usage: wgpu::BufferUsage::COPY_SRC | self.usage,
-            contents: bytemuck::cast_slice(data),
-        });
-
-        encoder.copy_buffer_to_buffer(
-            &to_add_buffer,
-            0,
-            &self.buffer,
-            at_byte as u64,
-            (data.len() * std::mem::size_of::<T>()) as u64,
-        );
-
-        self.len += data.len();
-    }
-
     // Append to the buffer
     pub fn insert_back(
         &mut self,
@@ -103,7 +62,7 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> DynamicBuffer<T> {
             0,
             &self.buffer,
             (self.len * std::mem::size_of::<T>()) as u64,
-            (data.len() * std::mem::size_of::<T>()) as u64,
+            std::mem::size_of_val(data) as u64,
         );
 
 
@@ -135,20 +94,7 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> DynamicBuffer<T> {
         self.size = new_size;
     }
 
-    pub fn remove_at(
-        &mut self, 
-        device: &wgpu::Device,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
-
-    }
-
     pub fn get_buffer(&self) -> &wgpu::Buffer {
         &self.buffer
     }
-}
-
-struct Segment {
-    size: usize,
-    free: bool,
 }
\ No newline at end of file
@@ -1,48 +1,113 @@
 use winit::{
-    event::*,
     dpi::PhysicalSize,
-    event::{Event, WindowEvent},
+    event::{Event, WindowEvent, ElementState},
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
     window::Window,
 };
 use futures::executor::block_on;
+use anyhow::Result;
+use std::path::PathBuf;
 
 use crate::render::{
     low::renderer::Renderer,
+    ui::{ui::{Ui, TEXT_SIZE}, debug::DebugOverlay},
 };
-use crate::game::state::State;
+use crate::game::state::{State, statestack::StateStack};
+use crate::resources::resourcemanager::ResourceManager;
+use crate::input::{
+    action::Action,
+    binding::Bindings,
+    inputmanager::InputManager,
+};
+use crate::settings::settings::Settings;
 
 pub struct Context {
     pub window: Window,
     pub event_loop: Option<EventLoop<()>>,
     pub renderer: Renderer,
+    pub input: InputManager,
+    pub ui: Ui,
+    pub debug: DebugOverlay,
+    pub settings: Settings,
 
+    /// Where the settings are saved
+    settings_path: PathBuf,
+    title: String,
+    /// Whether the game wants the cursor grabbed, it is released while the window is not focused
+    wants_cursor: bool,
+    cursor_captured: bool,
 }
 
 impl Context {
-    pub fn new(window_title: String, window_size: [u32; 2]) -> Self {
+    pub fn new(window_title: String, resources: ResourceManager, bindings: Bindings, settings: Settings, settings_path: PathBuf) -> Result<Self> {
         // Winit
         let event_loop = EventLoop::new();
         let window = WindowBuilder::new()
-            .with_title(window_title)
-            .with_inner_size(PhysicalSize::new(window_size[0], window_size[1]))
+            .with_title(&window_title)
+            .with_inner_size(PhysicalSize::new(settings.width, settings.height))
             .build(&event_loop)
             .unwrap();
 
-        let renderer = block_on(Renderer::new(&window));
+        let mut renderer = block_on(Renderer::new(&window, resources, &settings))?;
+        let font = renderer.load_font()?;
 
-        Self {
+        Ok(Self {
             event_loop: Some(event_loop),
             window,
             renderer,
+            input: InputManager::new(bindings),
+            ui: Ui::new(font),
+            debug: DebugOverlay::new(),
+            settings,
+            settings_path,
+            title: window_title,
+            wants_cursor: false,
+            cursor_captured: false,
+        })
+    }
+
+    /// Applies changed settings to the renderer. States read the rest of
+    /// the settings themselves.
+    pub fn apply_settings(&mut self) {
+        self.renderer.apply_settings(&self.settings);
+    }
+
+    pub fn save_settings(&self) {
+        if let Err(e) = self.settings.save(&self.settings_path) {
+            println!("Could not save the settings: {:?}", e);
+        }
+    }
+
+    /// Grabs and hides the cursor for looking around, or gives it back
+    pub fn capture_cursor(&mut self, capture: bool) {
+        self.wants_cursor = capture;
+        self.set_cursor_grab(capture);
+    }
+
+    /// Whether mouse movement is meant for the camera
+    pub fn cursor_captured(&self) -> bool {
+        self.cursor_captured
+    }
+
+    fn set_cursor_grab(&mut self, grab: bool) {
+        if let Err(e) = self.window.set_cursor_grab(grab) {
+            println!("Could not grab the cursor: {:?}", e);
+            // Still hide it, looking around works anyway on most platforms
         }
+        self.window.set_cursor_visible(!grab);
+        self.cursor_captured = grab;
     }
 
-    pub fn run<T: State + 'static>(mut self, mut state: T) {
+    /// Runs the game until the last state is gone, starting with `initial`
+    pub fn run(mut self, initial: Box<dyn State>) {
+        let mut states = StateStack::new();
+        states.push(initial, &mut self);
+
         let mut last_render_time = std::time::Instant::now();
         
         let mut frame: Option<wgpu::SwapChainFrame> = None;
+        let mut shader_errors: Option<String> = None;
 
         self.event_loop.take().unwrap().run(move |event, _, control_flow| {        
             match event {
@@ -50,7 +115,7 @@ impl Context {
                     ref event,
                     .. // We're not using device_id currently
                 } => {
-                    self.renderer.input(event);
+                    self.input.device_event(event);
                 }
 
                 Event::WindowEvent {
@@ -58,18 +123,27 @@ impl Context {
                     window_id,
                 } if window_id == self.window.id() => {
                     
+                    // Keys go to the text input that has focus instead of the game
+                    if !self.ui.window_event(event) {
+                        self.input.window_event(event);
+                        states.input(&mut self, event);
+                    }
+
                     match event {
                         WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                        WindowEvent::KeyboardInput { input, .. } => match input {
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            } => *control_flow = ControlFlow::Exit,
-                            _ => {}
-                        },
+                        WindowEvent::Focused(focused) => {
+                            // Give the cursor back to other windows
+                            self.set_cursor_grab(*focused && self.wants_cursor);
+                        }
+                        // Clicking the window takes the cursor back if it was lost
+                        WindowEvent::MouseInput { state: ElementState::Pressed, .. } if self.wants_cursor && !self.cursor_captured => {
+                            self.set_cursor_grab(true);
+                        }
                         WindowEvent::Resized(physical_size) => {
                             self.renderer.resize(*physical_size);
+                            // The next start uses the size the window was left at
+                            self.settings.width = physical_size.width.max(1);
+                            self.settings.height = physical_size.height.max(1);
                         }
                         WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                             // new_inner_size is &mut so w have to dereference it twice
@@ -85,6 +159,18 @@ impl Context {
                     last_render_time = now;
                     
                     self.renderer.update(dt);
+                    self.debug.record_frame(dt);
+                    self.renderer.update_shaders();
+
+                    // Shader errors are shown in the title and on the screen until they are fixed
+                    let errors = self.renderer.shaders.error_summary();
+                    if errors != shader_errors {
+                        match &errors {
+                            Some(errors) => self.window.set_title(&format!("{} - {}", self.title, errors)),
+                            None => self.window.set_title(&self.title),
+                        }
+                        shader_errors = errors;
+                    }
                                   
                     
                     match frame.take() {
@@ -102,25 +188,64 @@ impl Context {
                             }
                         }
                         Some(swapchainframe) => {
+                            // Hot reload textures and shaders
+                            if self.input.pressed(Action::ReloadResources) {
+                                match self.renderer.reload_resources().and_then(|_| self.renderer.load_font()) {
+                                    Ok(font) => {
+                                        self.ui.font = font;
+                                        println!("Reloaded resources");
+                                    }
+                                    Err(e) => println!("Could not reload resources: {:?}", e),
+                                }
+                            }
+                            if self.input.pressed(Action::ToggleDebug) {
+                                self.debug.toggle();
+                            }
+                            if self.input.pressed(Action::CycleShadows) {
+                                self.settings.shadows = self.settings.shadows.next();
+                                self.apply_settings();
+                                self.save_settings();
+                                println!("Shadow quality: {:?}", self.settings.shadows);
+                            }
+
                             let mut encoder = self.renderer.start_frame();
+
+                            let screen = [self.renderer.size.width as f32, self.renderer.size.height as f32];
+                            self.ui.begin_frame(screen, !self.cursor_captured);
                             
-                            state.update(&mut self, &mut encoder);
+                            states.update(&mut self, &mut encoder);
+                            if !states.running() {
+                                *control_flow = ControlFlow::Exit;
+                            }
+                            states.draw_ui(&mut self.ui);
+
+                            // Over the UI of every state
+                            self.ui.set_layer(states.depth());
+                            self.debug.draw(&mut self.ui);
+                            if let Some(errors) = &shader_errors {
+                                let x = (screen[0] - self.ui.font.text_width(errors, TEXT_SIZE)) / 2.0;
+                                self.ui.label(x.max(0.0), 4.0, errors);
+                            }
 
                             self.renderer.render(
-                                state.draw(),
+                                states.draw(),
                                 &mut encoder,
                                 &swapchainframe,
                             );
-
-                            // println!("FPS: {}", dt.as_secs_f64());
+                            self.renderer.render_ui(&self.ui, &mut encoder, &swapchainframe);
 
                             self.renderer.end_frame(encoder);
+                            self.ui.end_frame();
+                            self.input.end_frame();
                         }
                     }
                 }
                 Event::MainEventsCleared => {
                     self.window.request_redraw();
                 }
+                Event::LoopDestroyed => {
+                    states.exit(&mut self);
+                }
                 _ => {}
             }
         });
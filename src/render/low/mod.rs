@@ -5,4 +5,5 @@ pub mod vertex;
 pub mod renderer;
 pub mod textures;
 pub mod uniforms;
-pub mod shaders;
\ No newline at end of file
+pub mod shaders;
+pub mod validation;
\ No newline at end of file
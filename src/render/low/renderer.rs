@@ -1,24 +1,31 @@
 use wgpu;
 use winit::{
-    event::DeviceEvent,
     dpi::PhysicalSize,
     window::Window,
 };
 
+use anyhow::{Context, Result};
 use std::any::TypeId;
 use std::collections::HashMap;
 
 use crate::render::{
     low::{
         init::default_depth_texture,
-        vertex::Vertex,
-        textures::TextureManager,
-        uniforms::{MultiUniform, ChunkPositionUniform},
+        textures::{TextureManager, TERRAIN_TEXTURE},
+        shaders::shaders::ShaderManager,
+        validation::ValidationScope,
+        uniforms::{MultiUniform, Uniform, ChunkPositionUniform, BreakingUniform, SelectionUniform, MinimapUniform},
     },
     camera::Camera,
-    drawables::{Drawable, texture_vertex::TextureVertex, chunk::ChunkDrawable},
+    sky::Sky,
+    shadows::{Shadows, ShadowQuality},
+    drawables::{Drawable, texture_vertex::TextureVertex, chunk::{ChunkDrawable, ChunkShadow}, sky::SkyDrawable, ui::UiDrawable, breaking::{BreakingDrawable, breaking_uniform}, entity::EntityDrawable, selection::{SelectionDrawable, selection_uniform}, minimap::{MinimapDrawable, minimap_uniform}},
+    ui::{font::{Font, UI_FONT}, ui::Ui},
 };
+use crate::world::time::WorldTime;
 use crate::world::chunk::pos::ChunkPos;
+use crate::resources::resourcemanager::ResourceManager;
+use crate::settings::settings::Settings;
 
 pub struct Renderer {
     // General gpu setup
@@ -31,16 +38,31 @@ pub struct Renderer {
 
     // Other
     pub camera: Camera,
+    pub sky: Sky,
+    pub shadows: Shadows,
+    pub resources: ResourceManager,
     pub textures: TextureManager,
+    pub shaders: ShaderManager,
     pub chunkpos_uniform: MultiUniform<ChunkPos, ChunkPositionUniform>,
+    pub ui: UiDrawable,
+    /// The face of the block that is being broken
+    pub breaking: Uniform<BreakingUniform>,
+    /// The corners the player selected for editing
+    pub selection: Uniform<SelectionUniform>,
+    /// Where the minimap is drawn
+    pub minimap: Uniform<MinimapUniform>,
 
     // Used when rendering
     pub pipelines: HashMap<TypeId, wgpu::RenderPipeline>,
+    /// How to create every registered pipeline again when resources are reloaded
+    pipeline_builders: HashMap<TypeId, fn(&Renderer) -> wgpu::RenderPipeline>,
+    /// Catches pipelines that can't be created from changed shaders
+    validation: ValidationScope,
     pub depth_view: wgpu::TextureView,
 }
 
 impl Renderer {
-    pub async fn new(window: &Window) -> Self {
+    pub async fn new(window: &Window, resources: ResourceManager, settings: &Settings) -> Result<Self> {
 
         let size = window.inner_size();
 
@@ -61,24 +83,38 @@ impl Renderer {
             },
             None,
         ).await.unwrap();
+        let validation = ValidationScope::install(&device);
 
         let sc_desc = wgpu::SwapChainDescriptor { // How should the swap chain be used?
             usage: wgpu::TextureUsage::RENDER_ATTACHMENT, // Texture usage
             format: wgpu::TextureFormat::Bgra8UnormSrgb,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode: present_mode(settings.vsync),
         };
         let swap_chain = device.create_swap_chain(&surface, &sc_desc);
 
         let (_, depth_view, _) = default_depth_texture(&device, &sc_desc);
-        let camera = Camera::new(&device, sc_desc.width, sc_desc.height, cgmath::Point3 {x: 0.0, y: 0.0, z: 0.0});
+        let camera = Camera::new(
+            &device,
+            sc_desc.width,
+            sc_desc.height,
+            cgmath::Point3 {x: 0.0, y: 0.0, z: 0.0},
+            cgmath::Deg(settings.fov),
+            settings.camera_speed,
+        );
 
-        // Load a texture
-        let mut textures = TextureManager::new(&device);
-        textures.load("/home/duco/development/rust/gamedev/luwdigengine2d/assets/terrain.png", &device, &queue);
+        let sky = Sky::new(&device);
+        let shadows = Shadows::new(&device, &queue, &sky, settings.shadows);
+
+        let textures = Renderer::load_textures(&resources, &device, &queue)?;
+        let shaders = ShaderManager::new(&resources);
 
         let chunkpos_uniform = MultiUniform::new(&device, 3, 2);
+        let ui = UiDrawable::new(&device);
+        let breaking = breaking_uniform(&device);
+        let selection = selection_uniform(&device);
+        let minimap = minimap_uniform(&device);
 
         let mut t = Self {
             size,
@@ -89,17 +125,84 @@ impl Renderer {
             device,
 
             camera,
-            textures: textures,
+            sky,
+            shadows,
+            resources,
+            textures,
+            shaders,
             chunkpos_uniform,
+            ui,
+            breaking,
+            selection,
+            minimap,
 
             pipelines: HashMap::new(),
+            pipeline_builders: HashMap::new(),
+            validation,
             depth_view,
         };
 
         t.register_pipeline::<TextureVertex>();
         t.register_pipeline::<ChunkDrawable>();
+        t.register_pipeline::<SkyDrawable>();
+        t.register_pipeline::<UiDrawable>();
+        t.register_pipeline::<BreakingDrawable>();
+        t.register_pipeline::<EntityDrawable>();
+        t.register_pipeline::<SelectionDrawable>();
+        t.register_pipeline::<MinimapDrawable>();
+        t.register_pipeline_builder(TypeId::of::<ChunkShadow>(), ChunkShadow::create_pipeline);
+
+        Ok(t)
+    }
+
+    fn load_textures(resources: &ResourceManager, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<TextureManager> {
+        let mut textures = TextureManager::new(device);
+        textures.load(resources, TERRAIN_TEXTURE, device, queue)?;
+        textures.load_blocks(resources, TERRAIN_TEXTURE, device, queue)?;
+
+        Ok(textures)
+    }
+
+    /// Reads all resource packs again and rebuilds the textures and pipelines.
+    /// If anything fails the old resources are kept.
+    pub fn reload_resources(&mut self) -> Result<()> {
+        let resources = self.resources.reopen()?;
+        let textures = Renderer::load_textures(&resources, &self.device, &self.queue)?;
+
+        self.resources = resources;
+        self.textures = textures;
+
+        self.shaders.set_packs(&self.resources);
+
+        // The pipelines refer to the bind group layouts of the old textures
+        let ids: Vec<TypeId> = self.pipeline_builders.keys().copied().collect();
+        for id in ids {
+            self.rebuild_pipeline(id);
+        }
+
+        Ok(())
+    }
+
+    /// Rasterizes the UI font and uploads it. The returned metrics belong in the Ui
+    pub fn load_font(&mut self) -> Result<Font> {
+        let (font, atlas) = Font::rasterize(self.resources.read(UI_FONT)?)
+            .with_context(|| format!("Could not load font {}", UI_FONT))?;
+        self.ui.set_font_atlas(&self.device, &self.queue, atlas);
 
-        t
+        Ok(font)
+    }
+
+    /// The shader `name` from the shader manager, which knows whether it was
+    /// replaced by a resource pack or compiled at runtime.
+    pub fn shader(&self, name: &str, embedded: wgpu::ShaderModuleDescriptor<'static>) -> wgpu::ShaderModuleDescriptor<'static> {
+        self.shaders.load(&self.resources, name, embedded)
+    }
+
+    /// Recreates the pipelines whose shaders changed on disk
+    pub fn update_shaders(&mut self) {
+        for id in self.shaders.poll() {
+            self.rebuild_pipeline(id);
+        }
     }
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
@@ -124,7 +227,9 @@ impl Renderer {
         objs: Vec<&dyn Drawable>,
         encoder: &mut wgpu::CommandEncoder,
         frame: &wgpu::SwapChainFrame,
-    ) {        
+    ) {
+        self.render_shadows(&objs, encoder);
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render pass descriptor in renderer"),
             color_attachments: &[
@@ -132,12 +237,7 @@ impl Renderer {
                     attachment: &frame.output.view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color { // Clear color
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(self.sky.clear_color()),
                         store: true,
                     }
                 }
@@ -153,52 +253,214 @@ impl Renderer {
         });
 
         for obj in objs {
-            obj.draw(&mut render_pass, &self);
+            obj.draw(&mut render_pass, self);
         }          
     }
+
+    /// Draws the UI of this frame on top of what render drew
+    pub fn render_ui(
+        &mut self,
+        ui: &Ui,
+        encoder: &mut wgpu::CommandEncoder,
+        frame: &wgpu::SwapChainFrame,
+    ) {
+        let (vertices, indices) = ui.mesh();
+        let screen = [self.size.width as f32, self.size.height as f32];
+        self.ui.upload(&self.device, &self.queue, encoder, &vertices, &indices, screen);
+
+        if self.ui.is_empty() {
+            return
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UI pass"),
+            color_attachments: &[
+                wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &frame.output.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    }
+                }
+            ],
+            depth_stencil_attachment: None,
+        });
+
+        self.ui.draw(&mut render_pass, self);
+    }
+    /// Draws everything that casts shadows into every cascade of the shadow map
+    fn render_shadows(&self, objs: &[&dyn Drawable], encoder: &mut wgpu::CommandEncoder) {
+        for cascade in 0..self.shadows.cascade_count() {
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &self.shadows.cascade_views[cascade],
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            for obj in objs {
+                obj.draw_shadow(&mut shadow_pass, self, cascade as u32);
+            }
+        }
+    }
+
     pub fn update(&mut self, dt: std::time::Duration) {
         self.camera.controller.update_camera(&mut self.camera.view, dt);
         self.camera.update(&self.queue);
     }
 
-    pub fn input(&mut self, event: &DeviceEvent) -> bool {
-        self.camera.input(event)
+    /// Moves the sun and updates the sky light and fog for the given world time
+    pub fn update_sky(&mut self, time: &WorldTime, partial_tick: f32) {
+        self.sky.update(&self.queue, &self.camera, time, partial_tick);
+        self.shadows.update(&self.queue, &self.camera, time.sun_direction(partial_tick));
+    }
+
+    /// Shadows are expensive, lower qualities use fewer and smaller shadow maps
+    pub fn set_shadow_quality(&mut self, quality: ShadowQuality) {
+        self.shadows.set_quality(&self.device, &self.sky, quality);
+    }
+
+    /// Applies the graphics and camera settings that can change while the game runs
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        let present_mode = present_mode(settings.vsync);
+        if present_mode != self.sc_desc.present_mode {
+            self.sc_desc.present_mode = present_mode;
+            self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
+        }
+
+        if settings.shadows != self.shadows.quality {
+            self.set_shadow_quality(settings.shadows);
+        }
+
+        self.camera.projection.set_fovy(cgmath::Deg(settings.fov));
+        self.camera.controller.set_speed(settings.camera_speed);
+    }
+
+    /// How far the world is visible, in blocks. Everything further away is hidden by fog
+    pub fn set_view_distance(&mut self, distance: f32) {
+        self.sky.set_view_distance(distance);
+        // The corners of the loaded area are further away than its sides
+        self.camera.projection.set_zfar(distance * 1.5);
     }
 
     pub fn default_pipeline(
         &self,
         vertex: wgpu::ShaderModuleDescriptor,
         fragment: wgpu::ShaderModuleDescriptor,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> wgpu::RenderPipeline {
+        self.pipeline(
+            vertex,
+            fragment,
+            buffers,
+            bind_group_layouts,
+            wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            },
+        )
+    }
+
+    /// A pipeline without fragment shader that only writes depth, for shadow maps
+    pub fn depth_only_pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        buffers: &[wgpu::VertexBufferLayout],
         bind_group_layouts: &[&wgpu::BindGroupLayout],
     ) -> wgpu::RenderPipeline {
 
         let render_pipeline_layout =
             self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: bind_group_layouts,
+                label: Some("Depth Pipeline Layout"),
+                bind_group_layouts,
                 push_constant_ranges: &[],
             });
 
         self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
+            label: Some("Depth Pipeline"),
             layout: Some(&render_pipeline_layout),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+            multisample: wgpu::MultisampleState::default(),
+            vertex: wgpu::VertexState {
+                module: &self.device.create_shader_module(&vertex),
+                entry_point: "main", 
+                buffers,
             },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                // Pushes the depth away from the light against shadow acne
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+                clamp_depth: false,
+            }),
+        })
+    }
+
+    /// A pipeline without depth buffer that blends with what is already drawn, for the UI
+    pub fn overlay_pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        fragment: wgpu::ShaderModuleDescriptor,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> wgpu::RenderPipeline {
+
+        let render_pipeline_layout =
+            self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Overlay Pipeline Layout"),
+                bind_group_layouts,
+                push_constant_ranges: &[],
+            });
+
+        self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overlay Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            multisample: wgpu::MultisampleState::default(),
             vertex: wgpu::VertexState {
                 module: &self.device.create_shader_module(&vertex),
                 entry_point: "main", 
-                buffers: &[Vertex::desc()],
+                buffers,
             },
             fragment: Some(wgpu::FragmentState { 
                 module: &self.device.create_shader_module(&fragment),
                 entry_point: "main",
                 targets: &[wgpu::ColorTargetState {
                     format: self.sc_desc.format,
-                    color_blend: wgpu::BlendState::REPLACE,
-                    alpha_blend: wgpu::BlendState::REPLACE,
+                    color_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
                     write_mask: wgpu::ColorWrite::ALL,
                 }],
             }),
@@ -209,14 +471,172 @@ impl Renderer {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
+            depth_stencil: None,
+        })
+    }
+
+    /// Like default_pipeline, for pipelines that use the depth buffer differently
+    pub fn pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        fragment: wgpu::ShaderModuleDescriptor,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        depth_stencil: wgpu::DepthStencilState,
+    ) -> wgpu::RenderPipeline {
+        self.blended_pipeline(vertex, fragment, buffers, bind_group_layouts, depth_stencil, false, wgpu::PrimitiveTopology::TriangleList)
+    }
+
+    /// A pipeline for things that lie on top of the world, such as the cracks of a block
+    /// that is being broken. It is blended with what is behind it and is not hidden by
+    /// the surface it lies on.
+    pub fn decal_pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        fragment: wgpu::ShaderModuleDescriptor,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> wgpu::RenderPipeline {
+        self.blended_pipeline(
+            vertex,
+            fragment,
+            &[],
+            bind_group_layouts,
+            wgpu::DepthStencilState {
                 format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                // Pulls it towards the camera so it doesn't flicker with the surface
+                bias: wgpu::DepthBiasState {
+                    constant: -2,
+                    slope_scale: -1.0,
+                    clamp: 0.0,
+                },
+                clamp_depth: false,
+            },
+            true,
+            wgpu::PrimitiveTopology::TriangleList,
+        )
+    }
+
+    /// A pipeline that draws lines on top of the world, such as the edges of the
+    /// selection. Lines are hidden behind blocks but not by the faces they lie on.
+    pub fn line_pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        fragment: wgpu::ShaderModuleDescriptor,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> wgpu::RenderPipeline {
+        self.blended_pipeline(
+            vertex,
+            fragment,
+            &[],
+            bind_group_layouts,
+            wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            },
+            true,
+            wgpu::PrimitiveTopology::LineList,
+        )
+    }
+
+    /// A pipeline for flat things that are drawn over the world in its render
+    /// pass, such as the minimap. The depth buffer is ignored.
+    pub fn hud_pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        fragment: wgpu::ShaderModuleDescriptor,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> wgpu::RenderPipeline {
+        self.blended_pipeline(
+            vertex,
+            fragment,
+            &[],
+            bind_group_layouts,
+            wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
                 clamp_depth: false,
+            },
+            true,
+            wgpu::PrimitiveTopology::TriangleList,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn blended_pipeline(
+        &self,
+        vertex: wgpu::ShaderModuleDescriptor,
+        fragment: wgpu::ShaderModuleDescriptor,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        depth_stencil: wgpu::DepthStencilState,
+        blend: bool,
+        topology: wgpu::PrimitiveTopology,
+    ) -> wgpu::RenderPipeline {
+        let (color_blend, alpha_blend) = if blend {
+            (
+                wgpu::BlendState {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                wgpu::BlendState {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            )
+        } else {
+            (wgpu::BlendState::REPLACE, wgpu::BlendState::REPLACE)
+        };
+
+        let render_pipeline_layout =
+            self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts,
+                push_constant_ranges: &[],
+            });
+
+        self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            vertex: wgpu::VertexState {
+                module: &self.device.create_shader_module(&vertex),
+                entry_point: "main", 
+                buffers,
+            },
+            fragment: Some(wgpu::FragmentState { 
+                module: &self.device.create_shader_module(&fragment),
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: self.sc_desc.format,
+                    color_blend,
+                    alpha_blend,
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
             }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                topology,
+                strip_index_format: None,
+            },
+            depth_stencil: Some(depth_stencil),
         })
     }
 }
@@ -231,15 +651,55 @@ impl Renderer {
         self.queue.submit(vec![encoder.finish()]);
     }
 
-    pub fn get_pipeline<T: 'static + Drawable>(&self) -> &wgpu::RenderPipeline {
-        &self
+    pub fn get_pipeline<T: 'static>(&self) -> &wgpu::RenderPipeline {
+        self
             .pipelines
             .get(&std::any::TypeId::of::<T>())
             .expect("Pipeline was not registered in context")
     }
 
     pub fn register_pipeline<T: 'static + Drawable>(&mut self) {
-        self.pipelines
-            .insert(std::any::TypeId::of::<T>(), T::create_pipeline(self));
+        self.register_pipeline_builder(std::any::TypeId::of::<T>(), T::create_pipeline);
+    }
+
+    /// For pipelines that don't belong to a single Drawable, such as the shadow pipeline
+    pub fn register_pipeline_builder(&mut self, id: TypeId, builder: fn(&Renderer) -> wgpu::RenderPipeline) {
+        self.pipeline_builders.insert(id, builder);
+        self.rebuild_pipeline(id);
+    }
+
+    /// Creates a pipeline again. If that fails, for example because a shader
+    /// doesn't match the bind group layouts, the old pipeline is kept and the
+    /// error is shown with the shader errors.
+    fn rebuild_pipeline(&mut self, id: TypeId) {
+        let builder = self.pipeline_builders[&id];
+
+        self.validation.push();
+        self.shaders.start_pipeline(id);
+        let pipeline = builder(self);
+        self.shaders.end_pipeline();
+        let errors = self.validation.pop();
+
+        if errors.is_empty() {
+            self.pipelines.insert(id, pipeline);
+            return
+        }
+
+        let errors = errors.join("\n");
+        if !self.pipelines.contains_key(&id) {
+            // There is nothing to fall back to when the game starts
+            panic!("Could not create a pipeline:\n{}", errors);
+        }
+        println!("Could not create a pipeline, keeping the old one:\n{}", errors);
+        self.shaders.pipeline_failed(id, &errors);
+    }
+}
+
+/// Without vsync frames are shown right away, which can tear
+fn present_mode(vsync: bool) -> wgpu::PresentMode {
+    if vsync {
+        wgpu::PresentMode::Fifo
+    } else {
+        wgpu::PresentMode::Immediate
     }
 }
\ No newline at end of file
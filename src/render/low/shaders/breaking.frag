@@ -0,0 +1,19 @@
+// breaking.frag
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=1) flat in uint v_layer;
+layout(location=0) out vec4 f_color;
+
+layout(set = 2, binding = 0) uniform texture2DArray t_blocks;
+layout(set = 2, binding = 1) uniform sampler s_blocks;
+
+void main() {
+    vec4 color = texture(sampler2DArray(t_blocks, s_blocks), vec3(v_tex_coords, float(v_layer)));
+
+    if (color.a < 0.01) {
+        discard;
+    }
+
+    f_color = color;
+}
@@ -0,0 +1,40 @@
+// breaking.vert
+#version 450
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) flat out uint v_layer;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+layout(set=1, binding=0)
+uniform Breaking {
+    vec4 u_block;
+    vec4 u_normal;
+};
+
+void main() {
+    // Two triangles covering the face the normal points out of
+    vec2 corners[6] = vec2[6](
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 1.0)
+    );
+    vec2 corner = corners[gl_VertexIndex];
+
+    vec3 normal = u_normal.xyz;
+    vec3 tangent = abs(normal.y) > 0.5 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0);
+    vec3 bitangent = cross(normal, tangent);
+
+    vec3 center = u_block.xyz + 0.5 + normal * 0.5;
+    vec3 position = center + tangent * (corner.x - 0.5) + bitangent * (corner.y - 0.5);
+
+    v_tex_coords = corner;
+    v_layer = uint(u_block.w);
+    gl_Position = u_view_proj * vec4(position, 1.0);
+}
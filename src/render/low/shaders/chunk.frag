@@ -2,11 +2,88 @@
 #version 450
 
 layout(location=0) in vec2 v_tex_coords;
+layout(location=1) in float v_shade;
+layout(location=2) flat in uint v_layer;
+layout(location=3) in float v_fog;
+layout(location=4) in float v_sky_light;
+layout(location=5) in float v_block_light;
+layout(location=6) in vec3 v_shadow_position;
+layout(location=7) in float v_view_depth;
+layout(location=8) in float v_light_facing;
 layout(location=0) out vec4 f_color;
 
-layout(set = 1, binding = 0) uniform texture2D t_diffuse;
-layout(set = 1, binding = 1) uniform sampler s_diffuse;
+layout(set = 1, binding = 0) uniform texture2DArray t_blocks;
+layout(set = 1, binding = 1) uniform sampler s_blocks;
+
+layout(set=3, binding=0)
+uniform Sky {
+    mat4 u_inv_view_proj;
+    vec4 u_zenith_color;
+    vec4 u_horizon_color;
+    vec4 u_sun_direction;
+    vec4 u_camera_position;
+    float u_sky_light;
+    float u_fog_start;
+    float u_fog_end;
+};
+
+layout(set=3, binding=1)
+uniform Shadow {
+    mat4 u_light_view_proj[4];
+    vec4 u_cascade_splits;
+    vec4 u_light_direction;
+    uint u_cascade_count;
+    float u_shadow_strength;
+    float u_texel_size;
+};
+
+layout(set=3, binding=2) uniform texture2DArray t_shadow;
+layout(set=3, binding=3) uniform samplerShadow s_shadow;
+
+// How much of the sun or moon light reaches this fragment, between 0 and 1
+float light_visibility() {
+    if (u_cascade_count == 0u) {
+        return 1.0;
+    }
+
+    // Faces that point away from the light are in their own shadow
+    if (v_light_facing <= 0.0) {
+        return 0.0;
+    }
+
+    uint cascade = u_cascade_count;
+    for (uint i = 0u; i < u_cascade_count; i++) {
+        if (v_view_depth < u_cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+
+    // Beyond the last cascade
+    if (cascade == u_cascade_count) {
+        return 1.0;
+    }
+
+    vec4 clip = u_light_view_proj[cascade] * vec4(v_shadow_position, 1.0);
+    vec3 p = clip.xyz / clip.w;
+    vec2 uv = vec2(p.x * 0.5 + 0.5, 0.5 - p.y * 0.5);
+
+    // Percentage closer filtering, every sample is also filtered bilinearly by the sampler
+    float visibility = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset = vec2(float(x), float(y)) * u_texel_size;
+            visibility += texture(sampler2DArrayShadow(t_shadow, s_shadow), vec4(uv + offset, float(cascade), p.z));
+        }
+    }
+
+    return visibility / 9.0;
+}
 
 void main() {
-    f_color = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords);
-}
\ No newline at end of file
+    vec4 color = texture(sampler2DArray(t_blocks, s_blocks), vec3(v_tex_coords, float(v_layer)));
+    float sky_light = v_sky_light * (1.0 - u_shadow_strength * (1.0 - light_visibility()));
+    float light = max(sky_light, v_block_light);
+
+    f_color = vec4(mix(color.rgb * v_shade * light, u_horizon_color.rgb, v_fog), color.a);
+}
@@ -1,10 +1,18 @@
 // shader.vert
 #version 450
 
-layout(location=0) in vec3 a_position;
-layout(location=1) in vec2 a_tex_coords;
+// Packed vertex, see ChunkVertex in vertex.rs for the layout
+layout(location=0) in uvec2 a_data;
 
 layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out float v_shade;
+layout(location=2) flat out uint v_layer;
+layout(location=3) out float v_fog;
+layout(location=4) out float v_sky_light;
+layout(location=5) out float v_block_light;
+layout(location=6) out vec3 v_shadow_position;
+layout(location=7) out float v_view_depth;
+layout(location=8) out float v_light_facing;
 
 layout(set=0, binding=0) 
 uniform Uniforms {
@@ -16,17 +24,78 @@ uniform ChunkUniform {
     vec3 chunkPosition; 
 };
 
+layout(set=3, binding=0)
+uniform Sky {
+    mat4 u_inv_view_proj;
+    vec4 u_zenith_color;
+    vec4 u_horizon_color;
+    vec4 u_sun_direction;
+    vec4 u_camera_position;
+    float u_sky_light;
+    float u_fog_start;
+    float u_fog_end;
+};
+
+layout(set=3, binding=1)
+uniform Shadow {
+    mat4 u_light_view_proj[4];
+    vec4 u_cascade_splits;
+    vec4 u_light_direction;
+    uint u_cascade_count;
+    float u_shadow_strength;
+    float u_texel_size;
+};
+
+// Darken faces a bit depending on which way they face, in the order of Sides
+const float FACE_SHADE[6] = float[6](0.8, 0.7, 0.8, 0.7, 1.0, 0.5);
+
+const vec3 NORMALS[6] = vec3[6](
+    vec3(0.0, 0.0, -1.0), // Front
+    vec3(1.0, 0.0, 0.0),  // Left
+    vec3(0.0, 0.0, 1.0),  // Back
+    vec3(-1.0, 0.0, 0.0), // Right
+    vec3(0.0, 1.0, 0.0),  // Top
+    vec3(0.0, -1.0, 0.0)  // Bottom
+);
+
 void main() {
-    v_tex_coords = a_tex_coords;
+    uint x = a_data.x & 63u;
+    uint y = (a_data.x >> 6) & 127u;
+    uint z = (a_data.x >> 13) & 63u;
+    uint normal = (a_data.x >> 19) & 7u;
+    uint ao = (a_data.x >> 24) & 3u;
+
+    uint tex_index = a_data.y & 65535u;
+    uint light = (a_data.y >> 16) & 255u;
+
+    // Texture coordinates follow the position on the face, so the sampler can
+    // repeat the texture over quads that span more than one block
+    vec3 p = vec3(float(x), float(y), float(z));
+    vec2 uvs[6] = vec2[6](
+        vec2(-p.x, -p.y), // Front
+        vec2(-p.z, -p.y), // Left
+        vec2(p.x, -p.y),  // Back
+        vec2(p.z, -p.y),  // Right
+        vec2(-p.x, -p.z), // Top
+        vec2(-p.x, p.z)   // Bottom
+    );
+    v_tex_coords = uvs[normal];
+    v_layer = tex_index;
+
+    // Shadows only block sky light, so the light is combined in the fragment shader
+    v_sky_light = float(light >> 4) / 15.0 * u_sky_light;
+    v_block_light = float(light & 15u) / 15.0;
+    v_shade = FACE_SHADE[normal] * (0.4 + 0.6 * float(ao) / 3.0);
+
+    vec3 position = p + chunkPosition;
 
-    float x = a_position.x;
-    float y = a_position.y;
-    float z = a_position.z;
+    // Fog only depends on the horizontal distance, so it hides where the loaded chunks end
+    v_fog = smoothstep(u_fog_start, u_fog_end, distance(position.xz, u_camera_position.xz));
 
-    x += chunkPosition.x;
-    y += chunkPosition.y;
-    z += chunkPosition.z;
+    // Moving the position out of the face a bit keeps it from shadowing itself
+    v_shadow_position = position + NORMALS[normal] * 0.05;
+    v_light_facing = dot(NORMALS[normal], u_light_direction.xyz);
 
-    gl_Position = u_view_proj * vec4(vec3(x, y, z), 1.0);
+    gl_Position = u_view_proj * vec4(position, 1.0);
+    v_view_depth = gl_Position.w;
 }
- 
\ No newline at end of file
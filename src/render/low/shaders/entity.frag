@@ -0,0 +1,32 @@
+// entity.frag
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=1) flat in uint v_layer;
+layout(location=2) in float v_shade;
+layout(location=3) in float v_fog;
+layout(location=0) out vec4 f_color;
+
+layout(set=1, binding=0) uniform texture2DArray t_blocks;
+layout(set=1, binding=1) uniform sampler s_blocks;
+
+layout(set=2, binding=0)
+uniform Sky {
+    mat4 u_inv_view_proj;
+    vec4 u_zenith_color;
+    vec4 u_horizon_color;
+    vec4 u_sun_direction;
+    vec4 u_camera_position;
+    float u_sky_light;
+    float u_fog_start;
+    float u_fog_end;
+};
+
+void main() {
+    vec4 color = texture(sampler2DArray(t_blocks, s_blocks), vec3(v_tex_coords, float(v_layer)));
+    if (color.a < 0.5) {
+        discard;
+    }
+
+    f_color = vec4(mix(color.rgb * v_shade, u_horizon_color.rgb, v_fog), 1.0);
+}
@@ -0,0 +1,88 @@
+// entity.vert
+#version 450
+
+// Per instance, see EntityInstance in vertex.rs
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec3 a_size;
+layout(location=2) in float a_yaw;
+layout(location=3) in uvec3 a_textures_front;
+layout(location=4) in uvec3 a_textures_back;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) flat out uint v_layer;
+layout(location=2) out float v_shade;
+layout(location=3) out float v_fog;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+layout(set=2, binding=0)
+uniform Sky {
+    mat4 u_inv_view_proj;
+    vec4 u_zenith_color;
+    vec4 u_horizon_color;
+    vec4 u_sun_direction;
+    vec4 u_camera_position;
+    float u_sky_light;
+    float u_fog_start;
+    float u_fog_end;
+};
+
+// The same as in chunk.vert, in the order of Sides
+const float FACE_SHADE[6] = float[6](0.8, 0.7, 0.8, 0.7, 1.0, 0.5);
+
+const vec3 NORMALS[6] = vec3[6](
+    vec3(0.0, 0.0, -1.0), // Front
+    vec3(1.0, 0.0, 0.0),  // Left
+    vec3(0.0, 0.0, 1.0),  // Back
+    vec3(-1.0, 0.0, 0.0), // Right
+    vec3(0.0, 1.0, 0.0),  // Top
+    vec3(0.0, -1.0, 0.0)  // Bottom
+);
+
+void main() {
+    // Six vertices for every face
+    uint face = uint(gl_VertexIndex) / 6u;
+    vec2 corners[6] = vec2[6](
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 1.0)
+    );
+    vec2 corner = corners[uint(gl_VertexIndex) % 6u];
+
+    vec3 normal = NORMALS[face];
+    vec3 tangent = abs(normal.y) > 0.5 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0);
+    vec3 bitangent = cross(normal, tangent);
+
+    // A unit cube around the origin, with its bottom at 0
+    vec3 local = normal * 0.5 + tangent * (corner.x - 0.5) + bitangent * (corner.y - 0.5);
+    local = local * a_size + vec3(0.0, a_size.y * 0.5, 0.0);
+
+    // Turning by the yaw points the front where the entity goes
+    float yaw = radians(a_yaw);
+    vec3 turned = vec3(
+        local.x * cos(yaw) + local.z * sin(yaw),
+        local.y,
+        -local.x * sin(yaw) + local.z * cos(yaw)
+    );
+    vec3 position = a_position + turned;
+
+    // Side faces have the tangent pointing up, the texture should be upright
+    v_tex_coords = abs(normal.y) > 0.5 ? corner : vec2(corner.y, 1.0 - corner.x);
+
+    uint textures[6] = uint[6](
+        a_textures_front.x, a_textures_front.y, a_textures_front.z,
+        a_textures_back.x, a_textures_back.y, a_textures_back.z
+    );
+    v_layer = textures[face];
+
+    v_shade = FACE_SHADE[face] * u_sky_light;
+    v_fog = smoothstep(u_fog_start, u_fog_end, distance(position.xz, u_camera_position.xz));
+
+    gl_Position = u_view_proj * vec4(position, 1.0);
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result, anyhow, bail};
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use crate::resources::resourcemanager::ResourceManager;
+
+/// Where the shader sources live in the repository
+const SOURCE_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/render/low/shaders");
+
+enum CompiledShader {
+    SpirV(Vec<u32>),
+    Wgsl(String),
+}
+
+/// Watches the shader sources and compiles them at runtime. A shader
+/// "chunk.vert" is read from `chunk.vert.wgsl` if it exists and from the
+/// GLSL source `chunk.vert` otherwise. The `shaders` directories of
+/// resource packs come before the sources of the game.
+pub struct HotReload {
+    // Stops watching when dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+    directories: Vec<PathBuf>,
+
+    compiler: shaderc::Compiler,
+    /// Shaders that were compiled since the game started, these replace the embedded ones
+    compiled: HashMap<String, CompiledShader>,
+}
+
+impl HotReload {
+    pub fn new(resources: &ResourceManager) -> Result<Self> {
+        let (watcher, events, directories) = watch(resources)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            directories,
+
+            compiler: shaderc::Compiler::new().context("Unable to create shader compiler")?,
+            compiled: HashMap::new(),
+        })
+    }
+
+    /// Watches the shaders of other resource packs instead
+    pub fn set_packs(&mut self, resources: &ResourceManager) -> Result<()> {
+        let (watcher, events, directories) = watch(resources)?;
+        self._watcher = watcher;
+        self.events = events;
+        self.directories = directories;
+
+        Ok(())
+    }
+
+    /// The shaders that were compiled since the game started
+    pub fn compiled(&self) -> Vec<String> {
+        self.compiled.keys().cloned().collect()
+    }
+
+    /// The names of the shaders whose sources changed since the last call
+    pub fn changed(&self) -> HashSet<String> {
+        let mut names = HashSet::new();
+
+        for event in self.events.try_iter() {
+            let path = match event {
+                DebouncedEvent::Create(path) | DebouncedEvent::Write(path) | DebouncedEvent::Remove(path) => path,
+                DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+
+            if let Some(name) = shader_name(&path) {
+                names.insert(name);
+            }
+        }
+
+        names
+    }
+
+    /// Compiles the shader from the first directory that has a source for it.
+    /// If there is no source anymore the embedded shader is used again.
+    pub fn compile(&mut self, name: &str) -> Result<()> {
+        for dir in &self.directories {
+            let wgsl = dir.join(format!("{}.wgsl", name));
+            let glsl = dir.join(name);
+
+            if wgsl.is_file() {
+                let source = std::fs::read_to_string(&wgsl)?;
+
+                // wgpu panics on invalid WGSL, so it is validated here first
+                let module = naga::front::wgsl::parse_str(&source)
+                    .map_err(|e| anyhow!("{:?}", e))
+                    .with_context(|| format!("Could not parse {}", wgsl.display()))?;
+                naga::proc::Validator::new().validate(&module)
+                    .map_err(|e| anyhow!("{:?}", e))
+                    .with_context(|| format!("Invalid shader {}", wgsl.display()))?;
+
+                self.compiled.insert(name.to_string(), CompiledShader::Wgsl(source));
+                return Ok(())
+            }
+
+            if glsl.is_file() {
+                let kind = match glsl.extension().and_then(|e| e.to_str()) {
+                    Some("vert") => shaderc::ShaderKind::Vertex,
+                    Some("frag") => shaderc::ShaderKind::Fragment,
+                    Some("comp") => shaderc::ShaderKind::Compute,
+                    _ => bail!("Unsupported shader: {}", glsl.display()),
+                };
+
+                let source = std::fs::read_to_string(&glsl)?;
+                let compiled = self.compiler
+                    .compile_into_spirv(&source, kind, &glsl.to_string_lossy(), "main", None)
+                    .map_err(|e| anyhow!("{}", e))?;
+
+                self.compiled.insert(name.to_string(), CompiledShader::SpirV(compiled.as_binary().to_vec()));
+                return Ok(())
+            }
+        }
+
+        self.compiled.remove(name);
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<wgpu::ShaderModuleDescriptor<'static>> {
+        let source = match self.compiled.get(name)? {
+            CompiledShader::SpirV(words) => wgpu::ShaderSource::SpirV(Cow::Owned(words.clone())),
+            CompiledShader::Wgsl(source) => wgpu::ShaderSource::Wgsl(Cow::Owned(source.clone())),
+        };
+
+        Some(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source,
+            flags: wgpu::ShaderFlags::VALIDATION,
+        })
+    }
+}
+
+/// The shaders directories of the packs that are directories, then the sources of the game
+fn watch(resources: &ResourceManager) -> Result<(RecommendedWatcher, Receiver<DebouncedEvent>, Vec<PathBuf>)> {
+    let mut directories: Vec<PathBuf> = resources
+        .directories()
+        .into_iter()
+        .map(|pack| pack.join("shaders"))
+        .collect();
+    directories.push(PathBuf::from(SOURCE_DIRECTORY));
+    directories.retain(|dir| dir.is_dir());
+
+    let (sender, events) = channel();
+    let mut watcher: RecommendedWatcher = Watcher::new(sender, Duration::from_millis(200))?;
+
+    for dir in &directories {
+        watcher.watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Could not watch {}", dir.display()))?;
+    }
+
+    Ok((watcher, events, directories))
+}
+
+/// "chunk.vert" for both chunk.vert and chunk.vert.wgsl. Compiled
+/// shaders are ignored, build.rs writes those next to the sources.
+fn shader_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+
+    if file_name.ends_with(".spv") {
+        return None
+    }
+
+    Some(file_name.trim_end_matches(".wgsl").to_string())
+}
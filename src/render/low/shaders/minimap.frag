@@ -0,0 +1,41 @@
+// minimap.frag
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set=0, binding=0)
+uniform Minimap {
+    vec4 u_rect;
+    vec4 u_heading;
+};
+
+layout(set=1, binding=0) uniform texture2D t_map;
+layout(set=1, binding=1) uniform sampler s_map;
+
+const vec4 BACKGROUND = vec4(0.0, 0.0, 0.0, 0.5);
+const vec4 BORDER = vec4(0.1, 0.1, 0.1, 0.9);
+const vec4 MARKER = vec4(1.0, 1.0, 1.0, 1.0);
+
+void main() {
+    // In blocks from the player, who is at the center
+    vec2 offset = (v_tex_coords - 0.5) * u_heading.z;
+    vec2 edge = min(v_tex_coords, 1.0 - v_tex_coords) * u_heading.z;
+
+    if (min(edge.x, edge.y) < 1.0) {
+        f_color = BORDER;
+        return;
+    }
+
+    // A dot with a line in the direction the player looks
+    float along = dot(offset, u_heading.xy);
+    float across = abs(dot(offset, vec2(-u_heading.y, u_heading.x)));
+    if (length(offset) < 1.5 || (along > 0.0 && along < 5.0 && across < 0.6)) {
+        f_color = MARKER;
+        return;
+    }
+
+    vec4 color = texture(sampler2D(t_map, s_map), v_tex_coords);
+    // Chunks that are not loaded are transparent
+    f_color = color.a > 0.0 ? color : BACKGROUND;
+}
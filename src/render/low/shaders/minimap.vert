@@ -0,0 +1,23 @@
+// minimap.vert
+#version 450
+
+layout(location=0) out vec2 v_tex_coords;
+
+layout(set=0, binding=0)
+uniform Minimap {
+    vec4 u_rect;
+    vec4 u_heading;
+};
+
+// Two triangles covering the rectangle, as corners from the top left
+const vec2 CORNERS[6] = vec2[6](
+    vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0),
+    vec2(1.0, 1.0), vec2(0.0, 1.0), vec2(0.0, 0.0)
+);
+
+void main() {
+    vec2 corner = CORNERS[gl_VertexIndex];
+    v_tex_coords = corner;
+
+    gl_Position = vec4(mix(u_rect.xy, u_rect.zw, corner), 0.0, 1.0);
+}
@@ -1 +1,3 @@
-pub mod shaders;
\ No newline at end of file
+pub mod shaders;
+#[cfg(all(debug_assertions, feature = "hot-reload"))]
+pub mod hotreload;
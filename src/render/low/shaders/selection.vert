@@ -0,0 +1,46 @@
+// selection.vert
+#version 450
+
+layout(location=0) flat out vec4 v_color;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+layout(set=1, binding=0)
+uniform Selection {
+    vec4 u_first;
+    vec4 u_second;
+};
+
+// The twelve edges of a box, between corners numbered by their x, y and z bits
+const int EDGES[24] = int[24](
+    0, 1, 2, 3, 4, 5, 6, 7,
+    0, 2, 1, 3, 4, 6, 5, 7,
+    0, 4, 1, 5, 2, 6, 3, 7
+);
+
+void main() {
+    // Instance 0 is the box between the corners, 1 and 2 are the corner blocks
+    vec3 low;
+    vec3 high;
+    if (gl_InstanceIndex == 0) {
+        low = min(u_first.xyz, u_second.xyz);
+        high = max(u_first.xyz, u_second.xyz) + 1.0;
+        v_color = vec4(1.0, 0.85, 0.2, 1.0);
+    } else {
+        low = gl_InstanceIndex == 1 ? u_first.xyz : u_second.xyz;
+        high = low + 1.0;
+        v_color = gl_InstanceIndex == 1 ? vec4(1.0, 0.3, 0.3, 1.0) : vec4(0.3, 0.5, 1.0, 1.0);
+    }
+
+    // Slightly bigger, so the lines are not inside the faces of the blocks
+    low -= 0.005;
+    high += 0.005;
+
+    int corner = EDGES[gl_VertexIndex];
+    vec3 position = mix(low, high, vec3(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1));
+
+    gl_Position = u_view_proj * vec4(position, 1.0);
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Result, bail};
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use crate::resources::resourcemanager::ResourceManager;
+#[cfg(all(debug_assertions, feature = "hot-reload"))]
+use crate::render::low::shaders::hotreload::HotReload;
+
+/// Hands out the shaders pipelines are created from. Release builds use the
+/// shaders embedded in the binary, unless a resource pack replaces them with
+/// its own SPIR-V. Debug builds also compile GLSL and WGSL sources when they
+/// change on disk and tell which pipelines have to be created again.
+pub struct ShaderManager {
+    /// Which pipelines use a shader, so only those are rebuilt when it changes
+    users: RefCell<HashMap<String, HashSet<TypeId>>>,
+    /// The pipeline that is being created at the moment
+    building: Cell<Option<TypeId>>,
+    /// The last compile error of every shader that currently fails to compile
+    pub errors: HashMap<String, String>,
+
+    #[cfg(all(debug_assertions, feature = "hot-reload"))]
+    hot_reload: Option<HotReload>,
+}
+
+impl ShaderManager {
+    #[allow(unused_variables)]
+    pub fn new(resources: &ResourceManager) -> Self {
+        Self {
+            users: RefCell::new(HashMap::new()),
+            building: Cell::new(None),
+            errors: HashMap::new(),
+
+            #[cfg(all(debug_assertions, feature = "hot-reload"))]
+            hot_reload: match HotReload::new(resources) {
+                Ok(hot_reload) => Some(hot_reload),
+                Err(e) => {
+                    println!("Shader hot reloading is disabled: {:?}", e);
+                    None
+                }
+            },
+        }
+    }
+
+    /// The resource packs changed. Which pipelines use which shader is kept
+    /// and so are the shaders that were compiled while the game ran, they are
+    /// compiled again in case the new packs have their own source.
+    #[allow(unused_variables)]
+    pub fn set_packs(&mut self, resources: &ResourceManager) {
+        #[cfg(all(debug_assertions, feature = "hot-reload"))]
+        {
+            if let Some(hot_reload) = &mut self.hot_reload {
+                if let Err(e) = hot_reload.set_packs(resources) {
+                    println!("Could not watch the shaders of the new packs: {:?}", e);
+                }
+
+                for name in hot_reload.compiled() {
+                    match hot_reload.compile(&name) {
+                        Ok(()) => {
+                            self.errors.remove(&name);
+                        }
+                        Err(e) => {
+                            println!("Could not compile shader {}:\n{:?}", name, e);
+                            self.errors.insert(name, format!("{:?}", e));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Shaders that are loaded until end_pipeline is called belong to this pipeline
+    pub fn start_pipeline(&self, pipeline: TypeId) {
+        self.building.set(Some(pipeline));
+    }
+
+    pub fn end_pipeline(&self) {
+        self.building.set(None);
+    }
+
+    /// Gets the shader `name`, for example "chunk.vert". Falls back
+    /// to the embedded shader if there is no replacement.
+    pub fn load(
+        &self,
+        resources: &ResourceManager,
+        name: &str,
+        embedded: wgpu::ShaderModuleDescriptor<'static>,
+    ) -> wgpu::ShaderModuleDescriptor<'static> {
+        if let Some(pipeline) = self.building.get() {
+            self.users.borrow_mut().entry(name.to_string()).or_default().insert(pipeline);
+        }
+
+        #[cfg(all(debug_assertions, feature = "hot-reload"))]
+        {
+            if let Some(shader) = self.hot_reload.as_ref().and_then(|h| h.get(name)) {
+                return shader
+            }
+        }
+
+        let asset = format!("shaders/{}.spv", name);
+
+        let words = resources.read_optional(&asset).and_then(|bytes| match bytes {
+            Some(bytes) => spirv_words(&bytes).map(Some).with_context(|| format!("Invalid shader {}", asset)),
+            None => Ok(None),
+        });
+
+        match words {
+            Ok(Some(words)) => wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::SpirV(Cow::Owned(words)),
+                flags: embedded.flags,
+            },
+            Ok(None) => embedded,
+            Err(e) => {
+                println!("{:?}, using the built in shader", e);
+                embedded
+            }
+        }
+    }
+
+    /// Compiles shaders whose source changed since the last call. Returns the
+    /// pipelines that use a shader that compiled, these have to be created again.
+    pub fn poll(&mut self) -> HashSet<TypeId> {
+        #[allow(unused_mut)]
+        let mut rebuild = HashSet::new();
+
+        #[cfg(all(debug_assertions, feature = "hot-reload"))]
+        {
+            if let Some(hot_reload) = &mut self.hot_reload {
+                let users = self.users.borrow();
+
+                for name in hot_reload.changed() {
+                    // Only shaders that are used by a pipeline
+                    let pipelines = match users.get(&name) {
+                        Some(pipelines) => pipelines,
+                        None => continue,
+                    };
+
+                    match hot_reload.compile(&name) {
+                        Ok(()) => {
+                            println!("Reloaded shader {}", name);
+                            self.errors.remove(&name);
+                            rebuild.extend(pipelines.iter().copied());
+                        }
+                        Err(e) => {
+                            println!("Could not compile shader {}:\n{:?}", name, e);
+                            self.errors.insert(name, format!("{:?}", e));
+                        }
+                    }
+                }
+            }
+        }
+
+        rebuild
+    }
+
+    /// A pipeline could not be created from its shaders even though they
+    /// compiled, the error is shown for each of them until they change again
+    pub fn pipeline_failed(&mut self, pipeline: TypeId, error: &str) {
+        let users = self.users.borrow();
+        let names = users.iter().filter(|(_, pipelines)| pipelines.contains(&pipeline)).map(|(name, _)| name);
+
+        for name in names {
+            self.errors.insert(name.clone(), error.to_string());
+        }
+    }
+
+    /// A single line describing the compile errors, if there are any
+    pub fn error_summary(&self) -> Option<String> {
+        let mut names: Vec<&String> = self.errors.keys().collect();
+        names.sort();
+
+        match names.first() {
+            None => None,
+            Some(name) => {
+                let first_line = self.errors[*name].lines().next().unwrap_or("").to_string();
+
+                Some(format!("{} shader error(s), {}: {}", names.len(), name, first_line))
+            }
+        }
+    }
+}
+
+/// Turns the bytes of a SPIR-V file into words
+pub fn spirv_words(bytes: &[u8]) -> Result<Vec<u32>> {
+    const MAGIC: u32 = 0x07230203;
+
+    if bytes.len() % 4 != 0 || bytes.len() < 4 {
+        bail!("Length is not a multiple of 4");
+    }
+
+    let words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+        .collect();
+
+    if words[0] != MAGIC {
+        bail!("Not a SPIR-V file");
+    }
+
+    Ok(words)
+}
@@ -0,0 +1,25 @@
+// shadow.vert
+#version 450
+
+// Packed vertex, see ChunkVertex in vertex.rs for the layout
+layout(location=0) in uvec2 a_data;
+
+layout(set=0, binding=0)
+uniform Cascade {
+    mat4 u_light_view_proj;
+};
+
+layout(set=1, binding=3) 
+uniform ChunkUniform {
+    vec3 chunkPosition; 
+};
+
+void main() {
+    uint x = a_data.x & 63u;
+    uint y = (a_data.x >> 6) & 127u;
+    uint z = (a_data.x >> 13) & 63u;
+
+    vec3 position = vec3(float(x), float(y), float(z)) + chunkPosition;
+
+    gl_Position = u_light_view_proj * vec4(position, 1.0);
+}
@@ -0,0 +1,41 @@
+// sky.frag
+#version 450
+
+layout(location=0) in vec2 v_screen;
+layout(location=0) out vec4 f_color;
+
+layout(set=0, binding=0)
+uniform Sky {
+    mat4 u_inv_view_proj;
+    vec4 u_zenith_color;
+    vec4 u_horizon_color;
+    vec4 u_sun_direction;
+    vec4 u_camera_position;
+    float u_sky_light;
+    float u_fog_start;
+    float u_fog_end;
+};
+
+const vec3 SUN_COLOR = vec3(1.0, 0.9, 0.7);
+const vec3 MOON_COLOR = vec3(0.75, 0.78, 0.85);
+
+void main() {
+    // The direction this pixel looks in
+    vec4 far = u_inv_view_proj * vec4(v_screen, 1.0, 1.0);
+    vec3 direction = normalize(far.xyz / far.w - u_camera_position.xyz);
+
+    // Below the horizon everything is fog coloured, like the edge of the world
+    float height = max(direction.y, 0.0);
+    vec3 color = mix(u_horizon_color.rgb, u_zenith_color.rgb, smoothstep(0.0, 0.5, height));
+
+    // Sun and moon are on opposite sides of the sky and set behind the horizon
+    float above_horizon = smoothstep(-0.02, 0.02, direction.y);
+    float sun = dot(direction, u_sun_direction.xyz);
+    float moon = dot(direction, -u_sun_direction.xyz);
+
+    color += SUN_COLOR * smoothstep(0.9990, 0.9993, sun) * above_horizon;
+    color += SUN_COLOR * pow(max(sun, 0.0), 64.0) * 0.25 * above_horizon;
+    color += MOON_COLOR * smoothstep(0.9995, 0.9997, moon) * above_horizon;
+
+    f_color = vec4(color, 1.0);
+}
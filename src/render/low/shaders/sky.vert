@@ -0,0 +1,16 @@
+// sky.vert
+#version 450
+
+layout(location=0) out vec2 v_screen;
+
+void main() {
+    // A single triangle that covers the whole screen
+    vec2 positions[3] = vec2[3](
+        vec2(-1.0, -1.0),
+        vec2(3.0, -1.0),
+        vec2(-1.0, 3.0)
+    );
+
+    v_screen = positions[gl_VertexIndex];
+    gl_Position = vec4(v_screen, 1.0, 1.0);
+}
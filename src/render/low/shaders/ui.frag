@@ -0,0 +1,26 @@
+// ui.frag
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=1) in vec4 v_color;
+layout(location=2) in float v_texture;
+layout(location=0) out vec4 f_color;
+
+layout(set = 1, binding = 0) uniform texture2D t_font;
+layout(set = 1, binding = 1) uniform sampler s_font;
+
+layout(set = 2, binding = 0) uniform texture2DArray t_blocks;
+layout(set = 2, binding = 1) uniform sampler s_blocks;
+
+void main() {
+    vec4 color;
+
+    // Negative for text and plain rectangles, otherwise a block texture layer
+    if (v_texture < 0.0) {
+        color = texture(sampler2D(t_font, s_font), v_tex_coords);
+    } else {
+        color = texture(sampler2DArray(t_blocks, s_blocks), vec3(v_tex_coords, v_texture));
+    }
+
+    f_color = color * v_color;
+}
@@ -0,0 +1,25 @@
+// ui.vert
+#version 450
+
+layout(location=0) in vec2 a_position;
+layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec4 a_color;
+layout(location=3) in float a_texture;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
+layout(location=2) out float v_texture;
+
+layout(set=0, binding=0)
+uniform Ui {
+    mat4 u_projection;
+};
+
+void main() {
+    v_tex_coords = a_tex_coords;
+    // UI colours are given in sRGB, the swap chain converts from linear
+    v_color = vec4(pow(a_color.rgb, vec3(2.2)), a_color.a);
+    v_texture = a_texture;
+
+    gl_Position = u_projection * vec4(a_position, 0.0, 1.0);
+}
@@ -3,34 +3,30 @@ use anyhow::*;
 use cgmath::Point2;
 use image;
 
+use crate::resources::resourcemanager::ResourceManager;
+
+/// Where the block texture atlas is found in a resource pack
+pub const TERRAIN_TEXTURE: &str = "textures/terrain.png";
+
 pub const TEXTURE_WIDTH: u32 = 16;
 pub const TEXTURE_HEIGHT: u32 = 16;
 
 pub const TEXTURE_IMAGE_HEIGHT: u32 = 256;
 pub const TEXTURE_IMAGE_WIDTH: u32 = 256;
+/// Amount of tiles in a single row of the atlas
+pub const TEXTURE_ATLAS_COLUMNS: u32 = TEXTURE_IMAGE_WIDTH / TEXTURE_WIDTH;
 
 
-// Helper function
-pub fn coords_to_float(coords: [u32; 2]) -> [f32; 2] {
-    [
-        coords[0] as f32 / TEXTURE_IMAGE_WIDTH as f32,
-        coords[1] as f32 / TEXTURE_IMAGE_HEIGHT as f32,
-    ]
-}
-
 #[derive(Debug, Copy, Clone)]
 pub struct TextureTile {
     pub coords: Point2<u32>, // Index coord of texture
 }
 
 impl TextureTile {
-    pub fn to_usable(&self) -> [[f32; 2]; 4] {
-        [
-            coords_to_float([self.coords.x * TEXTURE_WIDTH + TEXTURE_WIDTH, self.coords.y * TEXTURE_HEIGHT]),
-            coords_to_float([self.coords.x * TEXTURE_WIDTH, self.coords.y * TEXTURE_HEIGHT]),
-            coords_to_float([self.coords.x * TEXTURE_WIDTH, self.coords.y * TEXTURE_HEIGHT + TEXTURE_HEIGHT]),
-            coords_to_float([self.coords.x * TEXTURE_WIDTH + TEXTURE_WIDTH, self.coords.y * TEXTURE_HEIGHT + TEXTURE_HEIGHT]),
-        ]
+    /// The index of the tile in the atlas, counting row by row. This is also
+    /// its layer in the block texture array and what gets packed into a ChunkVertex
+    pub fn index(&self) -> u32 {
+        self.coords.x + self.coords.y * TEXTURE_ATLAS_COLUMNS
     }
 
     pub const fn new(x: u32, y: u32) -> Self {
@@ -42,8 +38,11 @@ impl TextureTile {
 
 pub struct TextureManager {
     pub textures: Vec<wgpu::BindGroup>, // Todo support multple textures
+    /// Every tile of the atlas as a layer of a texture array, used for the blocks
+    pub block_textures: Option<wgpu::BindGroup>,
 
     pub texture_bind_group_layout: wgpu::BindGroupLayout,
+    pub block_texture_bind_group_layout: wgpu::BindGroupLayout,
 }
 
 impl TextureManager {
@@ -75,16 +74,45 @@ impl TextureManager {
             }
         );
 
+        let block_texture_bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            sample_type: wgpu::TextureSampleType::Float {filterable: true,},
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler {
+                            comparison: false,
+                            filtering: true,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("block_texture_bind_group_layout"),
+            }
+        );
+
         let textures = Vec::<wgpu::BindGroup>::new();
         
         Self {
             textures,
+            block_textures: None,
             texture_bind_group_layout,
+            block_texture_bind_group_layout,
         }
     }
 
-    pub fn load(&mut self, path: &str, device: &wgpu::Device, queue: &wgpu::Queue) {
-        let diffuse_texture = Texture::from_image(&device, &queue, path, Some("A texture")).unwrap();
+    pub fn load(&mut self, resources: &ResourceManager, asset: &str, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<()> {
+        let diffuse_texture = Texture::from_image(device, queue, &resources.load_image(asset)?, Some(asset));
 
         let diffuse_bind_group = device.create_bind_group(
             &wgpu::BindGroupDescriptor {
@@ -104,11 +132,46 @@ impl TextureManager {
         );
 
         self.textures.push(diffuse_bind_group);
+
+        Ok(())
+    }
+
+    /// Splits the atlas into tiles and loads them as the block texture array
+    pub fn load_blocks(&mut self, resources: &ResourceManager, asset: &str, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<()> {
+        let atlas = resources.load_image(asset)?;
+        let block_texture = Texture::array_from_atlas(device, queue, &atlas, [TEXTURE_WIDTH, TEXTURE_HEIGHT], Some("Block textures"))
+            .with_context(|| format!("Could not create block textures from {}", asset))?;
+
+        let block_bind_group = device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                layout: &self.block_texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&block_texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&block_texture.sampler),
+                    }
+                ],
+                label: Some("block_bind_group"),
+            }
+        );
+
+        self.block_textures = Some(block_bind_group);
+
+        Ok(())
     }
 
     pub fn get_bind_group(&self) -> &wgpu::BindGroup {
         &self.textures[0]
     }
+
+    /// Panics if no block textures were loaded
+    pub fn get_block_bind_group(&self) -> &wgpu::BindGroup {
+        self.block_textures.as_ref().expect("Block textures were not loaded")
+    }
 }
 
 
@@ -122,12 +185,10 @@ impl Texture {
     pub fn from_image(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        path: &str,
+        img: &image::DynamicImage,
         label: Option<&str>
-    ) -> Result<Self> {
-        let img = image::open(path).unwrap();
-
-        let rgba = img.as_rgba8().unwrap();
+    ) -> Self {
+        let rgba = img.to_rgba8();
         let dimensions = img.dimensions();
 
         let size = wgpu::Extent3d {
@@ -153,7 +214,7 @@ impl Texture {
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
             },
-            rgba,
+            &rgba,
             wgpu::TextureDataLayout {
                 offset: 0,
                 bytes_per_row: 4 * dimensions.0,
@@ -175,6 +236,133 @@ impl Texture {
             }
         );
         
+        Self { texture, view, sampler }
+    }
+
+    /// Replaces the pixels of a texture made with from_image. The image has
+    /// to be as large as the texture.
+    pub fn write(&self, queue: &wgpu::Queue, img: &image::RgbaImage) {
+        let (width, height) = img.dimensions();
+
+        queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            img,
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: 4 * width,
+                rows_per_image: height,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+        );
+    }
+
+    /// Creates a texture array from an atlas with one layer per tile, counting
+    /// row by row like TextureTile::index. Every layer gets a full mip chain
+    /// and the sampler repeats, so faces bigger than one block can tile.
+    pub fn array_from_atlas(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &image::DynamicImage,
+        tile_size: [u32; 2],
+        label: Option<&str>
+    ) -> Result<Self> {
+        let img = atlas.to_rgba8();
+        let dimensions = img.dimensions();
+
+        if dimensions.0 % tile_size[0] != 0 || dimensions.1 % tile_size[1] != 0 {
+            bail!("Atlas of {}x{} can not be divided into tiles of {}x{}", dimensions.0, dimensions.1, tile_size[0], tile_size[1]);
+        }
+
+        let columns = dimensions.0 / tile_size[0];
+        let rows = dimensions.1 / tile_size[1];
+        let layers = columns * rows;
+
+        // Halve until one of the sides is a single pixel
+        let mip_level_count = 32 - tile_size[0].min(tile_size[1]).leading_zeros();
+
+        let texture = device.create_texture(
+            &wgpu::TextureDescriptor {
+                label,
+                size: wgpu::Extent3d {
+                    width: tile_size[0],
+                    height: tile_size[1],
+                    depth: layers,
+                },
+                mip_level_count,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            }
+        );
+
+        for layer in 0..layers {
+            let tile = image::imageops::crop_imm(
+                &img,
+                (layer % columns) * tile_size[0],
+                (layer / columns) * tile_size[1],
+                tile_size[0],
+                tile_size[1],
+            ).to_image();
+
+            for level in 0..mip_level_count {
+                let width = (tile_size[0] >> level).max(1);
+                let height = (tile_size[1] >> level).max(1);
+
+                // Every level is sampled down from the full tile
+                let mip = if level == 0 {
+                    tile.clone()
+                } else {
+                    image::imageops::resize(&tile, width, height, image::imageops::FilterType::Triangle)
+                };
+
+                queue.write_texture(
+                    wgpu::TextureCopyView {
+                        texture: &texture,
+                        mip_level: level,
+                        origin: wgpu::Origin3d {x: 0, y: 0, z: layer},
+                    },
+                    &mip,
+                    wgpu::TextureDataLayout {
+                        offset: 0,
+                        bytes_per_row: 4 * width,
+                        rows_per_image: height,
+                    },
+                    wgpu::Extent3d {
+                        width,
+                        height,
+                        depth: 1,
+                    },
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label,
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(
+            &wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                // Keep the pixels sharp up close but blend them in the distance
+                mag_filter: wgpu::FilterMode::Nearest,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }
+        );
+
         Ok(Self { texture, view, sampler })
     }
-}
\ No newline at end of file
+}
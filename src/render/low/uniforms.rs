@@ -28,7 +28,7 @@ impl<K: Hash + Eq + Copy, T: bytemuck::Pod + bytemuck::Zeroable> MultiUniform<K,
         let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
-                    binding: binding,
+                    binding,
                     visibility: wgpu::ShaderStage::VERTEX,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
@@ -52,7 +52,7 @@ impl<K: Hash + Eq + Copy, T: bytemuck::Pod + bytemuck::Zeroable> MultiUniform<K,
             layout: &uniform_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
-                    binding: binding,
+                    binding,
                     resource: wgpu::BindingResource::Buffer {
                         buffer: &buffer,
                         offset: 0,
@@ -96,7 +96,6 @@ impl<K: Hash + Eq + Copy, T: bytemuck::Pod + bytemuck::Zeroable> MultiUniform<K,
         self.offset.remove(at);
     }
     
-    #[allow(dead_code)]
     pub fn modify(&mut self, queue: &wgpu::Queue, at: K, data: T) {
         let offset = self.offset.get(&at).unwrap();
 
@@ -132,8 +131,8 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> Uniform<T> {
         let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
-                    binding: binding,
-                    visibility: wgpu::ShaderStage::VERTEX,
+                    binding,
+                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -149,7 +148,7 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> Uniform<T> {
             layout: &uniform_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
-                    binding: binding,
+                    binding,
                     resource: wgpu::BindingResource::Buffer {
                         buffer: &uniform_buffer,
                         offset: 0,
@@ -204,4 +203,157 @@ impl CameraUniform {
     pub fn update_view_proj(&mut self, data: cgmath::Matrix4<f32>) {
         self.view_proj = data.into();
     }
+}
+
+/// Everything shaders need to know about the sky, lighting and fog
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkyUniform {
+    /// For turning screen positions back into view directions
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub zenith_color: [f32; 4],
+    /// Also the colour of the fog
+    pub horizon_color: [f32; 4],
+    pub sun_direction: [f32; 4],
+    pub camera_position: [f32; 4],
+    /// Multiplier for the sky light of blocks
+    pub sky_light: f32,
+    pub fog_start: f32,
+    pub fog_end: f32,
+    _padding: f32,
+}
+
+impl SkyUniform {
+    pub fn new() -> Self {
+        use cgmath::SquareMatrix;
+        Self {
+            inv_view_proj: cgmath::Matrix4::identity().into(),
+            zenith_color: [0.0; 4],
+            horizon_color: [0.0; 4],
+            sun_direction: [0.0, 1.0, 0.0, 0.0],
+            camera_position: [0.0; 4],
+            sky_light: 1.0,
+            fog_start: 0.0,
+            fog_end: 0.0,
+            _padding: 0.0,
+        }
+    }
+}
+
+/// The projection from the light for a single shadow cascade
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CascadeUniform {
+    pub light_view_proj: [[f32; 4]; 4],
+}
+
+impl CascadeUniform {
+    pub fn new() -> Self {
+        use cgmath::SquareMatrix;
+        Self {
+            light_view_proj: cgmath::Matrix4::identity().into(),
+        }
+    }
+}
+
+/// What the chunk shaders need for sampling the shadow maps
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowUniform {
+    pub light_view_proj: [[[f32; 4]; 4]; 4],
+    /// The view depth at which every cascade ends
+    pub cascade_splits: [f32; 4],
+    /// Towards the sun or moon
+    pub light_direction: [f32; 4],
+    /// 0 when shadows are disabled
+    pub cascade_count: u32,
+    pub strength: f32,
+    /// The size of a texel of the shadow map in texture coordinates
+    pub texel_size: f32,
+    _padding: f32,
+}
+
+impl ShadowUniform {
+    pub fn new() -> Self {
+        use cgmath::SquareMatrix;
+        let identity: [[f32; 4]; 4] = cgmath::Matrix4::identity().into();
+        Self {
+            light_view_proj: [identity; 4],
+            cascade_splits: [0.0; 4],
+            light_direction: [0.0, 1.0, 0.0, 0.0],
+            cascade_count: 0,
+            strength: 0.0,
+            texel_size: 0.0,
+            _padding: 0.0,
+        }
+    }
+}
+/// Turns pixels from the top left of the window into clip space
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UiUniform {
+    pub projection: [[f32; 4]; 4],
+}
+
+impl UiUniform {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            projection: cgmath::ortho(0.0, width, height, 0.0, -1.0, 1.0).into(),
+        }
+    }
+}
+
+/// The face of a block the breaking cracks are drawn on
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BreakingUniform {
+    /// The lowest corner of the block, w is the layer of the crack texture
+    pub block: [f32; 4],
+    /// Points out of the face
+    pub normal: [f32; 4],
+}
+
+impl BreakingUniform {
+    pub fn new() -> Self {
+        Self {
+            block: [0.0; 4],
+            normal: [0.0, 1.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// The corners of the selection, the lowest corner of each block
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SelectionUniform {
+    pub first: [f32; 4],
+    pub second: [f32; 4],
+}
+
+impl SelectionUniform {
+    pub fn new() -> Self {
+        Self {
+            first: [0.0; 4],
+            second: [0.0; 4],
+        }
+    }
+}
+
+/// Where the minimap is on the screen and which way the player looks on it
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MinimapUniform {
+    /// Left, top, right and bottom in normalized device coordinates
+    pub rect: [f32; 4],
+    /// x and z of the direction the player looks in, then the map size in blocks
+    pub heading: [f32; 4],
+}
+
+impl MinimapUniform {
+    pub fn new() -> Self {
+        Self {
+            rect: [0.0; 4],
+            heading: [1.0, 0.0, 1.0, 0.0],
+        }
+    }
 }
\ No newline at end of file
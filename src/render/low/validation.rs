@@ -0,0 +1,42 @@
+use std::sync::{Arc, Mutex};
+
+/// Catches the errors of wgpu calls that may fail, like creating a pipeline
+/// from a shader that was just edited. wgpu 0.7 has no error scopes, so this
+/// takes over the uncaptured error handler of the device. Errors outside of
+/// a scope are still fatal, the same as wgpu's own handler.
+#[derive(Clone)]
+pub struct ValidationScope {
+    /// The errors of the open scope, None when no scope is open
+    errors: Arc<Mutex<Option<Vec<String>>>>,
+}
+
+impl ValidationScope {
+    pub fn install(device: &wgpu::Device) -> Self {
+        let scope = Self {
+            errors: Arc::new(Mutex::new(None)),
+        };
+
+        let errors = scope.errors.clone();
+        device.on_uncaptured_error(move |error| {
+            match errors.lock().unwrap().as_mut() {
+                Some(errors) => errors.push(error.to_string()),
+                None => {
+                    eprintln!("wgpu error: {}\n", error);
+                    panic!("Handling wgpu errors as fatal by default");
+                }
+            }
+        });
+
+        scope
+    }
+
+    /// Errors are collected until pop is called
+    pub fn push(&self) {
+        *self.errors.lock().unwrap() = Some(vec![]);
+    }
+
+    /// The errors since push, empty if everything went fine
+    pub fn pop(&self) -> Vec<String> {
+        self.errors.lock().unwrap().take().unwrap_or_default()
+    }
+}
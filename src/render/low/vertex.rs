@@ -25,4 +25,214 @@ impl Vertex {
             ]
         }
     }
-}
\ No newline at end of file
+}
+
+/// A chunk vertex packed into two u32's (8 bytes). Decoded in chunk.vert,
+/// so the bit layout here must match the one in the shader.
+///
+/// data[0]: x (6 bits) | y (7 bits) | z (6 bits) | normal (3 bits) | corner (2 bits) | ao (2 bits)
+/// data[1]: texture layer (16 bits) | light (8 bits, sky light high nibble, block light low nibble)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ChunkVertex {
+    pub data: [u32; 2],
+}
+
+impl ChunkVertex {
+    /// x, y and z are local to the chunk and may be CHUNKSIZE or WORLDHEIGHT
+    /// because a vertex sits on the far edge of its block.
+    pub fn new(position: [u32; 3], normal: u32, corner: u32, ao: u32, texture: u32, light: u32) -> Self {
+        debug_assert!(position[0] < 64 && position[1] < 128 && position[2] < 64);
+        debug_assert!(normal < 6 && corner < 4 && ao < 4);
+
+        Self {
+            data: [
+                position[0]
+                    | position[1] << 6
+                    | position[2] << 13
+                    | normal << 19
+                    | corner << 22
+                    | ao << 24,
+                (texture & 0xFFFF) | (light & 0xFF) << 16,
+            ],
+        }
+    }
+
+    /// Local to the chunk, the same as given to new
+    pub fn position(&self) -> [u32; 3] {
+        [self.data[0] & 63, (self.data[0] >> 6) & 127, (self.data[0] >> 13) & 63]
+    }
+
+    /// Which way the face points, in the order of Sides
+    pub fn normal(&self) -> u32 {
+        (self.data[0] >> 19) & 7
+    }
+
+    /// Which corner of the face it is, for the texture coordinates
+    pub fn corner(&self) -> u32 {
+        (self.data[0] >> 22) & 3
+    }
+
+    /// How many blocks around the corner darken it
+    pub fn ao(&self) -> u32 {
+        (self.data[0] >> 24) & 3
+    }
+
+    /// The layer in the block texture array, also the index of the tile in the atlas
+    pub fn texture(&self) -> u32 {
+        self.data[1] & 0xFFFF
+    }
+
+    /// Sky light in the high nibble, block light in the low one
+    pub fn light(&self) -> u32 {
+        (self.data[1] >> 16) & 0xFF
+    }
+
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ChunkVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Uint2,
+                },
+            ]
+        }
+    }
+}
+
+/// A vertex of the 2D UI, in pixels from the top left of the window
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UiVertex {
+    pub position: [f32; 2],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 4],
+    /// Layer of the block textures, or negative for the font atlas
+    pub texture: f32,
+}
+
+impl UiVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<UiVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float,
+                },
+            ]
+        }
+    }
+}
+
+/// One entity drawn as a textured box, see EntityDrawable. Every
+/// instance is a cube of 36 vertices generated in entity.vert.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct EntityInstance {
+    /// The center of the bottom of the box
+    pub position: [f32; 3],
+    pub size: [f32; 3],
+    /// In degrees
+    pub yaw: f32,
+    /// Texture layers in the order of Sides
+    pub textures: [u32; 6],
+}
+
+impl EntityInstance {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<EntityInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Uint3,
+                },
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unpack(vertex: ChunkVertex) -> ([u32; 3], u32, u32, u32, u32, u32) {
+        (vertex.position(), vertex.normal(), vertex.corner(), vertex.ao(), vertex.texture(), vertex.light())
+    }
+
+    #[test]
+    fn chunk_vertices_survive_packing_at_their_maximum() {
+        let max = ([63, 127, 63], 5, 3, 3, 0xFFFF, 0xFF);
+        let vertex = ChunkVertex::new(max.0, max.1, max.2, max.3, max.4, max.5);
+        assert_eq!(unpack(vertex), max);
+
+        // Every field on its own, so none of them spills into another
+        let zero = ([0; 3], 0, 0, 0, 0, 0);
+        let fields = [
+            ([63, 0, 0], 0, 0, 0, 0, 0),
+            ([0, 127, 0], 0, 0, 0, 0, 0),
+            ([0, 0, 63], 0, 0, 0, 0, 0),
+            ([0; 3], 5, 0, 0, 0, 0),
+            ([0; 3], 0, 3, 0, 0, 0),
+            ([0; 3], 0, 0, 3, 0, 0),
+            ([0; 3], 0, 0, 0, 0xFFFF, 0),
+            ([0; 3], 0, 0, 0, 0, 0xFF),
+        ];
+        for field in fields.iter().copied().chain(std::iter::once(zero)) {
+            let vertex = ChunkVertex::new(field.0, field.1, field.2, field.3, field.4, field.5);
+            assert_eq!(unpack(vertex), field);
+        }
+
+        // The edges of a chunk, which are the largest positions meshing makes
+        let edge = ChunkVertex::new([32, 64, 32], 4, 2, 1, 300, 0xF3);
+        assert_eq!(unpack(edge), ([32, 64, 32], 4, 2, 1, 300, 0xF3));
+    }
+
+    #[test]
+    fn chunk_vertices_are_eight_bytes() {
+        assert_eq!(std::mem::size_of::<ChunkVertex>(), 8);
+    }
+}
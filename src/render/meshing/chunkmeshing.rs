@@ -1,11 +1,10 @@
 use crate::render::{
-    vertexarray::VertexArray,
-    shapes::shapes::Quad,
+    low::vertex::ChunkVertex,
     meshing::meshing::*,
 };
 use crate::world::{
     chunk::chunk::{Chunk, index_to_coord},
-    chunk::chunkmanager::ChunkManager,
+    chunk::chunkstore::ChunkStore,
     constants::*,
     block::blocks::{get_block, BlockID, Blocks, Sides},
     chunk::pos::*,
@@ -20,8 +19,12 @@ impl ChunkMesh {
         Self {mesh: Mesh::new()}
     }
 
-    pub fn to_vertex_array(&self) -> &VertexArray<Quad> {
-        &self.mesh.quads
+    pub fn vertices(&self) -> &[ChunkVertex] {
+        &self.mesh.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.mesh.indices
     }
 
     #[allow(dead_code)]
@@ -40,36 +43,15 @@ impl ChunkMesh {
                 continue
             }
 
-            mesh.add_face(MeshFace {
-                coordinate: [x, y, z],
-                face: Sides::RIGHT,
-                blocktype: b,
-            });
-            mesh.add_face(MeshFace {
-                coordinate: [x, y, z],
-                face: Sides::FRONT,
-                blocktype: b,
-            });
-            mesh.add_face(MeshFace {
-                coordinate: [x, y, z],
-                face: Sides::BACK,
-                blocktype: b,
-            });
-            mesh.add_face(MeshFace {
-                coordinate: [x, y, z],
-                face: Sides::LEFT,
-                blocktype: b,
-            });
-            mesh.add_face(MeshFace {
-                coordinate: [x, y, z],
-                face: Sides::TOP,
-                blocktype: b,
-            });
-            mesh.add_face(MeshFace {
-                coordinate: [x, y, z],
-                face: Sides::BOTTOM,
-                blocktype: b,
-            });
+            for face in Sides::ALL.iter() {
+                mesh.add_face(MeshFace {
+                    coordinate: [x, y, z],
+                    face: *face,
+                    blocktype: b,
+                    ao: [3; 4],
+                    light: FULL_LIGHT,
+                });
+            }
         }
 
         self.mesh = mesh;
@@ -78,7 +60,7 @@ impl ChunkMesh {
     #[allow(dead_code)]
     /// Creates a culled mesh. Faces that are not adjecent to a transparent
     /// will not be added to the mesh buffer
-    pub fn create_simple_mesh(&mut self, chunk: &Chunk, chunk_manager: &ChunkManager) {
+    pub fn create_simple_mesh(&mut self, chunk: &Chunk, chunk_manager: &ChunkStore) {
 
         let mut mesh = Mesh::new();
 
@@ -94,18 +76,12 @@ impl ChunkMesh {
                 continue
             }
 
-            // Left
-            ChunkMesh::add_if_needed(chunk, &mut mesh, ChunkCoord {x: coord.x + 1, ..coord}, coord, Sides::LEFT, blockid, chunk_manager);
-            // Right
-            ChunkMesh::add_if_needed(chunk, &mut mesh, ChunkCoord {x: coord.x - 1, ..coord}, coord, Sides::RIGHT, blockid, chunk_manager);
-            // Top
-            ChunkMesh::add_if_needed(chunk, &mut mesh, ChunkCoord {y: coord.y + 1, ..coord}, coord, Sides::TOP, blockid, chunk_manager);
-            // Bottom
-            ChunkMesh::add_if_needed(chunk, &mut mesh, ChunkCoord {y: coord.y - 1, ..coord}, coord, Sides::BOTTOM, blockid, chunk_manager);
-            // Back
-            ChunkMesh::add_if_needed(chunk, &mut mesh, ChunkCoord {z: coord.z + 1, ..coord}, coord, Sides::BACK, blockid, chunk_manager);
-            // Front
-            ChunkMesh::add_if_needed(chunk, &mut mesh, ChunkCoord {z: coord.z - 1, ..coord}, coord, Sides::FRONT, blockid, chunk_manager);        
+            // Every face that is next to a transparent block
+            for face in Sides::ALL.iter() {
+                let normal = FACES[*face as usize].normal;
+                let neighbor = ChunkCoord {x: coord.x + normal[0], y: coord.y + normal[1], z: coord.z + normal[2]};
+                ChunkMesh::add_if_needed(chunk, &mut mesh, neighbor, coord, *face, blockid, chunk_manager);
+            }
         }
 
         self.mesh = mesh;
@@ -118,35 +94,267 @@ impl ChunkMesh {
         coord: ChunkCoord,
         face: Sides,
         block: BlockID,
-        manager: &ChunkManager,
+        manager: &ChunkStore,
     ) {
-
-        let blockid: BlockID;
-
-        // If in bounds, get just get it from the current chunk (faster)
-        if Chunk::in_bounds(neighbor_block) {
-            blockid = chunk.at_coord(neighbor_block);
-
-        // If not in bounds, request the block from the chunkmanager
-        } else {
-            // println!("Block {:?} in chunk {:?}", neighbor_block, chunk.pos);
-            blockid = manager.get_block_at_coord(WorldCoord::from_chunk_pos(chunk.pos, neighbor_block)).unwrap_or(Blocks::AIR as BlockID)
-        }
-        
-        if get_block(blockid).transparent {
+        if !ChunkMesh::is_solid(chunk, [neighbor_block.x, neighbor_block.y, neighbor_block.z], manager) {
             mesh.add_face(MeshFace {
                 coordinate: [coord.x as u32, coord.y as u32, coord.z as u32],
-                face: face,
+                face,
                 blocktype: block,
+                ao: ChunkMesh::face_ao(chunk, coord, face, manager),
+                light: FULL_LIGHT,
             });
         }
     }
 
+    /// Calculates the ambient occlusion of the 4 corners of a face. Looks at the two
+    /// blocks next to a corner and the one diagonal to it, in the layer in front of the face.
+    fn face_ao(chunk: &Chunk, coord: ChunkCoord, face: Sides, manager: &ChunkStore) -> [u8; 4] {
+        let face = &FACES[face as usize];
+        let mut ao = [3; 4];
+
+        // The two axes the face lies in
+        let axis = (0..3).position(|axis| face.normal[axis] != 0).unwrap();
+        let tangents = [(axis + 1) % 3, (axis + 2) % 3];
+
+        for (corner, interval) in face.interval.iter().enumerate() {
+            let mut side1 = [coord.x + face.normal[0], coord.y + face.normal[1], coord.z + face.normal[2]];
+            let mut side2 = side1;
+
+            side1[tangents[0]] += if interval[tangents[0]] == 1 {1} else {-1};
+            side2[tangents[1]] += if interval[tangents[1]] == 1 {1} else {-1};
+
+            let mut diagonal = side1;
+            diagonal[tangents[1]] = side2[tangents[1]];
+
+            let side1 = ChunkMesh::is_solid(chunk, side1, manager);
+            let side2 = ChunkMesh::is_solid(chunk, side2, manager);
+            let diagonal = ChunkMesh::is_solid(chunk, diagonal, manager);
+
+            ao[corner] = if side1 && side2 {
+                0
+            } else {
+                3 - (side1 as u8 + side2 as u8 + diagonal as u8)
+            };
+        }
+
+        ao
+    }
+
+    fn is_solid(chunk: &Chunk, coord: [i16; 3], manager: &ChunkStore) -> bool {
+        let coord = ChunkCoord {x: coord[0], y: coord[1], z: coord[2]};
+
+        let blockid = if Chunk::in_bounds(coord) {
+            chunk.at_coord(coord)
+        } else if coord.y < 0 || coord.y >= WORLDHEIGHT as i16 {
+            Blocks::AIR as BlockID
+        } else {
+            manager.get_block_at_coord(WorldCoord::from_chunk_pos(chunk.pos, coord)).unwrap_or(Blocks::AIR as BlockID)
+        };
+
+        !get_block(blockid).transparent
+    }
+
     #[allow(dead_code)]
-    /// Create an optimized mesh where all adjecent block of the same type are
-    /// formed into a single quad. This is only useful if GPU memory usage is high
-    /// because generating this mesh is slower
-    pub fn create_greedy_mesh() {
-        // Todo implement this
+    /// Creates a culled mesh where the faces of the same block that lie next to
+    /// each other are joined into bigger quads. Faces whose corners have different
+    /// ambient occlusion are not joined, so it looks the same as create_simple_mesh.
+    /// Takes longer to create, but has far fewer vertices.
+    pub fn create_greedy_mesh(&mut self, chunk: &Chunk, chunk_manager: &ChunkStore) {
+        let size = [CHUNKSIZE, WORLDHEIGHT, CHUNKSIZE];
+        let mut mesh = Mesh::new();
+
+        for face in Sides::ALL.iter() {
+            let normal = FACES[*face as usize].normal;
+            let axis = (0..3).position(|axis| normal[axis] != 0).unwrap();
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+            for layer in 0..size[axis] {
+                // The visible faces in this layer, row by row along u
+                let mut mask: Vec<Option<(BlockID, [u8; 4])>> = vec![None; size[u] * size[v]];
+
+                for j in 0..size[v] {
+                    for i in 0..size[u] {
+                        let mut position = [0; 3];
+                        position[axis] = layer as i16;
+                        position[u] = i as i16;
+                        position[v] = j as i16;
+
+                        let coord = ChunkCoord {x: position[0], y: position[1], z: position[2]};
+                        let blockid = chunk.at_coord(coord);
+                        if get_block(blockid).transparent {
+                            continue
+                        }
+
+                        let neighbor = [coord.x + normal[0], coord.y + normal[1], coord.z + normal[2]];
+                        if !ChunkMesh::is_solid(chunk, neighbor, chunk_manager) {
+                            mask[i + j * size[u]] = Some((blockid, ChunkMesh::face_ao(chunk, coord, *face, chunk_manager)));
+                        }
+                    }
+                }
+
+                // Grows every face as far as it goes along u, then along v
+                for j in 0..size[v] {
+                    let mut i = 0;
+
+                    while i < size[u] {
+                        let (block, ao) = match mask[i + j * size[u]] {
+                            Some(cell) => cell,
+                            None => {
+                                i += 1;
+                                continue
+                            }
+                        };
+
+                        let joins = ao.iter().all(|&corner| corner == ao[0]);
+                        let same = |cell: Option<(BlockID, [u8; 4])>| joins && cell == Some((block, ao));
+
+                        let mut width = 1;
+                        while i + width < size[u] && same(mask[i + width + j * size[u]]) {
+                            width += 1;
+                        }
+
+                        let mut height = 1;
+                        while j + height < size[v] && (i..i + width).all(|k| same(mask[k + (j + height) * size[u]])) {
+                            height += 1;
+                        }
+
+                        for row in j..j + height {
+                            mask[i + row * size[u]..i + width + row * size[u]].fill(None);
+                        }
+
+                        let mut coordinate = [0; 3];
+                        coordinate[axis] = layer as u32;
+                        coordinate[u] = i as u32;
+                        coordinate[v] = j as u32;
+
+                        let mut extent = [1; 3];
+                        extent[u] = width as u32;
+                        extent[v] = height as u32;
+
+                        mesh.add_quad(MeshFace {
+                            coordinate,
+                            face: *face,
+                            blocktype: block,
+                            ao,
+                            light: FULL_LIGHT,
+                        }, extent);
+
+                        i += width;
+                    }
+                }
+            }
+        }
+
+        self.mesh = mesh;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::HashSet;
+
+    use crate::world::testing::setup;
+
+    /// Normal, the unit square on the face it covers, texture and ambient occlusion by corner
+    type Cell = (u32, [u32; 3], u32, [u32; 4]);
+
+    /// Every block sized square the quads of a mesh cover
+    fn cells(mesh: &ChunkMesh) -> HashSet<Cell> {
+        let mut cells = HashSet::new();
+
+        for quad in mesh.vertices().chunks(4) {
+            let normal = quad[0].normal();
+            let mut min = [u32::MAX; 3];
+            let mut max = [0; 3];
+            let mut ao = [0; 4];
+
+            for vertex in quad {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(vertex.position()[axis]);
+                    max[axis] = max[axis].max(vertex.position()[axis]);
+                }
+                ao[vertex.corner() as usize] = vertex.ao();
+            }
+
+            let axis = (0..3).position(|axis| min[axis] == max[axis]).unwrap();
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            for i in min[u]..max[u] {
+                for j in min[v]..max[v] {
+                    let mut at = min;
+                    at[u] = i;
+                    at[v] = j;
+                    assert!(cells.insert((normal, at, quad[0].texture(), ao)), "{:?} is covered twice", at);
+                }
+            }
+        }
+
+        cells
+    }
+
+    fn meshes(chunks: &ChunkStore) -> (ChunkMesh, ChunkMesh) {
+        let chunk = chunks.get(ChunkPos::new(0, 0, 0)).unwrap();
+        let mut simple = ChunkMesh::new();
+        simple.create_simple_mesh(chunk, chunks);
+        let mut greedy = ChunkMesh::new();
+        greedy.create_greedy_mesh(chunk, chunks);
+
+        (simple, greedy)
+    }
+
+    fn chunk_with(block: impl Fn(i64, i64, i64) -> Blocks) -> ChunkStore {
+        let mut chunks = ChunkStore::new();
+        chunks.insert(Chunk::new(ChunkPos::new(0, 0, 0)));
+
+        for x in 0..CHUNKSIZE as i64 {
+            for y in 0..WORLDHEIGHT as i64 {
+                for z in 0..CHUNKSIZE as i64 {
+                    chunks.set_block(WorldCoord { x, y, z }, block(x, y, z) as BlockID);
+                }
+            }
+        }
+
+        chunks
+    }
+
+    #[test]
+    fn a_box_is_six_quads() {
+        setup();
+        let chunks = chunk_with(|_, y, _| if y < 10 { Blocks::STONE } else { Blocks::AIR });
+        let (simple, greedy) = meshes(&chunks);
+
+        assert_eq!(greedy.vertices().len(), 6 * 4);
+        assert_eq!(greedy.indices().len(), 6 * 6);
+        assert_eq!(cells(&greedy), cells(&simple));
+    }
+
+    #[test]
+    fn greedy_meshes_cover_the_same_faces() {
+        setup();
+
+        // Layers of different blocks, with holes in them for ambient occlusion
+        let mut rng = StdRng::seed_from_u64(3);
+        let holes: HashSet<(i64, i64, i64)> = (0..400)
+            .map(|_| (rng.gen_range(0..32), rng.gen_range(0..20), rng.gen_range(0..32)))
+            .collect();
+        let layered = chunk_with(|x, y, z| match y {
+            _ if holes.contains(&(x, y, z)) => Blocks::AIR,
+            0..=7 => Blocks::STONE,
+            8..=11 => Blocks::DIRT,
+            12 => Blocks::GRASS,
+            _ => Blocks::AIR,
+        });
+        let (simple, greedy) = meshes(&layered);
+        assert_eq!(cells(&greedy), cells(&simple));
+        assert!(greedy.vertices().len() * 2 < simple.vertices().len());
+
+        // Noise, where little can be joined
+        let blocks = [Blocks::AIR, Blocks::STONE, Blocks::DIRT, Blocks::WATER];
+        let noise: Vec<Blocks> = (0..CHUNKSIZE * CHUNKSIZE * WORLDHEIGHT).map(|_| blocks[rng.gen_range(0..blocks.len())]).collect();
+        let noisy = chunk_with(|x, y, z| noise[(x as usize * WORLDHEIGHT + y as usize) * CHUNKSIZE + z as usize]);
+        let (simple, greedy) = meshes(&noisy);
+        assert_eq!(cells(&greedy), cells(&simple));
+    }
+}
@@ -1,51 +1,86 @@
-use crate::render::vertexarray::VertexArray;
-use crate::render::shapes::shapes::Quad;
+use crate::render::low::vertex::ChunkVertex;
 use crate::world::block::blocks::{BlockID, get_block, Sides};
 
 // Used for creating the corresponding faces. These represent coordinates of the 4 vertices in the correct order
 pub const FACES: [Face; 6] = [
-    Face {interval: [[0, 1, 0], [1, 1, 0], [1, 0, 0], [0, 0, 0]]},
-    Face {interval: [[1, 1, 0], [1, 1, 1], [1, 0, 1], [1, 0, 0]]},
-    Face {interval: [[1, 1, 1], [0, 1, 1], [0, 0, 1], [1, 0, 1]]},
-    Face {interval: [[0, 1, 1], [0, 1, 0], [0, 0, 0], [0, 0, 1]]},
-    Face {interval: [[0, 1, 1], [1, 1, 1], [1, 1, 0], [0, 1, 0]]},
-    Face {interval: [[0, 0, 0], [1, 0, 0], [1, 0, 1], [0, 0, 1]]},
+    Face {interval: [[0, 1, 0], [1, 1, 0], [1, 0, 0], [0, 0, 0]], normal: [0, 0, -1]},
+    Face {interval: [[1, 1, 0], [1, 1, 1], [1, 0, 1], [1, 0, 0]], normal: [1, 0, 0]},
+    Face {interval: [[1, 1, 1], [0, 1, 1], [0, 0, 1], [1, 0, 1]], normal: [0, 0, 1]},
+    Face {interval: [[0, 1, 1], [0, 1, 0], [0, 0, 0], [0, 0, 1]], normal: [-1, 0, 0]},
+    Face {interval: [[0, 1, 1], [1, 1, 1], [1, 1, 0], [0, 1, 0]], normal: [0, 1, 0]},
+    Face {interval: [[0, 0, 0], [1, 0, 0], [1, 0, 1], [0, 0, 1]], normal: [0, -1, 0]},
 ];
 
-/// A mesh holds quads and renders these quads
+/// Full sky light and no block light, used until there is a lighting engine
+pub const FULL_LIGHT: u8 = 0xF0;
+
+/// A mesh holds the packed vertices and indices of a chunk. Both can
+/// be handed to the gpu as they are, no conversion needed.
 pub struct Mesh {
-    pub quads: VertexArray<Quad>,
+    pub vertices: Vec<ChunkVertex>,
+    pub indices: Vec<u32>,
 }
 
 impl Mesh {
     pub fn new() -> Self {
-        let quads = VertexArray::new();
-
         Self {
-            quads,
+            vertices: vec![],
+            indices: vec![],
         }
     }
 
     pub fn add_face(&mut self, face: MeshFace) {
+        self.add_quad(face, [1; 3]);
+    }
+
+    /// A face that spans `size` blocks along each axis, 1 along its normal.
+    /// The texture repeats over it, see chunk.vert.
+    pub fn add_quad(&mut self, face: MeshFace, size: [u32; 3]) {
         let interval = &FACES[face.face as usize];
-        self.quads.push(Quad {
-            coords: [
-                [(face.coordinate[0] + interval.interval[0][0]) as f32, (face.coordinate[1] + interval.interval[0][1]) as f32, (face.coordinate[2] + interval.interval[0][2]) as f32],
-                [(face.coordinate[0] + interval.interval[1][0]) as f32, (face.coordinate[1] + interval.interval[1][1]) as f32, (face.coordinate[2] + interval.interval[1][2]) as f32],
-                [(face.coordinate[0] + interval.interval[2][0]) as f32, (face.coordinate[1] + interval.interval[2][1]) as f32, (face.coordinate[2] + interval.interval[2][2]) as f32],
-                [(face.coordinate[0] + interval.interval[3][0]) as f32, (face.coordinate[1] + interval.interval[3][1]) as f32, (face.coordinate[2] + interval.interval[3][2]) as f32],
-            ],
-            text_coords: get_block(face.blocktype).texture.sides[face.face as usize].to_usable(),
-        })
+        let texture = get_block(face.blocktype).texture.sides[face.face as usize].index();
+        let offset = self.vertices.len() as u32;
+
+        for (corner, corner_offset) in interval.interval.iter().enumerate() {
+            self.vertices.push(ChunkVertex::new(
+                [
+                    face.coordinate[0] + corner_offset[0] * size[0],
+                    face.coordinate[1] + corner_offset[1] * size[1],
+                    face.coordinate[2] + corner_offset[2] * size[2],
+                ],
+                face.face as u32,
+                corner as u32,
+                face.ao[corner] as u32,
+                texture,
+                face.light as u32,
+            ));
+        }
+
+        // Flip the diagonal when needed so ambient occlusion is interpolated
+        // the same way on every quad
+        if face.ao[0] as u32 + face.ao[2] as u32 > face.ao[1] as u32 + face.ao[3] as u32 {
+            self.indices.extend_from_slice(&[
+                1 + offset, 2 + offset, 3 + offset,
+                3 + offset, offset, 1 + offset,
+            ]);
+        } else {
+            self.indices.extend_from_slice(&[
+                offset, 1 + offset, 2 + offset,
+                2 + offset, 3 + offset, offset,
+            ]);
+        }
     }
 }
 
 pub struct Face {
-    interval: [[u32; 3]; 4],
+    pub interval: [[u32; 3]; 4],
+    pub normal: [i16; 3],
 }
 
 pub struct MeshFace {
     pub coordinate: [u32; 3],
     pub face: Sides, // Which face of block
     pub blocktype: BlockID,
-}
\ No newline at end of file
+    /// Ambient occlusion per corner, 0 is fully occluded and 3 is not occluded
+    pub ao: [u8; 4],
+    pub light: u8,
+}
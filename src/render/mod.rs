@@ -5,7 +5,15 @@ pub mod low;
 pub mod shapes;
 pub mod vertexarray;
 pub mod camera;
+/// Sky colours, sky light and fog
+pub mod sky;
+/// Cascaded shadow maps for sunlight
+pub mod shadows;
 /// Meshing of chunks and related 
 pub mod meshing;
+/// Writing chunk meshes to model files
+pub mod export;
 
-pub mod drawables;
\ No newline at end of file
+pub mod drawables;
+/// Screen space text, menus and HUD
+pub mod ui;
\ No newline at end of file
@@ -0,0 +1,356 @@
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3, Vector4, EuclideanSpace};
+use wgpu::util::DeviceExt;
+use std::num::NonZeroU32;
+use serde::{Deserialize, Serialize};
+
+use crate::helper::math::smoothstep;
+use crate::render::{
+    camera::{Camera, OPENGL_TO_WGPU_MATRIX},
+    sky::Sky,
+    low::uniforms::{MultiUniform, CascadeUniform, ShadowUniform},
+};
+
+/// The most cascades any quality uses, the shaders have room for this many
+pub const MAX_CASCADES: usize = 4;
+
+/// Shadows are only drawn up to this distance from the camera
+const SHADOW_DISTANCE: f32 = 160.0;
+/// How far behind a cascade blocks can still cast shadows into it
+const CASTER_DISTANCE: f32 = 96.0;
+/// Balance between evenly spaced cascades (0) and logarithmic ones (1)
+const SPLIT_LAMBDA: f32 = 0.7;
+/// How dark a shadow is, 1 means no sky light at all
+const SHADOW_STRENGTH: f32 = 0.55;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShadowQuality {
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl ShadowQuality {
+    /// The number of cascades and the size of the shadow map of each of them
+    pub fn cascades(&self) -> (usize, u32) {
+        match self {
+            ShadowQuality::Off => (0, 1),
+            ShadowQuality::Low => (2, 1024),
+            ShadowQuality::Medium => (3, 2048),
+            ShadowQuality::High => (4, 2048),
+        }
+    }
+
+    /// The quality as written in the settings file and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShadowQuality::Off => "off",
+            ShadowQuality::Low => "low",
+            ShadowQuality::Medium => "medium",
+            ShadowQuality::High => "high",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [ShadowQuality::Off, ShadowQuality::Low, ShadowQuality::Medium, ShadowQuality::High]
+            .iter()
+            .copied()
+            .find(|quality| quality.name() == name.to_lowercase())
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ShadowQuality::Off => ShadowQuality::Low,
+            ShadowQuality::Low => ShadowQuality::Medium,
+            ShadowQuality::Medium => ShadowQuality::High,
+            ShadowQuality::High => ShadowQuality::Off,
+        }
+    }
+}
+
+/// Cascaded shadow maps for the sun and moon. Every cascade covers a part of
+/// the view frustum, the closest one at the highest detail.
+///
+/// The bind group holds everything the chunk shaders need for lighting:
+/// the sky uniform, the shadow uniform and the shadow maps.
+pub struct Shadows {
+    pub quality: ShadowQuality,
+    cascade_count: usize,
+    resolution: u32,
+
+    /// A layer for every cascade
+    _texture: wgpu::Texture,
+    /// Views of single layers to render into
+    pub cascade_views: Vec<wgpu::TextureView>,
+    /// The light projection of every cascade, for the shadow pass
+    pub cascades: MultiUniform<u32, CascadeUniform>,
+
+    uniform: ShadowUniform,
+    uniform_buffer: wgpu::Buffer,
+
+    pub lighting_bind_group_layout: wgpu::BindGroupLayout,
+    pub lighting_bind_group: wgpu::BindGroup,
+}
+
+impl Shadows {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, sky: &Sky, quality: ShadowQuality) -> Self {
+        let lighting_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // Sky
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Shadow
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Depth,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        comparison: true,
+                        filtering: true,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("lighting_bind_group_layout"),
+        });
+
+        let uniform = ShadowUniform::new();
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow uniform buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let mut cascades = MultiUniform::new(device, 0, 0);
+        for cascade in 0..MAX_CASCADES as u32 {
+            cascades.add(queue, cascade, CascadeUniform::new());
+        }
+
+        let (cascade_count, resolution) = quality.cascades();
+        let (texture, cascade_views, lighting_bind_group) = Shadows::create_maps(
+            device,
+            &lighting_bind_group_layout,
+            &uniform_buffer,
+            sky,
+            cascade_count,
+            resolution,
+        );
+
+        Self {
+            quality,
+            cascade_count,
+            resolution,
+
+            _texture: texture,
+            cascade_views,
+            cascades,
+
+            uniform,
+            uniform_buffer,
+
+            lighting_bind_group_layout,
+            lighting_bind_group,
+        }
+    }
+
+    /// Creates the shadow maps again at the size the quality needs
+    pub fn set_quality(&mut self, device: &wgpu::Device, sky: &Sky, quality: ShadowQuality) {
+        let (cascade_count, resolution) = quality.cascades();
+        let (texture, cascade_views, lighting_bind_group) = Shadows::create_maps(
+            device,
+            &self.lighting_bind_group_layout,
+            &self.uniform_buffer,
+            sky,
+            cascade_count,
+            resolution,
+        );
+
+        self.quality = quality;
+        self.cascade_count = cascade_count;
+        self.resolution = resolution;
+        self._texture = texture;
+        self.cascade_views = cascade_views;
+        self.lighting_bind_group = lighting_bind_group;
+    }
+
+    fn create_maps(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sky: &Sky,
+        cascade_count: usize,
+        resolution: u32,
+    ) -> (wgpu::Texture, Vec<wgpu::TextureView>, wgpu::BindGroup) {
+        // Without shadows there still has to be something to bind
+        let layers = cascade_count.max(1) as u32;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("shadow maps"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+        });
+
+        let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let cascade_views = (0..cascade_count as u32)
+            .map(|layer| texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("shadow cascade"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: NonZeroU32::new(1),
+                ..Default::default()
+            }))
+            .collect();
+
+        // Compares with the depth in the shadow map and blends between the four closest texels
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("shadow sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: sky.uniform.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&array_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("lighting_bind_group"),
+        });
+
+        (texture, cascade_views, bind_group)
+    }
+
+    /// The number of shadow passes that have to be rendered
+    pub fn cascade_count(&self) -> usize {
+        self.cascade_count
+    }
+
+    /// Fits the cascades to the view frustum of the camera. `sun` is the direction towards the sun.
+    pub fn update(&mut self, queue: &wgpu::Queue, camera: &Camera, sun: [f32; 3]) {
+        let mut light = Vector3::new(sun[0], sun[1], sun[2]);
+        // At night the moon casts the shadows
+        if light.y < 0.0 {
+            light = -light;
+        }
+
+        // Shadows get very long and unstable when the light comes from the horizon
+        let strength = SHADOW_STRENGTH * smoothstep(0.05, 0.25, light.y);
+
+        let (near, far) = camera.depth_range();
+        let far = far.min(SHADOW_DISTANCE);
+
+        self.uniform.cascade_count = self.cascade_count as u32;
+        self.uniform.strength = strength;
+        self.uniform.texel_size = 1.0 / self.resolution as f32;
+        self.uniform.light_direction = [light.x, light.y, light.z, 0.0];
+
+        let mut split_near = near;
+        for cascade in 0..self.cascade_count {
+            // Practical split scheme, mixes logarithmic and uniform splits
+            let p = (cascade + 1) as f32 / self.cascade_count as f32;
+            let logarithmic = near * (far / near).powf(p);
+            let uniform = near + (far - near) * p;
+            let split_far = SPLIT_LAMBDA * logarithmic + (1.0 - SPLIT_LAMBDA) * uniform;
+
+            let corners = camera.frustum_corners(split_near, split_far);
+            let matrix = cascade_matrix(&corners, light, self.resolution);
+
+            self.uniform.light_view_proj[cascade] = matrix.into();
+            self.uniform.cascade_splits[cascade] = split_far;
+            self.cascades.modify(queue, cascade as u32, CascadeUniform { light_view_proj: matrix.into() });
+
+            split_near = split_far;
+        }
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+}
+
+/// An orthographic projection from the light that contains the bounding sphere of the
+/// frustum slice. Using a sphere keeps the size the same when the camera turns, and
+/// snapping to whole texels keeps the shadow edges from shimmering when it moves.
+fn cascade_matrix(corners: &[Point3<f32>; 8], light: Vector3<f32>, resolution: u32) -> Matrix4<f32> {
+    let center = corners.iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, c| sum + c.to_vec()) / 8.0;
+    let radius = corners.iter().map(|c| (c.to_vec() - center).magnitude()).fold(0.0, f32::max);
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let up = if light.y.abs() > 0.99 { Vector3::unit_z() } else { Vector3::unit_y() };
+    let light_view = Matrix4::look_at_dir(Point3::origin(), -light, up);
+
+    let texel = 2.0 * radius / resolution as f32;
+    let mut center: Vector4<f32> = light_view * center.extend(1.0);
+    center.x = (center.x / texel).floor() * texel;
+    center.y = (center.y / texel).floor() * texel;
+
+    // The light looks down -z, anything between the light and the slice can cast a shadow into it
+    let projection = cgmath::ortho(
+        center.x - radius,
+        center.x + radius,
+        center.y - radius,
+        center.y + radius,
+        -center.z - radius - CASTER_DISTANCE,
+        -center.z + radius,
+    );
+
+    OPENGL_TO_WGPU_MATRIX * projection * light_view
+}
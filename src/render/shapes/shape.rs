@@ -3,6 +3,8 @@ use crate::render::low::vertex::Vertex;
 pub trait Shape {
     fn num_indices() -> usize;
     fn num_vertices() -> usize;
-    fn vertices(&self) -> Vec<Vertex>;
-    fn indexes(&self, offset: u32) -> Vec<u32>;
+    /// Appends the vertices of this shape to `out`
+    fn vertices(&self, out: &mut Vec<Vertex>);
+    /// Appends the indices of this shape to `out`, starting at vertex `offset`
+    fn indexes(&self, offset: u32, out: &mut Vec<u32>);
 }
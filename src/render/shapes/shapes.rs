@@ -7,20 +7,20 @@ pub struct Quad {
 }
 
 impl Shape for Quad {
-    fn vertices(&self) -> Vec<Vertex> {
-        vec![
+    fn vertices(&self, out: &mut Vec<Vertex>) {
+        out.extend_from_slice(&[
             Vertex { position: [self.coords[0][0], self.coords[0][1], self.coords[0][2]], text_coords: self.text_coords[0] },
             Vertex { position: [self.coords[1][0], self.coords[1][1], self.coords[1][2]], text_coords: self.text_coords[1] },
             Vertex { position: [self.coords[2][0], self.coords[2][1], self.coords[2][2]], text_coords: self.text_coords[2] },
             Vertex { position: [self.coords[3][0], self.coords[3][1], self.coords[3][2]], text_coords: self.text_coords[3] },
-        ]
+        ]);
     }
     
-    fn indexes(&self, offset: u32) -> Vec<u32> {
-        vec![
-            0 + offset, 1 + offset, 2 + offset,
-            2 + offset, 3 + offset, 0 + offset,
-        ]
+    fn indexes(&self, offset: u32, out: &mut Vec<u32>) {
+        out.extend_from_slice(&[
+            offset, 1 + offset, 2 + offset,
+            2 + offset, 3 + offset, offset,
+        ]);
     }
 
     fn num_indices() -> usize {
@@ -38,18 +38,18 @@ pub struct Triangle {
 }
 
 impl Shape for Triangle {
-    fn vertices(&self) -> Vec<Vertex> {
-        vec![
+    fn vertices(&self, out: &mut Vec<Vertex>) {
+        out.extend_from_slice(&[
             Vertex { position: [self.coords[0][0], self.coords[0][1], 0.0], text_coords: self.colors },
             Vertex { position: [self.coords[1][0], self.coords[1][1], 0.0], text_coords: self.colors },
             Vertex { position: [self.coords[2][0], self.coords[2][1], 0.0], text_coords: self.colors },
-        ]
+        ]);
     }
     
-    fn indexes(&self, offset: u32) -> Vec<u32> {
-        vec![
-            0 + offset, 1 + offset, 2 + offset,
-        ]
+    fn indexes(&self, offset: u32, out: &mut Vec<u32>) {
+        out.extend_from_slice(&[
+            offset, 1 + offset, 2 + offset,
+        ]);
     }
 
     fn num_indices() -> usize {
@@ -0,0 +1,81 @@
+use cgmath::SquareMatrix;
+
+use crate::render::{
+    camera::Camera,
+    low::uniforms::{Uniform, SkyUniform},
+};
+use crate::world::time::WorldTime;
+
+const DAY_ZENITH: [f32; 3] = [0.16, 0.36, 0.85];
+const DAY_HORIZON: [f32; 3] = [0.55, 0.72, 0.95];
+const NIGHT_ZENITH: [f32; 3] = [0.002, 0.004, 0.02];
+const NIGHT_HORIZON: [f32; 3] = [0.015, 0.02, 0.05];
+const SUNSET_HORIZON: [f32; 3] = [0.9, 0.4, 0.15];
+
+/// Fog starts at this part of the view distance
+const FOG_START: f32 = 0.6;
+
+/// The colours of the sky, the sky light and the fog for the current world time
+pub struct Sky {
+    pub uniform: Uniform<SkyUniform>,
+    /// The distance at which the world is completely hidden by fog
+    view_distance: f32,
+}
+
+impl Sky {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            uniform: Uniform::new(device, SkyUniform::new(), 0, 3), // set = 3 in the chunk pipeline
+            view_distance: 100.0,
+        }
+    }
+
+    pub fn set_view_distance(&mut self, distance: f32) {
+        self.view_distance = distance;
+    }
+
+    pub fn update(&mut self, queue: &wgpu::Queue, camera: &Camera, time: &WorldTime, partial_tick: f32) {
+        let sun = time.sun_direction(partial_tick);
+        let sky_light = time.sky_light(partial_tick);
+
+        // 0 at night, 1 during the day
+        let day = (sky_light - 0.15) / 0.85;
+        // Strongest when the sun is at the horizon
+        let sunset = (1.0 - sun[1].abs() / 0.3).max(0.0) * 0.8;
+
+        let zenith = mix(NIGHT_ZENITH, DAY_ZENITH, day);
+        let horizon = mix(mix(NIGHT_HORIZON, DAY_HORIZON, day), SUNSET_HORIZON, sunset * day.max(0.3));
+
+        let data = &mut self.uniform.data;
+        data.inv_view_proj = camera.build_view_projection_matrix().invert().unwrap_or(cgmath::Matrix4::identity()).into();
+        data.zenith_color = [zenith[0], zenith[1], zenith[2], 1.0];
+        data.horizon_color = [horizon[0], horizon[1], horizon[2], 1.0];
+        data.sun_direction = [sun[0], sun[1], sun[2], 0.0];
+        data.camera_position = [camera.view.position.x, camera.view.position.y, camera.view.position.z, 1.0];
+        data.sky_light = sky_light;
+        data.fog_start = self.view_distance * FOG_START;
+        data.fog_end = self.view_distance;
+
+        self.uniform.update(queue);
+    }
+
+    /// The screen is cleared with the fog colour so the world fades into it
+    pub fn clear_color(&self) -> wgpu::Color {
+        let color = self.uniform.data.horizon_color;
+
+        wgpu::Color {
+            r: color[0] as f64,
+            g: color[1] as f64,
+            b: color[2] as f64,
+            a: 1.0,
+        }
+    }
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::render::ui::ui::{Ui, Rect, PANEL_COLOR, TEXT_COLOR, HINT_COLOR};
+
+/// Lines disappear this long after they arrived, while typing all are shown
+const SHOWN: Duration = Duration::from_secs(10);
+/// Older lines are forgotten
+const MAX_LINES: usize = 100;
+const VISIBLE_LINES: usize = 10;
+const LINE_SIZE: f32 = 18.0;
+const WIDTH: f32 = 460.0;
+/// Space for the hotbar below the chat
+const BOTTOM: f32 = 110.0;
+const INPUT_ID: &str = "chat";
+
+/// The chat in the bottom left corner and the line that is being typed
+pub struct Chat {
+    lines: VecDeque<(String, Instant)>,
+    /// None while the chat is closed
+    typing: Option<String>,
+    /// What Tab could finish the line with, when there is more than one
+    completions: Vec<String>,
+}
+
+impl Chat {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            typing: None,
+            completions: vec![],
+        }
+    }
+
+    pub fn add(&mut self, line: String) {
+        self.lines.push_back((line, Instant::now()));
+
+        if self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Starts typing a line that starts with text, such as / for a command
+    pub fn open(&mut self, ui: &mut Ui, text: &str) {
+        self.typing = Some(text.to_string());
+        self.completions.clear();
+        ui.focus(INPUT_ID, input_rect(ui));
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.typing.is_some()
+    }
+
+    /// Draws the chat. Returns the typed line when Enter is pressed, the chat
+    /// closes then. Escape closes it without sending anything. Tab finishes
+    /// the line with what `complete` gives for it.
+    pub fn update(&mut self, ui: &mut Ui, complete: impl Fn(&str) -> Vec<String>) -> Option<String> {
+        let input = input_rect(ui);
+        let mut sent = None;
+
+        if let Some(text) = &mut self.typing {
+            if !ui.is_focused(INPUT_ID, input) {
+                self.typing = None;
+            } else {
+                if ui.tab_pressed() {
+                    let completions = complete(text);
+                    match completions.as_slice() {
+                        [] => {}
+                        [only] => *text = format!("{} ", only),
+                        [first, ..] => {
+                            *text = completions.iter().fold(first.clone(), |prefix, completion| common_prefix(&prefix, completion));
+                            self.completions = completions;
+                        }
+                    }
+                }
+
+                let before = text.len();
+                if ui.text_input(input, INPUT_ID, text) {
+                    sent = Some(text.clone()).filter(|text| !text.trim().is_empty());
+                    self.typing = None;
+                } else if text.len() != before {
+                    self.completions.clear();
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let open = self.is_open();
+        let line_height = ui.font.line_height(LINE_SIZE);
+        let mut y = input.y - 4.0;
+
+        if !open {
+            self.completions.clear();
+        }
+        if !self.completions.is_empty() {
+            // Only the last words, the rest is already typed
+            let words: Vec<&str> = self.completions.iter().map(|completion| completion.rsplit(' ').next().unwrap_or("")).collect();
+            y -= line_height;
+            ui.rect(Rect::new(input.x, y, WIDTH, line_height), PANEL_COLOR);
+            ui.text(input.x + 4.0, y, LINE_SIZE, &words.join("  "), HINT_COLOR);
+        }
+
+        for (line, received) in self.lines.iter().rev().take(VISIBLE_LINES) {
+            if !open && now - *received > SHOWN {
+                break
+            }
+
+            y -= line_height;
+            ui.rect(Rect::new(input.x, y, WIDTH, line_height), PANEL_COLOR);
+            ui.text(input.x + 4.0, y, LINE_SIZE, line, TEXT_COLOR);
+        }
+
+        sent
+    }
+}
+
+fn input_rect(ui: &Ui) -> Rect {
+    let screen = ui.screen();
+    Rect::new(8.0, screen.h - BOTTOM, WIDTH, 32.0)
+}
+
+/// The longest start that both have
+fn common_prefix(a: &str, b: &str) -> String {
+    a.chars().zip(b.chars()).take_while(|(a, b)| a == b).map(|(a, _)| a).collect()
+}
@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::render::ui::ui::{Ui, Rect, Color, PANEL_COLOR, TEXT_COLOR};
+
+const TEXT_SIZE: f32 = 16.0;
+const MARGIN: f32 = 4.0;
+
+/// Frames in the frame time graph, one pixel wide each
+const FRAME_HISTORY: usize = 240;
+/// Seconds between two points of the FPS graph
+const FPS_INTERVAL: f32 = 0.25;
+const FPS_HISTORY: usize = 120;
+const GRAPH_HEIGHT: f32 = 60.0;
+/// Frame time at the top of the frame time graph, in milliseconds
+const GRAPH_MAX_TIME: f32 = 50.0;
+
+const FAST_COLOR: Color = [0.3, 0.9, 0.3, 0.9];
+const SLOW_COLOR: Color = [0.95, 0.8, 0.2, 0.9];
+const VERY_SLOW_COLOR: Color = [0.95, 0.25, 0.2, 0.9];
+const REFERENCE_COLOR: Color = [1.0, 1.0, 1.0, 0.3];
+
+/// The F3 overlay. Keeps track of frame times and shows the lines the
+/// states add to it every frame.
+pub struct DebugOverlay {
+    pub visible: bool,
+
+    /// In milliseconds, the newest last
+    frame_times: VecDeque<f32>,
+    fps: VecDeque<f32>,
+    /// Time and frames since the last point of the FPS graph
+    fps_time: f32,
+    fps_frames: u32,
+
+    lines: Vec<String>,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            fps: VecDeque::with_capacity(FPS_HISTORY),
+            fps_time: 0.0,
+            fps_frames: 0,
+
+            lines: vec![],
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Frames are recorded while the overlay is hidden too, so the graphs are full when it is opened
+    pub fn record_frame(&mut self, dt: Duration) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(dt.as_secs_f32() * 1000.0);
+
+        self.fps_time += dt.as_secs_f32();
+        self.fps_frames += 1;
+
+        if self.fps_time >= FPS_INTERVAL {
+            if self.fps.len() == FPS_HISTORY {
+                self.fps.pop_front();
+            }
+            self.fps.push_back(self.fps_frames as f32 / self.fps_time);
+
+            self.fps_time = 0.0;
+            self.fps_frames = 0;
+        }
+    }
+
+    /// Adds a line below the frame rate. Lines are only kept for one frame.
+    pub fn line(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    pub fn draw(&mut self, ui: &mut Ui) {
+        let lines = std::mem::take(&mut self.lines);
+        if !self.visible {
+            return
+        }
+
+        let average = self.frame_times.iter().rev().take(60).sum::<f32>() / self.frame_times.len().clamp(1, 60) as f32;
+        let slowest = self.frame_times.iter().copied().fold(0.0, f32::max);
+
+        let mut y = MARGIN;
+        let header = format!(
+            "{:.0} fps ({:.1} ms, slowest {:.1} ms)",
+            self.fps.back().copied().unwrap_or(0.0),
+            average,
+            slowest,
+        );
+
+        for line in std::iter::once(&header).chain(lines.iter()) {
+            y += self.line_with_background(ui, MARGIN, y, line);
+        }
+
+        let screen = ui.screen();
+        let bottom = screen.h - MARGIN;
+
+        self.draw_frame_times(ui, MARGIN, bottom);
+        self.draw_fps(ui, screen.w - MARGIN - FPS_HISTORY as f32 * 2.0, bottom);
+    }
+
+    /// Returns the height of the line
+    fn line_with_background(&self, ui: &mut Ui, x: f32, y: f32, line: &str) -> f32 {
+        let height = ui.font.line_height(TEXT_SIZE);
+
+        if !line.is_empty() {
+            let width = ui.font.text_width(line, TEXT_SIZE);
+            ui.rect(Rect::new(x, y, width + 4.0, height), PANEL_COLOR);
+            ui.text(x + 2.0, y, TEXT_SIZE, line, TEXT_COLOR);
+        }
+
+        height
+    }
+
+    /// Bars of the last frames, taller is slower. Lines at 60 and 30 fps.
+    fn draw_frame_times(&self, ui: &mut Ui, x: f32, bottom: f32) {
+        let top = bottom - GRAPH_HEIGHT;
+        ui.rect(Rect::new(x, top, FRAME_HISTORY as f32, GRAPH_HEIGHT), PANEL_COLOR);
+        self.line_with_background(ui, x, top - ui.font.line_height(TEXT_SIZE), "Frame time");
+
+        for (i, time) in self.frame_times.iter().enumerate() {
+            let height = (time / GRAPH_MAX_TIME).min(1.0) * GRAPH_HEIGHT;
+            let color = if *time <= 1000.0 / 60.0 {
+                FAST_COLOR
+            } else if *time <= 1000.0 / 30.0 {
+                SLOW_COLOR
+            } else {
+                VERY_SLOW_COLOR
+            };
+
+            ui.rect(Rect::new(x + i as f32, bottom - height, 1.0, height), color);
+        }
+
+        for fps in [60.0, 30.0].iter() {
+            let height = 1000.0 / fps / GRAPH_MAX_TIME * GRAPH_HEIGHT;
+            ui.rect(Rect::new(x, bottom - height, FRAME_HISTORY as f32, 1.0), REFERENCE_COLOR);
+        }
+    }
+
+    /// The frame rate of the last half minute, scaled to the highest frame rate in it
+    fn draw_fps(&self, ui: &mut Ui, x: f32, bottom: f32) {
+        let top = bottom - GRAPH_HEIGHT;
+        let highest = self.fps.iter().copied().fold(60.0, f32::max);
+
+        ui.rect(Rect::new(x, top, FPS_HISTORY as f32 * 2.0, GRAPH_HEIGHT), PANEL_COLOR);
+        self.line_with_background(ui, x, top - ui.font.line_height(TEXT_SIZE), &format!("FPS (max {:.0})", highest));
+
+        for (i, fps) in self.fps.iter().enumerate() {
+            let height = fps / highest * GRAPH_HEIGHT;
+            let color = if *fps >= 59.0 { FAST_COLOR } else if *fps >= 29.0 { SLOW_COLOR } else { VERY_SLOW_COLOR };
+
+            ui.rect(Rect::new(x + i as f32 * 2.0, bottom - height, 2.0, height), color);
+        }
+
+        let height = 60.0 / highest * GRAPH_HEIGHT;
+        ui.rect(Rect::new(x, bottom - height, FPS_HISTORY as f32 * 2.0, 1.0), REFERENCE_COLOR);
+    }
+}
+
+/// The compass direction of a horizontal direction, north is -Z and east is +X
+pub fn compass(x: f32, z: f32) -> &'static str {
+    if x.abs() > z.abs() {
+        if x > 0.0 { "east (+X)" } else { "west (-X)" }
+    } else if z > 0.0 {
+        "south (+Z)"
+    } else {
+        "north (-Z)"
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{Result, anyhow};
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Scale};
+use std::collections::HashMap;
+
+/// Where the UI font is found in a resource pack
+pub const UI_FONT: &str = "fonts/DejaVuSans.ttf";
+
+/// The size the glyphs are rasterized at, text of other sizes is scaled from it
+pub const FONT_PIXELS: f32 = 32.0;
+
+const ATLAS_WIDTH: u32 = 512;
+/// Empty pixels between glyphs, so they don't bleed into each other when filtered
+const PADDING: u32 = 2;
+/// A white square in the corner of the atlas, used for drawing plain rectangles
+const WHITE_SIZE: u32 = 4;
+
+/// The characters that are put in the atlas. Others are drawn as '?'
+fn characters() -> impl Iterator<Item = char> {
+    (' '..='~').chain('\u{a1}'..='\u{ff}')
+}
+
+/// Where a character is in the atlas and how it is placed. Everything
+/// is in pixels at FONT_PIXELS and relative to the pen on the baseline.
+#[derive(Debug, Copy, Clone)]
+pub struct Glyph {
+    /// Top left and bottom right texture coordinates
+    pub uv: [f32; 4],
+    pub offset: [f32; 2],
+    pub size: [f32; 2],
+    pub advance: f32,
+}
+
+/// A TrueType font rasterized into a texture atlas
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    /// Height above the baseline
+    ascent: f32,
+    /// Distance between the baselines of two lines
+    line_height: f32,
+    /// Texture coordinates of a pixel that is completely white
+    pub white: [f32; 2],
+}
+
+impl Font {
+    /// Rasterizes the font. Returns the metrics and the atlas, which is white
+    /// with the coverage of the glyphs in the alpha channel.
+    pub fn rasterize(bytes: Vec<u8>) -> Result<(Self, RgbaImage)> {
+        let font = rusttype::Font::from_bytes(bytes).map_err(|e| anyhow!("Invalid font: {}", e))?;
+        let scale = Scale::uniform(FONT_PIXELS);
+        let v_metrics = font.v_metrics(scale);
+
+        let mut glyphs = HashMap::new();
+        let mut placed = vec![];
+
+        // Glyphs are put in rows from left to right, after the white square
+        let mut x = WHITE_SIZE + PADDING;
+        let mut y = 0;
+        let mut row_height = WHITE_SIZE;
+
+        for c in characters() {
+            let glyph = font.glyph(c);
+            // Glyph 0 is the "missing character" box
+            if glyph.id().0 == 0 {
+                continue
+            }
+
+            let glyph = glyph.scaled(scale).positioned(point(0.0, 0.0));
+            let advance = glyph.unpositioned().h_metrics().advance_width;
+
+            let bounds = match glyph.pixel_bounding_box() {
+                Some(bounds) => bounds,
+                // Nothing to draw, like a space
+                None => {
+                    glyphs.insert(c, Glyph { uv: [0.0; 4], offset: [0.0; 2], size: [0.0; 2], advance });
+                    continue
+                }
+            };
+
+            let width = bounds.width() as u32;
+            let height = bounds.height() as u32;
+
+            if x + width > ATLAS_WIDTH {
+                x = 0;
+                y += row_height + PADDING;
+                row_height = 0;
+            }
+
+            glyphs.insert(c, Glyph {
+                uv: [x as f32, y as f32, (x + width) as f32, (y + height) as f32],
+                offset: [bounds.min.x as f32, bounds.min.y as f32],
+                size: [width as f32, height as f32],
+                advance,
+            });
+            placed.push((glyph, x, y));
+
+            x += width + PADDING;
+            row_height = row_height.max(height);
+        }
+
+        let atlas_height = (y + row_height).next_power_of_two();
+        let mut atlas = RgbaImage::from_pixel(ATLAS_WIDTH, atlas_height, Rgba([255, 255, 255, 0]));
+
+        for px in 0..WHITE_SIZE {
+            for py in 0..WHITE_SIZE {
+                atlas.put_pixel(px, py, Rgba([255, 255, 255, 255]));
+            }
+        }
+
+        for (glyph, gx, gy) in placed {
+            glyph.draw(|px, py, coverage| {
+                atlas.put_pixel(gx + px, gy + py, Rgba([255, 255, 255, (coverage * 255.0).round() as u8]));
+            });
+        }
+
+        // The atlas size is only known now, so the pixel positions become texture coordinates
+        let size = [ATLAS_WIDTH as f32, atlas_height as f32];
+        for glyph in glyphs.values_mut() {
+            glyph.uv = [glyph.uv[0] / size[0], glyph.uv[1] / size[1], glyph.uv[2] / size[0], glyph.uv[3] / size[1]];
+        }
+
+        let font = Self {
+            glyphs,
+            ascent: v_metrics.ascent,
+            line_height: v_metrics.ascent - v_metrics.descent + v_metrics.line_gap,
+            white: [WHITE_SIZE as f32 / 2.0 / size[0], WHITE_SIZE as f32 / 2.0 / size[1]],
+        };
+
+        Ok((font, atlas))
+    }
+
+    /// The glyph of a character, or of '?' if the font doesn't have it
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// The width of the widest line of the text when drawn at `size` pixels
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        let scale = size / FONT_PIXELS;
+
+        text.lines()
+            .map(|line| line.chars().filter_map(|c| self.glyph(c)).map(|g| g.advance).sum::<f32>() * scale)
+            .fold(0.0, f32::max)
+    }
+
+    /// How far the baseline is below the top of a line, at `size` pixels
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.ascent * size / FONT_PIXELS
+    }
+
+    /// The height of a line of text when drawn at `size` pixels
+    pub fn line_height(&self, size: f32) -> f32 {
+        self.line_height * size / FONT_PIXELS
+    }
+}
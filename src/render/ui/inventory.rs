@@ -0,0 +1,137 @@
+use crate::render::ui::ui::{Ui, Rect, Color, PANEL_COLOR, TEXT_COLOR, TEXT_SIZE, ACCENT_COLOR};
+use crate::game::player::{
+    player::{Player, InventoryAction},
+    inventory::{GameMode, HOTBAR_SLOTS, INVENTORY_SLOTS},
+    item::{Item, ItemStack},
+};
+use crate::world::block::blocks::{BlockID, Sides, get_block};
+
+const SLOT_SIZE: f32 = 48.0;
+const SLOT_SPACING: f32 = 4.0;
+/// Space between the icon and the edge of its slot
+const ICON_MARGIN: f32 = 8.0;
+const COUNT_SIZE: f32 = 16.0;
+const SLOT_COLOR: Color = [0.0, 0.0, 0.0, 0.4];
+const WHITE: Color = [1.0, 1.0, 1.0, 1.0];
+
+/// The layer of the block textures that shows a block in the UI
+pub fn block_icon(block: BlockID) -> u32 {
+    get_block(block).texture.sides[Sides::FRONT as usize].index()
+}
+
+/// The hotbar at the bottom of the screen, with the name of the selected block above it
+pub fn draw_hotbar(ui: &mut Ui, player: &Player) {
+    let screen = ui.screen();
+    let width = HOTBAR_SLOTS as f32 * (SLOT_SIZE + SLOT_SPACING) - SLOT_SPACING;
+    let x = (screen.w - width) / 2.0;
+    let y = screen.h - SLOT_SIZE - 12.0;
+
+    ui.rect(Rect::new(x, y, width, SLOT_SIZE).shrink(-4.0), PANEL_COLOR);
+
+    for slot in 0..HOTBAR_SLOTS {
+        let rect = Rect::new(x + slot as f32 * (SLOT_SIZE + SLOT_SPACING), y, SLOT_SIZE, SLOT_SIZE);
+        ui.rect(rect, SLOT_COLOR);
+        if slot == player.inventory.selected() {
+            outline(ui, rect, ACCENT_COLOR);
+        }
+
+        draw_stack(ui, rect, player.inventory.slot(slot), player.game_mode);
+    }
+
+    if let Some(stack) = player.inventory.selected_stack() {
+        let name = Rect::new(0.0, y - 36.0, screen.w, 28.0);
+        ui.text_centered(name, TEXT_SIZE, &stack.item.name(), TEXT_COLOR);
+    }
+}
+
+/// The whole inventory, in a panel over the game. Clicking a slot swaps it with
+/// the selected hotbar slot. Creative mode also has every item to pick from.
+/// Slots are buttons without text, told apart by where they are.
+/// Returns what was clicked, the caller does it.
+pub fn inventory_screen(ui: &mut Ui, player: &Player) -> Option<InventoryAction> {
+    let screen = ui.screen();
+    ui.rect(screen, PANEL_COLOR);
+
+    let columns = HOTBAR_SLOTS;
+    let step = SLOT_SIZE + SLOT_SPACING;
+    let width = columns as f32 * step - SLOT_SPACING;
+    let x = (screen.w - width) / 2.0;
+    let mut y = screen.h * 0.15;
+    let mut action = None;
+
+    let mode = Rect::new(x, y, width, 40.0);
+    if ui.button(mode, &format!("Game mode: {}", player.game_mode.name())) {
+        action = Some(InventoryAction::SetGameMode(player.game_mode.toggle()));
+    }
+    y += 40.0 + 16.0;
+
+    if player.game_mode == GameMode::Creative {
+        let items = Item::all();
+
+        for (i, item) in items.iter().enumerate() {
+            let rect = Rect::new(x + (i % columns) as f32 * step, y + (i / columns) as f32 * step, SLOT_SIZE, SLOT_SIZE);
+
+            if ui.button(rect, "") {
+                action = Some(InventoryAction::Pick(ItemStack::new(*item, item.max_stack())));
+            }
+            draw_item(ui, rect, *item);
+        }
+
+        let rows = (items.len() + columns - 1) / columns;
+        y += rows as f32 * step + 16.0;
+    }
+
+    // The hotbar is the bottom row, like on the HUD
+    let rows = INVENTORY_SLOTS / columns;
+    for slot in 0..INVENTORY_SLOTS {
+        let row = if slot < HOTBAR_SLOTS { rows - 1 } else { slot / columns - 1 };
+        let extra = if slot < HOTBAR_SLOTS { 12.0 } else { 0.0 };
+        let rect = Rect::new(x + (slot % columns) as f32 * step, y + row as f32 * step + extra, SLOT_SIZE, SLOT_SIZE);
+
+        if ui.button(rect, "") {
+            action = Some(if slot < HOTBAR_SLOTS {
+                InventoryAction::Select(slot)
+            } else {
+                InventoryAction::Swap(slot, player.inventory.selected())
+            });
+        }
+        if slot == player.inventory.selected() {
+            outline(ui, rect, ACCENT_COLOR);
+        }
+
+        draw_stack(ui, rect, player.inventory.slot(slot), player.game_mode);
+    }
+
+    action
+}
+
+/// Blocks show their texture. Tools have no textures, they show their name instead.
+fn draw_item(ui: &mut Ui, rect: Rect, item: Item) {
+    match item {
+        Item::Block(block) => ui.image(rect.shrink(ICON_MARGIN), block_icon(block), WHITE),
+        Item::Tool(tool) => {
+            let name: String = tool.name().chars().take(4).collect();
+            ui.text_centered(rect, COUNT_SIZE, &name, TEXT_COLOR);
+        }
+    }
+}
+
+fn draw_stack(ui: &mut Ui, rect: Rect, stack: Option<ItemStack>, game_mode: GameMode) {
+    if let Some(stack) = stack {
+        draw_item(ui, rect, stack.item);
+
+        // Creative mode never runs out, so counts don't matter
+        if game_mode == GameMode::Survival && stack.count > 1 {
+            let count = stack.count.to_string();
+            let width = ui.font.text_width(&count, COUNT_SIZE);
+            ui.text(rect.x + rect.w - width - 3.0, rect.y + rect.h - COUNT_SIZE - 5.0, COUNT_SIZE, &count, TEXT_COLOR);
+        }
+    }
+}
+
+fn outline(ui: &mut Ui, rect: Rect, color: Color) {
+    ui.rect(Rect::new(rect.x - 2.0, rect.y - 2.0, rect.w + 4.0, 2.0), color);
+    ui.rect(Rect::new(rect.x - 2.0, rect.y + rect.h, rect.w + 4.0, 2.0), color);
+    ui.rect(Rect::new(rect.x - 2.0, rect.y, 2.0, rect.h), color);
+    ui.rect(Rect::new(rect.x + rect.w, rect.y, 2.0, rect.h), color);
+}
@@ -0,0 +1,5 @@
+pub mod font;
+pub mod ui;
+pub mod debug;
+pub mod inventory;
+pub mod chat;
\ No newline at end of file
@@ -0,0 +1,465 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::render::{
+    low::vertex::UiVertex,
+    ui::font::{Font, FONT_PIXELS},
+};
+
+/// Colours are in sRGB with alpha
+pub type Color = [f32; 4];
+
+pub const TEXT_COLOR: Color = [1.0, 1.0, 1.0, 1.0];
+/// For text that explains what goes somewhere
+pub const HINT_COLOR: Color = [1.0, 1.0, 1.0, 0.5];
+pub const PANEL_COLOR: Color = [0.0, 0.0, 0.0, 0.5];
+pub const WIDGET_COLOR: Color = [0.15, 0.15, 0.15, 0.8];
+pub const HOVER_COLOR: Color = [0.3, 0.3, 0.4, 0.9];
+pub const ACCENT_COLOR: Color = [0.45, 0.6, 0.9, 1.0];
+
+pub const TEXT_SIZE: f32 = 20.0;
+
+/// A rectangle on the screen in pixels, from the top left corner
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// A rectangle of the given size in the middle of `outer`
+    pub fn centered(outer: Rect, w: f32, h: f32) -> Self {
+        Self::new(outer.x + (outer.w - w) / 2.0, outer.y + (outer.h - h) / 2.0, w, h)
+    }
+
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.x && point[0] < self.x + self.w && point[1] >= self.y && point[1] < self.y + self.h
+    }
+
+    /// Smaller on every side by `amount`
+    pub fn shrink(&self, amount: f32) -> Self {
+        Self::new(self.x + amount, self.y + amount, (self.w - amount * 2.0).max(0.0), (self.h - amount * 2.0).max(0.0))
+    }
+}
+
+#[derive(Default)]
+struct Mesh {
+    vertices: Vec<UiVertex>,
+    indices: Vec<u32>,
+}
+
+/// Immediate mode UI. Every frame the states describe what is on screen
+/// by calling the functions below, widgets return right away whether they
+/// were used. Everything is collected into one mesh that is drawn on top
+/// of the world by Renderer::render_ui.
+pub struct Ui {
+    pub font: Font,
+    /// Size of the window in pixels
+    screen: [f32; 2],
+
+    /// Everything of a state goes in the layer of its place on the state stack,
+    /// so the states above it are drawn over it
+    layers: Vec<Mesh>,
+    layer: usize,
+
+    /// None while the cursor is captured or outside the window
+    mouse: Option<[f32; 2]>,
+    cursor: [f32; 2],
+    moved: bool,
+    mouse_down: bool,
+    clicked: bool,
+    released: bool,
+
+    typed: String,
+    backspaces: usize,
+    enter: bool,
+    tab: bool,
+
+    /// The widget the mouse went down on
+    active: Option<u64>,
+    /// The text input that gets the keyboard
+    focused: Option<u64>,
+    focus_seen: bool,
+
+    started: Instant,
+}
+
+impl Ui {
+    pub fn new(font: Font) -> Self {
+        Self {
+            font,
+            screen: [1.0, 1.0],
+
+            layers: vec![],
+            layer: 0,
+
+            mouse: None,
+            cursor: [0.0, 0.0],
+            moved: false,
+            mouse_down: false,
+            clicked: false,
+            released: false,
+
+            typed: String::new(),
+            backspaces: 0,
+            enter: false,
+            tab: false,
+
+            active: None,
+            focused: None,
+            focus_seen: false,
+
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns true if the event was used for typing
+    pub fn window_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = [position.x as f32, position.y as f32];
+                self.moved = true;
+                false
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                match state {
+                    ElementState::Pressed => {
+                        self.mouse_down = true;
+                        self.clicked = true;
+                    }
+                    ElementState::Released => {
+                        self.mouse_down = false;
+                        self.released = true;
+                    }
+                }
+                false
+            }
+            WindowEvent::ReceivedCharacter(c) if self.focused.is_some() => {
+                if !c.is_control() {
+                    self.typed.push(*c);
+                }
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput { state: ElementState::Pressed, virtual_keycode, .. },
+                ..
+            } if self.focused.is_some() => {
+                match virtual_keycode {
+                    Some(VirtualKeyCode::Back) => self.backspaces += 1,
+                    Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => self.enter = true,
+                    Some(VirtualKeyCode::Tab) => self.tab = true,
+                    Some(VirtualKeyCode::Escape) => self.focused = None,
+                    _ => {}
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Starts a new frame. The mouse is ignored while it is used for looking around.
+    pub fn begin_frame(&mut self, screen: [f32; 2], mouse: bool) {
+        self.screen = screen;
+        self.mouse = if mouse { Some(self.cursor) } else { None };
+
+        for layer in &mut self.layers {
+            layer.vertices.clear();
+            layer.indices.clear();
+        }
+        self.layer = 0;
+        self.focus_seen = false;
+    }
+
+    /// Forgets the input of this frame, called after the mesh was drawn
+    pub fn end_frame(&mut self) {
+        self.moved = false;
+        self.clicked = false;
+        self.released = false;
+        self.typed.clear();
+        self.backspaces = 0;
+        self.enter = false;
+        self.tab = false;
+
+        if !self.mouse_down {
+            self.active = None;
+        }
+        // The text input is gone
+        if !self.focus_seen {
+            self.focused = None;
+        }
+    }
+
+    /// What is drawn after this goes over everything in lower layers
+    pub fn set_layer(&mut self, layer: usize) {
+        if self.layers.len() <= layer {
+            self.layers.resize_with(layer + 1, Mesh::default);
+        }
+        self.layer = layer;
+    }
+
+    /// Everything that was drawn this frame, lowest layer first
+    pub fn mesh(&self) -> (Vec<UiVertex>, Vec<u32>) {
+        let mut vertices = vec![];
+        let mut indices = vec![];
+
+        for layer in &self.layers {
+            let offset = vertices.len() as u32;
+            vertices.extend_from_slice(&layer.vertices);
+            indices.extend(layer.indices.iter().map(|i| i + offset));
+        }
+
+        (vertices, indices)
+    }
+
+    /// The whole window
+    pub fn screen(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.screen[0], self.screen[1])
+    }
+
+    fn quad(&mut self, rect: Rect, uv: [f32; 4], color: Color, texture: f32) {
+        if self.layers.is_empty() {
+            self.set_layer(0);
+        }
+        let mesh = &mut self.layers[self.layer];
+        let start = mesh.vertices.len() as u32;
+
+        let corners = [
+            ([rect.x, rect.y], [uv[0], uv[1]]),
+            ([rect.x + rect.w, rect.y], [uv[2], uv[1]]),
+            ([rect.x + rect.w, rect.y + rect.h], [uv[2], uv[3]]),
+            ([rect.x, rect.y + rect.h], [uv[0], uv[3]]),
+        ];
+        for (position, tex_coords) in corners.iter() {
+            mesh.vertices.push(UiVertex {
+                position: *position,
+                tex_coords: *tex_coords,
+                color,
+                texture,
+            });
+        }
+
+        mesh.indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    /// A plain coloured rectangle
+    pub fn rect(&mut self, rect: Rect, color: Color) {
+        let white = self.font.white;
+        self.quad(rect, [white[0], white[1], white[0], white[1]], color, -1.0);
+    }
+
+    /// A layer of the block texture array, tinted with `color`
+    pub fn image(&mut self, rect: Rect, layer: u32, color: Color) {
+        self.quad(rect, [0.0, 0.0, 1.0, 1.0], color, layer as f32);
+    }
+
+    /// Draws text with its top left corner at `x`, `y`. Returns its width.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, text: &str, color: Color) -> f32 {
+        let scale = size / FONT_PIXELS;
+        let mut baseline = (y + self.font.ascent(size)).round();
+        let mut width: f32 = 0.0;
+
+        for line in text.lines() {
+            let mut pen = x;
+
+            for c in line.chars() {
+                let glyph = match self.font.glyph(c) {
+                    Some(glyph) => *glyph,
+                    None => continue,
+                };
+
+                if glyph.size[0] > 0.0 {
+                    let rect = Rect::new(
+                        pen.round() + glyph.offset[0] * scale,
+                        baseline + glyph.offset[1] * scale,
+                        glyph.size[0] * scale,
+                        glyph.size[1] * scale,
+                    );
+                    self.quad(rect, glyph.uv, color, -1.0);
+                }
+
+                pen += glyph.advance * scale;
+            }
+
+            width = width.max(pen - x);
+            baseline += self.font.line_height(size).round();
+        }
+
+        width
+    }
+
+    /// Text in the middle of a rectangle
+    pub fn text_centered(&mut self, rect: Rect, size: f32, text: &str, color: Color) {
+        let width = self.font.text_width(text, size);
+        let height = self.font.line_height(size) * text.lines().count().max(1) as f32;
+
+        self.text(rect.x + (rect.w - width) / 2.0, rect.y + (rect.h - height) / 2.0, size, text, color);
+    }
+
+    /// Text with a dark background, readable on top of the world
+    pub fn label(&mut self, x: f32, y: f32, text: &str) -> Rect {
+        let rect = Rect::new(
+            x,
+            y,
+            self.font.text_width(text, TEXT_SIZE) + 8.0,
+            self.font.line_height(TEXT_SIZE) * text.lines().count().max(1) as f32 + 4.0,
+        );
+
+        self.rect(rect, PANEL_COLOR);
+        self.text(x + 4.0, y + 2.0, TEXT_SIZE, text, TEXT_COLOR);
+
+        rect
+    }
+
+    /// Whether the mouse is over the rectangle
+    pub fn hovered(&self, rect: Rect) -> bool {
+        match self.mouse {
+            Some(mouse) => rect.contains(mouse),
+            None => false,
+        }
+    }
+
+    /// Whether the mouse moved over the window since the last frame
+    pub fn mouse_moved(&self) -> bool {
+        self.moved && self.mouse.is_some()
+    }
+
+    /// Widgets are told apart by what they show and where
+    fn id(&self, label: &str, rect: Rect) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.layer, label, rect.x as i32, rect.y as i32).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Makes the widget active when the mouse goes down on it. Returns
+    /// true when the mouse is released on the widget it went down on.
+    fn interact(&mut self, id: u64, rect: Rect) -> bool {
+        let hovered = self.hovered(rect);
+
+        if hovered && self.clicked {
+            self.active = Some(id);
+        }
+
+        hovered && self.released && self.active == Some(id)
+    }
+
+    pub fn button(&mut self, rect: Rect, text: &str) -> bool {
+        self.selectable(rect, text, false)
+    }
+
+    /// A button that is highlighted while selected, for menus that are also used with the keyboard
+    pub fn selectable(&mut self, rect: Rect, text: &str, selected: bool) -> bool {
+        let id = self.id(text, rect);
+        let clicked = self.interact(id, rect);
+
+        let color = if selected || self.hovered(rect) { HOVER_COLOR } else { WIDGET_COLOR };
+        self.rect(rect, color);
+        if selected {
+            self.rect(Rect::new(rect.x, rect.y, 4.0, rect.h), ACCENT_COLOR);
+        }
+        self.text_centered(rect, TEXT_SIZE, text, TEXT_COLOR);
+
+        clicked
+    }
+
+    /// Drags `value` between `min` and `max`. Returns true if it changed.
+    pub fn slider(&mut self, rect: Rect, text: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        // The text usually shows the value, so it would change while dragging
+        let id = self.id("slider", rect);
+        self.interact(id, rect);
+
+        let old = *value;
+        if self.active == Some(id) && self.mouse_down {
+            if let Some(mouse) = self.mouse {
+                let t = ((mouse[0] - rect.x) / rect.w).clamp(0.0, 1.0);
+                *value = min + (max - min) * t;
+            }
+        }
+
+        let t = if max > min { (*value - min) / (max - min) } else { 0.0 };
+        let color = if self.hovered(rect) || self.active == Some(id) { HOVER_COLOR } else { WIDGET_COLOR };
+
+        self.rect(rect, color);
+        self.rect(Rect::new(rect.x, rect.y, rect.w * t, rect.h), [ACCENT_COLOR[0], ACCENT_COLOR[1], ACCENT_COLOR[2], 0.6]);
+        self.text_centered(rect, TEXT_SIZE, text, TEXT_COLOR);
+
+        *value != old
+    }
+
+    /// Whether Tab was pressed while a text input had the keyboard, for completion
+    pub fn tab_pressed(&self) -> bool {
+        self.tab
+    }
+
+    /// Gives the keyboard to a text input, as if it was clicked
+    pub fn focus(&mut self, id: &str, rect: Rect) {
+        self.focused = Some(self.id(id, rect));
+    }
+
+    /// Whether a text input has the keyboard. Escape takes it away.
+    pub fn is_focused(&self, id: &str, rect: Rect) -> bool {
+        self.focused == Some(self.id(id, rect))
+    }
+
+    /// A single line of editable text, it gets the keyboard when clicked.
+    /// Returns true when Enter is pressed.
+    pub fn text_input(&mut self, rect: Rect, id: &str, text: &mut String) -> bool {
+        let id = self.id(id, rect);
+
+        if self.clicked {
+            if self.hovered(rect) {
+                self.focused = Some(id);
+            } else if self.focused == Some(id) {
+                self.focused = None;
+            }
+        }
+
+        let focused = self.focused == Some(id);
+        let mut enter = false;
+
+        if focused {
+            self.focus_seen = true;
+
+            for _ in 0..self.backspaces {
+                text.pop();
+            }
+            text.push_str(&self.typed);
+            enter = self.enter;
+
+            self.backspaces = 0;
+            self.typed.clear();
+            self.enter = false;
+        }
+
+        let color = if focused || self.hovered(rect) { HOVER_COLOR } else { WIDGET_COLOR };
+        self.rect(rect, color);
+
+        let inner = rect.shrink(6.0);
+        let size = TEXT_SIZE.min(inner.h);
+
+        // Only the end of the text is shown if it is too long
+        let mut shown: &str = text;
+        while self.font.text_width(shown, size) > inner.w - 2.0 && !shown.is_empty() {
+            let mut chars = shown.chars();
+            chars.next();
+            shown = chars.as_str();
+        }
+
+        let y = inner.y + (inner.h - self.font.line_height(size)) / 2.0;
+        let width = self.text(inner.x, y, size, shown, TEXT_COLOR);
+
+        // Blinking cursor
+        if focused && self.started.elapsed().as_millis() % 1000 < 500 {
+            self.rect(Rect::new(inner.x + width + 1.0, y, 2.0, self.font.line_height(size)), TEXT_COLOR);
+        }
+
+        enter
+    }
+}
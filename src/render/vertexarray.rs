@@ -24,7 +24,7 @@ impl<T: Shape> VertexArray<T> {
         let mut v = Vec::<u32>::with_capacity(self.objects.len() * T::num_indices());
 
         for (i, val) in self.objects.iter().enumerate() {
-            v.append(&mut val.indexes((i * T::num_vertices()) as u32));
+            val.indexes((i * T::num_vertices()) as u32, &mut v);
         }
         
         v
@@ -34,9 +34,7 @@ impl<T: Shape> VertexArray<T> {
         let mut v = Vec::<Vertex>::with_capacity(self.objects.len() * T::num_vertices());
 
         for val in self.objects.iter() {
-            v.append(
-                &mut val.vertices()
-            );
+            val.vertices(&mut v);
         }
         
         v
@@ -0,0 +1,4 @@
+// Everything that has to do with loading assets from disk
+
+pub mod pack;
+pub mod resourcemanager;
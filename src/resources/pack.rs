@@ -0,0 +1,72 @@
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// A single resource pack. Either a directory or a zip file
+/// with the same layout as the assets directory.
+pub enum ResourcePack {
+    Directory(PathBuf),
+    Zip(PathBuf),
+}
+
+impl ResourcePack {
+    /// Opens the pack at path. Directories are used as they are,
+    /// files are treated as zip archives.
+    pub fn open(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            Ok(ResourcePack::Directory(path.to_path_buf()))
+        } else if path.is_file() {
+            // Make sure the archive is valid now rather than when the first asset is read
+            zip::ZipArchive::new(File::open(path)?)
+                .with_context(|| format!("Resource pack {} is not a valid zip file", path.display()))?;
+
+            Ok(ResourcePack::Zip(path.to_path_buf()))
+        } else {
+            bail!("Resource pack {} does not exist", path.display())
+        }
+    }
+
+    /// Reads the asset at a path relative to the root of the pack. Returns None if
+    /// the pack does not contain the asset, so the next pack can be tried.
+    pub fn read(&self, asset: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            ResourcePack::Directory(root) => {
+                let path = root.join(asset);
+
+                if !path.is_file() {
+                    return Ok(None)
+                }
+
+                let bytes = std::fs::read(&path)
+                    .with_context(|| format!("Could not read {}", path.display()))?;
+
+                Ok(Some(bytes))
+            }
+            ResourcePack::Zip(path) => {
+                // The archive is opened again for every read so it can be
+                // replaced while the game is running
+                let mut archive = zip::ZipArchive::new(File::open(path)?)
+                    .with_context(|| format!("Resource pack {} is not a valid zip file", path.display()))?;
+
+                let mut file = match archive.by_name(asset) {
+                    Ok(file) => file,
+                    Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+                    Err(e) => return Err(e).with_context(|| format!("Could not read {} from {}", asset, path.display())),
+                };
+
+                let mut bytes = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut bytes)?;
+
+                Ok(Some(bytes))
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ResourcePack::Directory(path) => path,
+            ResourcePack::Zip(path) => path,
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+
+use crate::resources::pack::ResourcePack;
+
+/// Environment variable that overrides the directory the assets are looked up in
+pub const ROOT_VAR: &str = "LUDWIG_ROOT";
+/// Environment variable with a comma separated list of packs, highest priority first
+pub const PACKS_VAR: &str = "LUDWIG_PACKS";
+
+/// The pack that ships with the game, always searched last
+pub const BASE_PACK: &str = "assets";
+/// Directory inside the root where extra packs are looked up by name
+pub const PACK_DIRECTORY: &str = "resourcepacks";
+
+/// Resolves assets such as textures, block definitions and shaders from an ordered
+/// list of resource packs. The first pack that contains an asset wins, the base
+/// pack is always the last one.
+pub struct ResourceManager {
+    root: PathBuf,
+    pack_names: Vec<String>,
+    packs: Vec<ResourcePack>,
+}
+
+impl ResourceManager {
+    pub fn new(root: PathBuf, pack_names: Vec<String>) -> Result<Self> {
+        let mut manager = Self {
+            root,
+            pack_names,
+            packs: vec![],
+        };
+
+        manager.reload()?;
+
+        Ok(manager)
+    }
+
+    /// Uses LUDWIG_ROOT and LUDWIG_PACKS if they are set
+    pub fn from_env() -> Result<Self> {
+        let root = match std::env::var_os(ROOT_VAR) {
+            Some(root) => PathBuf::from(root),
+            None => ResourceManager::find_root()?,
+        };
+
+        let pack_names = std::env::var(PACKS_VAR)
+            .map(|packs| packs.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect())
+            .unwrap_or_default();
+
+        ResourceManager::new(root, pack_names)
+    }
+
+    /// Looks for the directory containing the base pack. First next to the
+    /// executable and its parents, so it works both for a packaged game and
+    /// for `cargo run`, then in the current directory.
+    pub fn find_root() -> Result<PathBuf> {
+        let mut candidates = vec![];
+
+        if let Ok(exe) = std::env::current_exe() {
+            candidates.extend(exe.ancestors().skip(1).map(Path::to_path_buf));
+        }
+        if let Ok(dir) = std::env::current_dir() {
+            candidates.push(dir);
+        }
+
+        candidates
+            .into_iter()
+            .find(|dir| dir.join(BASE_PACK).is_dir())
+            .with_context(|| format!("Could not find the '{}' directory next to the executable or in the current directory. Set {} to the directory that contains it", BASE_PACK, ROOT_VAR))
+    }
+
+    /// A manager with the same packs, opened again. Picks up packs that were
+    /// added, removed or replaced and leaves this one as it is.
+    pub fn reopen(&self) -> Result<Self> {
+        ResourceManager::new(self.root.clone(), self.pack_names.clone())
+    }
+
+    /// Opens all packs again. Picks up packs that were added, removed or replaced
+    fn reload(&mut self) -> Result<()> {
+        let mut packs = vec![];
+
+        for name in &self.pack_names {
+            packs.push(ResourcePack::open(&self.pack_path(name))?);
+        }
+
+        packs.push(
+            ResourcePack::open(&self.root.join(BASE_PACK))
+                .context("The base resource pack is missing")?
+        );
+
+        self.packs = packs;
+
+        Ok(())
+    }
+
+    /// A pack name is either a path, or the name of a directory
+    /// or zip file in the resource pack directory.
+    fn pack_path(&self, name: &str) -> PathBuf {
+        let path = PathBuf::from(name);
+        if path.is_absolute() {
+            return path
+        }
+
+        let dir = self.root.join(PACK_DIRECTORY);
+        let zip = dir.join(format!("{}.zip", name));
+
+        if zip.is_file() {
+            zip
+        } else {
+            dir.join(name)
+        }
+    }
+
+    /// Reads an asset from the first pack that has it
+    pub fn read(&self, asset: &str) -> Result<Vec<u8>> {
+        if let Some(bytes) = self.read_optional(asset)? {
+            return Ok(bytes)
+        }
+
+        bail!(
+            "Missing asset '{}', looked in: {}",
+            asset,
+            self.packs.iter().map(|p| p.path().display().to_string()).collect::<Vec<_>>().join(", "),
+        )
+    }
+
+    /// Reads an asset if any pack has it. For assets that have a built in fallback
+    pub fn read_optional(&self, asset: &str) -> Result<Option<Vec<u8>>> {
+        for pack in &self.packs {
+            if let Some(bytes) = pack.read(asset)? {
+                return Ok(Some(bytes))
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The packs that are directories, highest priority first
+    pub fn directories(&self) -> Vec<PathBuf> {
+        self.packs
+            .iter()
+            .filter_map(|pack| match pack {
+                ResourcePack::Directory(path) => Some(path.clone()),
+                ResourcePack::Zip(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn read_string(&self, asset: &str) -> Result<String> {
+        String::from_utf8(self.read(asset)?)
+            .with_context(|| format!("Asset '{}' is not valid UTF-8", asset))
+    }
+
+    pub fn load_image(&self, asset: &str) -> Result<image::DynamicImage> {
+        image::load_from_memory(&self.read(asset)?)
+            .with_context(|| format!("Asset '{}' is not a valid image", asset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A root with the base pack and two extra packs, `high` and `low`
+    fn root() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        let write = |pack: &Path, asset: &str, contents: &str| {
+            fs::create_dir_all(pack).unwrap();
+            fs::write(pack.join(asset), contents).unwrap();
+        };
+
+        let base = root.path().join(BASE_PACK);
+        let high = root.path().join(PACK_DIRECTORY).join("high");
+        let low = root.path().join(PACK_DIRECTORY).join("low");
+        write(&base, "everywhere.txt", "base");
+        write(&base, "base.txt", "base");
+        write(&base, "low.txt", "base");
+        write(&low, "everywhere.txt", "low");
+        write(&low, "low.txt", "low");
+        write(&high, "everywhere.txt", "high");
+
+        root
+    }
+
+    #[test]
+    fn first_pack_wins() {
+        let root = root();
+        let resources = ResourceManager::new(root.path().to_path_buf(), vec!["high".into(), "low".into()]).unwrap();
+
+        assert_eq!(resources.read_string("everywhere.txt").unwrap(), "high");
+        assert_eq!(resources.read_string("low.txt").unwrap(), "low");
+        assert_eq!(resources.read_string("base.txt").unwrap(), "base");
+    }
+
+    #[test]
+    fn order_of_packs_decides() {
+        let root = root();
+        let resources = ResourceManager::new(root.path().to_path_buf(), vec!["low".into(), "high".into()]).unwrap();
+
+        assert_eq!(resources.read_string("everywhere.txt").unwrap(), "low");
+        assert_eq!(resources.directories().last().unwrap(), &root.path().join(BASE_PACK));
+    }
+
+    #[test]
+    fn missing_asset_is_an_error() {
+        let root = root();
+        let resources = ResourceManager::new(root.path().to_path_buf(), vec!["high".into()]).unwrap();
+
+        let error = resources.read("missing.txt").unwrap_err().to_string();
+        assert!(error.contains("Missing asset 'missing.txt'"), "{}", error);
+        assert!(resources.read_optional("missing.txt").unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_packs_are_errors() {
+        let root = root();
+
+        assert!(ResourceManager::new(root.path().to_path_buf(), vec!["unknown".into()]).is_err());
+        assert!(ResourceManager::new(root.path().join("nowhere"), vec![]).is_err());
+    }
+
+    #[test]
+    fn reopen_leaves_the_old_packs() {
+        let root = root();
+        let resources = ResourceManager::new(root.path().to_path_buf(), vec!["high".into()]).unwrap();
+
+        fs::remove_dir_all(root.path().join(PACK_DIRECTORY).join("high")).unwrap();
+        assert!(resources.reopen().is_err());
+        assert_eq!(resources.directories().len(), 2);
+
+        fs::write(root.path().join(BASE_PACK).join("everywhere.txt"), "changed").unwrap();
+        let resources = ResourceManager::new(root.path().to_path_buf(), vec![]).unwrap().reopen().unwrap();
+        assert_eq!(resources.read_string("everywhere.txt").unwrap(), "changed");
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::{Result, bail};
+
+use crate::server::network::DEFAULT_PORT;
+use crate::settings::{
+    args::{number, Options},
+    settings::{MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE},
+};
+
+pub const SERVER_USAGE: &str = "Usage: ludwig-server [options]
+
+Options:
+    --world <name>            The world in saves/ to host, created if it doesn't exist (default: server)
+    --seed <number>           Seed if the world is created
+    --port <port>             TCP and UDP port to listen on (default: 24454)
+    --view-distance <n>       The farthest players can see, in chunks (default: 8)
+    --op <name>               Lets a player use every command, can be given more than once
+    --help                    Show this message
+
+Commands are typed in the console without the /, help lists them. Type stop
+to save the world and stop the server, list to see who is playing and
+say <text> to talk to the players.";
+
+/// The command line arguments of the dedicated server
+#[derive(Debug)]
+pub struct ServerArgs {
+    pub help: bool,
+    pub world: String,
+    pub seed: Option<u32>,
+    pub port: u16,
+    pub view_distance: u32,
+    /// Players that can use every command
+    pub operators: Vec<String>,
+}
+
+impl Default for ServerArgs {
+    fn default() -> Self {
+        Self {
+            help: false,
+            world: "server".to_string(),
+            seed: None,
+            port: DEFAULT_PORT,
+            view_distance: 8,
+            operators: vec![],
+        }
+    }
+}
+
+impl ServerArgs {
+    /// Parses the arguments without the program name, see Options
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut parsed = ServerArgs::default();
+        let mut options = Options::new(args);
+
+        for option in &mut options {
+            let (name, value) = option?;
+
+            match name.as_str() {
+                "--world" => parsed.world = value,
+                "--seed" => parsed.seed = Some(number(&name, &value)?),
+                "--port" => parsed.port = number(&name, &value)?,
+                "--view-distance" => {
+                    parsed.view_distance = number::<u32>(&name, &value)?.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE)
+                }
+                "--op" => parsed.operators.push(value),
+                _ => bail!("Unknown option '{}'", name),
+            }
+        }
+        parsed.help = options.help;
+
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,935 @@
+use anyhow::{Result, anyhow, bail};
+use cgmath::Point3;
+use std::str::FromStr;
+
+use crate::game::player::item::{Item, ItemStack};
+use crate::world::{
+    world::World,
+    block::{blocks::{BlockID, Blocks}, colors::block_colors, registry::registry},
+    chunk::pos::{ChunkPos, WorldCoord},
+    constants::WORLDHEIGHT,
+    edit::{
+        clipboard::{Axis, Clipboard},
+        history::changed_chunks,
+        region::{Region, Selection},
+        schematic,
+        session::EditSession,
+    },
+    structure::{placement::locate, registry::structures},
+};
+
+/// The most blocks a single fill may change
+pub const MAX_FILL: i64 = 32 * 32 * 64;
+/// The biggest selection that can be edited or copied at once
+pub const MAX_EDIT: i64 = 1 << 20;
+/// The farthest /regen reaches, in chunks around the player
+const MAX_REGEN_RADIUS: i32 = 4;
+const MAX_GIVE: u32 = 64 * 36;
+/// How many squares of spacing chunks /locate looks at around the player
+const LOCATE_REGIONS: i32 = 32;
+
+/// What kind of value an argument is, for tab completion
+#[derive(Debug, Clone, Copy)]
+pub enum Arg {
+    /// A number, or ~ for where the player is and ~5 for 5 blocks from there
+    Coord,
+    Number,
+    Block,
+    Item,
+    Structure,
+    Choice(&'static [&'static str]),
+    /// The name of a player, which can be left out, see Arguments::target
+    Player,
+}
+
+/// A command the player can type after a /, or the server console without it
+pub struct Command {
+    pub name: &'static str,
+    /// The arguments as they are shown in help
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub args: &'static [Arg],
+    /// Only operators may run it, see CommandContext
+    pub operator: bool,
+    /// None for commands that the game runs itself, such as rd
+    run: Option<fn(&mut CommandContext, &mut Arguments) -> Result<String>>,
+}
+
+/// What the server has to tell its clients after a command changed something
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    Block(WorldCoord),
+    /// A chunk changed too much to send single blocks
+    Chunk(ChunkPos),
+    /// The player was moved by the server
+    Teleport(String),
+    /// The selection of a player changed
+    Selection(String),
+    Chat(String),
+    Stop,
+}
+
+/// Where a command runs
+pub struct CommandContext<'a> {
+    pub world: &'a mut World,
+    /// The player that runs it, None for the server console
+    pub player: Option<String>,
+    /// Operators can change the world, other players can only look
+    pub operator: bool,
+    pub effects: Vec<Effect>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(world: &'a mut World, player: Option<String>, operator: bool) -> Self {
+        Self {
+            world,
+            player,
+            operator,
+            effects: vec![],
+        }
+    }
+
+    /// Where relative coordinates start
+    fn origin(&self) -> Option<Point3<f32>> {
+        self.player.as_ref().and_then(|name| self.world.players.get(name)).map(|player| player.position)
+    }
+}
+
+/// The words after the name of a command
+pub struct Arguments<'a> {
+    words: Vec<&'a str>,
+    next: usize,
+}
+
+impl<'a> Arguments<'a> {
+    fn new(words: Vec<&'a str>) -> Self {
+        Self {
+            words,
+            next: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.words.get(self.next).copied()
+    }
+
+    fn word(&mut self, what: &str) -> Result<&'a str> {
+        let word = self.peek().ok_or_else(|| anyhow!("Missing {}", what))?;
+        self.next += 1;
+        Ok(word)
+    }
+
+    fn number<T: FromStr>(&mut self, what: &str) -> Result<T> {
+        let word = self.word(what)?;
+        word.parse().map_err(|_| anyhow!("'{}' is not a valid {}", word, what))
+    }
+
+    /// A number or a relative ~ coordinate
+    fn relative(&mut self, what: &str, origin: Option<f32>) -> Result<f32> {
+        let word = self.word(what)?;
+
+        let (base, offset) = match word.strip_prefix('~') {
+            Some(offset) => (origin.ok_or_else(|| anyhow!("~ needs a player"))?, offset),
+            None => (0.0, word),
+        };
+        let offset: f32 = match offset {
+            "" => 0.0,
+            offset => offset.parse().map_err(|_| anyhow!("'{}' is not a valid {}", word, what))?,
+        };
+
+        Ok(base + offset)
+    }
+
+    /// x y z of a point, relative to origin with ~
+    fn position(&mut self, origin: Option<Point3<f32>>) -> Result<Point3<f32>> {
+        Ok(Point3::new(
+            self.relative("x", origin.map(|o| o.x))?,
+            self.relative("y", origin.map(|o| o.y))?,
+            self.relative("z", origin.map(|o| o.z))?,
+        ))
+    }
+
+    /// x y z of a block in the world
+    fn coord(&mut self, origin: Option<Point3<f32>>) -> Result<WorldCoord> {
+        in_world(WorldCoord::from_point(self.position(origin)?))
+    }
+
+    fn block(&mut self) -> Result<BlockID> {
+        let name = self.word("block")?;
+        registry().id(name).ok_or_else(|| anyhow!("There is no block '{}'", name))
+    }
+
+    /// The index of a structure in the structure registry
+    fn structure(&mut self) -> Result<usize> {
+        let name = self.word("structure")?;
+        structures().all()
+            .iter()
+            .position(|structure| structure.name == name)
+            .ok_or_else(|| anyhow!("There is no structure '{}'", name))
+    }
+
+    fn item(&mut self) -> Result<Item> {
+        let name = self.word("item")?;
+        Item::from_name(name).ok_or_else(|| anyhow!("There is no item '{}'", name))
+    }
+
+    /// Commands that act on a player can start with its name. Without one it is
+    /// the player that runs the command.
+    fn target(&mut self, context: &CommandContext) -> Result<String> {
+        if let Some(word) = self.peek() {
+            if context.world.players.contains_key(word) {
+                self.next += 1;
+                return Ok(word.to_string())
+            }
+        }
+
+        context.player.clone().ok_or_else(|| anyhow!("Which player? Put a name first"))
+    }
+
+    fn end(&self) -> Result<()> {
+        match self.peek() {
+            Some(word) => bail!("Too many arguments, from '{}'", word),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Every command, found by name
+pub struct Commands {
+    commands: Vec<Command>,
+}
+
+impl Commands {
+    pub fn builtin() -> Self {
+        Self {
+            commands: vec![
+                Command { name: "help", usage: "", help: "Lists the commands", args: &[], operator: false, run: None },
+                Command { name: "tp", usage: "[player] <x> <y> <z>", help: "Moves a player", args: &[Arg::Player, Arg::Coord, Arg::Coord, Arg::Coord], operator: true, run: Some(tp) },
+                Command { name: "setblock", usage: "<x> <y> <z> <block>", help: "Changes a block", args: &[Arg::Coord, Arg::Coord, Arg::Coord, Arg::Block], operator: true, run: Some(setblock) },
+                Command { name: "fill", usage: "<x1> <y1> <z1> <x2> <y2> <z2> <block>", help: "Fills a box with a block", args: &[Arg::Coord, Arg::Coord, Arg::Coord, Arg::Coord, Arg::Coord, Arg::Coord, Arg::Block], operator: true, run: Some(fill) },
+                Command { name: "time", usage: "set <time> | query", help: "Changes the time of day, in ticks or day, noon, night or midnight", args: &[Arg::Choice(&["set", "query"]), Arg::Choice(&["day", "noon", "night", "midnight"])], operator: true, run: Some(time) },
+                Command { name: "seed", usage: "", help: "Shows the seed of the world", args: &[], operator: false, run: Some(seed) },
+                Command { name: "rd", usage: "<chunks>", help: "Changes the render distance", args: &[Arg::Number], operator: false, run: None },
+                Command { name: "locate", usage: "<structure>", help: "Finds the closest structure of a kind", args: &[Arg::Structure], operator: false, run: Some(locate_structure) },
+                Command { name: "regen", usage: "[radius]", help: "Generates the chunks around the player again, undoing all changes", args: &[Arg::Number], operator: true, run: Some(regen) },
+                Command { name: "give", usage: "[player] <item> [count]", help: "Gives a player items", args: &[Arg::Player, Arg::Item, Arg::Number], operator: true, run: Some(give) },
+                Command { name: "list", usage: "", help: "Lists the players", args: &[], operator: false, run: Some(list) },
+                Command { name: "say", usage: "<message>", help: "Tells every player something", args: &[], operator: true, run: Some(say) },
+                Command { name: "pos1", usage: "[x y z]", help: "Selects the first corner, where the player is without coordinates", args: &[Arg::Coord, Arg::Coord, Arg::Coord], operator: true, run: Some(pos1) },
+                Command { name: "pos2", usage: "[x y z]", help: "Selects the second corner", args: &[Arg::Coord, Arg::Coord, Arg::Coord], operator: true, run: Some(pos2) },
+                Command { name: "sel", usage: "[clear]", help: "Shows or clears the selection", args: &[Arg::Choice(&["clear"])], operator: true, run: Some(sel) },
+                Command { name: "set", usage: "<block>", help: "Fills the selection with a block", args: &[Arg::Block], operator: true, run: Some(set) },
+                Command { name: "replace", usage: "<from> <to>", help: "Replaces a block with another in the selection", args: &[Arg::Block, Arg::Block], operator: true, run: Some(replace) },
+                Command { name: "hollow", usage: "<block>", help: "Makes the selection a box of a block with air inside", args: &[Arg::Block], operator: true, run: Some(hollow) },
+                Command { name: "walls", usage: "<block>", help: "Builds the four upright sides of the selection", args: &[Arg::Block], operator: true, run: Some(walls) },
+                Command { name: "copy", usage: "", help: "Copies the selection, relative to where the player is", args: &[], operator: true, run: Some(copy) },
+                Command { name: "paste", usage: "[noair]", help: "Pastes the copy relative to where the player is, noair leaves the blocks where the copy has air", args: &[Arg::Choice(&["noair"])], operator: true, run: Some(paste) },
+                Command { name: "rotate", usage: "<90|180|270>", help: "Turns the copy clockwise as seen from above", args: &[Arg::Choice(&["90", "180", "270"])], operator: true, run: Some(rotate) },
+                Command { name: "mirror", usage: "<x|y|z>", help: "Flips the copy along an axis", args: &[Arg::Choice(&["x", "y", "z"])], operator: true, run: Some(mirror) },
+                Command { name: "schem", usage: "save <name> | load <name> | list", help: "Saves the copy to a schematic or loads one to paste, .vox models can be loaded too", args: &[Arg::Choice(&["save", "load", "list"])], operator: true, run: Some(schem) },
+                Command { name: "undo", usage: "[count]", help: "Undoes your last edits", args: &[Arg::Number], operator: true, run: Some(undo) },
+                Command { name: "redo", usage: "[count]", help: "Does undone edits again", args: &[Arg::Number], operator: true, run: Some(redo) },
+                Command { name: "stop", usage: "", help: "Saves the world and stops the server", args: &[], operator: true, run: Some(stop) },
+            ],
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+    /// Runs a line, with or without the /. Returns what should be shown to
+    /// whoever ran it, errors are shown as well.
+    pub fn run(&self, line: &str, context: &mut CommandContext) -> Result<String> {
+        let (name, words) = split(line);
+        let command = self.get(name).ok_or_else(|| anyhow!("Unknown command '{}', try help", name))?;
+
+        if command.operator && !context.operator {
+            bail!("Only operators can use {}", name);
+        }
+
+        match command.run {
+            Some(run) => run(context, &mut Arguments::new(words)),
+            None if name == "help" => Ok(self.help(context.operator)),
+            None => bail!("{} can only be used in the game", name),
+        }
+    }
+
+    fn help(&self, operator: bool) -> String {
+        self.commands
+            .iter()
+            .filter(|command| operator || !command.operator)
+            .map(|command| format!("{} {}", command.name, command.usage).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Ways to finish the last word of a line, each as the whole line. Players
+    /// are the names that can be completed, and that are taken for a player
+    /// where one can be left out.
+    pub fn complete(&self, line: &str, players: &[String]) -> Vec<String> {
+        let slash = if line.starts_with('/') { "/" } else { "" };
+        let line = &line[slash.len()..];
+        let start = line.rfind(' ').map_or(0, |i| i + 1);
+        let (done, word) = line.split_at(start);
+
+        let candidates: Vec<String> = if start == 0 {
+            self.commands.iter().map(|command| command.name.to_string()).collect()
+        } else {
+            let (name, words) = split(done);
+            let args = self.get(name).map_or(&[][..], |command| command.args);
+            let at = argument_at(args, &words, players);
+
+            match args.get(at) {
+                // Without a name the word is the argument after it
+                Some(Arg::Player) => players.iter().cloned().chain(candidates(args.get(at + 1))).collect(),
+                arg => candidates(arg),
+            }
+        };
+
+        let mut completions: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| format!("{}{}{}", slash, done, candidate))
+            .collect();
+        completions.sort();
+        completions.dedup();
+
+        completions
+    }
+}
+
+/// Which argument the word after the words is. Words that are not the name of
+/// a player go to the argument after an Arg::Player, like Arguments::target does.
+fn argument_at(args: &[Arg], words: &[&str], players: &[String]) -> usize {
+    let mut at = 0;
+
+    for word in words {
+        if matches!(args.get(at), Some(Arg::Player)) && !players.iter().any(|player| player == word) {
+            at += 1;
+        }
+        at += 1;
+    }
+
+    at
+}
+
+/// The words an argument can be
+fn candidates(arg: Option<&Arg>) -> Vec<String> {
+    match arg {
+        Some(Arg::Coord) => vec!["~".to_string()],
+        Some(Arg::Block) => registry().ids().map(|id| registry().get(id).name.clone()).collect(),
+        Some(Arg::Item) => Item::all().iter().map(|item| item.name()).collect(),
+        Some(Arg::Structure) => structures().all().iter().map(|structure| structure.name.clone()).collect(),
+        Some(Arg::Choice(choices)) => choices.iter().map(|choice| choice.to_string()).collect(),
+        Some(Arg::Player) | Some(Arg::Number) | None => vec![],
+    }
+}
+
+/// Blocks can only be above the bottom and below the top of the world
+fn in_world(coord: WorldCoord) -> Result<WorldCoord> {
+    if coord.y < 0 || coord.y >= WORLDHEIGHT as i64 {
+        bail!("y has to be from 0 to {}", WORLDHEIGHT - 1);
+    }
+
+    Ok(coord)
+}
+
+/// The name of the command and its arguments
+pub fn split(line: &str) -> (&str, Vec<&str>) {
+    let mut words = line.trim().trim_start_matches('/').split_whitespace();
+    let name = words.next().unwrap_or("");
+
+    (name, words.collect())
+}
+
+fn tp(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let name = args.target(context)?;
+    let origin = context.world.players.get(&name).map(|player| player.position);
+    let position = args.position(origin)?;
+    args.end()?;
+
+    if let Some(player) = context.world.players.get_mut(&name) {
+        player.position = position;
+    }
+    context.effects.push(Effect::Teleport(name.clone()));
+
+    Ok(format!("Moved {} to {:.1} {:.1} {:.1}", name, position.x, position.y, position.z))
+}
+
+fn setblock(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let coord = args.coord(context.origin())?;
+    let block = args.block()?;
+    args.end()?;
+
+    if !context.world.set_block(coord, block) {
+        bail!("{} {} {} is not loaded", coord.x, coord.y, coord.z);
+    }
+    context.effects.push(Effect::Block(coord));
+
+    Ok(format!("Set {} {} {} to {}", coord.x, coord.y, coord.z, registry().get(block).name))
+}
+
+fn fill(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let origin = context.origin();
+    let region = Region::new(args.coord(origin)?, args.coord(origin)?);
+    let block = args.block()?;
+    args.end()?;
+
+    if region.volume() > MAX_FILL {
+        bail!("That is {} blocks, at most {} can be filled at once", region.volume(), MAX_FILL);
+    }
+
+    Ok(edit(context, region.coords().map(|at| (at, block))))
+}
+
+fn time(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    match args.word("set or query")? {
+        "query" => {
+            args.end()?;
+            let time = context.world.time;
+            Ok(format!("It is day {}, {} ticks into the day", time.day(), time.time_of_day()))
+        }
+        "set" => {
+            let time = match args.word("time")? {
+                "day" => 1000,
+                "noon" => 6000,
+                "night" => 13000,
+                "midnight" => 18000,
+                ticks => ticks.parse().map_err(|_| anyhow!("'{}' is not a time, use ticks or day, noon, night or midnight", ticks))?,
+            };
+            args.end()?;
+
+            context.world.set_time(time);
+            Ok(format!("Set the time to {}", context.world.time.time_of_day()))
+        }
+        other => bail!("Unknown time command '{}', use set or query", other),
+    }
+}
+
+fn seed(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    args.end()?;
+    Ok(format!("Seed: {}", context.world.seed))
+}
+
+fn locate_structure(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let kind = args.structure()?;
+    args.end()?;
+
+    // The console looks around the middle of the world
+    let origin = context.origin().map(WorldCoord::from_point).unwrap_or(WorldCoord { x: 0, y: 0, z: 0 });
+    let name = &structures().all()[kind].name;
+
+    match locate(&context.world.map, kind, origin.to_chunk_coord(), LOCATE_REGIONS) {
+        Some(found) => {
+            let [x, y, z] = found.position();
+            let distance = (((x - origin.x).pow(2) + (z - origin.z).pow(2)) as f64).sqrt();
+            Ok(format!("The closest {} is at {} {} {}, {:.0} blocks away", name, x, y, z, distance))
+        }
+        None => bail!("There is no {} nearby", name),
+    }
+}
+
+fn regen(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let radius = match args.peek() {
+        Some(_) => args.number::<i32>("radius")?,
+        None => 0,
+    };
+    args.end()?;
+
+    if !(0..=MAX_REGEN_RADIUS).contains(&radius) {
+        bail!("The radius is from 0 to {} chunks", MAX_REGEN_RADIUS);
+    }
+    let center = match context.origin() {
+        Some(position) => WorldCoord::from_point(position).to_chunk_coord(),
+        None => bail!("regen needs a player"),
+    };
+
+    let mut count = 0;
+    for x in center.x - radius..=center.x + radius {
+        for z in center.z - radius..=center.z + radius {
+            let pos = ChunkPos::new(x, 0, z);
+            if context.world.regenerate_chunk(pos) {
+                context.effects.push(Effect::Chunk(pos));
+                count += 1;
+            }
+        }
+    }
+
+    Ok(format!("Generated {} chunks again", count))
+}
+
+fn give(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let name = args.target(context)?;
+    let item = args.item()?;
+    let count = match args.peek() {
+        Some(_) => args.number::<u32>("count")?,
+        None => 1,
+    };
+    args.end()?;
+
+    if count == 0 || count > MAX_GIVE {
+        bail!("The count is from 1 to {}", MAX_GIVE);
+    }
+
+    let player = context.world.players.get_mut(&name).ok_or_else(|| anyhow!("{} is not playing", name))?;
+    let left = player.pick_up(ItemStack::new(item, count));
+
+    Ok(format!("Gave {} {} {}", name, count - left, item.name()))
+}
+
+fn list(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    args.end()?;
+
+    let mut names: Vec<&str> = context.world.players.keys().map(|name| name.as_str()).collect();
+    names.sort_unstable();
+
+    Ok(format!("{} playing: {}", names.len(), names.join(", ")))
+}
+
+fn say(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let message = args.words[args.next..].join(" ");
+    if message.is_empty() {
+        bail!("Missing message");
+    }
+
+    let from = context.player.clone().unwrap_or_else(|| "Server".to_string());
+    context.effects.push(Effect::Chat(format!("[{}] {}", from, message)));
+
+    Ok(String::new())
+}
+
+fn stop(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    args.end()?;
+    context.effects.push(Effect::Stop);
+
+    Ok("Stopping the server".to_string())
+}
+
+
+/// Changes blocks, tells the clients about the chunks that changed and
+/// remembers it for undo
+fn edit(context: &mut CommandContext, blocks: impl IntoIterator<Item = (WorldCoord, BlockID)>) -> String {
+    let changes = context.world.set_blocks(blocks);
+    let count = changes.len();
+
+    // Whole chunks are sent, an edit can change thousands of blocks
+    context.effects.extend(changed_chunks(&changes).into_iter().map(Effect::Chunk));
+    if let Some(name) = &context.player {
+        context.world.edits.entry(name.clone()).or_default().history.push(changes);
+    }
+
+    format!("Changed {} blocks", count)
+}
+
+/// The edit session of the player that runs the command
+fn session<'b>(context: &'b mut CommandContext) -> Result<&'b mut EditSession> {
+    let name = context.player.clone().ok_or_else(|| anyhow!("Only players can edit a selection"))?;
+    Ok(context.world.edits.entry(name).or_default())
+}
+
+/// The block the player is in, which copies and pastes are relative to
+fn player_block(context: &CommandContext) -> Result<WorldCoord> {
+    let position = context.origin().ok_or_else(|| anyhow!("That needs a player"))?;
+    Ok(WorldCoord::from_point(position))
+}
+
+/// The selected region, if it is not too big to edit
+fn selected(context: &mut CommandContext) -> Result<Region> {
+    let region = session(context)?.selection.region()
+        .ok_or_else(|| anyhow!("Select two corners first, with pos1 and pos2"))?;
+
+    if region.volume() > MAX_EDIT {
+        bail!("The selection has {} blocks, at most {} can be edited at once", region.volume(), MAX_EDIT);
+    }
+
+    Ok(region)
+}
+
+fn describe(selection: Selection) -> String {
+    let corner = |coord: Option<WorldCoord>| match coord {
+        Some(coord) => format!("{} {} {}", coord.x, coord.y, coord.z),
+        None => "not set".to_string(),
+    };
+    let mut description = format!("First corner {}, second corner {}", corner(selection.first), corner(selection.second));
+
+    if let Some(region) = selection.region() {
+        let [x, y, z] = region.size();
+        description += &format!(", {} by {} by {} is {} blocks", x, y, z, region.volume());
+    }
+
+    description
+}
+
+fn pos1(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    corner(context, args, false)
+}
+
+fn pos2(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    corner(context, args, true)
+}
+
+fn corner(context: &mut CommandContext, args: &mut Arguments, second: bool) -> Result<String> {
+    let coord = match args.peek() {
+        Some(_) => args.coord(context.origin())?,
+        None => in_world(player_block(context)?)?,
+    };
+    args.end()?;
+
+    let session = session(context)?;
+    match second {
+        false => session.selection.first = Some(coord),
+        true => session.selection.second = Some(coord),
+    }
+    let selection = session.selection;
+
+    let name = context.player.clone().unwrap();
+    context.effects.push(Effect::Selection(name));
+
+    Ok(describe(selection))
+}
+
+fn sel(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let clear = match args.peek() {
+        Some("clear") => {
+            args.next += 1;
+            true
+        }
+        Some(word) => bail!("Unknown selection command '{}', use clear", word),
+        None => false,
+    };
+    args.end()?;
+
+    let session = session(context)?;
+    if !clear {
+        return Ok(describe(session.selection))
+    }
+
+    session.selection = Selection::default();
+    let name = context.player.clone().unwrap();
+    context.effects.push(Effect::Selection(name));
+
+    Ok("Cleared the selection".to_string())
+}
+
+fn set(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let block = args.block()?;
+    args.end()?;
+
+    let region = selected(context)?;
+    Ok(edit(context, region.coords().map(|at| (at, block))))
+}
+
+fn replace(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let (from, to) = (args.block()?, args.block()?);
+    args.end()?;
+
+    let region = selected(context)?;
+    let world = &context.world;
+    let blocks: Vec<(WorldCoord, BlockID)> = region.coords()
+        .filter(|at| world.get_block(*at) == Some(from))
+        .map(|at| (at, to))
+        .collect();
+
+    Ok(edit(context, blocks))
+}
+
+fn hollow(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let block = args.block()?;
+    args.end()?;
+
+    let region = selected(context)?;
+    let blocks = region.coords().map(|at| match region.on_side(at) {
+        true => (at, block),
+        false => (at, Blocks::AIR as BlockID),
+    });
+
+    Ok(edit(context, blocks))
+}
+
+fn walls(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let block = args.block()?;
+    args.end()?;
+
+    let region = selected(context)?;
+    Ok(edit(context, region.coords().filter(|at| region.on_wall(*at)).map(|at| (at, block))))
+}
+
+fn copy(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    args.end()?;
+
+    let region = selected(context)?;
+    let clipboard = Clipboard::copy(&context.world.chunks, region, player_block(context)?);
+    session(context)?.clipboard = Some(clipboard);
+
+    Ok(format!("Copied {} blocks", region.volume()))
+}
+
+fn paste(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let skip_air = match args.peek() {
+        Some("noair") => {
+            args.next += 1;
+            true
+        }
+        Some(word) => bail!("Unknown paste option '{}', use noair", word),
+        None => false,
+    };
+    args.end()?;
+
+    let origin = player_block(context)?;
+    let clipboard = session(context)?.clipboard.as_ref().ok_or_else(|| anyhow!("Copy something first"))?;
+    // Loaded schematics can be larger than a selection may be
+    let volume = clipboard.block_ids().len() as i64;
+    if volume > MAX_EDIT {
+        bail!("The copy has {} blocks, at most {} can be pasted at once", volume, MAX_EDIT);
+    }
+
+    let blocks: Vec<(WorldCoord, BlockID)> = clipboard
+        .blocks(origin)
+        .filter(|(_, block)| !skip_air || *block != Blocks::AIR as BlockID)
+        .collect();
+
+    Ok(edit(context, blocks))
+}
+
+/// The copy of the player that runs the command
+fn clipboard<'b>(context: &'b mut CommandContext) -> Result<&'b mut Clipboard> {
+    session(context)?.clipboard.as_mut().ok_or_else(|| anyhow!("Copy something first"))
+}
+
+fn rotate(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let degrees = args.number::<i64>("angle")?;
+    args.end()?;
+
+    if degrees % 90 != 0 {
+        bail!("The copy can only be turned by 90, 180 or 270 degrees");
+    }
+
+    let clipboard = clipboard(context)?;
+    clipboard.rotate((degrees / 90).rem_euclid(4) as u32);
+    let [x, y, z] = clipboard.size();
+
+    Ok(format!("Turned the copy by {} degrees, it is {} by {} by {}", degrees, x, y, z))
+}
+
+fn mirror(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let axis = match args.word("axis")? {
+        "x" => Axis::X,
+        "y" => Axis::Y,
+        "z" => Axis::Z,
+        other => bail!("Unknown axis '{}', use x, y or z", other),
+    };
+    args.end()?;
+
+    clipboard(context)?.mirror(axis);
+
+    Ok(format!("Flipped the copy along {:?}", axis))
+}
+
+fn schem(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    let action = args.word("save, load or list")?;
+    if action == "list" {
+        args.end()?;
+        let names = schematic::list_schematics();
+        return Ok(format!("{} schematics: {}", names.len(), names.join(", ")))
+    }
+
+    let name = args.word("name")?;
+    args.end()?;
+    let path = schematic::schematic_file(name)
+        .ok_or_else(|| anyhow!("'{}' can't be used as a name, use letters, digits, - and _", name))?;
+
+    match action {
+        "save" => {
+            let clipboard = session(context)?.clipboard.as_ref().ok_or_else(|| anyhow!("Copy something first"))?;
+            schematic::save(&path, clipboard)?;
+            Ok(format!("Saved the copy to {}", path.display()))
+        }
+        "load" => {
+            let loaded = schematic::load(&path, block_colors())?;
+            let [x, y, z] = loaded.clipboard.size();
+            session(context)?.clipboard = Some(loaded.clipboard);
+
+            let unknown = match loaded.placeholders {
+                0 => String::new(),
+                count => format!(", {} unknown blocks became {}", count, schematic::PLACEHOLDER_BLOCK),
+            };
+            Ok(format!("Loaded {}, {} by {} by {}{}, paste it with paste", path.display(), x, y, z, unknown))
+        }
+        other => bail!("Unknown schematic command '{}', use save, load or list", other),
+    }
+}
+
+fn undo(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    step_history(context, args, true)
+}
+
+fn redo(context: &mut CommandContext, args: &mut Arguments) -> Result<String> {
+    step_history(context, args, false)
+}
+
+/// Undoes or redoes a number of edits
+fn step_history(context: &mut CommandContext, args: &mut Arguments, undo: bool) -> Result<String> {
+    let count = match args.peek() {
+        Some(_) => args.number::<usize>("count")?,
+        None => 1,
+    };
+    args.end()?;
+
+    let (mut edits, mut blocks) = (0, 0);
+    for _ in 0..count {
+        let history = &mut session(context)?.history;
+        let changes: Vec<(WorldCoord, BlockID)> = match undo {
+            true => match history.undo() {
+                Some(changes) => changes.iter().rev().map(|change| (change.at, change.old)).collect(),
+                None => break,
+            },
+            false => match history.redo() {
+                Some(changes) => changes.iter().map(|change| (change.at, change.new)).collect(),
+                None => break,
+            },
+        };
+
+        let changes = context.world.set_blocks(changes);
+        context.effects.extend(changed_chunks(&changes).into_iter().map(Effect::Chunk));
+        edits += 1;
+        blocks += changes.len();
+    }
+
+    match (edits, undo) {
+        (0, true) => bail!("Nothing to undo"),
+        (0, false) => bail!("Nothing to redo"),
+        (_, true) => Ok(format!("Undid {} edits, {} blocks changed", edits, blocks)),
+        (_, false) => Ok(format!("Redid {} edits, {} blocks changed", edits, blocks)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::testing::setup;
+
+    fn world() -> World {
+        setup();
+        let mut world = World::new(7);
+        world.join("alice").unwrap();
+        world.join("bob").unwrap();
+        world
+    }
+
+    fn run(world: &mut World, line: &str) -> Result<String> {
+        let mut context = CommandContext::new(world, Some("alice".to_string()), true);
+        Commands::builtin().run(line, &mut context)
+    }
+
+    fn complete(line: &str) -> Vec<String> {
+        setup();
+        Commands::builtin().complete(line, &["alice".to_string(), "bob".to_string()])
+    }
+
+    #[test]
+    fn lines_are_split_in_words() {
+        assert_eq!(split("/tp  1 2\t3 "), ("tp", vec!["1", "2", "3"]));
+        assert_eq!(split("seed"), ("seed", vec![]));
+        assert_eq!(split(""), ("", vec![]));
+    }
+
+    #[test]
+    fn coordinates_can_be_relative() {
+        let mut world = world();
+        world.players.get_mut("alice").unwrap().position = Point3::new(10.0, 40.0, -5.0);
+
+        run(&mut world, "/tp ~ ~5 ~-1.5").unwrap();
+        assert_eq!(world.players["alice"].position, Point3::new(10.0, 45.0, -6.5));
+
+        run(&mut world, "tp 1 2 3").unwrap();
+        assert_eq!(world.players["alice"].position, Point3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn the_player_can_be_left_out() {
+        let mut world = world();
+        run(&mut world, "tp bob 4 50 4").unwrap();
+        assert_eq!(world.players["bob"].position, Point3::new(4.0, 50.0, 4.0));
+
+        let before = world.players["alice"].inventory.clone();
+        run(&mut world, "give stone 3").unwrap();
+        assert!(world.players["alice"].inventory != before);
+        run(&mut world, "give bob stone").unwrap();
+    }
+
+    #[test]
+    fn wrong_arguments_are_errors() {
+        let mut world = world();
+
+        assert!(run(&mut world, "tp carol 1 2 3").is_err());
+        assert!(run(&mut world, "tp 1 2").is_err());
+        assert!(run(&mut world, "tp 1 2 3 4").is_err());
+        assert!(run(&mut world, "setblock 0 -1 0 stone").is_err());
+        assert!(run(&mut world, "setblock 0 1 0 cheese").is_err());
+        assert!(run(&mut world, "give stone 0").is_err());
+        assert!(run(&mut world, "dance").is_err());
+    }
+
+    #[test]
+    fn only_operators_change_the_world() {
+        let mut world = world();
+        let mut context = CommandContext::new(&mut world, Some("bob".to_string()), false);
+
+        assert!(Commands::builtin().run("setblock 0 1 0 stone", &mut context).is_err());
+        assert!(Commands::builtin().run("seed", &mut context).is_ok());
+    }
+
+    #[test]
+    fn large_copies_are_not_pasted() {
+        let mut world = world();
+        run(&mut world, "pos1 0 1 0").unwrap();
+        run(&mut world, "pos2 1 1 1").unwrap();
+        run(&mut world, "copy").unwrap();
+        assert!(run(&mut world, "paste").is_ok());
+
+        let size = [128, 64, 129];
+        let blocks = vec![Blocks::AIR as BlockID; (size[0] * size[1] * size[2]) as usize];
+        world.edits.get_mut("alice").unwrap().clipboard = Clipboard::from_blocks([0, 0, 0], size, blocks);
+
+        let error = run(&mut world, "paste").unwrap_err().to_string();
+        assert!(error.contains("at most"), "{}", error);
+    }
+
+    #[test]
+    fn command_names_are_completed() {
+        assert_eq!(complete("/se"), vec!["/seed", "/sel", "/set", "/setblock"]);
+        assert_eq!(complete("loc"), vec!["locate"]);
+        assert!(complete("/nothing").is_empty());
+    }
+
+    #[test]
+    fn arguments_are_completed() {
+        assert_eq!(complete("/time set n"), vec!["/time set night", "/time set noon"]);
+        assert_eq!(complete("/locate vil"), vec!["/locate village"]);
+        assert_eq!(complete("/setblock 1 2 3 sto"), vec!["/setblock 1 2 3 stone"]);
+        assert!(complete("/setblock 1 2 3 stone ").is_empty());
+    }
+
+    #[test]
+    fn optional_players_are_completed() {
+        // A player, or the first coordinate without one
+        assert_eq!(complete("/tp "), vec!["/tp alice", "/tp bob", "/tp ~"]);
+        assert_eq!(complete("/tp b"), vec!["/tp bob"]);
+        assert_eq!(complete("/tp bob "), vec!["/tp bob ~"]);
+        assert_eq!(complete("/tp bob 1 2 "), vec!["/tp bob 1 2 ~"]);
+        assert!(complete("/tp bob 1 2 3 ").is_empty());
+
+        // Without a player the coordinates start right away
+        assert_eq!(complete("/tp 1 2 "), vec!["/tp 1 2 ~"]);
+        assert!(complete("/tp 1 2 3 ").is_empty());
+
+        assert_eq!(complete("/give bob sto"), vec!["/give bob stone"]);
+        assert_eq!(complete("/give sto"), vec!["/give stone"]);
+        assert!(complete("/give stone ").is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+use crate::server::protocol::{ClientMessage, ServerMessage};
+
+/// One end of a connection between a client and a server, in the same
+/// process or over the network, see network.rs
+pub struct Connection<S, R> {
+    sender: Sender<S>,
+    /// Messages that may get lost or arrive out of order, None if everything is reliable
+    unreliable: Option<Sender<S>>,
+    receiver: Receiver<R>,
+    closed: bool,
+}
+
+/// The end the client sends and receives with
+pub type ClientConnection = Connection<ClientMessage, ServerMessage>;
+/// The end the server sends and receives with
+pub type ServerConnection = Connection<ServerMessage, ClientMessage>;
+
+/// Two connected ends, for a server that runs in the same process as its client
+pub fn local_connection() -> (ClientConnection, ServerConnection) {
+    let (client_sender, server_receiver) = channel();
+    let (server_sender, client_receiver) = channel();
+
+    (
+        Connection::new(client_sender, None, client_receiver),
+        Connection::new(server_sender, None, server_receiver),
+    )
+}
+
+impl<S, R> Connection<S, R> {
+    pub fn new(sender: Sender<S>, unreliable: Option<Sender<S>>, receiver: Receiver<R>) -> Self {
+        Self {
+            sender,
+            unreliable,
+            receiver,
+            closed: false,
+        }
+    }
+
+    /// Messages to a closed connection are dropped
+    pub fn send(&mut self, message: S) {
+        if self.sender.send(message).is_err() {
+            self.closed = true;
+        }
+    }
+
+    /// For messages that are sent again and again, where only the last one
+    /// matters, such as positions. They are dropped if a newer one arrived first.
+    pub fn send_unreliable(&mut self, message: S) {
+        let sent = match &self.unreliable {
+            Some(unreliable) => unreliable.send(message).is_ok(),
+            None => self.sender.send(message).is_ok(),
+        };
+
+        if !sent {
+            self.closed = true;
+        }
+    }
+
+    /// Every message that arrived since the last call
+    pub fn receive(&mut self) -> Vec<R> {
+        let mut messages = vec![];
+
+        loop {
+            match self.receiver.try_recv() {
+                Ok(message) => messages.push(message),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break
+                }
+            }
+        }
+
+        messages
+    }
+
+    /// True once the other end is gone
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
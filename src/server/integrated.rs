@@ -0,0 +1,39 @@
+use std::sync::mpsc::channel;
+use std::thread::JoinHandle;
+
+use crate::server::{
+    connection::{local_connection, ClientConnection},
+    server::{Server, ServerKind},
+};
+use crate::world::world::World;
+
+/// A server on its own thread in the game itself, for singleplayer
+pub struct IntegratedServer {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IntegratedServer {
+    /// Starts a server for the world and returns the connection to it
+    pub fn start(world: World) -> (Self, ClientConnection) {
+        let (client, connection) = local_connection();
+        let (incoming, connections) = channel();
+        // Can't fail, the receiver is right here
+        let _ = incoming.send(connection);
+
+        let thread = std::thread::Builder::new()
+            .name("server".to_string())
+            .spawn(move || Server::new(world, connections, ServerKind::Integrated).run())
+            .expect("Could not start the server thread");
+
+        (Self { thread: Some(thread) }, client)
+    }
+
+    /// Waits until the server stopped, after the client left. The world is saved by then.
+    pub fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                println!("The server stopped with an error, the world may not be saved");
+            }
+        }
+    }
+}
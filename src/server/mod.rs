@@ -0,0 +1,7 @@
+pub mod protocol;
+pub mod connection;
+pub mod network;
+pub mod command;
+pub mod server;
+pub mod integrated;
+pub mod args;
\ No newline at end of file
@@ -0,0 +1,393 @@
+use anyhow::{bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{channel, Receiver, Sender},
+    Arc, Mutex,
+};
+use std::time::Duration;
+
+use crate::server::{
+    connection::{ClientConnection, Connection, ServerConnection},
+    protocol::{ClientMessage, ServerMessage, PROTOCOL_VERSION},
+};
+use crate::world::block::registry::registry;
+
+/// The port servers listen on if no other one is given, for both TCP and UDP
+pub const DEFAULT_PORT: u16 = 24454;
+
+/// Starts every handshake, so something that is not the game is noticed right away
+const MAGIC: [u8; 4] = *b"LWLD";
+/// Frames that say they are larger than this are not from the game
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+/// Larger messages go over TCP, so datagrams are not split up on the way
+const MAX_DATAGRAM: usize = 1200;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the network threads check whether they should stop
+const POLL: Duration = Duration::from_millis(100);
+
+/// The first frame both ends send over TCP
+#[derive(Debug, Serialize, Deserialize)]
+struct Handshake {
+    magic: [u8; 4],
+    version: u32,
+    /// See BlockRegistry::fingerprint
+    blocks: u64,
+    /// Chosen by the server, the client puts it in front of its datagrams
+    token: u64,
+}
+
+impl Handshake {
+    fn new(token: u64) -> Self {
+        Self {
+            magic: MAGIC,
+            version: PROTOCOL_VERSION,
+            blocks: registry().fingerprint(),
+            token,
+        }
+    }
+
+    /// Fails if the other end can't be talked to
+    fn check(&self) -> Result<()> {
+        if self.magic != MAGIC {
+            bail!("The other end is not Ludwig World 3D")
+        }
+        if self.version != PROTOCOL_VERSION {
+            bail!("The other end uses protocol version {}, this is version {}", self.version, PROTOCOL_VERSION)
+        }
+        if self.blocks != registry().fingerprint() {
+            bail!("The other end has other blocks, both need the same resource packs")
+        }
+
+        Ok(())
+    }
+}
+
+/// Unreliable messages go over UDP in these
+#[derive(Serialize, Deserialize)]
+struct Datagram<M> {
+    token: u64,
+    /// Counts up, datagrams that arrive after a newer one are dropped
+    sequence: u64,
+    message: M,
+}
+
+/// Joins a server. The address is a host name or ip address, with the port after a : if it is not the default port.
+pub fn connect(address: &str) -> Result<ClientConnection> {
+    let address = resolve(address)?;
+
+    let stream = TcpStream::connect_timeout(&address, HANDSHAKE_TIMEOUT)
+        .with_context(|| format!("Could not connect to {}", address))?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    write_frame(&mut &stream, &Handshake::new(0))?;
+    let server: Handshake = read_frame(&mut &stream).context("The server did not answer")?;
+    server.check()?;
+    stream.set_read_timeout(None)?;
+
+    let local = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(address)?;
+    socket.set_read_timeout(Some(POLL))?;
+
+    let (sender, outgoing) = channel();
+    let (unreliable, unreliable_outgoing) = channel::<ClientMessage>();
+    let (incoming, receiver) = channel();
+    let closed = Arc::new(AtomicBool::new(false));
+    // A datagram of the server arrived, so datagrams get through both ways
+    let heard = Arc::new(AtomicBool::new(false));
+
+    spawn_writer(stream.try_clone()?, outgoing);
+    spawn_reader(stream, incoming.clone(), closed.clone(), || {});
+
+    let token = server.token;
+    let reliable = sender.clone();
+    let (send_socket, send_heard) = (socket.try_clone()?, heard.clone());
+    std::thread::spawn(move || {
+        for (sequence, message) in unreliable_outgoing.iter().enumerate() {
+            let datagram = Datagram { token, sequence: sequence as u64 + 1, message: &message };
+            let bytes = match bincode::serialize(&datagram) {
+                Ok(bytes) => bytes,
+                Err(_) => continue,
+            };
+
+            let fits = bytes.len() <= MAX_DATAGRAM;
+            if fits {
+                // Lost datagrams are not a problem, the next one is on its way
+                let _ = send_socket.send(&bytes);
+            }
+            // Until the server answered, datagrams may not get through at all
+            if (!fits || !send_heard.load(Ordering::Relaxed)) && reliable.send(message).is_err() {
+                break
+            }
+        }
+    });
+
+    std::thread::spawn(move || {
+        let mut buffer = vec![0; 65536];
+        let mut last = 0;
+
+        while !closed.load(Ordering::Relaxed) {
+            let length = match socket.recv(&mut buffer) {
+                Ok(length) => length,
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => continue,
+                // The server is not listening for datagrams, everything goes over TCP
+                Err(_) => continue,
+            };
+
+            if let Ok(datagram) = bincode::deserialize::<Datagram<ServerMessage>>(&buffer[..length]) {
+                if datagram.token == token && datagram.sequence > last {
+                    last = datagram.sequence;
+                    heard.store(true, Ordering::Relaxed);
+
+                    if incoming.send(datagram.message).is_err() {
+                        break
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Connection::new(sender, Some(unreliable), receiver))
+}
+
+/// A client that did the handshake, to find it again when its datagrams arrive
+struct Route {
+    messages: Sender<ClientMessage>,
+    /// Where its datagrams come from, None until the first one arrived
+    address: Arc<Mutex<Option<SocketAddr>>>,
+    sequence: u64,
+}
+
+/// Accepts clients on a port, on its own threads. Stops when it is dropped.
+pub struct Listener {
+    /// The port that is listened on, for when port 0 was asked for
+    pub port: u16,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Listener {
+    /// Listens on a port, 0 picks a free one. Every client that did the
+    /// handshake comes out of the returned receiver, see Server::new.
+    pub fn bind(port: u16) -> Result<(Self, Receiver<ServerConnection>)> {
+        let tcp = TcpListener::bind(("0.0.0.0", port)).with_context(|| format!("Could not listen on port {}", port))?;
+        let port = tcp.local_addr()?.port();
+        let udp = UdpSocket::bind(("0.0.0.0", port)).with_context(|| format!("Could not listen for datagrams on port {}", port))?;
+
+        tcp.set_nonblocking(true)?;
+        udp.set_read_timeout(Some(POLL))?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let routes: Arc<Mutex<HashMap<u64, Route>>> = Arc::new(Mutex::new(HashMap::new()));
+        let (connections, receiver) = channel();
+
+        let (accept_stopped, accept_routes, accept_udp) = (stopped.clone(), routes.clone(), udp.try_clone()?);
+        std::thread::spawn(move || {
+            while !accept_stopped.load(Ordering::Relaxed) {
+                match tcp.accept() {
+                    Ok((stream, address)) => {
+                        let (connections, routes) = (connections.clone(), accept_routes.clone());
+                        let udp = match accept_udp.try_clone() {
+                            Ok(udp) => udp,
+                            Err(_) => continue,
+                        };
+
+                        // The handshake waits for the client, others can join in the meantime
+                        std::thread::spawn(move || {
+                            match accept(stream, udp, routes) {
+                                Ok(connection) => {
+                                    let _ = connections.send(connection);
+                                }
+                                Err(e) => println!("Refused {}: {:?}", address, e),
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(POLL),
+                    Err(e) => {
+                        println!("Could not accept a client: {:?}", e);
+                        std::thread::sleep(POLL);
+                    }
+                }
+            }
+        });
+
+        let udp_stopped = stopped.clone();
+        std::thread::spawn(move || {
+            let mut buffer = vec![0; 65536];
+
+            while !udp_stopped.load(Ordering::Relaxed) {
+                let (length, from) = match udp.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    Err(_) => continue,
+                };
+
+                let datagram = match bincode::deserialize::<Datagram<ClientMessage>>(&buffer[..length]) {
+                    Ok(datagram) => datagram,
+                    Err(_) => continue,
+                };
+
+                let mut routes = routes.lock().unwrap();
+                if let Some(route) = routes.get_mut(&datagram.token) {
+                    if datagram.sequence > route.sequence {
+                        route.sequence = datagram.sequence;
+                        *route.address.lock().unwrap() = Some(from);
+                        let _ = route.messages.send(datagram.message);
+                    }
+                }
+            }
+        });
+
+        Ok((Self { port, stopped }, receiver))
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Does the handshake with a client that just connected
+fn accept(stream: TcpStream, udp: UdpSocket, routes: Arc<Mutex<HashMap<u64, Route>>>) -> Result<ServerConnection> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    let client: Handshake = read_frame(&mut &stream).context("No handshake")?;
+    let token = loop {
+        let token = rand::random();
+        if token != 0 && !routes.lock().unwrap().contains_key(&token) {
+            break token
+        }
+    };
+    // Also when the client can't join, so it can tell the player why
+    write_frame(&mut &stream, &Handshake::new(token))?;
+    client.check()?;
+    stream.set_read_timeout(None)?;
+
+    let (sender, outgoing) = channel();
+    let (unreliable, unreliable_outgoing) = channel::<ServerMessage>();
+    let (incoming, receiver) = channel();
+    let address = Arc::new(Mutex::new(None));
+
+    routes.lock().unwrap().insert(token, Route {
+        messages: incoming.clone(),
+        address: address.clone(),
+        sequence: 0,
+    });
+
+    spawn_writer(stream.try_clone()?, outgoing);
+    spawn_reader(stream, incoming, Arc::new(AtomicBool::new(false)), move || {
+        routes.lock().unwrap().remove(&token);
+    });
+
+    let reliable = sender.clone();
+    std::thread::spawn(move || {
+        for (sequence, message) in unreliable_outgoing.iter().enumerate() {
+            let datagram = Datagram { token, sequence: sequence as u64 + 1, message: &message };
+            let bytes = bincode::serialize(&datagram).unwrap_or_default();
+            let to = *address.lock().unwrap();
+
+            match to {
+                Some(to) if !bytes.is_empty() && bytes.len() <= MAX_DATAGRAM => {
+                    let _ = udp.send_to(&bytes, to);
+                }
+                // No datagram of the client arrived yet, or it is too large
+                _ => {
+                    if reliable.send(message).is_err() {
+                        break
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Connection::new(sender, Some(unreliable), receiver))
+}
+
+/// Adds the default port if the address has none
+fn resolve(address: &str) -> Result<SocketAddr> {
+    let address = address.trim();
+    let with_port = match address.to_socket_addrs() {
+        Ok(addresses) => addresses.collect::<Vec<_>>(),
+        Err(_) => (address, DEFAULT_PORT)
+            .to_socket_addrs()
+            .with_context(|| format!("Unknown address '{}'", address))?
+            .collect(),
+    };
+
+    with_port.into_iter().next().with_context(|| format!("Unknown address '{}'", address))
+}
+
+/// Sends the messages of a channel until it is closed, then closes the stream
+fn spawn_writer<S: Serialize + Send + 'static>(stream: TcpStream, messages: Receiver<S>) {
+    std::thread::spawn(move || {
+        let mut writer = BufWriter::new(&stream);
+
+        while let Ok(message) = messages.recv() {
+            let mut written = write_frame(&mut writer, &message);
+
+            // Everything that is waiting goes out at once
+            while let (true, Ok(message)) = (written.is_ok(), messages.try_recv()) {
+                written = write_frame(&mut writer, &message);
+            }
+
+            if written.is_err() || writer.flush().is_err() {
+                break
+            }
+        }
+
+        let _ = writer.flush();
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+}
+
+/// Passes on every message that arrives until the stream is closed
+fn spawn_reader<R: DeserializeOwned + Send + 'static>(
+    stream: TcpStream,
+    messages: Sender<R>,
+    closed: Arc<AtomicBool>,
+    on_close: impl FnOnce() + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(&stream);
+
+        while let Ok(message) = read_frame(&mut reader) {
+            if messages.send(message).is_err() {
+                break
+            }
+        }
+
+        closed.store(true, Ordering::Relaxed);
+        on_close();
+    });
+}
+
+/// A frame is the length of a message and then the message
+fn write_frame<T: Serialize>(writer: &mut impl Write, value: &T) -> Result<()> {
+    let bytes = bincode::serialize(value)?;
+
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+
+    Ok(())
+}
+
+fn read_frame<T: DeserializeOwned>(reader: &mut impl Read) -> Result<T> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_FRAME {
+        bail!("A frame of {} bytes is too large", length)
+    }
+
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bincode::deserialize(&bytes)?)
+}
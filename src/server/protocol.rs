@@ -0,0 +1,90 @@
+use cgmath::{Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::game::player::{
+    inventory::{GameMode, Inventory},
+    player::InventoryAction,
+};
+use crate::world::{
+    block::blocks::BlockID,
+    chunk::pos::{ChunkPos, WorldCoord},
+    entity::entity::Entity,
+};
+
+/// Goes up whenever a message changes, clients only join servers with the same version
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// What a client tells the server. The client never changes the world itself,
+/// it asks the server and waits for the change to come back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// The first message, with the name of the player and how many chunks
+    /// around the player should be sent
+    Join { name: String, view_distance: u32 },
+    ViewDistance(u32),
+    /// Where the player is and looks, in degrees. Sent every tick, unreliable.
+    Move { position: Point3<f32>, yaw: f32, pitch: f32 },
+    /// Started breaking a block in survival mode, the server times it from here
+    StartBreaking(WorldCoord),
+    /// Stopped breaking before the block broke
+    AbortBreaking,
+    /// A block was broken. In survival mode only once it had the time since
+    /// StartBreaking, the server drops the items.
+    BreakBlock(WorldCoord),
+    /// Places the held block
+    PlaceBlock(WorldCoord),
+    /// Throws one of the held item in a direction
+    ThrowItem(Vector3<f32>),
+    Inventory(InventoryAction),
+    /// Singleplayer stops the world while a menu is open. The server saves when paused.
+    Pause(bool),
+    /// A line for the other players
+    Chat(String),
+    /// A line that was typed after a /, see Commands
+    Command(String),
+    /// The server saves the player and the connection is closed
+    Leave,
+}
+
+/// What the server tells a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// The answer to Join, with where the player was left
+    Welcome { seed: u32, position: Point3<f32>, yaw: f32, pitch: f32 },
+    /// A chunk came into view. Chunks are sent the way they are saved, see
+    /// codec::encode_chunk, so the network and the disk can't drift apart.
+    Chunk(Vec<u8>),
+    /// A chunk went out of view
+    UnloadChunk(ChunkPos),
+    BlockChange { coord: WorldCoord, block: BlockID },
+    /// Chunks that changed together, such as by an edit. They replace the
+    /// ones the client has and are meshed once. Encoded like Chunk.
+    Chunks(Vec<Vec<u8>>),
+    /// The corners the player selected for editing
+    Selection { first: Option<WorldCoord>, second: Option<WorldCoord> },
+    /// The world time in ticks, sent every tick
+    Time(u64),
+    /// The inventory changed on the server, it replaces the one of the client
+    Inventory { game_mode: GameMode, inventory: Box<Inventory> },
+    /// Every entity close to the player, sent every tick
+    Entities(Vec<Entity>),
+    /// The other players close to the player, sent every tick, unreliable
+    Players(Vec<PlayerState>),
+    /// A line of chat, or that someone joined or left
+    Chat(String),
+    /// The player was moved, by a command
+    Teleport(Point3<f32>),
+    /// The client has to leave, with the reason. The connection is closed after it.
+    Disconnect(String),
+}
+
+/// Another player, as far as a client needs to know
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub name: String,
+    /// Where its eyes are
+    pub position: Point3<f32>,
+    /// In degrees
+    pub yaw: f32,
+    pub pitch: f32,
+}
@@ -0,0 +1,678 @@
+use cgmath::{MetricSpace, Point3, Vector3};
+use std::collections::HashSet;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use crate::game::{
+    breaking::ticks_to_break,
+    clock::{TickClock, TICKS_PER_SECOND},
+    player::{
+        inventory::{GameMode, Inventory},
+        item::{Item, ItemStack, ToolKind},
+        player::{valid_player_name, REACH},
+    },
+};
+use crate::server::{
+    command::{Commands, CommandContext, Effect},
+    connection::ServerConnection,
+    protocol::{ClientMessage, ServerMessage, PlayerState},
+};
+use crate::settings::settings::{MAX_RENDER_DISTANCE, MAX_CAMERA_SPEED};
+use crate::world::{
+    world::World,
+    block::blocks::{get_block, BlockID, Blocks},
+    chunk::{codec::encode_chunk, pos::{ChunkPos, WorldCoord}},
+    constants::CHUNKSIZE,
+    entity::{aabb::Aabb, entity::EntityKind},
+};
+
+/// Save the world every minute
+const AUTOSAVE_TICKS: u64 = 60 * TICKS_PER_SECOND as u64;
+/// Chunks generated and sent to a client every tick, more would make ticks late
+const CHUNKS_PER_TICK: usize = 16;
+/// How long the server waits for messages between ticks
+const IDLE: Duration = Duration::from_millis(1);
+/// Longer chat lines are cut off
+const MAX_CHAT_LENGTH: usize = 256;
+/// How far a player can move in a tick, in blocks. The camera goes faster
+/// when it moves diagonally, up and scrolls at the same time.
+const MAX_MOVE: f32 = 3.0 * MAX_CAMERA_SPEED / TICKS_PER_SECOND as f32;
+/// Blocks are reached from the eyes to their middle, and placed against the
+/// block that was hit, so they can be a bit farther away than REACH
+const MAX_REACH: f32 = REACH + 2.0;
+/// Breaking can be done this many ticks early, the messages that start and
+/// finish it don't always arrive the same number of ticks apart
+const BREAK_LENIENCY: u64 = 2;
+
+/// How the server is run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerKind {
+    /// In the game, for singleplayer. The world stops while the game is paused
+    /// and the server stops when the player leaves.
+    Integrated,
+    /// On its own, players join over the network. Runs until it is stopped from the console.
+    Dedicated,
+}
+
+/// A block a player started breaking in survival mode
+struct Breaking {
+    coord: WorldCoord,
+    /// The tick it was started in
+    started: u64,
+    /// The tool that was held then
+    tool: Option<ToolKind>,
+}
+
+/// A connection to the server and what the server sent over it
+struct Client {
+    connection: ServerConnection,
+    /// The name of its player, None until it joined
+    name: Option<String>,
+
+    /// How many chunks around the player are sent
+    view_distance: u32,
+    /// The chunks the client has
+    sent: HashSet<ChunkPos>,
+    /// The inventory the client has, to only send it when it changed
+    sent_inventory: Option<(GameMode, Inventory)>,
+    /// The tick of the last move that was allowed
+    moved: u64,
+    breaking: Option<Breaking>,
+
+    paused: bool,
+    /// It left or was disconnected, it is removed after the messages are handled
+    gone: bool,
+}
+
+impl Client {
+    fn new(connection: ServerConnection) -> Self {
+        Self {
+            connection,
+            name: None,
+
+            view_distance: 0,
+            sent: HashSet::new(),
+            sent_inventory: None,
+            moved: 0,
+            breaking: None,
+
+            paused: false,
+            gone: false,
+        }
+    }
+}
+
+/// Owns the world and simulates it: generates chunks, ticks and decides what
+/// happens to blocks and items. Runs without any graphics, it only talks to
+/// its clients through connections.
+pub struct Server {
+    world: World,
+    kind: ServerKind,
+    clients: Vec<Client>,
+    /// New clients, see Listener and IntegratedServer
+    incoming: Receiver<ServerConnection>,
+    /// Lines typed in the console of a dedicated server
+    console: Option<Receiver<String>>,
+
+    /// The farthest a client can see, in chunks
+    pub max_view_distance: u32,
+    commands: Commands,
+    /// Players that can use every command. In singleplayer the player always can.
+    pub operators: HashSet<String>,
+    running: bool,
+    /// Ticks since the server started
+    ticks: u64,
+}
+
+impl Server {
+    pub fn new(world: World, incoming: Receiver<ServerConnection>, kind: ServerKind) -> Self {
+        Self {
+            world,
+            kind,
+            clients: vec![],
+            incoming,
+            console: None,
+
+            max_view_distance: MAX_RENDER_DISTANCE,
+            commands: Commands::builtin(),
+            operators: HashSet::new(),
+            running: true,
+            ticks: 0,
+        }
+    }
+
+    /// Takes commands from these lines, see run_command
+    pub fn set_console(&mut self, lines: Receiver<String>) {
+        self.console = Some(lines);
+    }
+
+    /// Ticks the world until it is stopped, then saves it
+    pub fn run(mut self) {
+        // A new world is listed as soon as it exists
+        self.save();
+        let mut clock = TickClock::new();
+
+        while self.running {
+            self.receive();
+            self.read_console();
+
+            if self.paused() {
+                // Don't catch up on the time spent paused
+                clock.resume();
+            } else {
+                for _ in 0..clock.advance() {
+                    self.tick();
+                }
+            }
+
+            std::thread::sleep(IDLE);
+        }
+
+        self.save();
+    }
+
+    /// Accepts new clients and handles every message that arrived since the last call
+    pub fn receive(&mut self) {
+        while let Ok(connection) = self.incoming.try_recv() {
+            self.clients.push(Client::new(connection));
+        }
+
+        for index in 0..self.clients.len() {
+            for message in self.clients[index].connection.receive() {
+                self.handle(index, message);
+            }
+
+            // The client is gone without saying goodbye
+            let client = &self.clients[index];
+            if client.connection.is_closed() && !client.gone {
+                self.handle(index, ClientMessage::Leave);
+            }
+        }
+
+        self.clients.retain(|client| !client.gone);
+
+        if self.kind == ServerKind::Integrated && self.clients.is_empty() {
+            self.running = false;
+        }
+    }
+
+    /// Handles a message of the client at an index in clients
+    fn handle(&mut self, index: usize, message: ClientMessage) {
+        if self.clients[index].gone {
+            return
+        }
+
+        let name = match (message, self.clients[index].name.clone()) {
+            (ClientMessage::Join { name, view_distance }, None) => return self.join(index, name, view_distance),
+            (ClientMessage::Leave, None) => {
+                self.clients[index].gone = true;
+                return
+            }
+            (message, Some(name)) => {
+                self.handle_player(index, &name, message);
+                name
+            }
+            // Nothing happens before joining
+            (_, None) => return,
+        };
+
+        if !self.clients[index].gone {
+            self.send_inventory(index, &name);
+        }
+    }
+
+    /// Handles a message of a client that joined
+    fn handle_player(&mut self, index: usize, name: &str, message: ClientMessage) {
+        match message {
+            // Only once
+            ClientMessage::Join { .. } => {}
+            ClientMessage::ViewDistance(view_distance) => {
+                self.clients[index].view_distance = view_distance.min(self.max_view_distance);
+            }
+            ClientMessage::Move { position, yaw, pitch } => self.move_player(index, name, position, yaw, pitch),
+            ClientMessage::StartBreaking(coord) => {
+                let tool = self.world.players.get(name).and_then(|player| player.held_item()).and_then(|item| item.tool());
+                self.clients[index].breaking = Some(Breaking { coord, started: self.ticks, tool });
+            }
+            ClientMessage::AbortBreaking => self.clients[index].breaking = None,
+            ClientMessage::BreakBlock(coord) => self.break_block(index, name, coord),
+            ClientMessage::PlaceBlock(coord) => self.place_block(name, coord),
+            ClientMessage::ThrowItem(direction) => self.throw_item(name, direction),
+            ClientMessage::Inventory(action) => {
+                // The client already did it, it has to be told when it wasn't allowed
+                let allowed = self.world.players.get_mut(name).map_or(false, |player| player.apply(action));
+                if !allowed {
+                    self.clients[index].sent_inventory = None;
+                }
+            }
+            ClientMessage::Pause(paused) => {
+                self.clients[index].paused = paused;
+                if paused && self.kind == ServerKind::Integrated {
+                    self.save();
+                }
+            }
+            ClientMessage::Chat(line) => {
+                let line: String = line.trim().chars().filter(|c| !c.is_control()).take(MAX_CHAT_LENGTH).collect();
+                if !line.is_empty() {
+                    self.chat(format!("<{}> {}", name, line));
+                }
+            }
+            ClientMessage::Command(line) => {
+                println!("{} ran /{}", name, line.trim().trim_start_matches('/'));
+                if let Some(output) = self.run_command(Some(name), &line) {
+                    self.clients[index].connection.send(ServerMessage::Chat(output));
+                }
+            }
+            ClientMessage::Leave => self.leave(index, None),
+        }
+    }
+
+    /// Puts the player of a client in the world and starts sending it chunks
+    fn join(&mut self, index: usize, name: String, view_distance: u32) {
+        if !valid_player_name(&name) {
+            return self.leave(index, Some(format!("'{}' can't be used as a name, use letters, digits, - and _", name)))
+        }
+        if self.world.players.contains_key(&name) {
+            return self.leave(index, Some(format!("{} is already playing", name)))
+        }
+
+        let player = match self.world.join(&name) {
+            Ok(player) => player.clone(),
+            Err(e) => {
+                println!("Could not load player {}: {:?}", name, e);
+                return self.leave(index, Some("Your player could not be loaded".to_string()))
+            }
+        };
+
+        let client = &mut self.clients[index];
+        client.name = Some(name.clone());
+        client.moved = self.ticks;
+        client.view_distance = view_distance.min(self.max_view_distance);
+        client.connection.send(ServerMessage::Welcome {
+            seed: self.world.seed,
+            position: player.position,
+            yaw: player.yaw,
+            pitch: player.pitch,
+        });
+        self.send_inventory(index, &name);
+
+        if self.kind == ServerKind::Dedicated {
+            self.chat(format!("{} joined the game", name));
+        }
+    }
+
+    /// Saves the player of a client and removes the client. With a reason the client is told to leave.
+    fn leave(&mut self, index: usize, reason: Option<String>) {
+        let client = &mut self.clients[index];
+        client.gone = true;
+
+        if let Some(reason) = reason {
+            client.connection.send(ServerMessage::Disconnect(reason));
+        }
+
+        if let Some(name) = client.name.take() {
+            if let Err(e) = self.world.leave(&name) {
+                println!("Could not save player {}: {:?}", name, e);
+            }
+
+            if self.kind == ServerKind::Dedicated {
+                self.chat(format!("{} left the game", name));
+            }
+        }
+    }
+
+    /// Advances the world by one tick and tells the clients what changed
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        self.world.tick();
+
+        self.update_chunks();
+
+        for index in 0..self.clients.len() {
+            let name = match &self.clients[index].name {
+                Some(name) => name.clone(),
+                None => continue,
+            };
+
+            let position = self.world.players[&name].position;
+            let range = (self.clients[index].view_distance * CHUNKSIZE as u32) as f32;
+
+            let entities = self.world.entities.within_radius(position, range).into_iter().cloned().collect();
+            let players = self.world.players
+                .iter()
+                .filter(|(other, player)| **other != name && player.position.distance2(position) <= range * range)
+                .map(|(other, player)| PlayerState {
+                    name: other.clone(),
+                    position: player.position,
+                    yaw: player.yaw,
+                    pitch: player.pitch,
+                })
+                .collect();
+
+            let connection = &mut self.clients[index].connection;
+            connection.send(ServerMessage::Time(self.world.time.ticks));
+            connection.send(ServerMessage::Entities(entities));
+            connection.send_unreliable(ServerMessage::Players(players));
+
+            self.send_inventory(index, &name);
+        }
+
+        if self.ticks % AUTOSAVE_TICKS == 0 {
+            self.save();
+        }
+    }
+
+    /// The world, for looking at it from outside the server
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Tells every player something
+    pub fn chat(&mut self, line: String) {
+        println!("{}", line);
+
+        for client in self.clients.iter_mut().filter(|client| client.name.is_some()) {
+            client.connection.send(ServerMessage::Chat(line.clone()));
+        }
+    }
+
+    /// Integrated servers stop the world while the game is paused
+    fn paused(&self) -> bool {
+        self.kind == ServerKind::Integrated && !self.clients.is_empty() && self.clients.iter().all(|client| client.paused)
+    }
+
+    fn read_console(&mut self) {
+        let lines: Vec<String> = match &self.console {
+            Some(console) => console.try_iter().collect(),
+            None => return,
+        };
+
+        for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+            if let Some(output) = self.run_command(None, line) {
+                println!("{}", output);
+            }
+        }
+    }
+
+    /// Runs a command for a player, or for the console without one. Returns what
+    /// should be shown to whoever ran it.
+    pub fn run_command(&mut self, player: Option<&str>, line: &str) -> Option<String> {
+        let operator = match player {
+            Some(name) => self.kind == ServerKind::Integrated || self.operators.contains(name),
+            None => true,
+        };
+
+        let mut context = CommandContext::new(&mut self.world, player.map(str::to_string), operator);
+        let output = match self.commands.run(line, &mut context) {
+            Ok(output) => output,
+            Err(e) => format!("{:#}", e),
+        };
+
+        // Every changed chunk is sent once, after the command is done
+        let mut chunks: Vec<ChunkPos> = vec![];
+        for effect in std::mem::take(&mut context.effects) {
+            match effect {
+                Effect::Chunk(pos) if !chunks.contains(&pos) => chunks.push(pos),
+                Effect::Chunk(_) => {}
+                effect => self.apply(effect),
+            }
+        }
+        self.send_chunks(&chunks);
+
+        Some(output).filter(|output| !output.is_empty())
+    }
+
+    /// Tells the clients what a command changed
+    fn apply(&mut self, effect: Effect) {
+        match effect {
+            Effect::Block(coord) => self.send_block(coord),
+            Effect::Chunk(pos) => self.send_chunks(&[pos]),
+            Effect::Teleport(name) => {
+                let position = match self.world.players.get(&name) {
+                    Some(player) => player.position,
+                    None => return,
+                };
+                for client in self.clients.iter_mut().filter(|client| client.name.as_ref() == Some(&name)) {
+                    client.connection.send(ServerMessage::Teleport(position));
+                }
+            }
+            Effect::Selection(name) => {
+                let selection = self.world.edits.get(&name).map(|session| session.selection).unwrap_or_default();
+                for client in self.clients.iter_mut().filter(|client| client.name.as_ref() == Some(&name)) {
+                    client.connection.send(ServerMessage::Selection { first: selection.first, second: selection.second });
+                }
+            }
+            Effect::Chat(line) => self.chat(line),
+            Effect::Stop => {
+                self.running = false;
+
+                for index in 0..self.clients.len() {
+                    self.leave(index, Some("The server stopped".to_string()));
+                }
+            }
+        }
+    }
+
+    /// Loads the chunks around every player and sends them, closest first.
+    /// Chunks that no player can see are unloaded.
+    fn update_chunks(&mut self) {
+        let mut in_view = HashSet::new();
+
+        for index in 0..self.clients.len() {
+            let (client, players) = (&mut self.clients[index], &self.world.players);
+            let player = match client.name.as_ref().and_then(|name| players.get(name)) {
+                Some(player) => player,
+                None => continue,
+            };
+
+            let center = WorldCoord::from_point(player.position).to_chunk_coord();
+            let wanted = center.area_around(client.view_distance);
+            let wanted_set: HashSet<ChunkPos> = wanted.iter().copied().collect();
+
+            let out_of_view: Vec<ChunkPos> = client.sent.iter().copied().filter(|pos| !wanted_set.contains(pos)).collect();
+            for pos in out_of_view {
+                client.sent.remove(&pos);
+                client.connection.send(ServerMessage::UnloadChunk(pos));
+            }
+
+            let missing: Vec<ChunkPos> = wanted.into_iter().filter(|pos| !client.sent.contains(pos)).take(CHUNKS_PER_TICK).collect();
+            for pos in missing {
+                let chunk = match self.world.chunks.get(pos) {
+                    Some(chunk) => chunk.clone(),
+                    None => self.world.load_chunk(pos).clone(),
+                };
+
+                let client = &mut self.clients[index];
+                client.connection.send(ServerMessage::Chunk(encode_chunk(&chunk)));
+                client.sent.insert(pos);
+            }
+
+            in_view.extend(wanted_set);
+        }
+
+        let out_of_view: Vec<ChunkPos> = self.world.chunks.positions().filter(|pos| !in_view.contains(pos)).collect();
+        for pos in out_of_view {
+            self.world.unload_chunk(pos);
+        }
+    }
+
+    /// Moves can be lost, so a player can move as far as it could have in
+    /// every tick since the last one. A player that goes farther, or
+    /// somewhere that isn't a position, is put back where it was.
+    fn move_player(&mut self, index: usize, name: &str, position: Point3<f32>, yaw: f32, pitch: f32) {
+        let player = match self.world.players.get_mut(name) {
+            Some(player) => player,
+            None => return,
+        };
+        let client = &mut self.clients[index];
+
+        let ticks = self.ticks.saturating_sub(client.moved) + 1;
+        let finite = [position.x, position.y, position.z, yaw, pitch].iter().all(|value| value.is_finite());
+        if !finite || position.distance(player.position) > MAX_MOVE * ticks as f32 {
+            client.connection.send(ServerMessage::Teleport(player.position));
+            return
+        }
+
+        player.position = position;
+        player.yaw = yaw;
+        player.pitch = pitch;
+        client.moved = self.ticks;
+    }
+
+    /// Whether the player can reach a block from where the server thinks it is
+    fn in_reach(&self, name: &str, coord: WorldCoord) -> bool {
+        let middle = Point3::new(coord.x as f32 + 0.5, coord.y as f32 + 0.5, coord.z as f32 + 0.5);
+
+        self.world.players.get(name).map_or(false, |player| player.position.distance(middle) <= MAX_REACH)
+    }
+
+    /// Breaking takes time, the client only says when it is done. In survival
+    /// mode it has to have started breaking that block long enough ago with
+    /// the tool it held then or holds now, and the block drops an item.
+    fn break_block(&mut self, index: usize, name: &str, coord: WorldCoord) {
+        let breaking = self.clients[index].breaking.take();
+        if !self.in_reach(name, coord) {
+            return
+        }
+
+        let block = match self.world.get_block(coord) {
+            Some(block) if block != Blocks::AIR as BlockID && get_block(block).hardness >= 0.0 => block,
+            _ => return,
+        };
+
+        if self.world.players[name].game_mode == GameMode::Survival {
+            let breaking = match breaking {
+                Some(breaking) if breaking.coord == coord => breaking,
+                _ => return,
+            };
+
+            let held = self.world.players[name].held_item().and_then(|item| item.tool());
+            let needed = [breaking.tool, held]
+                .iter()
+                .filter_map(|&tool| ticks_to_break(get_block(block), tool))
+                .min()
+                .unwrap_or(0);
+            if self.ticks - breaking.started + BREAK_LENIENCY < needed {
+                return
+            }
+        }
+
+        self.set_block(coord, Blocks::AIR as BlockID);
+
+        if self.world.players[name].game_mode == GameMode::Survival {
+            if let Some(drop) = get_block(block).drops {
+                let position = Point3::new(coord.x as f32 + 0.5, coord.y as f32 + 0.375, coord.z as f32 + 0.5);
+                let stack = ItemStack::new(Item::Block(drop), 1);
+                self.world.entities.spawn(EntityKind::Item(stack), position, Vector3::new(0.0, 0.15, 0.0));
+            }
+        }
+    }
+
+    /// Places the held block, if nothing is in the way. Blocks that are not
+    /// solid, like water, are replaced.
+    fn place_block(&mut self, name: &str, coord: WorldCoord) {
+        if !self.in_reach(name, coord) {
+            return
+        }
+
+        match self.world.get_block(coord) {
+            Some(block) if !get_block(block).solid => (),
+            _ => return,
+        }
+
+        // Items end up on top of the block, but mobs and players are in the way
+        let block = Aabb::new(
+            Point3::new(coord.x as f32, coord.y as f32, coord.z as f32),
+            Point3::new(coord.x as f32 + 1.0, coord.y as f32 + 1.0, coord.z as f32 + 1.0),
+        );
+        let blocked = self.world.entities.within_box(&block)
+            .iter()
+            .any(|entity| !matches!(entity.kind, EntityKind::Item(_)));
+        let occupied = self.world.players
+            .iter()
+            .any(|(other, player)| other != name && WorldCoord::from_point(player.position) == coord);
+
+        if blocked || occupied {
+            return
+        }
+
+        let block = self.world.players.get_mut(name).and_then(|player| player.take_block());
+        if let Some(block) = block {
+            self.set_block(coord, block);
+        }
+    }
+
+    fn throw_item(&mut self, name: &str, direction: Vector3<f32>) {
+        let player = match self.world.players.get_mut(name) {
+            Some(player) => player,
+            None => return,
+        };
+
+        if let Some(stack) = player.throw_item() {
+            let position = player.position - Vector3::new(0.0, 0.3, 0.0);
+            let velocity = direction * 0.35 + Vector3::new(0.0, 0.1, 0.0);
+            self.world.entities.spawn(EntityKind::Item(stack), position, velocity);
+        }
+    }
+
+    /// Changes a block and tells every client that has its chunk
+    fn set_block(&mut self, coord: WorldCoord, block: BlockID) {
+        if self.world.set_block(coord, block) {
+            self.send_block(coord);
+        }
+    }
+
+    fn send_block(&mut self, coord: WorldCoord) {
+        let block = match self.world.get_block(coord) {
+            Some(block) => block,
+            None => return,
+        };
+
+        let pos = coord.to_chunk_coord();
+        for client in self.clients.iter_mut().filter(|client| client.sent.contains(&pos)) {
+            client.connection.send(ServerMessage::BlockChange { coord, block });
+        }
+    }
+
+    /// Sends changed chunks again, all at once to every client that has some of them
+    fn send_chunks(&mut self, positions: &[ChunkPos]) {
+        let chunks = &self.world.chunks;
+
+        for client in &mut self.clients {
+            let changed: Vec<_> = positions
+                .iter()
+                .filter(|pos| client.sent.contains(pos))
+                .filter_map(|pos| chunks.get(*pos).map(encode_chunk))
+                .collect();
+
+            if !changed.is_empty() {
+                client.connection.send(ServerMessage::Chunks(changed));
+            }
+        }
+    }
+
+    /// Sends the inventory if it changed since it was sent last
+    fn send_inventory(&mut self, index: usize, name: &str) {
+        let player = match self.world.players.get(name) {
+            Some(player) => player,
+            None => return,
+        };
+        let client = &mut self.clients[index];
+        let current = (player.game_mode, player.inventory.clone());
+
+        if client.sent_inventory.as_ref() != Some(&current) {
+            client.connection.send(ServerMessage::Inventory { game_mode: current.0, inventory: Box::new(current.1.clone()) });
+            client.sent_inventory = Some(current);
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.world.save() {
+            println!("Could not save the world: {:?}", e);
+        }
+    }
+}
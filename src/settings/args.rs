@@ -0,0 +1,228 @@
+use anyhow::{Context, Result, anyhow, bail};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::render::shadows::ShadowQuality;
+use crate::settings::settings::Settings;
+
+pub const USAGE: &str = "Usage: ludwig-world-3d [options]
+
+Options:
+    --world <name>            Open or create a world right away, skipping the menus
+    --seed <number>           Seed for new worlds
+    --connect <address>       Join a server right away, the port is optional
+    --name <name>             The name of the player on servers
+    --settings <file>         Use another settings file, for reproducing a bug report
+    --render-distance <n>     Render distance in chunks
+    --fov <degrees>           Vertical field of view
+    --vsync <on|off>          Wait for the display before showing a frame
+    --shadows <quality>       off, low, medium or high
+    --width <pixels>          Window width
+    --height <pixels>         Window height
+    --help                    Show this message
+
+Options override the settings file for this run. Settings that are changed
+in the settings menu are saved together with the overrides.";
+
+/// The command line arguments
+#[derive(Debug, Default)]
+pub struct Args {
+    pub help: bool,
+    pub world: Option<String>,
+    pub connect: Option<String>,
+    pub settings: Option<PathBuf>,
+
+    seed: Option<u32>,
+    name: Option<String>,
+    render_distance: Option<u32>,
+    fov: Option<f32>,
+    vsync: Option<bool>,
+    shadows: Option<ShadowQuality>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+impl Args {
+    /// Parses the arguments without the program name, see Options
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut parsed = Args::default();
+        let mut options = Options::new(args);
+
+        for option in &mut options {
+            let (name, value) = option?;
+
+            match name.as_str() {
+                "--world" => parsed.world = Some(value),
+                "--connect" => parsed.connect = Some(value),
+                "--name" => parsed.name = Some(value),
+                "--settings" => parsed.settings = Some(PathBuf::from(value)),
+                "--seed" => parsed.seed = Some(number(&name, &value)?),
+                "--render-distance" => parsed.render_distance = Some(number(&name, &value)?),
+                "--fov" => parsed.fov = Some(number(&name, &value)?),
+                "--vsync" => parsed.vsync = Some(match value.as_str() {
+                    "on" | "true" | "yes" => true,
+                    "off" | "false" | "no" => false,
+                    _ => bail!("--vsync is either on or off, not '{}'", value),
+                }),
+                "--shadows" => parsed.shadows = Some(match ShadowQuality::from_name(&value) {
+                    Some(quality) => quality,
+                    None => bail!("Unknown shadow quality '{}'", value),
+                }),
+                "--width" => parsed.width = Some(number(&name, &value)?),
+                "--height" => parsed.height = Some(number(&name, &value)?),
+                _ => bail!("Unknown option '{}'", name),
+            }
+        }
+        parsed.help = options.help;
+
+        Ok(parsed)
+    }
+
+    /// Puts the options that were given over the settings
+    pub fn apply(&self, settings: &mut Settings) {
+        if let Some(seed) = self.seed {
+            settings.seed = Some(seed);
+        }
+        if let Some(name) = &self.name {
+            settings.name = name.clone();
+        }
+        if let Some(render_distance) = self.render_distance {
+            settings.render_distance = render_distance;
+        }
+        if let Some(fov) = self.fov {
+            settings.fov = fov;
+        }
+        if let Some(vsync) = self.vsync {
+            settings.vsync = vsync;
+        }
+        if let Some(shadows) = self.shadows {
+            settings.shadows = shadows;
+        }
+        if let Some(width) = self.width {
+            settings.width = width;
+        }
+        if let Some(height) = self.height {
+            settings.height = height;
+        }
+
+        *settings = settings.clone().clamped();
+    }
+}
+
+/// The options of a command line, each with its value. Both `--seed 5` and
+/// `--seed=5` work. `--help` and `-h` have no value, they only set `help`.
+pub struct Options<I: Iterator<Item = String>> {
+    args: I,
+    pub help: bool,
+}
+
+impl<I: Iterator<Item = String>> Options<I> {
+    pub fn new<A: IntoIterator<Item = String, IntoIter = I>>(args: A) -> Self {
+        Self {
+            args: args.into_iter(),
+            help: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for Options<I> {
+    /// The name with the dashes and the value
+    type Item = Result<(String, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let arg = self.args.next()?;
+            let (name, inline) = match arg.find('=') {
+                Some(i) if arg.starts_with("--") => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+                _ => (arg.clone(), None),
+            };
+
+            if name == "--help" || name == "-h" {
+                self.help = true;
+                continue
+            }
+
+            return Some(match inline.or_else(|| self.args.next()) {
+                Some(value) => Ok((name, value)),
+                None => Err(anyhow!("Missing value for {}", name)),
+            })
+        }
+    }
+}
+
+/// The value of an option as a number
+pub fn number<T: FromStr>(name: &str, value: &str) -> Result<T>
+    where T::Err: std::error::Error + Send + Sync + 'static
+{
+    value.parse().with_context(|| format!("Invalid value '{}' for {}", value, name))
+}
+
+/// The value of an option as a column of blocks, given as <x>,<z>
+pub fn column(name: &str, value: &str) -> Result<[i64; 2]> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != 2 {
+        bail!("Invalid value '{}' for {}, expected <x>,<z>", value, name);
+    }
+
+    Ok([number(name, parts[0].trim())?, number(name, parts[1].trim())?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::args::ServerArgs;
+    use crate::settings::settings::MAX_RENDER_DISTANCE;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn values_follow_or_are_inline() {
+        let options: Vec<(String, String)> = Options::new(args("--seed 5 --world=a=b"))
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(options, vec![
+            ("--seed".to_string(), "5".to_string()),
+            ("--world".to_string(), "a=b".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn help_has_no_value() {
+        let mut options = Options::new(args("-h --seed 5 --help"));
+
+        assert_eq!(options.next().unwrap().unwrap(), ("--seed".to_string(), "5".to_string()));
+        assert!(options.next().is_none());
+        assert!(options.help);
+    }
+
+    #[test]
+    fn missing_values_are_an_error() {
+        assert!(Args::parse(args("--world")).is_err());
+        assert!(Args::parse(args("--seed five")).is_err());
+        assert!(Args::parse(args("--unknown 1")).is_err());
+    }
+
+    #[test]
+    fn columns_are_two_numbers() {
+        assert_eq!(column("--center", "-3, 7").unwrap(), [-3, 7]);
+        assert!(column("--center", "3").is_err());
+        assert!(column("--center", "3,4,5").is_err());
+    }
+
+    #[test]
+    fn game_and_server_parse_the_same_way() {
+        let game = Args::parse(args("--seed=9 --world w --help")).unwrap();
+        assert!(game.help);
+        assert_eq!(game.seed, Some(9));
+        assert_eq!(game.world.as_deref(), Some("w"));
+
+        let server = ServerArgs::parse(args("--seed=9 --op a --op=b --view-distance 1000")).unwrap();
+        assert!(!server.help);
+        assert_eq!(server.seed, Some(9));
+        assert_eq!(server.operators, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(server.view_distance, MAX_RENDER_DISTANCE);
+    }
+}
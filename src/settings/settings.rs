@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::render::shadows::ShadowQuality;
+
+/// Environment variable that overrides the directory the settings and bindings are kept in
+pub const CONFIG_VAR: &str = "LUDWIG_CONFIG";
+/// Name of the settings file in the config directory
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+const APPLICATION: &str = "ludwig-world-3d";
+
+pub const MIN_RENDER_DISTANCE: u32 = 2;
+pub const MAX_RENDER_DISTANCE: u32 = 16;
+pub const MIN_FOV: f32 = 30.0;
+pub const MAX_FOV: f32 = 110.0;
+pub const MIN_CAMERA_SPEED: f32 = 1.0;
+/// In blocks per second. Servers don't let players move much faster than this.
+pub const MAX_CAMERA_SPEED: f32 = 50.0;
+
+/// Everything the player can change about the game. Stored in settings.toml
+/// in the config directory, see config_directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Window size in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Waits for the display before showing a frame, no tearing but at most the refresh rate
+    pub vsync: bool,
+
+    /// In chunks from the camera
+    pub render_distance: u32,
+    /// Vertical field of view in degrees
+    pub fov: f32,
+    /// Blocks per second
+    pub camera_speed: f32,
+    pub shadows: ShadowQuality,
+
+    /// The name of the player on servers
+    pub name: String,
+    /// The server that was joined last, with or without a port
+    pub server: String,
+
+    /// Seed for new worlds, a random one if None. Only set on the command line
+    #[serde(skip)]
+    pub seed: Option<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 800,
+            vsync: true,
+
+            render_distance: 5,
+            fov: 45.0,
+            camera_speed: 8.0,
+            shadows: ShadowQuality::Medium,
+
+            name: "player".to_string(),
+            server: "localhost".to_string(),
+
+            seed: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        let settings: Settings = toml::from_str(&source)
+            .with_context(|| format!("{} is not a valid settings file", path.display()))?;
+
+        Ok(settings.clamped())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Could not write {}", path.display()))
+    }
+
+    /// Loads the settings file, or writes one with the defaults so it can be edited
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        if path.is_file() {
+            return Settings::load(path)
+        }
+
+        let settings = Settings::default();
+        settings.save(path)?;
+
+        Ok(settings)
+    }
+
+    /// Keeps values that were edited by hand in a range the game can handle
+    pub fn clamped(mut self) -> Self {
+        self.width = self.width.max(1);
+        self.height = self.height.max(1);
+        self.render_distance = self.render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+        self.fov = self.fov.clamp(MIN_FOV, MAX_FOV);
+        self.camera_speed = self.camera_speed.clamp(0.1, MAX_CAMERA_SPEED);
+        self
+    }
+
+    /// The seed for a new world
+    pub fn new_world_seed(&self) -> u32 {
+        self.seed.unwrap_or_else(rand::random)
+    }
+}
+
+/// Where the settings and key bindings are kept. LUDWIG_CONFIG if it is set,
+/// otherwise the config directory of the user on the platform.
+pub fn config_directory() -> PathBuf {
+    if let Some(dir) = std::env::var_os(CONFIG_VAR) {
+        return PathBuf::from(dir)
+    }
+
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home().map(|home| home.join(".config")))
+    };
+
+    // Without a home directory the current directory is the best there is
+    base.unwrap_or_default().join(APPLICATION)
+}
@@ -1,17 +1,9 @@
-use cgmath::Point2;
-
 use crate::render::low::textures::TextureTile;
+use crate::world::block::registry::registry;
+use crate::game::player::item::ToolKind;
 
 pub type BlockID = u16; // A block is a 2 byte unsigned integer
 
-pub const BLOCKS: [Block; 5] = [
-    Block {transparent: true, texture: TextureSides::single_layout(TextureTile::new(14, 0))}, // air
-    Block {transparent: false, texture: TextureSides::single_layout(TextureTile::new(1, 0))}, // Stone
-    Block {transparent: false, texture: TextureSides::normal_layout(TextureTile::new(0, 0), TextureTile::new(2, 0), TextureTile::new(3, 0))}, // Grass
-    Block {transparent: false, texture: TextureSides::single_layout(TextureTile::new(2, 0))}, // Dirt
-    Block {transparent: false, texture: TextureSides::single_layout(TextureTile::new(15, 0))}, // help
-];
-
 #[derive(Debug, Copy, Clone)]
 /// This array must correspond to the FACES array in meshing.rs
 pub enum Sides {
@@ -23,12 +15,38 @@ pub enum Sides {
     BOTTOM = 5,
 }
 
+impl Sides {
+    pub const ALL: [Sides; 6] = [Sides::FRONT, Sides::LEFT, Sides::BACK, Sides::RIGHT, Sides::TOP, Sides::BOTTOM];
+}
+
 #[repr(u16)]
+#[derive(Debug, Copy, Clone)]
+/// Blocks the engine itself depends on. Their ids must match blocks.toml
 pub enum Blocks {
     AIR = 0,
     STONE = 1,
     GRASS = 2,
     DIRT = 3,
+    SAND = 9,
+    GRAVEL = 10,
+    WATER = 11,
+}
+
+impl Blocks {
+    pub const ALL: [Blocks; 7] = [Blocks::AIR, Blocks::STONE, Blocks::GRASS, Blocks::DIRT, Blocks::SAND, Blocks::GRAVEL, Blocks::WATER];
+
+    /// The name the block has in the block definitions
+    pub fn name(&self) -> &'static str {
+        match self {
+            Blocks::AIR => "air",
+            Blocks::STONE => "stone",
+            Blocks::GRASS => "grass",
+            Blocks::DIRT => "dirt",
+            Blocks::SAND => "sand",
+            Blocks::GRAVEL => "gravel",
+            Blocks::WATER => "water",
+        }
+    }
 }
 
 pub struct TextureSides {
@@ -36,13 +54,13 @@ pub struct TextureSides {
 }
 
 impl TextureSides {
-    const fn single_layout(text: TextureTile) -> Self {
+    pub const fn single_layout(text: TextureTile) -> Self {
         Self {
             sides: [text; 6],
         }
     }
 
-    const fn normal_layout(top: TextureTile, bottom: TextureTile, side: TextureTile) -> Self {
+    pub const fn normal_layout(top: TextureTile, bottom: TextureTile, side: TextureTile) -> Self {
         Self {
             sides: [side, side, side, side, top, bottom],
         }
@@ -50,10 +68,19 @@ impl TextureSides {
 }
 
 pub struct Block {
+    pub name: String,
     pub transparent: bool,
+    /// Entities can't go through it and the crosshair stops at it
+    pub solid: bool,
     pub texture: TextureSides,
+    /// Seconds it takes to break by hand, negative if it can't be broken
+    pub hardness: f32,
+    /// Breaks faster with this tool
+    pub tool: Option<ToolKind>,
+    /// What a broken block drops, if anything
+    pub drops: Option<BlockID>,
 }
 
 pub fn get_block<'a>(id: BlockID) -> &'a Block {
-    &BLOCKS[id as usize]
-}
\ No newline at end of file
+    registry().get(id)
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result, anyhow, bail};
+use once_cell::sync::OnceCell;
+
+use crate::render::low::textures::{TERRAIN_TEXTURE, TEXTURE_ATLAS_COLUMNS, TEXTURE_HEIGHT, TEXTURE_WIDTH};
+use crate::resources::resourcemanager::ResourceManager;
+use crate::world::block::{
+    blocks::{BlockID, Blocks, Sides},
+    registry::registry,
+};
+
+static COLORS: OnceCell<BlockColors> = OnceCell::new();
+
+/// The colors that were installed, or those of the built in textures if none were
+pub fn block_colors() -> &'static BlockColors {
+    COLORS.get_or_init(BlockColors::builtin)
+}
+
+/// The average color of the top of every block, for when a block is a single
+/// pixel or has to be found by its color
+pub struct BlockColors {
+    colors: Vec<[u8; 3]>,
+}
+
+impl BlockColors {
+    /// From the textures of the base pack, compiled into the game
+    pub fn builtin() -> Self {
+        let atlas = image::load_from_memory(include_bytes!("../../../assets/textures/terrain.png"))
+            .expect("Built in textures are invalid");
+
+        BlockColors::from_atlas(&atlas.to_rgba8()).expect("Built in textures are invalid")
+    }
+
+    pub fn load(resources: &ResourceManager) -> Result<Self> {
+        let atlas = resources.load_image(TERRAIN_TEXTURE)?;
+        BlockColors::from_atlas(&atlas.to_rgba8())
+            .with_context(|| format!("Could not find the block colors in {}", TERRAIN_TEXTURE))
+    }
+
+    /// Averages the pixels of the top texture of every block in the registry.
+    /// Transparent pixels are left out.
+    pub fn from_atlas(atlas: &image::RgbaImage) -> Result<Self> {
+        let (width, height) = atlas.dimensions();
+        let mut colors = vec![];
+
+        for id in registry().ids() {
+            let block = registry().get(id);
+            let tile = block.texture.sides[Sides::TOP as usize].coords;
+            let (left, top) = (tile.x * TEXTURE_WIDTH, tile.y * TEXTURE_HEIGHT);
+
+            if tile.x >= TEXTURE_ATLAS_COLUMNS || left + TEXTURE_WIDTH > width || top + TEXTURE_HEIGHT > height {
+                bail!("The texture of block '{}' is outside the atlas", block.name);
+            }
+
+            let mut sum = [0u64; 4];
+            for y in top..top + TEXTURE_HEIGHT {
+                for x in left..left + TEXTURE_WIDTH {
+                    let [r, g, b, a] = atlas.get_pixel(x, y).0;
+                    let a = a as u64;
+                    sum[0] += r as u64 * a;
+                    sum[1] += g as u64 * a;
+                    sum[2] += b as u64 * a;
+                    sum[3] += a;
+                }
+            }
+
+            let alpha = sum[3].max(1);
+            colors.push([(sum[0] / alpha) as u8, (sum[1] / alpha) as u8, (sum[2] / alpha) as u8]);
+        }
+
+        Ok(Self {
+            colors,
+        })
+    }
+
+    /// Makes these the colors returned by block_colors(). Can only be done once.
+    pub fn install(self) -> Result<()> {
+        COLORS
+            .set(self)
+            .map_err(|_| anyhow!("Block colors were already installed"))
+    }
+
+    /// Panics if the id does not exist
+    pub fn get(&self, id: BlockID) -> [u8; 3] {
+        self.colors[id as usize]
+    }
+
+    /// The block that looks most like a color, never air
+    pub fn nearest(&self, color: [u8; 3]) -> BlockID {
+        let distance = |other: [u8; 3]| -> i32 {
+            (0..3).map(|i| (color[i] as i32 - other[i] as i32).pow(2)).sum()
+        };
+
+        (0..self.colors.len() as BlockID)
+            .filter(|id| *id != Blocks::AIR as BlockID)
+            .min_by_key(|id| distance(self.colors[*id as usize]))
+            .unwrap_or(Blocks::STONE as BlockID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::testing::setup;
+
+    #[test]
+    fn every_block_has_a_color() {
+        setup();
+        let colors = BlockColors::builtin();
+
+        assert_eq!(colors.colors.len(), registry().ids().count());
+        assert_ne!(colors.get(Blocks::GRASS as BlockID), colors.get(Blocks::STONE as BlockID));
+    }
+
+    #[test]
+    fn colors_are_averages_of_opaque_pixels() {
+        setup();
+        let size = (TEXTURE_WIDTH * TEXTURE_ATLAS_COLUMNS, TEXTURE_HEIGHT * TEXTURE_ATLAS_COLUMNS);
+        // Half red, half transparent
+        let atlas = image::RgbaImage::from_fn(size.0, size.1, |x, _| {
+            if x % 2 == 0 { image::Rgba([200, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 0]) }
+        });
+
+        let colors = BlockColors::from_atlas(&atlas).unwrap();
+        assert!(registry().ids().all(|id| colors.get(id) == [200, 0, 0]));
+    }
+
+    #[test]
+    fn nearest_finds_a_block_with_the_color() {
+        setup();
+        let colors = BlockColors::builtin();
+
+        for id in registry().ids().filter(|id| *id != Blocks::AIR as BlockID) {
+            let color = colors.get(id);
+            assert_eq!(colors.get(colors.nearest(color)), color);
+        }
+        assert_ne!(colors.nearest(colors.get(Blocks::AIR as BlockID)), Blocks::AIR as BlockID);
+    }
+}
@@ -1 +1,3 @@
-pub mod blocks;
\ No newline at end of file
+pub mod blocks;
+pub mod registry;
+pub mod colors;
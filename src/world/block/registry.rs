@@ -0,0 +1,173 @@
+use anyhow::{Context, Result, anyhow, bail};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::render::low::textures::TextureTile;
+use crate::resources::resourcemanager::ResourceManager;
+use crate::world::block::blocks::{Block, BlockID, Blocks, TextureSides};
+use crate::game::player::item::ToolKind;
+
+/// Where the block definitions are found in a resource pack
+pub const BLOCKS_ASSET: &str = "blocks.toml";
+
+static REGISTRY: OnceCell<BlockRegistry> = OnceCell::new();
+
+/// The registry that was installed, or the built in one if none was
+pub fn registry() -> &'static BlockRegistry {
+    REGISTRY.get_or_init(BlockRegistry::builtin)
+}
+
+#[derive(Deserialize)]
+struct BlockFile {
+    block: Vec<BlockDefinition>,
+}
+
+/// A block as it is written in blocks.toml
+#[derive(Deserialize)]
+pub struct BlockDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub transparent: bool,
+    /// Whether entities collide with the block, not for transparent blocks if left out
+    pub solid: Option<bool>,
+    pub texture: Option<[u32; 2]>,
+    pub top: Option<[u32; 2]>,
+    pub bottom: Option<[u32; 2]>,
+    pub side: Option<[u32; 2]>,
+    /// Seconds it takes to break the block by hand, negative if it can't be broken
+    #[serde(default = "default_hardness")]
+    pub hardness: f32,
+    /// The tool that breaks the block faster
+    pub tool: Option<ToolKind>,
+    /// The name of the block a broken block drops, empty for nothing.
+    /// A block drops itself if this is left out.
+    pub drops: Option<String>,
+}
+
+fn default_hardness() -> f32 {
+    1.0
+}
+
+impl BlockDefinition {
+    /// Drops are resolved when all blocks are known, see BlockRegistry::from_toml
+    fn into_block(self) -> Result<Block> {
+        let tile = |t: [u32; 2]| TextureTile::new(t[0], t[1]);
+
+        let texture = match (self.texture, self.top, self.bottom, self.side) {
+            (Some(all), None, None, None) => TextureSides::single_layout(tile(all)),
+            // A single texture can be combined with a few sides that differ
+            (all, top, bottom, side) => {
+                let (top, bottom, side) = match (top.or(all), bottom.or(all), side.or(all)) {
+                    (Some(top), Some(bottom), Some(side)) => (top, bottom, side),
+                    _ => bail!("Block '{}' needs either a texture or a top, bottom and side texture", self.name),
+                };
+
+                TextureSides::normal_layout(tile(top), tile(bottom), tile(side))
+            }
+        };
+
+        Ok(Block {
+            name: self.name,
+            transparent: self.transparent,
+            solid: self.solid.unwrap_or(!self.transparent),
+            texture,
+            hardness: self.hardness,
+            tool: self.tool,
+            drops: None,
+        })
+    }
+}
+
+/// All blocks the game knows about. The index of a block is its BlockID
+pub struct BlockRegistry {
+    blocks: Vec<Block>,
+    ids: HashMap<String, BlockID>,
+}
+
+impl BlockRegistry {
+    /// The block definitions of the base pack, compiled into the game
+    pub fn builtin() -> Self {
+        BlockRegistry::from_toml(include_str!("../../../assets/blocks.toml"))
+            .expect("Built in block definitions are invalid")
+    }
+
+    pub fn load(resources: &ResourceManager) -> Result<Self> {
+        BlockRegistry::from_toml(&resources.read_string(BLOCKS_ASSET)?)
+            .with_context(|| format!("Invalid block definitions in {}", BLOCKS_ASSET))
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let file: BlockFile = toml::from_str(source)?;
+
+        let mut blocks = Vec::with_capacity(file.block.len());
+        let mut ids = HashMap::new();
+        let mut drops = Vec::with_capacity(file.block.len());
+
+        for definition in file.block {
+            if ids.insert(definition.name.clone(), blocks.len() as BlockID).is_some() {
+                bail!("Block '{}' is defined twice", definition.name);
+            }
+
+            drops.push(definition.drops.clone().unwrap_or_else(|| definition.name.clone()));
+            blocks.push(definition.into_block()?);
+        }
+
+        for (block, drop) in blocks.iter_mut().zip(drops) {
+            block.drops = match drop.as_str() {
+                "" => None,
+                name => Some(*ids.get(name).with_context(|| format!("Block '{}' drops unknown block '{}'", block.name, name))?),
+            };
+        }
+
+        // The engine refers to these blocks by id
+        for block in Blocks::ALL.iter() {
+            if ids.get(block.name()) != Some(&(*block as BlockID)) {
+                bail!("Block '{}' must be block number {}", block.name(), *block as BlockID);
+            }
+        }
+
+        Ok(Self {
+            blocks,
+            ids,
+        })
+    }
+
+    /// Makes this the registry returned by registry(). Can only be done once,
+    /// before any block is looked up.
+    pub fn install(self) -> Result<()> {
+        REGISTRY
+            .set(self)
+            .map_err(|_| anyhow!("A block registry was already installed"))
+    }
+
+    /// Panics if the id does not exist
+    pub fn get(&self, id: BlockID) -> &Block {
+        &self.blocks[id as usize]
+    }
+
+    pub fn id(&self, name: &str) -> Option<BlockID> {
+        self.ids.get(name).copied()
+    }
+
+    /// Every BlockID, in order
+    pub fn ids(&self) -> impl Iterator<Item = BlockID> {
+        0..self.blocks.len() as BlockID
+    }
+
+    /// Changes when a block is added, removed or renamed. Blocks are sent over
+    /// the network by id, so a server and its clients need the same fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        // FNV-1a, the same in every build of the game
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+        for block in &self.blocks {
+            for byte in block.name.bytes().chain(std::iter::once(0)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+
+        hash
+    }
+}
@@ -1,13 +1,12 @@
-use rand::Rng;
-
 use crate::world::block::blocks::{
     Blocks,
     BlockID
 };
 use crate::world::constants::{CHUNKSIZE, WORLDHEIGHT};
+use crate::world::terrain::Column;
 use crate::world::chunk::pos::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Chunk {
     /// blocks[x][y][z]
     blocks: [BlockID; CHUNKSIZE * CHUNKSIZE * WORLDHEIGHT],
@@ -24,32 +23,30 @@ impl Chunk {
         }
     }
 
-    pub fn generate(&mut self, height: [u32; CHUNKSIZE * CHUNKSIZE]) {
-        let mut rgn = rand::thread_rng();
-
-        // self.blocks[coord_to_index(0, 0, 0)] = Blocks::GRASS as BlockID;
-        // self.blocks[coord_to_index(0, 1, 0)] = Blocks::GRASS as BlockID;
-
+    /// Fills the chunk with the terrain, columns go x first, then z
+    pub fn generate(&mut self, columns: &[Column]) {
         for x in 0..(CHUNKSIZE) as i16 {
             for z in 0..(CHUNKSIZE) as i16 {
+                let column = columns[x as usize + z as usize * CHUNKSIZE];
+                let (top, soil) = column.surface.blocks();
+                let (top, soil) = (top as BlockID, soil as BlockID);
 
-                let grassheight = height[x as usize + z as usize * CHUNKSIZE] as i16;
-                let dirtheight = grassheight - rgn.gen_range(1..4);
-                let stoneheight = dirtheight;
+                let height = column.height as i16;
+                let soilheight = height - column.soil as i16;
 
-                let mut y = 0;
-                while y < stoneheight {
+                for y in 0..soilheight.max(0) {
                     self.blocks[coord_to_index(x, y, z)] = Blocks::STONE as BlockID;
-                    y += 1;
                 }
 
-                y = dirtheight;
-                while y < grassheight {
-                    self.blocks[coord_to_index(x, y, z)] = Blocks::DIRT as BlockID;
-                    y += 1;
+                for y in soilheight.max(0)..height {
+                    self.blocks[coord_to_index(x, y, z)] = soil;
                 }
 
-                self.blocks[coord_to_index(x, grassheight, z)] = Blocks::GRASS as BlockID;
+                self.blocks[coord_to_index(x, height, z)] = top;
+
+                for y in height + 1..=height + column.water as i16 {
+                    self.blocks[coord_to_index(x, y, z)] = Blocks::WATER as BlockID;
+                }
             }
         }
     }
@@ -59,10 +56,10 @@ impl Chunk {
     /// Also makes sure coordinate is in bounds
     pub fn at_coord_bounds(&self, coord: ChunkCoord) -> BlockID {
         if !Chunk::in_bounds(coord) {
-            return 0
+            0
         }
         else {
-            return self.blocks[coord_to_index(coord.x, coord.y, coord.z)]
+            self.blocks[coord_to_index(coord.x, coord.y, coord.z)]
         }      
     }
 
@@ -88,16 +85,24 @@ impl Chunk {
     pub fn place_block(&mut self, pos: ChunkCoord, block: BlockID) {
         self.blocks[coord_to_index(pos.x, pos.y, pos.z)] = block;
     }
+
+    /// Every block, in the order of coord_to_index
+    pub fn blocks(&self) -> &[BlockID] {
+        &self.blocks
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut [BlockID] {
+        &mut self.blocks
+    }
 }
 
-/// Y represents height, Z depth and X width
 pub fn coord_to_index(x: i16, y: i16, z: i16) -> usize {
     ((x + z * CHUNKSIZE as i16) as i32 + y as i32 * (CHUNKSIZE * CHUNKSIZE) as i32) as usize
 }
 
 pub fn index_to_coord(index: usize) -> (u32, u32, u32) {
     let x = index % CHUNKSIZE;
-    let y = (index / (CHUNKSIZE * CHUNKSIZE)) as u32;
+    let y = index / (CHUNKSIZE * CHUNKSIZE);
     let z = (index / CHUNKSIZE) % CHUNKSIZE;
 
     (x as u32, y as u32, z as u32)
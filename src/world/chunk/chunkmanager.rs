@@ -1,11 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 use crate::world::{
-    chunk::{chunk::Chunk, pos::*},
+    chunk::{chunk::Chunk, chunkstore::ChunkStore, pos::*},
     constants::*,
     block::blocks::BlockID,
-    world::World,
 };
 use crate::render::{
     low::{
@@ -16,102 +15,108 @@ use crate::render::{
     drawables::chunk::ChunkDrawable,
 };
 
-/// Takes care of loading chunks, meshing chunks, unloading chunks
+/// Numbers for the debug overlay
+pub struct ChunkStats {
+    pub loaded: usize,
+    pub meshed: usize,
+    /// Chunks that have their mesh on the GPU
+    pub drawn: usize,
+    pub vertices: usize,
+    pub indices: usize,
+    /// Chunks that were received but not meshed yet
+    pub queued: usize,
+    /// Average time it took to mesh a chunk, in microseconds
+    pub meshing_time: u128,
+}
+
+/// The client side of the chunks: keeps the chunks the server sent, meshes them
+/// and holds their buffers for rendering. Which chunks are loaded is up to the server.
 pub struct ChunkManager {
-    loaded_chunks: HashMap<ChunkPos, Chunk>,
+    chunks: ChunkStore,
     chunks_meshes: HashMap<ChunkPos, ChunkMesh>,
     /// The buffers used for rendering
     pub chunk_buffers: HashMap<ChunkPos, ChunkDrawable>,
-    load_queue: Vec<ChunkPos>,
-
-    render_distance: u32,
-    center_chunk: ChunkPos,
+    /// Received chunks that still have to be meshed
+    mesh_queue: Vec<ChunkPos>,
 
     updated: bool,
 
     chunk_meshing_time: u128,
-    chunk_loading_time: u128,
+    /// How many chunks were meshed, for the average time
+    meshes_created: u128,
 }
 
 impl ChunkManager {
-    pub fn new(render_distance: u32) -> Self {
-        let loaded_chunks = HashMap::new();
-        let chunks_meshes = HashMap::new();
-        let chunk_buffers = HashMap::new();
-
+    pub fn new() -> Self {
         Self {
-            loaded_chunks,
-            chunks_meshes,
-            chunk_buffers,
-            load_queue: vec![],
-
-            render_distance,
-            center_chunk: ChunkPos::new(0, 0, 0),
+            chunks: ChunkStore::new(),
+            chunks_meshes: HashMap::new(),
+            chunk_buffers: HashMap::new(),
+            mesh_queue: vec![],
 
             updated: false,
 
-            chunk_meshing_time: 1,
-            chunk_loading_time: 1,
+            chunk_meshing_time: 0,
+            meshes_created: 0,
         }
     }
 
-    pub fn set_camera_location(&mut self, coord: WorldCoord, renderer: &mut Renderer) {
-        let chunkpos = coord.to_chunk_coord();
-
-        // If center chunk is not yet loaded
-        if self.center_chunk != chunkpos {
-            self.center_around(chunkpos, renderer);
-            
-            self.updated = false;
-        }
+    /// The loaded chunks, for looking up blocks
+    pub fn chunks(&self) -> &ChunkStore {
+        &self.chunks
     }
 
-    pub fn center_around(&mut self, pos: ChunkPos, renderer: &mut Renderer) {
-        // The chunks we want to load
-        let mut targets = vec![];
+    /// Adds a chunk the server sent. It is meshed later, see mesh_queue.
+    pub fn insert_chunk(&mut self, chunk: Chunk, renderer: &mut Renderer) {
+        let pos = chunk.pos;
 
-        self.center_chunk = pos; 
+        renderer.chunkpos_uniform.add(&renderer.queue, pos, pos.to_raw());
+        self.chunks.insert(chunk);
 
-        // The chunks we want to have loaded
-        for x in -1 * (self.render_distance as i32)..self.render_distance as i32 {
-            for z in -1 * (self.render_distance as i32)..self.render_distance as i32 {
-                targets.push(ChunkPos::new(pos.x + x, 0, pos.z + z));
-            }
+        if !self.mesh_queue.contains(&pos) {
+            self.mesh_queue.push(pos);
         }
+    }
+
+    /// Replaces chunks that changed together and meshes each of them and their
+    /// neighbors once, so a big edit doesn't mesh the same chunk over and over
+    pub fn replace_chunks(&mut self, chunks: Vec<Chunk>, renderer: &mut Renderer) {
+        let mut changed = HashSet::new();
 
-        // If a chunk should not be loaded we unload it
-        for pos in self.loaded_chunks.keys().cloned().collect::<Vec<_>>() {
-            if !targets.contains(&pos) {
-                self.unload_chunk(&pos, renderer);
+        for chunk in chunks {
+            let pos = chunk.pos;
+            if self.chunks.get(pos).is_none() {
+                self.insert_chunk(chunk, renderer);
+                continue
             }
+
+            self.chunks.insert(chunk);
+            changed.insert(pos);
+            changed.insert(ChunkPos {x: pos.x + 1, ..pos});
+            changed.insert(ChunkPos {x: pos.x - 1, ..pos});
+            changed.insert(ChunkPos {z: pos.z + 1, ..pos});
+            changed.insert(ChunkPos {z: pos.z - 1, ..pos});
         }
 
-        // If a chunk is not yet loaded and it should be loaded, we load it
-        for pos in targets {
-            if !self.loaded_chunks.contains_key(&pos) {
-                self.queue_chunk_load(pos)
-            }
+        for pos in changed {
+            self.mesh_chunk(pos);
         }
 
+        self.updated = false;
     }
 
-    /// Loads and meshes a single chunks
-    pub fn load_chunk(&mut self, pos: ChunkPos, height: [u32; CHUNKSIZE * CHUNKSIZE], renderer: &mut Renderer) {
-        let mut chunk = Chunk::new(pos);
-
-        renderer.chunkpos_uniform.add(&renderer.queue, pos, pos.to_raw());
-
-        let now = Instant::now();
-        chunk.generate(height);
-        let lapsed = now.elapsed();
+    /// Meshes at most `limit` received chunks and their neighbors, so a frame never takes too long
+    pub fn mesh_queue(&mut self, limit: usize) {
+        if !self.mesh_queue.is_empty() {
+            let count = limit.min(self.mesh_queue.len());
 
-        self.loaded_chunks.insert(
-            pos,
-            chunk,
-        );
-        self.chunk_loading_time += lapsed.as_micros();
+            for pos in self.mesh_queue.drain(..count).collect::<Vec<_>>() {
+                self.mesh_neighbors(pos);
+            }
 
-        self.mesh_neighbors(pos);
+            // The new meshes still have to be uploaded
+            self.updated = false;
+        }
     }
 
     pub fn mesh_neighbors(&mut self, pos: ChunkPos) {
@@ -125,107 +130,94 @@ impl ChunkManager {
 
     /// Mesh a single chunk. Does nothing if pos is not loaded
     pub fn mesh_chunk(&mut self, pos: ChunkPos) {
-        
-        let c = &self.loaded_chunks.get(&pos);
-        if !c.is_none() {
+        if let Some(chunk) = self.chunks.get(pos) {
             let mut mesh = ChunkMesh::new();
 
             let now = Instant::now();
-            mesh.create_simple_mesh(c.unwrap(), &self);
+            mesh.create_simple_mesh(chunk, &self.chunks);
             let elapsed = now.elapsed();
 
             self.chunks_meshes.insert(
-                pos, 
+                pos,
                 mesh
             );
 
             self.chunk_meshing_time += elapsed.as_micros();
+            self.meshes_created += 1;
         }
     }
 
-    /// Returns chunks around a given chunk in this order:
-    /// [U, R, D, L]
-    pub fn get_neighbors(&self, center: ChunkPos) -> [Option<&Chunk>; 4]{
-        [
-            self.get_chunk_option(center + ChunkPos::new(0, 0, 1)),
-            self.get_chunk_option(center + ChunkPos::new(1, 0, 0)),
-            self.get_chunk_option(center + ChunkPos::new(0, 0, -1)),
-            self.get_chunk_option(center + ChunkPos::new(-1, 0, 0)),
-        ]
-    }
-
-    // Queue a chunk for loading
-    pub fn queue_chunk_load(&mut self, pos: ChunkPos) {
-        self.load_queue.push(pos);
-    }
-
-    /// Load and mesh all chunks in queue
-    pub fn load_queue(&mut self, world: &World, renderer: &mut Renderer) {
-        if self.load_queue.len() > 0 {
-            for pos in self.load_queue.clone() {
-                self.load_chunk(pos.clone(), world.map.create_heightmap(&pos), renderer);
-            }
-    
-            self.load_queue.clear();
-        }
-        // println!("Chunk meshing time: {}, chunk loading time: {}", self.meshing_time(), self.loading_time());
-    }
-
     pub fn unload_chunk(&mut self, pos: &ChunkPos, renderer: &mut Renderer) {
         self.chunks_meshes.remove(pos);
-        self.loaded_chunks.remove(pos);
+        self.chunks.remove(pos);
         self.chunk_buffers.remove(pos);
-        
+        self.mesh_queue.retain(|queued| queued != pos);
+
         renderer.chunkpos_uniform.remove(pos);
     }
 
+    /// Unloads every chunk, for when the world is closed
+    pub fn unload_all(&mut self, renderer: &mut Renderer) {
+        for pos in self.chunks.positions().collect::<Vec<_>>() {
+            self.unload_chunk(&pos, renderer);
+        }
+        self.mesh_queue.clear();
+    }
+
     /// A low level function that updates the buffers according to the meshes for rendering
     pub fn update(&mut self, context: &mut Context, encoder: &mut wgpu::CommandEncoder) {
         if !self.updated {
             self.updated = true;
             for (pos, chunk) in &self.chunks_meshes {
                 let mut c = ChunkDrawable::new(&context.renderer.device, *pos);
-                c.from_chunk_mesh(&chunk, &context.renderer.device, encoder);
+                c.from_chunk_mesh(chunk, &context.renderer.device, encoder);
 
                 self.chunk_buffers.insert(*pos, c);
             }
         }
     }
 
-    /// Panics if pos is not loaded
-    pub fn get_chunk(&self, pos: ChunkPos) -> &Chunk {
-        self.loaded_chunks.get(&pos).unwrap()
+    pub fn get_block_at_coord(&self, coord: WorldCoord) -> Option<BlockID> {
+        self.chunks.get_block_at_coord(coord)
     }
 
-    pub fn get_chunk_option(&self, pos: ChunkPos) -> Option<&Chunk> {
-        self.loaded_chunks.get(&pos)
-    }
+    /// Changes a block and meshes its chunk again, and the neighbor it touches if
+    /// it is on the edge. Returns false if the chunk is not loaded.
+    pub fn set_block(&mut self, coord: WorldCoord, block: BlockID) -> bool {
+        if !self.chunks.set_block(coord, block) {
+            return false
+        }
 
-    pub fn get_chunk_mut_option(&mut self, pos: ChunkPos) -> Option<&mut Chunk> {
-        self.loaded_chunks.get_mut(&pos)
-    }
+        let pos = coord.to_chunk_coord();
+        let local = coord.to_chunk_local();
 
-    pub fn get_mesh(&self, pos: ChunkPos) -> &ChunkMesh {
-        self.chunks_meshes.get(&pos).unwrap()
-    }
+        self.mesh_chunk(pos);
 
-    /// Get the block at the given coord. Returns an option
-    pub fn get_block_at_coord(&self, coord: WorldCoord) -> Option<BlockID> {
-        if let Some(chunk) = self.loaded_chunks.get(&coord.to_chunk_coord()) {
-            return Some(chunk.at_coord(coord.to_chunk_local()))
-        } 
-        
-        else {
-            return None
-        }
+        let last = CHUNKSIZE as i16 - 1;
+        if local.x == 0 { self.mesh_chunk(ChunkPos {x: pos.x - 1, ..pos}); }
+        if local.x == last { self.mesh_chunk(ChunkPos {x: pos.x + 1, ..pos}); }
+        if local.z == 0 { self.mesh_chunk(ChunkPos {z: pos.z - 1, ..pos}); }
+        if local.z == last { self.mesh_chunk(ChunkPos {z: pos.z + 1, ..pos}); }
+
+        self.updated = false;
+
+        true
     }
 
     // Some timing stuff
     pub fn meshing_time(&self) -> u128 {
-        self.chunk_meshing_time / self.chunks_meshes.len() as u128
-    }
-
-    pub fn loading_time(&self) -> u128 {
-        self.chunk_loading_time / self.chunks_meshes.len() as u128
+        self.chunk_meshing_time / self.meshes_created.max(1)
+    }
+
+    pub fn stats(&self) -> ChunkStats {
+        ChunkStats {
+            loaded: self.chunks.len(),
+            meshed: self.chunks_meshes.len(),
+            drawn: self.chunk_buffers.len(),
+            vertices: self.chunk_buffers.values().map(|c| c.vertex_count()).sum(),
+            indices: self.chunk_buffers.values().map(|c| c.index_count()).sum(),
+            queued: self.mesh_queue.len(),
+            meshing_time: self.meshing_time(),
+        }
     }
 }
\ No newline at end of file
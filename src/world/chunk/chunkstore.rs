@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::world::{
+    chunk::{chunk::Chunk, pos::*},
+    block::blocks::BlockID,
+};
+
+/// Loaded chunks by position, without anything for rendering. The server keeps the
+/// chunks of the world in one, a client keeps the chunks it was sent in another.
+pub struct ChunkStore {
+    chunks: HashMap<ChunkPos, Chunk>,
+}
+
+impl ChunkStore {
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, chunk: Chunk) {
+        self.chunks.insert(chunk.pos, chunk);
+    }
+
+    pub fn remove(&mut self, pos: &ChunkPos) -> Option<Chunk> {
+        self.chunks.remove(pos)
+    }
+
+    pub fn get(&self, pos: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&pos)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The block at a coordinate, None if its chunk is not loaded
+    pub fn get_block_at_coord(&self, coord: WorldCoord) -> Option<BlockID> {
+        self.chunks
+            .get(&coord.to_chunk_coord())
+            .map(|chunk| chunk.at_coord(coord.to_chunk_local()))
+    }
+
+    /// Changes a block. Returns false if its chunk is not loaded.
+    pub fn set_block(&mut self, coord: WorldCoord, block: BlockID) -> bool {
+        match self.chunks.get_mut(&coord.to_chunk_coord()) {
+            Some(chunk) => {
+                chunk.place_block(coord.to_chunk_local(), block);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,675 @@
+use anyhow::{Context, Result, bail};
+use cgmath::{Point3, Vector3};
+use std::collections::HashMap;
+
+use crate::game::player::item::{Item, ItemStack};
+use crate::world::{
+    block::{blocks::{BlockID, Blocks}, registry::registry},
+    chunk::{chunk::Chunk, pos::ChunkPos},
+    constants::{CHUNKSIZE, WORLDHEIGHT},
+    entity::entity::{Entity, EntityKind},
+};
+
+/// The version chunks are written with. Older versions are upgraded when they
+/// are read, see migrate.
+pub const CODEC_VERSION: u16 = 2;
+
+/// The first bytes of an encoded chunk
+const MAGIC: &[u8; 4] = b"LWCK";
+/// Magic, version, sections, x, z, payload length and checksum
+const HEADER_SIZE: usize = 4 + 2 + 2 + 4 + 4 + 4 + 4;
+/// No chunk comes close, anything larger is corrupted
+const MAX_PAYLOAD: usize = 16 * 1024 * 1024;
+const BLOCK_COUNT: usize = CHUNKSIZE * CHUNKSIZE * WORLDHEIGHT;
+
+/// Which of the optional sections follow the blocks
+const SECTION_LIGHT: u16 = 1;
+const SECTION_METADATA: u16 = 2;
+const SECTION_ENTITIES: u16 = 4;
+const KNOWN_SECTIONS: u16 = SECTION_LIGHT | SECTION_METADATA | SECTION_ENTITIES;
+
+/// Everything that is stored about a chunk. Light and metadata have a byte for
+/// every block, in the order of the blocks. The game doesn't use them yet.
+#[derive(Debug, Clone)]
+pub struct ChunkData {
+    pub chunk: Chunk,
+    pub light: Option<Vec<u8>>,
+    pub metadata: Option<Vec<u8>>,
+    /// Ids are not stored, entities get new ones when they are loaded
+    pub entities: Vec<Entity>,
+}
+
+impl ChunkData {
+    pub fn new(chunk: Chunk) -> Self {
+        Self {
+            chunk,
+            light: None,
+            metadata: None,
+            entities: vec![],
+        }
+    }
+}
+
+/// Encodes a chunk with its light, metadata and entities, see encode_chunk
+pub fn encode(data: &ChunkData) -> Vec<u8> {
+    encode_parts(&data.chunk, data.light.as_deref(), data.metadata.as_deref(), &data.entities)
+}
+
+/// Encodes only the blocks of a chunk.
+///
+/// All numbers are little endian. A header with the magic, the version, the
+/// sections, the position, the length of the payload and a checksum of all
+/// that is followed by the payload: a palette of block names, the blocks
+/// as runs of palette indices and then the sections.
+pub fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+    encode_parts(chunk, None, None, &[])
+}
+
+fn encode_parts(chunk: &Chunk, light: Option<&[u8]>, metadata: Option<&[u8]>, entities: &[Entity]) -> Vec<u8> {
+    let mut payload = Writer::new();
+
+    // Blocks are stored by name, so their ids can change between resource packs
+    let mut palette: Vec<BlockID> = vec![];
+    let mut indices: HashMap<BlockID, u64> = HashMap::new();
+    let mut runs: Vec<(u64, u64)> = vec![];
+
+    for &block in chunk.blocks() {
+        let index = *indices.entry(block).or_insert_with(|| {
+            palette.push(block);
+            palette.len() as u64 - 1
+        });
+
+        match runs.last_mut() {
+            Some((count, last)) if *last == index => *count += 1,
+            _ => runs.push((1, index)),
+        }
+    }
+
+    payload.varint(palette.len() as u64);
+    for block in palette {
+        payload.string(&registry().get(block).name);
+    }
+    payload.varint(runs.len() as u64);
+    for (count, index) in runs {
+        payload.varint(count);
+        payload.varint(index);
+    }
+
+    let mut sections = 0;
+    if let Some(light) = light {
+        sections |= SECTION_LIGHT;
+        payload.byte_runs(light);
+    }
+    if let Some(metadata) = metadata {
+        sections |= SECTION_METADATA;
+        payload.byte_runs(metadata);
+    }
+    if !entities.is_empty() {
+        sections |= SECTION_ENTITIES;
+        payload.varint(entities.len() as u64);
+        for entity in entities {
+            payload.entity(entity);
+        }
+    }
+
+    let payload = payload.bytes;
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&CODEC_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&sections.to_le_bytes());
+    bytes.extend_from_slice(&chunk.pos.x.to_le_bytes());
+    bytes.extend_from_slice(&chunk.pos.z.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    let sum = checksum(&[&bytes, &payload]);
+    bytes.extend_from_slice(&sum.to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    bytes
+}
+
+/// Decodes a chunk of this or an older version. Fails on anything that was cut
+/// off, changed or written by a newer version of the game.
+pub fn decode(bytes: &[u8]) -> Result<ChunkData> {
+    let mut header = Reader::new(bytes);
+
+    if header.take(4)? != MAGIC {
+        bail!("Not chunk data");
+    }
+    let version = header.u16()?;
+    let sections = header.u16()?;
+    let pos = ChunkPos::new(header.i32()?, 0, header.i32()?);
+    let length = header.u32()? as usize;
+    let expected = header.u32()?;
+
+    if version > CODEC_VERSION {
+        bail!("Chunk {} {} has version {}, this game only knows up to {}", pos.x, pos.z, version, CODEC_VERSION);
+    }
+    if sections & !KNOWN_SECTIONS != 0 {
+        bail!("Chunk {} {} has unknown sections {:#x}", pos.x, pos.z, sections);
+    }
+    if length > MAX_PAYLOAD {
+        bail!("Chunk {} {} claims {} bytes of data", pos.x, pos.z, length);
+    }
+
+    let payload = header.take(length)?;
+    if !header.is_empty() {
+        bail!("Chunk {} {} has {} bytes after its data", pos.x, pos.z, header.remaining());
+    }
+    if checksum(&[&bytes[..HEADER_SIZE - 4], payload]) != expected {
+        bail!("Chunk {} {} is corrupted, the checksum does not match", pos.x, pos.z);
+    }
+
+    migrate(version, sections, pos, &mut Reader::new(payload))
+        .with_context(|| format!("Could not decode chunk {} {} of version {}", pos.x, pos.z, version))
+}
+
+/// Reads the payload of any known version into the current ChunkData
+fn migrate(version: u16, sections: u16, pos: ChunkPos, payload: &mut Reader) -> Result<ChunkData> {
+    let mut data = ChunkData::new(Chunk::new(pos));
+
+    match version {
+        1 => read_version_1(sections, payload, &mut data)?,
+        2 => read_version_2(sections, payload, &mut data)?,
+        _ => bail!("Unknown version {}", version),
+    }
+
+    if !payload.is_empty() {
+        bail!("{} bytes left after the data", payload.remaining());
+    }
+
+    Ok(data)
+}
+
+/// The blocks of version 1 by their id, the blocks of the base pack when it was
+/// written. Version 1 stored ids instead of names.
+const VERSION_1_BLOCKS: [&str; 5] = ["air", "stone", "grass", "dirt", "help"];
+
+/// Version 1 has the blocks as runs of ids and nothing else
+fn read_version_1(sections: u16, payload: &mut Reader, data: &mut ChunkData) -> Result<()> {
+    if sections != 0 {
+        bail!("Version 1 has no sections, got {:#x}", sections);
+    }
+
+    let pos = data.chunk.pos;
+    let runs = payload.varint()?;
+    let mut blocks = Filler(data.chunk.blocks_mut(), 0);
+
+    for _ in 0..runs {
+        let count = payload.varint()?;
+        let id = payload.u16()?;
+        match VERSION_1_BLOCKS.get(id as usize) {
+            Some(name) => blocks.fill(count, block_id(name, pos))?,
+            None => bail!("Block id {} out of {}", id, VERSION_1_BLOCKS.len()),
+        }
+    }
+
+    blocks.finish()
+}
+
+/// Version 2 has a palette of block names and the optional sections
+fn read_version_2(sections: u16, payload: &mut Reader, data: &mut ChunkData) -> Result<()> {
+    let pos = data.chunk.pos;
+    let palette = (0..payload.varint()?)
+        .map(|_| payload.string().map(|name| block_id(&name, pos)))
+        .collect::<Result<Vec<BlockID>>>()?;
+
+    let runs = payload.varint()?;
+    let mut blocks = Filler(data.chunk.blocks_mut(), 0);
+
+    for _ in 0..runs {
+        let count = payload.varint()?;
+        let index = payload.varint()?;
+        match palette.get(index as usize) {
+            Some(&block) => blocks.fill(count, block)?,
+            None => bail!("Palette index {} out of {}", index, palette.len()),
+        }
+    }
+    blocks.finish()?;
+
+    if sections & SECTION_LIGHT != 0 {
+        data.light = Some(payload.byte_runs()?);
+    }
+    if sections & SECTION_METADATA != 0 {
+        data.metadata = Some(payload.byte_runs()?);
+    }
+    if sections & SECTION_ENTITIES != 0 {
+        for _ in 0..payload.varint()? {
+            if let Some(entity) = payload.entity()? {
+                data.entities.push(entity);
+            } else {
+                println!("Dropping an entity of chunk {} {} that can't be loaded", pos.x, pos.z);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Blocks that were removed from the resource pack become air
+fn block_id(name: &str, pos: ChunkPos) -> BlockID {
+    registry().id(name).unwrap_or_else(|| {
+        println!("Chunk {} {} has the unknown block '{}', it is replaced with air", pos.x, pos.z, name);
+        Blocks::AIR as BlockID
+    })
+}
+
+/// FNV-1a over the parts one after another, the same in every build of the game
+fn checksum(parts: &[&[u8]]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+
+    for &byte in parts.iter().flat_map(|part| part.iter()) {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+
+    hash
+}
+
+/// Fills the blocks of a chunk with runs, in order
+struct Filler<'a>(&'a mut [BlockID], usize);
+
+impl Filler<'_> {
+    fn fill(&mut self, count: u64, block: BlockID) -> Result<()> {
+        let end = self.1 + count as usize;
+        if count as usize > BLOCK_COUNT || end > self.0.len() {
+            bail!("More blocks than fit in a chunk");
+        }
+
+        self.0[self.1..end].fill(block);
+        self.1 = end;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.1 != self.0.len() {
+            bail!("{} blocks instead of {}", self.1, self.0.len());
+        }
+        Ok(())
+    }
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self {
+            bytes: vec![],
+        }
+    }
+
+    /// Seven bits at a time, small numbers take a single byte
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.varint(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    /// An array of a byte per block, as runs of the same byte
+    fn byte_runs(&mut self, values: &[u8]) {
+        let mut runs: Vec<(u64, u8)> = vec![];
+        for &value in values {
+            match runs.last_mut() {
+                Some((count, last)) if *last == value => *count += 1,
+                _ => runs.push((1, value)),
+            }
+        }
+
+        self.varint(runs.len() as u64);
+        for (count, value) in runs {
+            self.varint(count);
+            self.bytes.push(value);
+        }
+    }
+
+    /// Items are stored by name, like in the inventory
+    fn entity(&mut self, entity: &Entity) {
+        self.string(entity.kind.name());
+        for value in [entity.position.x, entity.position.y, entity.position.z] {
+            self.f32(value);
+        }
+        for value in [entity.velocity.x, entity.velocity.y, entity.velocity.z] {
+            self.f32(value);
+        }
+        self.f32(entity.yaw);
+        self.varint(entity.age);
+
+        if let EntityKind::Item(stack) = entity.kind {
+            self.string(&stack.item.name());
+            self.varint(stack.count as u64);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            at: 0,
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.at
+    }
+
+    fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        if count > self.remaining() {
+            bail!("The data ends {} bytes early", count - self.remaining());
+        }
+
+        let bytes = &self.bytes[self.at..self.at + count];
+        self.at += count;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        let value = f32::from_le_bytes(self.array()?);
+        if !value.is_finite() {
+            bail!("A number is not finite");
+        }
+        Ok(value)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value)
+            }
+        }
+
+        bail!("A number is too long")
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = self.varint()? as usize;
+        if length > self.remaining() {
+            bail!("A text is longer than the data");
+        }
+
+        String::from_utf8(self.take(length)?.to_vec()).context("A text is not UTF-8")
+    }
+
+    fn byte_runs(&mut self) -> Result<Vec<u8>> {
+        let mut values = Vec::with_capacity(BLOCK_COUNT);
+
+        for _ in 0..self.varint()? {
+            let count = self.varint()? as usize;
+            let value = self.u8()?;
+            if count > BLOCK_COUNT - values.len() {
+                bail!("More values than blocks in a chunk");
+            }
+            values.resize(values.len() + count, value);
+        }
+
+        if values.len() != BLOCK_COUNT {
+            bail!("{} values instead of one for each of the {} blocks", values.len(), BLOCK_COUNT);
+        }
+        Ok(values)
+    }
+
+    /// None for kinds and items that don't exist (anymore)
+    fn entity(&mut self) -> Result<Option<Entity>> {
+        let kind = self.string()?;
+        let position = Point3::new(self.f32()?, self.f32()?, self.f32()?);
+        let velocity = Vector3::new(self.f32()?, self.f32()?, self.f32()?);
+        let yaw = self.f32()?;
+        let age = self.varint()?;
+
+        let kind = match kind.as_str() {
+            "item" => {
+                let item = self.string()?;
+                let count = self.varint()? as u32;
+                match Item::from_name(&item) {
+                    Some(item) => EntityKind::Item(ItemStack::new(item, count)),
+                    None => return Ok(None),
+                }
+            }
+            "wanderer" => EntityKind::Wanderer { walk_ticks: 0 },
+            // Other kinds may have more data, the rest can't be read
+            _ => bail!("Unknown entity kind '{}'", kind),
+        };
+
+        let mut entity = Entity::new(0, kind, position);
+        entity.velocity = velocity;
+        entity.yaw = yaw;
+        entity.age = age;
+
+        Ok(Some(entity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::world::testing::setup;
+
+    /// Runs of random blocks, so there is something to compress
+    fn random_chunk(rng: &mut StdRng) -> Chunk {
+        let blocks: Vec<BlockID> = registry().ids().collect();
+        let mut chunk = Chunk::new(ChunkPos::new(rng.gen_range(-1000..1000), 0, rng.gen_range(-1000..1000)));
+
+        let mut at = 0;
+        while at < BLOCK_COUNT {
+            let end = (at + rng.gen_range(1..200)).min(BLOCK_COUNT);
+            chunk.blocks_mut()[at..end].fill(blocks[rng.gen_range(0..blocks.len())]);
+            at = end;
+        }
+
+        chunk
+    }
+
+    fn random_bytes(rng: &mut StdRng) -> Vec<u8> {
+        (0..BLOCK_COUNT).map(|_| rng.gen_range(0..4)).collect()
+    }
+
+    fn random_entity(rng: &mut StdRng) -> Entity {
+        let kind = if rng.gen() {
+            let block = rng.gen_range(1..registry().ids().count()) as BlockID;
+            EntityKind::Item(ItemStack::new(Item::Block(block), rng.gen_range(1..64)))
+        } else {
+            EntityKind::Wanderer { walk_ticks: 0 }
+        };
+
+        let mut entity = Entity::new(0, kind, Point3::new(rng.gen(), rng.gen_range(0.0..100.0), rng.gen()));
+        entity.velocity = Vector3::new(rng.gen(), rng.gen(), rng.gen());
+        entity.yaw = rng.gen_range(0.0..360.0);
+        entity.age = rng.gen_range(0..100_000);
+        entity
+    }
+
+    fn random_data(rng: &mut StdRng) -> ChunkData {
+        ChunkData {
+            chunk: random_chunk(rng),
+            light: Some(random_bytes(rng)).filter(|_| rng.gen()),
+            metadata: Some(random_bytes(rng)).filter(|_| rng.gen()),
+            entities: (0..rng.gen_range(0..5)).map(|_| random_entity(rng)).collect(),
+        }
+    }
+
+    fn assert_same(data: &ChunkData, decoded: &ChunkData) {
+        assert_eq!(decoded.chunk.pos, data.chunk.pos);
+        assert!(decoded.chunk.blocks() == data.chunk.blocks());
+        assert_eq!(decoded.light, data.light);
+        assert_eq!(decoded.metadata, data.metadata);
+
+        assert_eq!(decoded.entities.len(), data.entities.len());
+        for (decoded, entity) in decoded.entities.iter().zip(&data.entities) {
+            assert_eq!(decoded.kind, entity.kind);
+            assert_eq!(decoded.position, entity.position);
+            assert_eq!(decoded.velocity, entity.velocity);
+            assert_eq!(decoded.yaw, entity.yaw);
+            assert_eq!(decoded.age, entity.age);
+        }
+    }
+
+    #[test]
+    fn random_chunks_survive_encoding() {
+        setup();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..20 {
+            let data = random_data(&mut rng);
+            assert_same(&data, &decode(&encode(&data)).unwrap());
+        }
+    }
+
+    #[test]
+    fn only_blocks_survive_encoding() {
+        setup();
+        let chunk = random_chunk(&mut StdRng::seed_from_u64(2));
+
+        assert_same(&ChunkData::new(chunk.clone()), &decode(&encode_chunk(&chunk)).unwrap());
+    }
+
+    #[test]
+    fn truncated_chunks_are_errors() {
+        setup();
+        let mut rng = StdRng::seed_from_u64(3);
+        let bytes = encode(&random_data(&mut rng));
+
+        for length in (0..bytes.len()).step_by(7).chain([HEADER_SIZE - 1, HEADER_SIZE, bytes.len() - 1]) {
+            assert!(decode(&bytes[..length]).is_err(), "{} of {} bytes were read", length, bytes.len());
+        }
+    }
+
+    #[test]
+    fn flipped_bits_are_errors() {
+        setup();
+        let mut rng = StdRng::seed_from_u64(4);
+        let bytes = encode(&random_data(&mut rng));
+
+        // Every bit of the header, and bits all over the payload
+        let bits = (0..HEADER_SIZE * 8).chain((0..500).map(|_| rng.gen_range(HEADER_SIZE * 8..bytes.len() * 8)));
+        for bit in bits {
+            let mut flipped = bytes.clone();
+            flipped[bit / 8] ^= 1 << (bit % 8);
+            assert!(decode(&flipped).is_err(), "bit {} was flipped", bit);
+        }
+    }
+
+    #[test]
+    fn newer_versions_are_errors() {
+        setup();
+        let mut bytes = encode_chunk(&Chunk::new(ChunkPos::new(3, 0, -4)));
+        bytes[4..6].copy_from_slice(&(CODEC_VERSION + 1).to_le_bytes());
+
+        let error = format!("{:#}", decode(&bytes).unwrap_err());
+        assert!(error.contains("version"), "{}", error);
+    }
+
+    /// Runs of ids like the game wrote before block names were stored
+    fn encode_version_1(pos: ChunkPos, runs: &[(u64, u16)], sections: u16) -> Vec<u8> {
+        let mut payload = Writer::new();
+        payload.varint(runs.len() as u64);
+        for &(count, id) in runs {
+            payload.varint(count);
+            payload.bytes.extend_from_slice(&id.to_le_bytes());
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&sections.to_le_bytes());
+        bytes.extend_from_slice(&pos.x.to_le_bytes());
+        bytes.extend_from_slice(&pos.z.to_le_bytes());
+        bytes.extend_from_slice(&(payload.bytes.len() as u32).to_le_bytes());
+        let sum = checksum(&[&bytes, &payload.bytes]);
+        bytes.extend_from_slice(&sum.to_le_bytes());
+        bytes.extend_from_slice(&payload.bytes);
+        bytes
+    }
+
+    #[test]
+    fn version_1_is_upgraded() {
+        setup();
+        let pos = ChunkPos::new(-2, 0, 7);
+        let half = BLOCK_COUNT as u64 / 2;
+        let bytes = encode_version_1(pos, &[(half - 10, 1), (10, 4), (half, 0)], 0);
+
+        let data = decode(&bytes).unwrap();
+        assert_eq!(data.chunk.pos, pos);
+        let blocks = data.chunk.blocks();
+        assert!(blocks[..half as usize - 10].iter().all(|&block| block == Blocks::STONE as BlockID));
+        assert!(blocks[half as usize - 10..half as usize].iter().all(|&block| block == registry().id("help").unwrap()));
+        assert!(blocks[half as usize..].iter().all(|&block| block == Blocks::AIR as BlockID));
+        assert!(data.light.is_none() && data.metadata.is_none() && data.entities.is_empty());
+
+        // Saving it again writes the current version
+        let upgraded = encode(&data);
+        assert_eq!(upgraded[4..6], CODEC_VERSION.to_le_bytes());
+        assert_same(&data, &decode(&upgraded).unwrap());
+    }
+
+    #[test]
+    fn version_1_ids_are_checked() {
+        setup();
+        let pos = ChunkPos::new(0, 0, 0);
+        let count = BLOCK_COUNT as u64;
+
+        assert!(decode(&encode_version_1(pos, &[(count, VERSION_1_BLOCKS.len() as u16)], 0)).is_err());
+        assert!(decode(&encode_version_1(pos, &[(count - 1, 1)], 0)).is_err());
+        assert!(decode(&encode_version_1(pos, &[(count, 1)], SECTION_LIGHT)).is_err());
+    }
+
+    #[test]
+    fn versions_before_the_first_are_errors() {
+        setup();
+        let mut bytes = encode_chunk(&Chunk::new(ChunkPos::new(0, 0, 0)));
+        bytes[4..6].copy_from_slice(&0u16.to_le_bytes());
+        // The checksum covers the version
+        let length = bytes.len();
+        let sum = checksum(&[&bytes[..HEADER_SIZE - 4], &bytes[HEADER_SIZE..length]]);
+        bytes[HEADER_SIZE - 4..HEADER_SIZE].copy_from_slice(&sum.to_le_bytes());
+
+        assert!(decode(&bytes).is_err());
+    }
+}
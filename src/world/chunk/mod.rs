@@ -1,3 +1,6 @@
 pub mod chunk;
 pub mod pos;
-pub mod chunkmanager;
\ No newline at end of file
+pub mod chunkmanager;
+pub mod chunkstore;
+pub mod codec;
+pub mod storage;
\ No newline at end of file
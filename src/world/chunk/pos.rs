@@ -1,9 +1,10 @@
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, Sub};
 
 use crate::world::constants::*;
 use crate::render::low::uniforms::ChunkPositionUniform;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Chunk coordinate in world so (0, 0) is from 
 /// x 0 to 16 and y 0 to 16
 pub struct ChunkPos {
@@ -19,10 +20,26 @@ impl ChunkPos {
         }
     }
 
+    /// The square of chunks that is loaded around this one for a view distance
+    /// in chunks, closest first
+    pub fn area_around(&self, distance: u32) -> Vec<ChunkPos> {
+        let distance = distance as i32;
+        let mut area = vec![];
+
+        for x in -distance..distance {
+            for z in -distance..distance {
+                area.push(ChunkPos::new(self.x + x, 0, self.z + z));
+            }
+        }
+
+        area.sort_by_key(|pos| (pos.x - self.x).pow(2) + (pos.z - self.z).pow(2));
+        area
+    }
+
     pub fn to_raw(&self) -> ChunkPositionUniform {
         ChunkPositionUniform{ location: [
             (self.x * CHUNKSIZE as i32) as f32, 
-            (self.y * 1 as i32) as f32, 
+            self.y as f32, 
             (self.z * CHUNKSIZE as i32) as f32, ] 
         }
     }
@@ -60,7 +77,7 @@ pub struct ChunkCoord {
     pub z: i16,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Global world position
 /// this is not used for entities
 pub struct WorldCoord {
@@ -70,18 +87,19 @@ pub struct WorldCoord {
 }
 
 impl WorldCoord {
+    /// The block the point is in
     pub fn from_point(point: cgmath::Point3<f32>) -> Self {
         Self {
-            x: point.x as i64,
-            y: point.y as i64,
-            z: point.z as i64,
+            x: point.x.floor() as i64,
+            y: point.y.floor() as i64,
+            z: point.z.floor() as i64,
         }
     }
 
     pub fn from_chunk_pos(chunkpos: ChunkPos, chunkcoord: ChunkCoord) -> Self {
         Self {
             x: (chunkpos.x * (CHUNKSIZE as i32 ) + chunkcoord.x as i32) as i64,
-            y: (chunkpos.y * (1 as i32 ) + chunkcoord.y as i32) as i64,
+            y: (chunkpos.y + chunkcoord.y as i32) as i64,
             z: (chunkpos.z * (CHUNKSIZE as i32 ) + chunkcoord.z as i32) as i64,
         }
     }
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::game::player::item::{Item, ItemStack};
+use crate::world::{
+    chunk::{codec::{self, ChunkData}, pos::ChunkPos},
+    entity::entity::{Entity, EntityKind},
+};
+
+/// The directory in a world directory with a file for every chunk that was
+/// changed or has entities. Other chunks are generated again when they load.
+pub const CHUNK_DIRECTORY: &str = "chunks";
+/// Entities used to be saved on their own, in a toml file for every chunk
+const LEGACY_ENTITY_DIRECTORY: &str = "entities";
+
+/// The file of a chunk
+pub fn chunk_file(directory: &Path, pos: ChunkPos) -> PathBuf {
+    directory.join(CHUNK_DIRECTORY).join(format!("{}_{}.chunk", pos.x, pos.z))
+}
+
+/// Reads a saved chunk, None if it was never saved. A file that can't be
+/// read is moved aside, so the chunk can be generated again without losing it.
+pub fn load(directory: &Path, pos: ChunkPos) -> Result<Option<ChunkData>> {
+    let path = chunk_file(directory, pos);
+    if !path.is_file() {
+        return Ok(None)
+    }
+
+    let bytes = std::fs::read(&path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    match codec::decode(&bytes) {
+        Ok(data) if data.chunk.pos == pos => Ok(Some(data)),
+        result => {
+            let broken = path.with_extension("chunk.broken");
+            std::fs::rename(&path, &broken)
+                .with_context(|| format!("Could not move {} aside", path.display()))?;
+
+            let error = match result {
+                Ok(data) => anyhow::anyhow!("It holds chunk {} {}", data.chunk.pos.x, data.chunk.pos.z),
+                Err(e) => e,
+            };
+            Err(error.context(format!("{} is broken and was moved to {}", path.display(), broken.display())))
+        }
+    }
+}
+
+pub fn save(directory: &Path, data: &ChunkData) -> Result<()> {
+    let path = chunk_file(directory, data.chunk.pos);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create chunk directory {}", parent.display()))?;
+    }
+
+    // Written next to it first, so a crash doesn't leave half a chunk
+    let temporary = path.with_extension("chunk.tmp");
+    std::fs::write(&temporary, codec::encode(data))
+        .with_context(|| format!("Could not write {}", temporary.display()))?;
+    std::fs::rename(&temporary, &path)
+        .with_context(|| format!("Could not write {}", path.display()))?;
+
+    remove_legacy_entities(directory, data.chunk.pos)
+}
+
+/// Removes the file of a chunk that doesn't need one anymore
+pub fn remove(directory: &Path, pos: ChunkPos) -> Result<()> {
+    let path = chunk_file(directory, pos);
+    if path.is_file() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Could not remove {}", path.display()))?;
+    }
+
+    remove_legacy_entities(directory, pos)
+}
+
+/// The entities of a chunk from before they were saved with the chunk.
+/// The file is removed once the chunk is saved.
+pub fn load_legacy_entities(directory: &Path, pos: ChunkPos) -> Result<Vec<Entity>> {
+    let path = legacy_entity_file(directory, pos);
+    if !path.is_file() {
+        return Ok(vec![])
+    }
+
+    let source = std::fs::read_to_string(&path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let data: LegacyEntities = toml::from_str(&source)
+        .with_context(|| format!("{} is not a valid entity file", path.display()))?;
+
+    let mut entities = vec![];
+    for entity in data.entities {
+        match entity.into_entity() {
+            Some(entity) => entities.push(entity),
+            None => println!("Dropping an entity of chunk {} {} that can't be loaded", pos.x, pos.z),
+        }
+    }
+
+    Ok(entities)
+}
+
+fn remove_legacy_entities(directory: &Path, pos: ChunkPos) -> Result<()> {
+    let path = legacy_entity_file(directory, pos);
+    if path.is_file() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Could not remove {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn legacy_entity_file(directory: &Path, pos: ChunkPos) -> PathBuf {
+    directory.join(LEGACY_ENTITY_DIRECTORY).join(format!("{}_{}.toml", pos.x, pos.z))
+}
+
+#[derive(Deserialize)]
+struct LegacyEntities {
+    #[serde(default)]
+    entities: Vec<LegacyEntity>,
+}
+
+#[derive(Deserialize)]
+struct LegacyEntity {
+    kind: String,
+    position: [f32; 3],
+    #[serde(default)]
+    velocity: [f32; 3],
+    #[serde(default)]
+    yaw: f32,
+    #[serde(default)]
+    age: u64,
+    #[serde(default)]
+    item: Option<String>,
+    #[serde(default)]
+    count: Option<u32>,
+}
+
+impl LegacyEntity {
+    /// None for kinds and items that don't exist (anymore)
+    fn into_entity(self) -> Option<Entity> {
+        let kind = match self.kind.as_str() {
+            "item" => {
+                let item = Item::from_name(self.item.as_deref()?)?;
+                EntityKind::Item(ItemStack::new(item, self.count.unwrap_or(1)))
+            }
+            "wanderer" => EntityKind::Wanderer { walk_ticks: 0 },
+            _ => return None,
+        };
+
+        let mut entity = Entity::new(0, kind, self.position.into());
+        entity.velocity = self.velocity.into();
+        entity.yaw = self.yaw;
+        entity.age = self.age;
+
+        Some(entity)
+    }
+}
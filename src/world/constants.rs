@@ -1,2 +1,4 @@
 pub const CHUNKSIZE: usize = 32;
-pub const WORLDHEIGHT: usize = 64;
\ No newline at end of file
+pub const WORLDHEIGHT: usize = 64;
+/// Where the ground is lower, water fills the world up to and including this height
+pub const SEA_LEVEL: usize = 24;
\ No newline at end of file
@@ -0,0 +1,214 @@
+use crate::world::{
+    block::blocks::{BlockID, Blocks},
+    chunk::{chunkstore::ChunkStore, pos::WorldCoord},
+    edit::region::Region,
+};
+
+/// An axis to mirror along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Copied blocks, kept relative to the block the player was in when they
+/// were copied. Pasting puts them relative to where the player is then.
+#[derive(Debug, Clone)]
+pub struct Clipboard {
+    /// From the player to the lowest corner
+    offset: [i64; 3],
+    size: [i64; 3],
+    /// x + z * size x + y * size x * size z, like in a chunk
+    blocks: Vec<BlockID>,
+}
+
+impl Clipboard {
+    /// Copies the blocks in a region, relative to `origin`. Blocks in chunks
+    /// that are not loaded are copied as air.
+    pub fn copy(chunks: &ChunkStore, region: Region, origin: WorldCoord) -> Self {
+        let size = region.size();
+        let mut clipboard = Self {
+            offset: [region.min.x - origin.x, region.min.y - origin.y, region.min.z - origin.z],
+            size,
+            blocks: vec![Blocks::AIR as BlockID; (size[0] * size[1] * size[2]) as usize],
+        };
+
+        for at in region.coords() {
+            let local = [at.x - region.min.x, at.y - region.min.y, at.z - region.min.z];
+            let index = clipboard.index(local);
+            clipboard.blocks[index] = chunks.get_block_at_coord(at).unwrap_or(Blocks::AIR as BlockID);
+        }
+
+        clipboard
+    }
+
+    /// Blocks in the order of index, see schematic. None if there are not
+    /// as many as fit in the size.
+    pub fn from_blocks(offset: [i64; 3], size: [i64; 3], blocks: Vec<BlockID>) -> Option<Self> {
+        if size.iter().any(|side| *side <= 0) || (size[0] * size[1] * size[2]) as usize != blocks.len() {
+            return None
+        }
+
+        Some(Self {
+            offset,
+            size,
+            blocks,
+        })
+    }
+
+    pub fn size(&self) -> [i64; 3] {
+        self.size
+    }
+
+    /// From the player to the lowest corner
+    pub fn offset(&self) -> [i64; 3] {
+        self.offset
+    }
+
+    /// All blocks from the lowest corner, x first, then z, then y
+    pub fn block_ids(&self) -> &[BlockID] {
+        &self.blocks
+    }
+
+    /// Every block and where it goes when pasted relative to `origin`
+    pub fn blocks(&self, origin: WorldCoord) -> impl Iterator<Item = (WorldCoord, BlockID)> + '_ {
+        let corner = WorldCoord {
+            x: origin.x + self.offset[0],
+            y: origin.y + self.offset[1],
+            z: origin.z + self.offset[2],
+        };
+
+        self.positions().map(move |local| {
+            let at = WorldCoord { x: corner.x + local[0], y: corner.y + local[1], z: corner.z + local[2] };
+            (at, self.blocks[self.index(local)])
+        })
+    }
+
+    /// Turns the blocks clockwise as seen from above, a quarter turn at a time,
+    /// around the block the player was in
+    pub fn rotate(&mut self, quarter_turns: u32) {
+        for _ in 0..quarter_turns % 4 {
+            self.transform(|[x, y, z]| [-z, y, x]);
+        }
+    }
+
+    /// Flips the blocks along an axis, through the block the player was in
+    pub fn mirror(&mut self, axis: Axis) {
+        match axis {
+            Axis::X => self.transform(|[x, y, z]| [-x, y, z]),
+            Axis::Y => self.transform(|[x, y, z]| [x, -y, z]),
+            Axis::Z => self.transform(|[x, y, z]| [x, y, -z]),
+        }
+    }
+
+    /// Moves every block to where `f` puts it relative to the player. `f` has to
+    /// turn the box into a box, only rotations and mirrors do that.
+    fn transform(&mut self, f: impl Fn([i64; 3]) -> [i64; 3]) {
+        let relative = |local: [i64; 3]| [local[0] + self.offset[0], local[1] + self.offset[1], local[2] + self.offset[2]];
+
+        // Two opposite corners are enough to find the new box
+        let last = [self.size[0] - 1, self.size[1] - 1, self.size[2] - 1];
+        let (a, b) = (f(relative([0; 3])), f(relative(last)));
+        let offset = [a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2])];
+        let size = [(a[0] - b[0]).abs() + 1, (a[1] - b[1]).abs() + 1, (a[2] - b[2]).abs() + 1];
+
+        let mut transformed = Self {
+            offset,
+            size,
+            blocks: vec![Blocks::AIR as BlockID; self.blocks.len()],
+        };
+        for local in self.positions() {
+            let moved = f(relative(local));
+            let index = transformed.index([moved[0] - offset[0], moved[1] - offset[1], moved[2] - offset[2]]);
+            transformed.blocks[index] = self.blocks[self.index(local)];
+        }
+
+        *self = transformed;
+    }
+
+    /// Every position in the box, from the lowest corner
+    fn positions(&self) -> impl Iterator<Item = [i64; 3]> {
+        let [sx, sy, sz] = self.size;
+        (0..sy).flat_map(move |y| (0..sz).flat_map(move |z| (0..sx).map(move |x| [x, y, z])))
+    }
+
+    fn index(&self, local: [i64; 3]) -> usize {
+        (local[0] + local[2] * self.size[0] + local[1] * self.size[0] * self.size[2]) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every block is different, so anything that moves to the wrong place shows
+    fn numbered(offset: [i64; 3], size: [i64; 3]) -> Clipboard {
+        let blocks = (0..size.iter().product::<i64>() as BlockID).collect();
+        Clipboard::from_blocks(offset, size, blocks).unwrap()
+    }
+
+    fn same(a: &Clipboard, b: &Clipboard) -> bool {
+        a.size() == b.size() && a.offset() == b.offset() && a.block_ids() == b.block_ids()
+    }
+
+    #[test]
+    fn four_quarter_turns_are_no_turn() {
+        let original = numbered([-1, 2, -3], [2, 3, 4]);
+
+        let mut turned = original.clone();
+        for _ in 0..4 {
+            turned.rotate(1);
+        }
+        assert!(same(&turned, &original));
+
+        turned.rotate(4);
+        assert!(same(&turned, &original));
+    }
+
+    #[test]
+    fn turning_swaps_width_and_depth() {
+        let mut clipboard = numbered([0, 0, 0], [2, 3, 4]);
+
+        clipboard.rotate(1);
+        assert_eq!(clipboard.size(), [4, 3, 2]);
+        clipboard.rotate(1);
+        assert_eq!(clipboard.size(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn turning_moves_blocks_clockwise() {
+        // A row going east becomes a row going south
+        let mut clipboard = numbered([0, 0, 0], [3, 1, 1]);
+        clipboard.rotate(1);
+
+        assert_eq!(clipboard.offset(), [0, 0, 0]);
+        assert_eq!(clipboard.block_ids(), &[0, 1, 2]);
+        let blocks: Vec<(WorldCoord, BlockID)> = clipboard.blocks(WorldCoord { x: 0, y: 0, z: 0 }).collect();
+        assert_eq!(blocks[2], (WorldCoord { x: 0, y: 0, z: 2 }, 2));
+    }
+
+    #[test]
+    fn mirroring_twice_is_no_mirror() {
+        let original = numbered([-2, 0, 1], [3, 2, 2]);
+
+        for axis in [Axis::X, Axis::Y, Axis::Z] {
+            let mut mirrored = original.clone();
+            mirrored.mirror(axis);
+            assert!(!same(&mirrored, &original));
+            assert_eq!(mirrored.size(), original.size());
+
+            mirrored.mirror(axis);
+            assert!(same(&mirrored, &original));
+        }
+    }
+
+    #[test]
+    fn mirroring_goes_through_the_player() {
+        let mut clipboard = numbered([1, 0, 0], [2, 1, 1]);
+        clipboard.mirror(Axis::X);
+
+        assert_eq!(clipboard.offset(), [-2, 0, 0]);
+        assert_eq!(clipboard.block_ids(), &[1, 0]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use crate::world::{
+    block::blocks::BlockID,
+    chunk::pos::{ChunkPos, WorldCoord},
+};
+
+/// Edits that can be undone, older ones are forgotten
+pub const MAX_HISTORY: usize = 32;
+/// Block changes that are kept over all edits, they take 32 bytes each
+pub const MAX_HISTORY_BLOCKS: usize = 1 << 20;
+
+/// A block that was changed, with what it was before
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChange {
+    pub at: WorldCoord,
+    pub old: BlockID,
+    pub new: BlockID,
+}
+
+/// The chunks that have to be sent again after changing blocks
+pub fn changed_chunks(changes: &[BlockChange]) -> Vec<ChunkPos> {
+    let mut chunks: Vec<ChunkPos> = vec![];
+    for change in changes {
+        let pos = change.at.to_chunk_coord();
+        // Changes come a chunk at a time, see Region::coords
+        if chunks.last() != Some(&pos) && !chunks.contains(&pos) {
+            chunks.push(pos);
+        }
+    }
+
+    chunks
+}
+
+/// The edits of a player, newest last. Undoing moves an edit to the redo
+/// list, a new edit throws the redo list away.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: VecDeque<Vec<BlockChange>>,
+    redo: Vec<Vec<BlockChange>>,
+}
+
+impl History {
+    /// Remembers an edit. The oldest edits are forgotten when there are too
+    /// many, the newest is always kept.
+    pub fn push(&mut self, changes: Vec<BlockChange>) {
+        if changes.is_empty() {
+            return
+        }
+
+        self.redo.clear();
+        self.undo.push_back(changes);
+
+        while self.undo.len() > 1 && (self.undo.len() > MAX_HISTORY || self.blocks() > MAX_HISTORY_BLOCKS) {
+            self.undo.pop_front();
+        }
+    }
+
+    /// The newest edit, to be undone. Its blocks have to be set back to old in reverse order.
+    pub fn undo(&mut self) -> Option<&[BlockChange]> {
+        let changes = self.undo.pop_back()?;
+        self.redo.push(changes);
+        self.redo.last().map(|changes| changes.as_slice())
+    }
+
+    /// The last undone edit, to be done again
+    pub fn redo(&mut self) -> Option<&[BlockChange]> {
+        let changes = self.redo.pop()?;
+        self.undo.push_back(changes);
+        self.undo.back().map(|changes| changes.as_slice())
+    }
+
+    /// Block changes over all edits that can be undone or redone
+    fn blocks(&self) -> usize {
+        self.undo.iter().chain(self.redo.iter()).map(|changes| changes.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An edit of `blocks` changes, the first one at x = id
+    fn edit(id: i64, blocks: usize) -> Vec<BlockChange> {
+        (0..blocks as i64)
+            .map(|i| BlockChange { at: WorldCoord { x: id, y: i, z: 0 }, old: 0, new: 1 })
+            .collect()
+    }
+
+    fn first_x(changes: Option<&[BlockChange]>) -> Option<i64> {
+        changes.map(|changes| changes[0].at.x)
+    }
+
+    #[test]
+    fn undo_and_redo_go_back_and_forth() {
+        let mut history = History::default();
+        history.push(edit(1, 1));
+        history.push(edit(2, 1));
+
+        assert_eq!(first_x(history.undo()), Some(2));
+        assert_eq!(first_x(history.redo()), Some(2));
+        assert_eq!(first_x(history.undo()), Some(2));
+        assert_eq!(first_x(history.undo()), Some(1));
+        assert_eq!(first_x(history.undo()), None);
+        assert_eq!(first_x(history.redo()), Some(1));
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut history = History::default();
+        history.push(edit(1, 1));
+        history.undo();
+
+        history.push(edit(2, 1));
+        assert_eq!(first_x(history.redo()), None);
+        assert_eq!(first_x(history.undo()), Some(2));
+    }
+
+    #[test]
+    fn empty_edits_are_not_remembered() {
+        let mut history = History::default();
+        history.push(edit(1, 1));
+        history.undo();
+
+        history.push(vec![]);
+        assert_eq!(first_x(history.redo()), Some(1));
+    }
+
+    #[test]
+    fn the_oldest_edits_are_forgotten() {
+        let mut history = History::default();
+        for id in 0..MAX_HISTORY as i64 + 5 {
+            history.push(edit(id, 1));
+        }
+
+        let mut undone = vec![];
+        while let Some(x) = first_x(history.undo()) {
+            undone.push(x);
+        }
+        assert_eq!(undone.len(), MAX_HISTORY);
+        assert_eq!(undone.last(), Some(&5));
+    }
+
+    #[test]
+    fn too_many_blocks_forget_old_edits_but_keep_the_newest() {
+        let mut history = History::default();
+        history.push(edit(1, 10));
+        history.push(edit(2, MAX_HISTORY_BLOCKS));
+
+        assert_eq!(first_x(history.undo()), Some(2));
+        assert_eq!(first_x(history.undo()), None);
+    }
+
+    #[test]
+    fn changed_chunks_are_listed_once() {
+        let at = |x: i64| BlockChange { at: WorldCoord { x, y: 0, z: 0 }, old: 0, new: 1 };
+        let chunks = changed_chunks(&[at(0), at(1), at(40), at(2)]);
+
+        assert_eq!(chunks, vec![at(0).at.to_chunk_coord(), at(40).at.to_chunk_coord()]);
+    }
+}
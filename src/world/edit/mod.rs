@@ -0,0 +1,5 @@
+pub mod region;
+pub mod clipboard;
+pub mod history;
+pub mod session;
+pub mod schematic;
\ No newline at end of file
@@ -0,0 +1,157 @@
+use crate::world::{
+    chunk::pos::{ChunkPos, WorldCoord},
+    constants::{CHUNKSIZE, WORLDHEIGHT},
+};
+
+/// A box of blocks between two corners, both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: WorldCoord,
+    pub max: WorldCoord,
+}
+
+impl Region {
+    /// The box between any two opposite corners. It is cut off at the
+    /// bottom and top of the world.
+    pub fn new(a: WorldCoord, b: WorldCoord) -> Self {
+        let top = WORLDHEIGHT as i64 - 1;
+
+        Self {
+            min: WorldCoord { x: a.x.min(b.x), y: a.y.min(b.y).max(0), z: a.z.min(b.z) },
+            max: WorldCoord { x: a.x.max(b.x), y: a.y.max(b.y).min(top), z: a.z.max(b.z) },
+        }
+    }
+
+    /// Blocks along every axis
+    pub fn size(&self) -> [i64; 3] {
+        [
+            self.max.x - self.min.x + 1,
+            (self.max.y - self.min.y + 1).max(0),
+            self.max.z - self.min.z + 1,
+        ]
+    }
+
+    pub fn volume(&self) -> i64 {
+        let [x, y, z] = self.size();
+        x * y * z
+    }
+
+    pub fn contains(&self, at: WorldCoord) -> bool {
+        (self.min.x..=self.max.x).contains(&at.x)
+            && (self.min.y..=self.max.y).contains(&at.y)
+            && (self.min.z..=self.max.z).contains(&at.z)
+    }
+
+    /// On one of the six sides
+    pub fn on_side(&self, at: WorldCoord) -> bool {
+        at.y == self.min.y || at.y == self.max.y || self.on_wall(at)
+    }
+
+    /// On one of the four upright sides
+    pub fn on_wall(&self, at: WorldCoord) -> bool {
+        at.x == self.min.x || at.x == self.max.x || at.z == self.min.z || at.z == self.max.z
+    }
+
+    /// Every block in the box, a chunk column at a time
+    pub fn coords(&self) -> impl Iterator<Item = WorldCoord> + '_ {
+        self.chunks().into_iter().flat_map(move |pos| {
+            let chunk = self.in_chunk(pos);
+            (chunk.min.x..=chunk.max.x).flat_map(move |x| {
+                (chunk.min.z..=chunk.max.z).flat_map(move |z| {
+                    (chunk.min.y..=chunk.max.y).map(move |y| WorldCoord { x, y, z })
+                })
+            })
+        })
+    }
+
+    /// The chunks the box is in
+    pub fn chunks(&self) -> Vec<ChunkPos> {
+        let (min, max) = (self.min.to_chunk_coord(), self.max.to_chunk_coord());
+
+        let mut chunks = vec![];
+        for x in min.x..=max.x {
+            for z in min.z..=max.z {
+                chunks.push(ChunkPos::new(x, 0, z));
+            }
+        }
+
+        chunks
+    }
+
+    /// The part of the box that is in a chunk
+    fn in_chunk(&self, pos: ChunkPos) -> Region {
+        let size = CHUNKSIZE as i64;
+        let (x, z) = (pos.x as i64 * size, pos.z as i64 * size);
+
+        Region {
+            min: WorldCoord { x: self.min.x.max(x), y: self.min.y, z: self.min.z.max(z) },
+            max: WorldCoord { x: self.max.x.min(x + size - 1), y: self.max.y, z: self.max.z.min(z + size - 1) },
+        }
+    }
+}
+
+/// The two corners a player picked, see Region
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub first: Option<WorldCoord>,
+    pub second: Option<WorldCoord>,
+}
+
+impl Selection {
+    /// None until both corners are picked
+    pub fn region(&self) -> Option<Region> {
+        Some(Region::new(self.first?, self.second?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn region(a: [i64; 3], b: [i64; 3]) -> Region {
+        Region::new(WorldCoord { x: a[0], y: a[1], z: a[2] }, WorldCoord { x: b[0], y: b[1], z: b[2] })
+    }
+
+    #[test]
+    fn corners_can_be_given_in_any_order() {
+        assert_eq!(region([3, 5, -2], [-1, 2, 4]), region([-1, 2, -2], [3, 5, 4]));
+        assert_eq!(region([0, 0, 0], [2, 3, 4]).size(), [3, 4, 5]);
+    }
+
+    #[test]
+    fn regions_are_cut_off_at_the_world() {
+        let cut = region([0, -10, 0], [1, WORLDHEIGHT as i64 + 10, 1]);
+
+        assert_eq!(cut.min.y, 0);
+        assert_eq!(cut.max.y, WORLDHEIGHT as i64 - 1);
+    }
+
+    #[test]
+    fn coords_are_every_block_once() {
+        // Crosses chunk borders on both sides
+        let across = region([-3, 4, -20], [CHUNKSIZE as i64 + 2, 9, 5]);
+
+        let coords: Vec<WorldCoord> = across.coords().collect();
+        let unique: HashSet<WorldCoord> = coords.iter().copied().collect();
+        assert_eq!(coords.len() as i64, across.volume());
+        assert_eq!(unique.len(), coords.len());
+        assert!(coords.iter().all(|at| across.contains(*at)));
+        assert_eq!(across.chunks().len(), 3 * 2);
+    }
+
+    #[test]
+    fn sides_and_walls() {
+        let cube = region([0, 0, 0], [3, 3, 3]);
+
+        // Hollow keeps everything but the 2 by 2 by 2 inside
+        assert_eq!(cube.coords().filter(|at| cube.on_side(*at)).count(), 64 - 8);
+        // Walls leave out the 2 by 2 columns inside, top and bottom included
+        assert_eq!(cube.coords().filter(|at| cube.on_wall(*at)).count(), 64 - 2 * 2 * 4);
+
+        let inside = WorldCoord { x: 1, y: 1, z: 2 };
+        let floor = WorldCoord { x: 1, y: 0, z: 2 };
+        assert!(!cube.on_side(inside) && !cube.on_wall(inside));
+        assert!(cube.on_side(floor) && !cube.on_wall(floor));
+    }
+}
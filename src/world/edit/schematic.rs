@@ -0,0 +1,449 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::world::{
+    block::{blocks::{BlockID, Blocks}, colors::BlockColors, registry::registry},
+    edit::clipboard::Clipboard,
+};
+
+/// Schematics are shared between worlds, so they are kept next to the saves
+pub const SCHEMATIC_DIRECTORY: &str = "schematics";
+/// Our own format, see encode
+pub const SCHEMATIC_EXTENSION: &str = "lwschem";
+/// MagicaVoxel models, see read_vox
+pub const VOX_EXTENSION: &str = "vox";
+/// Blocks that don't exist in this game become this block, so they stand out
+pub const PLACEHOLDER_BLOCK: &str = "help";
+
+const MAGIC: &[u8; 4] = b"LWSC";
+const SCHEMATIC_VERSION: u16 = 1;
+/// Anything larger is not a schematic anyone made on purpose
+const MAX_BLOCKS: i64 = 1 << 24;
+
+/// A schematic that was read
+#[derive(Debug)]
+pub struct Loaded {
+    pub clipboard: Clipboard,
+    /// How many of its blocks are not known here and became PLACEHOLDER_BLOCK
+    pub placeholders: usize,
+}
+
+/// What is stored after the magic and the version
+#[derive(Serialize, Deserialize)]
+struct SchematicFile {
+    /// From the player to the lowest corner when it was copied
+    offset: [i64; 3],
+    size: [u32; 3],
+    /// The names of the blocks, the runs refer to them by index
+    palette: Vec<String>,
+    /// How many blocks in a row have the same palette index, in the order of Clipboard::block_ids
+    runs: Vec<(u32, u32)>,
+}
+
+/// Where a schematic is saved. Without an extension it is in our own format.
+/// None for names that would point outside the schematic directory.
+pub fn schematic_file(name: &str) -> Option<PathBuf> {
+    let valid = !name.is_empty() && !name.starts_with('.')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !valid {
+        return None
+    }
+
+    let path = PathBuf::from(SCHEMATIC_DIRECTORY).join(name);
+    match extension(&path) {
+        Some(_) => Some(path),
+        None => Some(path.with_extension(SCHEMATIC_EXTENSION)),
+    }
+}
+
+/// The schematics that can be loaded, by name with their extension, sorted
+pub fn list_schematics() -> Vec<String> {
+    let entries = match std::fs::read_dir(SCHEMATIC_DIRECTORY) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| matches!(extension(path).as_deref(), Some(SCHEMATIC_EXTENSION) | Some(VOX_EXTENSION)))
+        .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+
+    names
+}
+
+/// Writes blocks to a file in our own format
+pub fn save(path: &Path, clipboard: &Clipboard) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create {}", parent.display()))?;
+    }
+
+    std::fs::write(path, encode(clipboard))
+        .with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// Reads a schematic in our own format, or a MagicaVoxel model by its extension
+pub fn load(path: &Path, colors: &BlockColors) -> Result<Loaded> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    let loaded = match extension(path).as_deref() {
+        Some(VOX_EXTENSION) => read_vox(&bytes, colors),
+        _ => decode(&bytes),
+    };
+
+    loaded.with_context(|| format!("{} is not a valid schematic", path.display()))
+}
+
+/// The magic, the version as a little endian u16 and a SchematicFile. Blocks
+/// are stored by name, so they survive blocks being added to the game.
+pub fn encode(clipboard: &Clipboard) -> Vec<u8> {
+    let mut palette: Vec<BlockID> = vec![];
+    let mut runs: Vec<(u32, u32)> = vec![];
+
+    for &block in clipboard.block_ids() {
+        let index = match palette.iter().position(|known| *known == block) {
+            Some(index) => index,
+            None => {
+                palette.push(block);
+                palette.len() - 1
+            }
+        } as u32;
+
+        match runs.last_mut() {
+            Some((count, last)) if *last == index => *count += 1,
+            _ => runs.push((1, index)),
+        }
+    }
+
+    let [x, y, z] = clipboard.size();
+    let file = SchematicFile {
+        offset: clipboard.offset(),
+        size: [x as u32, y as u32, z as u32],
+        palette: palette.iter().map(|id| registry().get(*id).name.clone()).collect(),
+        runs,
+    };
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&SCHEMATIC_VERSION.to_le_bytes());
+    bytes.extend(bincode::serialize(&file).expect("A schematic can always be serialized"));
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Loaded> {
+    if bytes.len() < 6 || &bytes[..4] != MAGIC {
+        bail!("Not a schematic");
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != SCHEMATIC_VERSION {
+        bail!("Unknown version {}", version);
+    }
+
+    let file: SchematicFile = bincode::deserialize(&bytes[6..])?;
+    let size = [file.size[0] as i64, file.size[1] as i64, file.size[2] as i64];
+    if size.contains(&0) {
+        bail!("{} by {} by {} has no blocks", size[0], size[1], size[2]);
+    }
+    if size.iter().product::<i64>() > MAX_BLOCKS {
+        bail!("{} by {} by {} is too big", size[0], size[1], size[2]);
+    }
+
+    // None for the blocks that are not known
+    let palette: Vec<Option<BlockID>> = file.palette
+        .iter()
+        .map(|name| {
+            let block = registry().id(name);
+            if block.is_none() {
+                println!("The schematic has the unknown block '{}', it is replaced with {}", name, PLACEHOLDER_BLOCK);
+            }
+            block
+        })
+        .collect();
+
+    let total = size.iter().product::<i64>() as usize;
+    let mut blocks = Vec::with_capacity(total);
+    let mut placeholders = 0;
+    for (count, index) in file.runs {
+        let block = *palette.get(index as usize).ok_or_else(|| anyhow!("Palette index {} is out of range", index))?;
+        if blocks.len() + count as usize > total {
+            bail!("More blocks than fit in the size");
+        }
+        if block.is_none() {
+            placeholders += count as usize;
+        }
+        blocks.extend(std::iter::repeat(block.unwrap_or_else(placeholder)).take(count as usize));
+    }
+
+    let clipboard = Clipboard::from_blocks(file.offset, size, blocks).ok_or_else(|| anyhow!("Fewer blocks than fit in the size"))?;
+    Ok(Loaded { clipboard, placeholders })
+}
+
+/// Reads the first model of a MagicaVoxel file. Every color becomes the block
+/// that looks most like it. Z is up in MagicaVoxel, the model is turned so it
+/// stands upright and is centered on the player. Voxels without a color become
+/// PLACEHOLDER_BLOCK.
+pub fn read_vox(bytes: &[u8], colors: &BlockColors) -> Result<Loaded> {
+    let mut reader = VoxReader { bytes, next: 0 };
+    if reader.take(4)? != b"VOX " {
+        bail!("Not a MagicaVoxel file");
+    }
+    let _version = reader.u32()?;
+
+    // Every chunk has an id, the size of its content and the size of its children.
+    // MAIN holds all others as children.
+    if reader.take(4)? != b"MAIN" {
+        bail!("The MAIN chunk is missing");
+    }
+    let content = reader.u32()? as usize;
+    let _children = reader.u32()?;
+    reader.take(content)?;
+
+    let mut size: Option<[u32; 3]> = None;
+    let mut voxels: Option<Vec<[u8; 4]>> = None;
+    let mut palette: Option<Vec<[u8; 3]>> = None;
+
+    while !reader.is_empty() {
+        let id = reader.take(4)?;
+        let content = reader.u32()? as usize;
+        let children = reader.u32()? as usize;
+        let mut chunk = VoxReader { bytes: reader.take(content)?, next: 0 };
+        reader.take(children)?;
+
+        match id {
+            // Only the first model, scenes with more are not supported
+            b"SIZE" if size.is_none() => size = Some([chunk.u32()?, chunk.u32()?, chunk.u32()?]),
+            b"XYZI" if voxels.is_none() => {
+                let count = chunk.u32()? as usize;
+                let mut read = Vec::with_capacity(count.min(content / 4));
+                for _ in 0..count {
+                    let voxel = chunk.take(4)?;
+                    read.push([voxel[0], voxel[1], voxel[2], voxel[3]]);
+                }
+                voxels = Some(read);
+            }
+            b"RGBA" => {
+                let mut read = vec![];
+                for _ in 0..256 {
+                    let color = chunk.take(4)?;
+                    read.push([color[0], color[1], color[2]]);
+                }
+                palette = Some(read);
+            }
+            _ => {}
+        }
+    }
+
+    let [sx, sy, sz] = size.ok_or_else(|| anyhow!("The SIZE chunk is missing"))?;
+    let voxels = voxels.ok_or_else(|| anyhow!("The XYZI chunk is missing"))?;
+    if sx as i64 * sy as i64 * sz as i64 > MAX_BLOCKS {
+        bail!("{} by {} by {} is too big", sx, sy, sz);
+    }
+
+    // Our y is their z, and their y points away from us
+    let size = [sx as i64, sz as i64, sy as i64];
+    let mut blocks = vec![Blocks::AIR as BlockID; (size[0] * size[1] * size[2]) as usize];
+
+    // Without a palette the colors are not known
+    if palette.is_none() {
+        println!("The model has no palette, its voxels are replaced with {}", PLACEHOLDER_BLOCK);
+    }
+    // None for color 0, which MagicaVoxel never uses, or without a palette
+    let mut block_of: Vec<Option<BlockID>> = vec![None; 256];
+    if let Some(palette) = &palette {
+        // Color index i is palette entry i - 1
+        for index in 1..256 {
+            block_of[index] = Some(colors.nearest(palette[index - 1]));
+        }
+    }
+
+    let mut placeholders = 0;
+    for [x, y, z, color] in voxels {
+        let (x, y, z) = (x as i64, z as i64, sy as i64 - 1 - y as i64);
+        if x >= size[0] || y >= size[1] || z < 0 || z >= size[2] {
+            bail!("A voxel is outside the model");
+        }
+
+        let block = block_of[color as usize].unwrap_or_else(|| {
+            placeholders += 1;
+            placeholder()
+        });
+        blocks[(x + z * size[0] + y * size[0] * size[2]) as usize] = block;
+    }
+
+    let offset = [-size[0] / 2, 0, -size[2] / 2];
+    let clipboard = Clipboard::from_blocks(offset, size, blocks).ok_or_else(|| anyhow!("The model is empty"))?;
+    Ok(Loaded { clipboard, placeholders })
+}
+
+fn placeholder() -> BlockID {
+    registry().id(PLACEHOLDER_BLOCK).unwrap_or(Blocks::STONE as BlockID)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+/// Reads little endian numbers from the bytes of a MagicaVoxel file
+struct VoxReader<'a> {
+    bytes: &'a [u8],
+    next: usize,
+}
+
+impl<'a> VoxReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        let end = self.next.checked_add(count).filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| anyhow!("The file ends too early"))?;
+        let bytes = &self.bytes[self.next..end];
+        self.next = end;
+
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.next >= self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::world::testing::setup;
+
+    fn file_bytes(file: &SchematicFile) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SCHEMATIC_VERSION.to_le_bytes());
+        bytes.extend(bincode::serialize(file).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        setup();
+        let stone = Blocks::STONE as BlockID;
+        let air = Blocks::AIR as BlockID;
+        let blocks = vec![stone, stone, air, stone, air, air, stone, stone, stone, air, stone, air];
+        let clipboard = Clipboard::from_blocks([-1, 0, 2], [3, 2, 2], blocks).unwrap();
+
+        let loaded = decode(&encode(&clipboard)).unwrap();
+        assert_eq!(loaded.placeholders, 0);
+        let loaded = loaded.clipboard;
+
+        assert_eq!(loaded.size(), clipboard.size());
+        assert_eq!(loaded.offset(), clipboard.offset());
+        assert_eq!(loaded.block_ids(), clipboard.block_ids());
+    }
+
+    #[test]
+    fn empty_sizes_are_an_error() {
+        setup();
+        let bytes = file_bytes(&SchematicFile {
+            offset: [0, 0, 0],
+            size: [4, 0, 4],
+            palette: vec!["stone".to_string()],
+            runs: vec![],
+        });
+
+        let error = decode(&bytes).unwrap_err().to_string();
+        assert!(error.contains("has no blocks"), "{}", error);
+    }
+
+    #[test]
+    fn missing_blocks_are_an_error() {
+        setup();
+        let bytes = file_bytes(&SchematicFile {
+            offset: [0, 0, 0],
+            size: [2, 2, 2],
+            palette: vec!["stone".to_string()],
+            runs: vec![(7, 0)],
+        });
+
+        assert!(decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn unknown_blocks_are_placeholders() {
+        setup();
+        let bytes = file_bytes(&SchematicFile {
+            offset: [0, 0, 0],
+            size: [3, 1, 1],
+            palette: vec!["stone".to_string(), "not a block".to_string()],
+            runs: vec![(1, 0), (2, 1)],
+        });
+
+        let loaded = decode(&bytes).unwrap();
+        assert_eq!(loaded.clipboard.block_ids(), &[Blocks::STONE as BlockID, placeholder(), placeholder()]);
+        assert_eq!(loaded.placeholders, 2);
+    }
+
+    /// A MagicaVoxel file with one model, and a palette if there are colors
+    fn vox_bytes(size: [u32; 3], voxels: &[[u8; 4]], colors: Option<&[[u8; 3]]>) -> Vec<u8> {
+        let chunk = |id: &[u8; 4], content: Vec<u8>| -> Vec<u8> {
+            let mut bytes = id.to_vec();
+            bytes.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.extend(content);
+            bytes
+        };
+
+        let mut children = chunk(b"SIZE", size.iter().flat_map(|n| n.to_le_bytes()).collect());
+        let mut xyzi = (voxels.len() as u32).to_le_bytes().to_vec();
+        xyzi.extend(voxels.iter().flatten());
+        children.extend(chunk(b"XYZI", xyzi));
+        if let Some(colors) = colors {
+            let mut rgba = vec![0; 256 * 4];
+            for (i, color) in colors.iter().enumerate() {
+                rgba[i * 4..i * 4 + 3].copy_from_slice(color);
+                rgba[i * 4 + 3] = 255;
+            }
+            children.extend(chunk(b"RGBA", rgba));
+        }
+
+        let mut bytes = b"VOX ".to_vec();
+        bytes.extend_from_slice(&150u32.to_le_bytes());
+        bytes.extend_from_slice(b"MAIN");
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(children.len() as u32).to_le_bytes());
+        bytes.extend(children);
+        bytes
+    }
+
+    #[test]
+    fn vox_colors_become_the_nearest_blocks() {
+        setup();
+        let colors = BlockColors::builtin();
+        let stone = colors.get(Blocks::STONE as BlockID);
+        let bytes = vox_bytes([2, 1, 1], &[[0, 0, 0, 1], [1, 0, 0, 1]], Some(&[stone]));
+
+        let loaded = read_vox(&bytes, &colors).unwrap();
+        assert_eq!(loaded.placeholders, 0);
+        assert_eq!(loaded.clipboard.block_ids(), &[Blocks::STONE as BlockID; 2]);
+    }
+
+    #[test]
+    fn vox_voxels_without_colors_are_counted() {
+        setup();
+        let colors = BlockColors::builtin();
+
+        // No palette at all
+        let bytes = vox_bytes([2, 2, 1], &[[0, 0, 0, 1], [1, 0, 0, 7], [0, 1, 0, 7]], None);
+        let loaded = read_vox(&bytes, &colors).unwrap();
+        assert_eq!(loaded.placeholders, 3);
+        assert_eq!(loaded.clipboard.block_ids().iter().filter(|&&block| block == placeholder()).count(), 3);
+
+        // Color 0 is not a color
+        let stone = colors.get(Blocks::STONE as BlockID);
+        let bytes = vox_bytes([2, 1, 1], &[[0, 0, 0, 0], [1, 0, 0, 1]], Some(&[stone]));
+        assert_eq!(read_vox(&bytes, &colors).unwrap().placeholders, 1);
+    }
+}
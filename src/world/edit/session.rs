@@ -0,0 +1,14 @@
+use crate::world::edit::{
+    clipboard::Clipboard,
+    history::History,
+    region::Selection,
+};
+
+/// What a player is building with: the selected corners, the copied blocks
+/// and the edits that can be undone. It is kept until the player leaves.
+#[derive(Debug, Default)]
+pub struct EditSession {
+    pub selection: Selection,
+    pub clipboard: Option<Clipboard>,
+    pub history: History,
+}
\ No newline at end of file
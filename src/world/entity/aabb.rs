@@ -0,0 +1,46 @@
+use cgmath::{Point3, Vector3};
+
+/// An axis aligned bounding box in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self {
+            min,
+            max,
+        }
+    }
+
+    /// A box of the given size standing on `feet`, the center of its bottom
+    pub fn from_feet(feet: Point3<f32>, size: Vector3<f32>) -> Self {
+        Self {
+            min: Point3::new(feet.x - size.x / 2.0, feet.y, feet.z - size.z / 2.0),
+            max: Point3::new(feet.x + size.x / 2.0, feet.y + size.y, feet.z + size.z / 2.0),
+        }
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x && self.max.x > other.min.x &&
+        self.min.y < other.max.y && self.max.y > other.min.y &&
+        self.min.z < other.max.z && self.max.z > other.min.z
+    }
+
+    pub fn translate(&self, by: Vector3<f32>) -> Self {
+        Self {
+            min: self.min + by,
+            max: self.max + by,
+        }
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        Point3::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+}
@@ -0,0 +1,189 @@
+use cgmath::{Point3, Vector3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::clock::TICKS_PER_SECOND;
+use crate::game::player::item::{Item, ItemStack};
+use crate::render::low::textures::TextureTile;
+use crate::world::{
+    block::blocks::get_block,
+    chunk::chunkstore::ChunkStore,
+    entity::{aabb::Aabb, physics::move_entity},
+};
+
+/// Unique while the world is open. Ids are not saved, entities get new ones when their chunk loads.
+pub type EntityId = u64;
+
+/// Items can't be picked up right after they were dropped, so a thrown item gets away first
+pub const PICKUP_DELAY: u64 = TICKS_PER_SECOND as u64;
+/// Items disappear when they lie around for five minutes
+pub const ITEM_LIFETIME: u64 = 5 * 60 * TICKS_PER_SECOND as u64;
+
+/// In blocks per tick
+const WALK_SPEED: f32 = 0.08;
+/// Upwards velocity of a jump, enough to get on a block
+const JUMP_VELOCITY: f32 = 0.3;
+/// Degrees per tick that dropped items turn
+const ITEM_SPIN: f32 = 4.0;
+
+/// What an entity is, and anything that only that kind of entity has
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EntityKind {
+    /// An item lying in the world until someone picks it up
+    Item(ItemStack),
+    /// A mob that walks around without a goal
+    Wanderer {
+        /// Ticks it keeps walking, it stands still at 0
+        walk_ticks: u32,
+    },
+}
+
+impl EntityKind {
+    /// The name the kind is saved with
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntityKind::Item(_) => "item",
+            EntityKind::Wanderer { .. } => "wanderer",
+        }
+    }
+}
+
+/// How an entity looks: a box with a block texture on every side, in the order of Sides.
+/// The front faces where the entity is going.
+pub struct Model {
+    pub size: Vector3<f32>,
+    pub textures: [TextureTile; 6],
+}
+
+/// Anything in the world that moves: dropped items and mobs. Entities
+/// are updated every tick and kept in the chunk they are in, see EntityManager.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    #[allow(dead_code)]
+    pub id: EntityId,
+    pub kind: EntityKind,
+    /// The center of the bottom of its box
+    pub position: Point3<f32>,
+    /// Where it was at the previous tick, to move smoothly between ticks while rendering
+    pub previous: Point3<f32>,
+    /// In blocks per tick
+    pub velocity: Vector3<f32>,
+    /// Where it faces, in degrees
+    pub yaw: f32,
+    pub on_ground: bool,
+    /// Ticks since it was spawned
+    pub age: u64,
+}
+
+impl Entity {
+    pub fn new(id: EntityId, kind: EntityKind, position: Point3<f32>) -> Self {
+        Self {
+            id,
+            kind,
+            position,
+            previous: position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            yaw: 0.0,
+            on_ground: false,
+            age: 0,
+        }
+    }
+
+    /// The size of its box, in blocks
+    pub fn size(&self) -> Vector3<f32> {
+        match self.kind {
+            EntityKind::Item(_) => Vector3::new(0.25, 0.25, 0.25),
+            EntityKind::Wanderer { .. } => Vector3::new(0.8, 0.8, 0.8),
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_feet(self.position, self.size())
+    }
+
+    pub fn model(&self) -> Model {
+        let textures = match self.kind {
+            EntityKind::Item(ItemStack { item: Item::Block(block), .. }) => get_block(block).texture.sides,
+            // Tools have no textures of their own, they look like a small plank
+            EntityKind::Item(ItemStack { item: Item::Tool(_), .. }) => [TextureTile::new(4, 0); 6],
+            EntityKind::Wanderer { .. } => {
+                let side = TextureTile::new(6, 7);
+                [TextureTile::new(7, 7), side, side, side, TextureTile::new(6, 6), side]
+            }
+        };
+
+        Model {
+            size: self.size(),
+            textures,
+        }
+    }
+
+    /// Moves it by one tick and lets it decide what to do next
+    pub fn tick(&mut self, chunks: &ChunkStore, rng: &mut impl Rng) {
+        self.previous = self.position;
+        self.age += 1;
+
+        let walking = match &mut self.kind {
+            EntityKind::Item(_) => {
+                self.yaw = (self.yaw + ITEM_SPIN) % 360.0;
+                false
+            }
+            EntityKind::Wanderer { walk_ticks } => {
+                // Now and then it stops, or turns and walks somewhere else
+                if rng.gen_ratio(1, 80) {
+                    *walk_ticks = if rng.gen_bool(0.3) { 0 } else { rng.gen_range(20..100) };
+                    self.yaw = rng.gen_range(0.0..360.0);
+                }
+
+                if *walk_ticks > 0 {
+                    *walk_ticks -= 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+
+        if walking {
+            let heading = self.heading();
+            self.velocity.x = heading.x * WALK_SPEED;
+            self.velocity.z = heading.z * WALK_SPEED;
+        }
+
+        let on_ground = self.on_ground;
+        let blocked = move_entity(self, chunks);
+
+        // Jumps onto blocks that are in the way
+        if walking && blocked && on_ground {
+            self.velocity.y = JUMP_VELOCITY;
+        }
+    }
+
+    /// Where to draw it, `partial_tick` of the way from the previous tick to the current one
+    pub fn interpolated_position(&self, partial_tick: f32) -> Point3<f32> {
+        self.previous + (self.position - self.previous) * partial_tick.min(1.0)
+    }
+
+    /// The direction it faces, flat on the ground
+    pub fn heading(&self) -> Vector3<f32> {
+        let yaw = self.yaw.to_radians();
+        Vector3::new(-yaw.sin(), 0.0, -yaw.cos())
+    }
+
+    pub fn can_be_picked_up(&self) -> bool {
+        matches!(self.kind, EntityKind::Item(_)) && self.age >= PICKUP_DELAY
+    }
+
+    /// Entities that are gone for good are removed after the tick
+    pub fn is_expired(&self) -> bool {
+        match self.kind {
+            EntityKind::Item(stack) => stack.count == 0 || self.age >= ITEM_LIFETIME,
+            EntityKind::Wanderer { .. } => false,
+        }
+    }
+
+    /// The center of its box
+    pub fn center(&self) -> Point3<f32> {
+        self.aabb().center()
+    }
+}
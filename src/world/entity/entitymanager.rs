@@ -0,0 +1,319 @@
+use cgmath::{Point3, Vector3, MetricSpace};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::collections::HashMap;
+
+use crate::game::player::item::ItemStack;
+use crate::world::{
+    chunk::{chunkstore::ChunkStore, pos::{ChunkPos, WorldCoord}},
+    block::blocks::{get_block, BlockID, Blocks},
+    constants::WORLDHEIGHT,
+    entity::{aabb::Aabb, entity::{Entity, EntityId, EntityKind}},
+};
+
+/// Wanderers spawn around the player until there are this many close by
+const MAX_WANDERERS: usize = 6;
+/// How far away wanderers count as close by, in blocks
+const WANDERER_RANGE: f32 = 64.0;
+/// Wanderers spawn out of sight, but not too far away
+const SPAWN_DISTANCE: std::ops::Range<f32> = 16.0..40.0;
+
+/// Keeps the entities of the loaded chunks, in a bucket for every chunk. Entities
+/// are loaded and unloaded with their chunk and saved in the chunk file, see storage.
+pub struct EntityManager {
+    buckets: HashMap<ChunkPos, Vec<Entity>>,
+    next_id: EntityId,
+    rng: StdRng,
+}
+
+impl EntityManager {
+    pub fn new() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            next_id: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Adds an entity to the chunk it is in. Returns None if that chunk is not loaded.
+    pub fn spawn(&mut self, kind: EntityKind, position: Point3<f32>, velocity: Vector3<f32>) -> Option<EntityId> {
+        let id = self.next_id;
+        let bucket = self.buckets.get_mut(&chunk_of(position))?;
+
+        let mut entity = Entity::new(id, kind, position);
+        entity.velocity = velocity;
+        bucket.push(entity);
+
+        self.next_id += 1;
+        Some(id)
+    }
+
+    /// Takes the entities of a chunk that was just loaded, they get new ids
+    pub fn load_chunk(&mut self, pos: ChunkPos, entities: Vec<Entity>) {
+        let mut bucket = Vec::with_capacity(entities.len());
+
+        for mut entity in entities {
+            entity.id = self.next_id;
+            bucket.push(entity);
+            self.next_id += 1;
+        }
+
+        self.buckets.insert(pos, bucket);
+    }
+
+    /// Gives back the entities of a chunk that is unloaded
+    pub fn unload_chunk(&mut self, pos: ChunkPos) -> Vec<Entity> {
+        self.buckets.remove(&pos).unwrap_or_default()
+    }
+
+    /// The entities in a loaded chunk
+    pub fn in_chunk(&self, pos: ChunkPos) -> &[Entity] {
+        self.buckets.get(&pos).map(|bucket| bucket.as_slice()).unwrap_or(&[])
+    }
+
+    /// Moves every entity by one tick, then puts entities that went to another chunk in its bucket
+    pub fn tick(&mut self, chunks: &ChunkStore) {
+        let mut moved = vec![];
+
+        for (pos, bucket) in self.buckets.iter_mut() {
+            for entity in bucket.iter_mut() {
+                entity.tick(chunks, &mut self.rng);
+            }
+            bucket.retain(|entity| !entity.is_expired());
+
+            let mut i = 0;
+            while i < bucket.len() {
+                let target = chunk_of(bucket[i].position);
+                if target != *pos {
+                    moved.push((*pos, target, bucket.swap_remove(i)));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        for (from, target, entity) in moved {
+            // Unloaded chunks are solid to entities, but one that got in anyway stays with its old chunk
+            let pos = if self.buckets.contains_key(&target) { target } else { from };
+            self.buckets.entry(pos).or_default().push(entity);
+        }
+    }
+
+    /// Now and then spawns a wanderer on the ground near `around`, while there are not too many
+    pub fn spawn_wanderers(&mut self, chunks: &ChunkStore, around: Point3<f32>) {
+        if !self.rng.gen_ratio(1, 100) {
+            return
+        }
+
+        let nearby = self.within_radius(around, WANDERER_RANGE)
+            .iter()
+            .filter(|entity| matches!(entity.kind, EntityKind::Wanderer { .. }))
+            .count();
+        if nearby >= MAX_WANDERERS {
+            return
+        }
+
+        let angle: f32 = self.rng.gen_range(0.0..std::f32::consts::TAU);
+        let distance = self.rng.gen_range(SPAWN_DISTANCE);
+        let x = (around.x + angle.cos() * distance).floor() as i64;
+        let z = (around.z + angle.sin() * distance).floor() as i64;
+
+        // On top of the highest block, but not in water
+        let ground = (0..WORLDHEIGHT as i64).rev().find_map(|y| {
+            match chunks.get_block_at_coord(WorldCoord { x, y, z }) {
+                Some(block) if block != Blocks::AIR as BlockID => Some((y, block)),
+                _ => None,
+            }
+        });
+
+        if let Some((y, _)) = ground.filter(|(_, block)| get_block(*block).solid) {
+            let position = Point3::new(x as f32 + 0.5, y as f32 + 1.0, z as f32 + 0.5);
+            self.spawn(EntityKind::Wanderer { walk_ticks: 0 }, position, Vector3::new(0.0, 0.0, 0.0));
+        }
+    }
+
+    /// Gives the items within `radius` of a point to `pick_up`, which returns how many it could not take
+    pub fn collect_items(&mut self, around: Point3<f32>, radius: f32, mut pick_up: impl FnMut(ItemStack) -> u32) {
+        for pos in buckets_near(around, radius) {
+            if let Some(bucket) = self.buckets.get_mut(&pos) {
+                for entity in bucket.iter_mut() {
+                    if !entity.can_be_picked_up() || entity.center().distance2(around) > radius * radius {
+                        continue
+                    }
+
+                    if let EntityKind::Item(stack) = &mut entity.kind {
+                        stack.count = pick_up(*stack);
+                    }
+                }
+
+                bucket.retain(|entity| !entity.is_expired());
+            }
+        }
+    }
+
+    /// Every entity with its center within `radius` of a point
+    pub fn within_radius(&self, center: Point3<f32>, radius: f32) -> Vec<&Entity> {
+        buckets_near(center, radius)
+            .into_iter()
+            .filter_map(|pos| self.buckets.get(&pos))
+            .flatten()
+            .filter(|entity| entity.center().distance2(center) <= radius * radius)
+            .collect()
+    }
+
+    /// Every entity whose box overlaps with `aabb`
+    pub fn within_box(&self, aabb: &Aabb) -> Vec<&Entity> {
+        let center = aabb.center();
+        let radius = (aabb.max.x - aabb.min.x).max(aabb.max.z - aabb.min.z);
+
+        buckets_near(center, radius)
+            .into_iter()
+            .filter_map(|pos| self.buckets.get(&pos))
+            .flatten()
+            .filter(|entity| entity.aabb().intersects(aabb))
+            .collect()
+    }
+
+    /// The number of loaded entities
+    #[allow(dead_code)]
+    pub fn count(&self) -> usize {
+        self.buckets.values().map(|bucket| bucket.len()).sum()
+    }
+}
+
+fn chunk_of(position: Point3<f32>) -> ChunkPos {
+    WorldCoord::from_point(position).to_chunk_coord()
+}
+
+/// The chunks a sphere reaches into
+fn buckets_near(center: Point3<f32>, radius: f32) -> Vec<ChunkPos> {
+    let min = chunk_of(Point3::new(center.x - radius, 0.0, center.z - radius));
+    let max = chunk_of(Point3::new(center.x + radius, 0.0, center.z + radius));
+
+    let mut chunks = vec![];
+    for x in min.x..=max.x {
+        for z in min.z..=max.z {
+            chunks.push(ChunkPos::new(x, 0, z));
+        }
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::player::item::Item;
+    use crate::world::{chunk::chunk::Chunk, testing::setup};
+
+    /// Empty chunks, so entities fall freely
+    fn store(chunks: &[(i32, i32)]) -> ChunkStore {
+        let mut store = ChunkStore::new();
+        for &(x, z) in chunks {
+            store.insert(Chunk::new(ChunkPos::new(x, 0, z)));
+        }
+        store
+    }
+
+    fn manager(buckets: &[(i32, i32)]) -> EntityManager {
+        let mut manager = EntityManager::new();
+        for &(x, z) in buckets {
+            manager.load_chunk(ChunkPos::new(x, 0, z), vec![]);
+        }
+        manager
+    }
+
+    fn item() -> EntityKind {
+        EntityKind::Item(ItemStack::new(Item::Block(Blocks::DIRT as BlockID), 1))
+    }
+
+    #[test]
+    fn entities_change_bucket_across_chunk_borders() {
+        setup();
+        let chunks = store(&[(0, 0), (1, 0)]);
+        let mut entities = manager(&[(0, 0), (1, 0)]);
+
+        let id = entities.spawn(item(), Point3::new(31.8, 10.0, 5.0), Vector3::new(0.5, 0.0, 0.0)).unwrap();
+        entities.tick(&chunks);
+
+        assert!(entities.in_chunk(ChunkPos::new(0, 0, 0)).is_empty());
+        let moved = entities.in_chunk(ChunkPos::new(1, 0, 0));
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].id, id);
+        assert!(moved[0].position.x > 32.0);
+    }
+
+    #[test]
+    fn entities_stay_in_their_bucket_next_to_unloaded_chunks() {
+        setup();
+        let velocity = Vector3::new(0.5, 0.0, 0.0);
+
+        // The chunk is not loaded at all, it is solid
+        let chunks = store(&[(0, 0)]);
+        let mut entities = manager(&[(0, 0)]);
+        entities.spawn(item(), Point3::new(31.8, 10.0, 5.0), velocity).unwrap();
+        entities.tick(&chunks);
+
+        let stopped = entities.in_chunk(ChunkPos::new(0, 0, 0));
+        assert_eq!(stopped.len(), 1);
+        assert!(stopped[0].aabb().max.x <= 32.0);
+
+        // Its blocks are loaded but its entities are not, it goes in anyway
+        let chunks = store(&[(0, 0), (1, 0)]);
+        let mut entities = manager(&[(0, 0)]);
+        entities.spawn(item(), Point3::new(31.8, 10.0, 5.0), velocity).unwrap();
+        entities.tick(&chunks);
+
+        let kept = entities.in_chunk(ChunkPos::new(0, 0, 0));
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0].position.x > 32.0);
+        assert!(entities.in_chunk(ChunkPos::new(1, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn within_radius_reaches_into_neighbouring_chunks() {
+        setup();
+        let mut entities = manager(&[(-1, 0), (0, 0), (1, 0)]);
+        let still = Vector3::new(0.0, 0.0, 0.0);
+
+        let near = entities.spawn(item(), Point3::new(31.5, 10.0, 5.0), still).unwrap();
+        let across = entities.spawn(item(), Point3::new(32.5, 10.0, 5.0), still).unwrap();
+        let behind = entities.spawn(item(), Point3::new(-0.5, 10.0, 5.0), still).unwrap();
+        entities.spawn(item(), Point3::new(40.0, 10.0, 5.0), still).unwrap();
+
+        let mut found: Vec<EntityId> = entities.within_radius(Point3::new(31.9, 10.0, 5.0), 2.0).iter().map(|entity| entity.id).collect();
+        found.sort_unstable();
+        assert_eq!(found, vec![near, across]);
+
+        let found: Vec<EntityId> = entities.within_radius(Point3::new(0.5, 10.0, 5.0), 1.5).iter().map(|entity| entity.id).collect();
+        assert_eq!(found, vec![behind]);
+    }
+
+    #[test]
+    fn unloaded_entities_come_back_with_their_chunk() {
+        setup();
+        let pos = ChunkPos::new(2, 0, -3);
+        let mut entities = manager(&[(2, -3)]);
+        let positions = [Point3::new(70.0, 10.0, -90.0), Point3::new(80.5, 3.0, -65.0)];
+
+        entities.spawn(EntityKind::Wanderer { walk_ticks: 0 }, positions[0], Vector3::new(0.0, 0.0, 0.0)).unwrap();
+        entities.spawn(item(), positions[1], Vector3::new(0.1, 0.2, 0.3)).unwrap();
+
+        let unloaded = entities.unload_chunk(pos);
+        assert_eq!(unloaded.len(), 2);
+        assert_eq!(entities.count(), 0);
+        assert!(entities.spawn(item(), positions[0], Vector3::new(0.0, 0.0, 0.0)).is_none());
+
+        let old_ids: Vec<EntityId> = unloaded.iter().map(|entity| entity.id).collect();
+        entities.load_chunk(pos, unloaded.clone());
+        let loaded = entities.in_chunk(pos);
+
+        assert_eq!(loaded.len(), 2);
+        for (loaded, entity) in loaded.iter().zip(&unloaded) {
+            assert_eq!(loaded.kind, entity.kind);
+            assert_eq!(loaded.position, entity.position);
+            assert_eq!(loaded.velocity, entity.velocity);
+            assert!(!old_ids.contains(&loaded.id));
+        }
+        assert_ne!(loaded[0].id, loaded[1].id);
+    }
+}
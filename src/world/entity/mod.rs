@@ -0,0 +1,4 @@
+pub mod aabb;
+pub mod entity;
+pub mod physics;
+pub mod entitymanager;
\ No newline at end of file
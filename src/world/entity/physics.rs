@@ -0,0 +1,186 @@
+use cgmath::Vector3;
+
+use crate::world::{
+    chunk::{chunkstore::ChunkStore, pos::WorldCoord},
+    block::blocks::get_block,
+    constants::WORLDHEIGHT,
+    entity::{aabb::Aabb, entity::Entity},
+};
+
+/// Added to the vertical velocity every tick, in blocks per tick squared
+pub const GRAVITY: f32 = 0.04;
+/// Velocity is multiplied by this every tick
+const AIR_DRAG: f32 = 0.98;
+/// Horizontal velocity is multiplied by this every tick on the ground
+const GROUND_FRICTION: f32 = 0.6;
+/// Entities never fall faster than this, in blocks per tick
+const TERMINAL_VELOCITY: f32 = 3.0;
+/// Keeps boxes from touching blocks exactly, so they don't count as inside them
+const EPSILON: f32 = 0.001;
+
+/// Blocks in chunks that are not loaded are solid, so entities don't fall out of the
+/// world where it isn't loaded. Above the world there is only air.
+pub fn is_solid(chunks: &ChunkStore, coord: WorldCoord) -> bool {
+    if coord.y < 0 {
+        return true
+    }
+    if coord.y >= WORLDHEIGHT as i64 {
+        return false
+    }
+
+    match chunks.get_block_at_coord(coord) {
+        Some(block) => get_block(block).solid,
+        None => true,
+    }
+}
+
+/// Applies gravity and moves the entity by its velocity, stopping at blocks.
+/// Returns true if a block was in the way horizontally.
+pub fn move_entity(entity: &mut Entity, chunks: &ChunkStore) -> bool {
+    entity.velocity.y = (entity.velocity.y - GRAVITY).max(-TERMINAL_VELOCITY);
+
+    let mut aabb = entity.aabb();
+    let wanted = entity.velocity;
+    let mut moved = Vector3::new(0.0, 0.0, 0.0);
+
+    // Vertical first, so walking off an edge falls instead of snagging on it
+    for &axis in &[1, 0, 2] {
+        let distance = clip_axis(chunks, &aabb, axis, wanted[axis]);
+
+        let mut step = Vector3::new(0.0, 0.0, 0.0);
+        step[axis] = distance;
+        aabb = aabb.translate(step);
+        moved[axis] = distance;
+    }
+
+    entity.position += moved;
+    entity.on_ground = wanted.y < 0.0 && moved.y > wanted.y;
+
+    let blocked = [moved.x != wanted.x, moved.y != wanted.y, moved.z != wanted.z];
+    for (axis, _) in blocked.iter().enumerate().filter(|(_, blocked)| **blocked) {
+        entity.velocity[axis] = 0.0;
+    }
+
+    entity.velocity *= AIR_DRAG;
+    if entity.on_ground {
+        entity.velocity.x *= GROUND_FRICTION;
+        entity.velocity.z *= GROUND_FRICTION;
+    }
+
+    blocked[0] || blocked[2]
+}
+
+/// How far a box can move along an axis before it hits a solid block, up to `distance`
+fn clip_axis(chunks: &ChunkStore, aabb: &Aabb, axis: usize, distance: f32) -> f32 {
+    if distance == 0.0 {
+        return 0.0
+    }
+
+    let mut offset = Vector3::new(0.0, 0.0, 0.0);
+    offset[axis] = distance;
+    let moved = aabb.translate(offset);
+
+    // Every block the box touches on its way
+    let min = [
+        aabb.min.x.min(moved.min.x), aabb.min.y.min(moved.min.y), aabb.min.z.min(moved.min.z),
+    ];
+    let max = [
+        aabb.max.x.max(moved.max.x), aabb.max.y.max(moved.max.y), aabb.max.z.max(moved.max.z),
+    ];
+
+    let mut allowed = distance;
+
+    for x in min[0].floor() as i64..=(max[0] - EPSILON).floor() as i64 {
+        for y in min[1].floor() as i64..=(max[1] - EPSILON).floor() as i64 {
+            for z in min[2].floor() as i64..=(max[2] - EPSILON).floor() as i64 {
+                if !is_solid(chunks, WorldCoord { x, y, z }) {
+                    continue
+                }
+
+                let block = [x, y, z][axis] as f32;
+
+                // Blocks the box is already inside of don't stop it, so it can get out
+                if distance > 0.0 && block >= aabb.max[axis] - EPSILON {
+                    allowed = allowed.min(block - aabb.max[axis] - EPSILON);
+                } else if distance < 0.0 && block + 1.0 <= aabb.min[axis] + EPSILON {
+                    allowed = allowed.max(block + 1.0 - aabb.min[axis] + EPSILON);
+                }
+            }
+        }
+    }
+
+    // Never backwards
+    if distance > 0.0 {
+        allowed.max(0.0)
+    } else {
+        allowed.min(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Point3;
+    use crate::world::{
+        block::blocks::{BlockID, Blocks},
+        chunk::{chunk::Chunk, pos::ChunkPos},
+        entity::entity::EntityKind,
+        testing::setup,
+    };
+
+    /// A chunk of air with a stone floor at y 4
+    fn floor() -> ChunkStore {
+        let mut chunks = ChunkStore::new();
+        chunks.insert(Chunk::new(ChunkPos::new(0, 0, 0)));
+        for x in 0..32 {
+            for z in 0..32 {
+                chunks.set_block(WorldCoord { x, y: 4, z }, Blocks::STONE as BlockID);
+            }
+        }
+        chunks
+    }
+
+    fn wanderer(x: f32, y: f32, z: f32) -> Entity {
+        Entity::new(0, EntityKind::Wanderer { walk_ticks: 0 }, Point3::new(x, y, z))
+    }
+
+    #[test]
+    fn entities_fall_onto_blocks() {
+        setup();
+        let chunks = floor();
+        let mut entity = wanderer(5.5, 20.0, 5.5);
+
+        for _ in 0..100 {
+            move_entity(&mut entity, &chunks);
+        }
+
+        assert!(entity.on_ground);
+        assert!((entity.position.y - 5.0).abs() < 0.01, "{}", entity.position.y);
+        assert_eq!(entity.position.x, 5.5);
+    }
+
+    #[test]
+    fn walls_stop_entities() {
+        setup();
+        let mut chunks = floor();
+        chunks.set_block(WorldCoord { x: 7, y: 5, z: 5 }, Blocks::STONE as BlockID);
+        let mut entity = wanderer(5.5, 5.0 + EPSILON, 5.5);
+        entity.velocity.x = 2.0;
+
+        assert!(move_entity(&mut entity, &chunks));
+        assert_eq!(entity.velocity.x, 0.0);
+        assert!(entity.aabb().max.x <= 7.0 && entity.aabb().max.x > 6.9);
+    }
+
+    #[test]
+    fn unloaded_chunks_are_solid_and_the_sky_is_not() {
+        setup();
+        let chunks = floor();
+
+        assert!(is_solid(&chunks, WorldCoord { x: 5, y: 4, z: 5 }));
+        assert!(!is_solid(&chunks, WorldCoord { x: 5, y: 5, z: 5 }));
+        assert!(is_solid(&chunks, WorldCoord { x: 40, y: 30, z: 5 }));
+        assert!(is_solid(&chunks, WorldCoord { x: 5, y: -1, z: 5 }));
+        assert!(!is_solid(&chunks, WorldCoord { x: 40, y: WORLDHEIGHT as i64, z: 5 }));
+    }
+}
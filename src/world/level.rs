@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where worlds are saved, relative to the current directory
+pub const SAVE_DIRECTORY: &str = "saves";
+/// The file in a world directory with everything except the chunks
+pub const LEVEL_FILE: &str = "level.toml";
+
+/// The global state of a world that is saved in level.toml
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelData {
+    pub seed: u32,
+    /// World time in ticks, see WorldTime
+    #[serde(default)]
+    pub time: u64,
+}
+
+impl LevelData {
+    /// Reads level.toml from a world directory. Returns None for a new world.
+    pub fn load(directory: &Path) -> Result<Option<Self>> {
+        let path = directory.join(LEVEL_FILE);
+
+        if !path.is_file() {
+            return Ok(None)
+        }
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        let level = toml::from_str(&source)
+            .with_context(|| format!("{} is not a valid level file", path.display()))?;
+
+        Ok(Some(level))
+    }
+
+    pub fn save(&self, directory: &Path) -> Result<()> {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Could not create world directory {}", directory.display()))?;
+
+        let path = directory.join(LEVEL_FILE);
+        // Write to a temporary file first so a crash can't leave half a level file behind
+        let temporary = path.with_extension("toml.tmp");
+
+        std::fs::write(&temporary, toml::to_string(self)?)
+            .with_context(|| format!("Could not write {}", temporary.display()))?;
+        std::fs::rename(&temporary, &path)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// The directory of the world with the given name
+pub fn world_directory(name: &str) -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join(name)
+}
+
+/// The names of all saved worlds, sorted
+pub fn list_worlds() -> Vec<String> {
+    let entries = match std::fs::read_dir(SAVE_DIRECTORY) {
+        Ok(entries) => entries,
+        // No world was saved yet
+        Err(_) => return vec![],
+    };
+
+    let mut worlds: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(LEVEL_FILE).is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .collect();
+
+    worlds.sort();
+    worlds
+}
+
+/// A name for a new world that is not used yet: "world", "world 2", ...
+/// Leaves out everything that can't be in the name of a world directory
+pub fn clean_world_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+pub fn new_world_name() -> String {
+    let mut name = String::from("world");
+    let mut number = 1;
+
+    while world_directory(&name).exists() {
+        number += 1;
+        name = format!("world {}", number);
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_worlds_have_no_level() {
+        let directory = tempfile::tempdir().unwrap();
+
+        assert!(LevelData::load(directory.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn level_survives_saving() {
+        let directory = tempfile::tempdir().unwrap();
+        LevelData { seed: 42, time: 1234 }.save(directory.path()).unwrap();
+
+        let level = LevelData::load(directory.path()).unwrap().unwrap();
+        assert_eq!((level.seed, level.time), (42, 1234));
+    }
+
+    #[test]
+    fn corrupt_level_is_an_error() {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(directory.path().join(LEVEL_FILE), "seed = \"not a number").unwrap();
+
+        assert!(LevelData::load(directory.path()).is_err());
+    }
+}
@@ -1,34 +1,57 @@
 use crate::world::constants::{*};
-use crate::world::chunk::pos::ChunkPos;
-use crate::helper::noise::NoiseGenerator;
+use crate::world::chunk::{chunk::Chunk, pos::ChunkPos};
+use crate::world::structure::placement::place_structures;
+use crate::world::terrain::{Column, Terrain};
 
 /// The Map takes care of generating the world
-/// and it contains and hands out heightmaps
+/// and it hands out the columns of the terrain
 pub struct Map {
-    noise_generator: NoiseGenerator,
+    pub seed: u32,
+    terrain: Terrain,
 }
 
 impl Map {
     pub fn new(seed: u32) -> Self {
 
-        let noise_generator = NoiseGenerator::new(seed);
+        let terrain = Terrain::new(seed);
         
         Self {
-            noise_generator,
+            seed,
+            terrain,
         }
     }
 
-    pub fn create_heightmap(&self, chunk: &ChunkPos) -> [u32; CHUNKSIZE * CHUNKSIZE] {
-        let mut heights = [0; CHUNKSIZE * CHUNKSIZE];
+    /// The terrain of a chunk with the parts of structures that reach into it
+    pub fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        chunk.generate(&self.create_columns(&pos));
+        place_structures(self, &mut chunk);
 
-        for x in 0..CHUNKSIZE {
-            for z in 0..CHUNKSIZE {
-                heights[x as usize + z as usize * CHUNKSIZE] = ((self.noise_generator.get(x as f64 + chunk.x as f64 * CHUNKSIZE as f64, z as f64 + chunk.z as f64 * CHUNKSIZE as f64) * 20.0) as i32 + 20) as u32;
+        chunk
+    }
+
+    /// A single column of the terrain, only depends on the seed and where it is
+    pub fn column_at(&self, x: i64, z: i64) -> Column {
+        self.terrain.column(x, z)
+    }
+
+    /// The height of the terrain at a single column
+    pub fn height_at(&self, x: i64, z: i64) -> u32 {
+        self.column_at(x, z).height
+    }
+
+    pub fn create_columns(&self, chunk: &ChunkPos) -> Vec<Column> {
+        let mut columns = Vec::with_capacity(CHUNKSIZE * CHUNKSIZE);
+
+        for z in 0..CHUNKSIZE {
+            for x in 0..CHUNKSIZE {
+                columns.push(self.column_at(
+                    x as i64 + chunk.x as i64 * CHUNKSIZE as i64,
+                    z as i64 + chunk.z as i64 * CHUNKSIZE as i64,
+                ));
             }
         }
 
-        // println!("{:?}", heights);
-
-        heights
+        columns
     }
 }
\ No newline at end of file
@@ -2,4 +2,14 @@ pub mod block;
 pub mod chunk;
 pub mod world;
 pub mod constants;
-pub mod map;
\ No newline at end of file
+pub mod map;
+pub mod terrain;
+pub mod time;
+pub mod level;
+pub mod raycast;
+pub mod entity;
+pub mod edit;
+pub mod topdown;
+pub mod structure;
+#[cfg(test)]
+pub mod testing;
\ No newline at end of file
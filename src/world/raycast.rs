@@ -0,0 +1,82 @@
+use cgmath::{Point3, Vector3, InnerSpace};
+
+use crate::world::{
+    chunk::{chunkstore::ChunkStore, pos::WorldCoord},
+    block::blocks::{get_block, BlockID},
+    constants::WORLDHEIGHT,
+};
+
+/// The block a ray hit first
+#[derive(Debug, Clone, Copy)]
+pub struct RaycastHit {
+    pub coord: WorldCoord,
+    pub block: BlockID,
+    /// Points out of the face the ray went in through, zero if it started inside the block
+    pub normal: [i64; 3],
+    pub distance: f32,
+}
+
+/// Walks through the blocks along a ray until it finds one that is not air.
+/// Blocks in chunks that are not loaded count as air.
+pub fn raycast(chunks: &ChunkStore, origin: Point3<f32>, direction: Vector3<f32>, max_distance: f32) -> Option<RaycastHit> {
+    if direction.magnitude2() == 0.0 {
+        return None
+    }
+    let direction = direction.normalize();
+
+    let mut block = [origin.x.floor() as i64, origin.y.floor() as i64, origin.z.floor() as i64];
+    let origin = [origin.x, origin.y, origin.z];
+    let direction = [direction.x, direction.y, direction.z];
+
+    // Visits the blocks in the order the ray enters them, by stepping along
+    // whichever axis reaches its next block boundary first
+    let mut step = [0i64; 3];
+    // Distance along the ray to the next boundary on every axis
+    let mut next = [f32::INFINITY; 3];
+    // Distance along the ray between two boundaries on every axis
+    let mut delta = [f32::INFINITY; 3];
+
+    for axis in 0..3 {
+        if direction[axis] > 0.0 {
+            step[axis] = 1;
+            delta[axis] = 1.0 / direction[axis];
+            next[axis] = (block[axis] as f32 + 1.0 - origin[axis]) * delta[axis];
+        } else if direction[axis] < 0.0 {
+            step[axis] = -1;
+            delta[axis] = -1.0 / direction[axis];
+            next[axis] = (origin[axis] - block[axis] as f32) * delta[axis];
+        }
+    }
+
+    let mut normal = [0i64; 3];
+    let mut distance = 0.0;
+
+    while distance <= max_distance {
+        if block[1] >= 0 && block[1] < WORLDHEIGHT as i64 {
+            let coord = WorldCoord { x: block[0], y: block[1], z: block[2] };
+
+            if let Some(id) = chunks.get_block_at_coord(coord) {
+                if get_block(id).solid {
+                    return Some(RaycastHit { coord, block: id, normal, distance })
+                }
+            }
+        }
+
+        let axis = if next[0] < next[1] && next[0] < next[2] {
+            0
+        } else if next[1] < next[2] {
+            1
+        } else {
+            2
+        };
+
+        distance = next[axis];
+        next[axis] += delta[axis];
+        block[axis] += step[axis];
+
+        normal = [0; 3];
+        normal[axis] = -step[axis];
+    }
+
+    None
+}
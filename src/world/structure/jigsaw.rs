@@ -0,0 +1,296 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::world::{
+    block::blocks::{BlockID, Blocks},
+    chunk::{chunk::Chunk, pos::{ChunkCoord, ChunkPos}},
+    constants::{CHUNKSIZE, WORLDHEIGHT},
+    map::Map,
+    structure::{
+        placement::StructureRng,
+        registry::{structures, Placement, StructureDefinition},
+        template::{PlacedPiece, Template},
+    },
+};
+
+/// A structure assembled from pieces. Assembling only needs the seed and the
+/// height of the ground, so it comes out the same for every chunk it reaches
+/// into and can be done without generating any chunk.
+#[derive(Debug, Clone)]
+pub struct Structure {
+    /// The index of the structure in the registry
+    pub kind: usize,
+    pub start: ChunkPos,
+    /// The first piece is the start
+    pub pieces: Vec<PlacedPiece>,
+}
+
+impl Structure {
+    /// Starts with a piece from the start pool in the middle of the start chunk
+    /// and attaches pieces to its connectors, breadth first. None if the start
+    /// piece doesn't fit in the world there.
+    pub fn assemble(map: &Map, kind: usize, start: ChunkPos) -> Option<Self> {
+        Self::build(map, kind, &structures().all()[kind], start)
+    }
+
+    /// Assembles a structure of a kind the way the definition says
+    fn build(map: &Map, kind: usize, definition: &StructureDefinition, start: ChunkPos) -> Option<Self> {
+        let mut rng = StructureRng::new(&[map.seed as u64, definition.salt, start.x as u64, start.z as u64, 1]);
+
+        let template = pick(&mut rng, structures().pool(&definition.start_pool))?;
+        let rotation = rng.below(4) as u8;
+        let mut first = PlacedPiece { template, rotation, origin: [0; 3] };
+        let size = first.size(structures().template(template));
+        let center = [
+            start.x as i64 * CHUNKSIZE as i64 + CHUNKSIZE as i64 / 2,
+            start.z as i64 * CHUNKSIZE as i64 + CHUNKSIZE as i64 / 2,
+        ];
+        first.origin = [center[0] - size[0] / 2, 0, center[1] - size[2] / 2];
+        first.origin[1] = match definition.placement {
+            Placement::Surface => ground(map, &first, structures().template(template)),
+            Placement::Underground => map.height_at(center[0], center[1]) as i64 - definition.depth,
+        };
+
+        let mut structure = Self {
+            kind,
+            start,
+            pieces: vec![],
+        };
+        if !structure.fits(map, definition, &first, center) {
+            return None
+        }
+        structure.pieces.push(first);
+
+        // Connectors that still can get a piece, with how far they are from the start
+        let mut open: VecDeque<(usize, usize, u32)> = VecDeque::new();
+        // Connectors that a piece was attached with
+        let mut used: HashSet<(usize, usize)> = HashSet::new();
+        open.extend(open_connectors(&first, 0, 0));
+
+        while let Some((piece, connector, depth)) = open.pop_front() {
+            if depth >= definition.max_depth || used.contains(&(piece, connector)) {
+                continue
+            }
+
+            let parent = structure.pieces[piece];
+            let parent_template = structures().template(parent.template);
+            let pool = match &parent_template.connectors[connector].pool {
+                Some(pool) => pool,
+                None => continue,
+            };
+            let (_, at, facing) = parent.connectors(parent_template).nth(connector).unwrap();
+            let offset = facing.offset();
+            let target = [at[0] + offset[0], at[1] + offset[1], at[2] + offset[2]];
+
+            // Tries the pieces of the pool in a random order, more likely the ones with more weight
+            let mut candidates = structures().pool(pool).to_vec();
+            'candidates: while let Some(template) = pick(&mut rng, &candidates) {
+                candidates.retain(|candidate| *candidate != template);
+                let child_template = structures().template(template);
+                let first_rotation = rng.below(4) as u8;
+
+                for turn in 0..4 {
+                    let rotation = (first_rotation + turn) % 4;
+                    let mut child = PlacedPiece { template, rotation, origin: [0; 3] };
+
+                    let joins: Vec<(usize, [i64; 3])> = child.connectors(child_template)
+                        .filter(|(_, _, child_facing)| *child_facing == facing.opposite())
+                        .map(|(i, child_at, _)| (i, child_at))
+                        .collect();
+
+                    for (child_connector, child_at) in joins {
+                        // The origin is still zero, so the connector is relative to it
+                        child.origin = [target[0] - child_at[0], target[1] - child_at[1], target[2] - child_at[2]];
+                        if definition.placement == Placement::Surface {
+                            child.origin[1] = ground(map, &child, child_template);
+                        }
+
+                        if structure.fits(map, definition, &child, center) {
+                            let index = structure.pieces.len();
+                            structure.pieces.push(child);
+                            used.insert((index, child_connector));
+                            open.extend(open_connectors(&child, index, depth + 1));
+                            break 'candidates
+                        }
+                        child.origin = [0; 3];
+                    }
+                }
+            }
+        }
+
+        Some(structure)
+    }
+
+    /// The middle of the start piece at the height of its lowest layer
+    pub fn position(&self) -> [i64; 3] {
+        let first = &self.pieces[0];
+        let size = first.size(structures().template(first.template));
+        [first.origin[0] + size[0] / 2, first.origin[1], first.origin[2] + size[2] / 2]
+    }
+
+    /// Puts the blocks of the pieces that are in a chunk in it. On the surface
+    /// the gap below a piece is filled with its foundation.
+    pub fn place(&self, chunk: &mut Chunk) {
+        let low = [chunk.pos.x as i64 * CHUNKSIZE as i64, chunk.pos.z as i64 * CHUNKSIZE as i64];
+        let high = [low[0] + CHUNKSIZE as i64 - 1, low[1] + CHUNKSIZE as i64 - 1];
+        let local = |at: [i64; 3]| ChunkCoord {
+            x: (at[0] - low[0]) as i16,
+            y: at[1] as i16,
+            z: (at[2] - low[1]) as i16,
+        };
+        let inside = |at: [i64; 3]| at[0] >= low[0] && at[0] <= high[0] && at[2] >= low[1] && at[2] <= high[1];
+
+        for piece in &self.pieces {
+            let template = structures().template(piece.template);
+            let (min, max) = piece.bounds(template);
+            if max[0] < low[0] || min[0] > high[0] || max[2] < low[1] || min[2] > high[1] {
+                continue
+            }
+
+            for (at, block) in piece.blocks(template).filter(|(at, _)| inside(*at)) {
+                chunk.place_block(local(at), block);
+
+                let solid_bottom = at[1] == piece.origin[1] && block != Blocks::AIR as BlockID;
+                if let (true, Some(foundation)) = (solid_bottom, template.foundation) {
+                    let mut below = [at[0], at[1] - 1, at[2]];
+                    while below[1] >= 0 && chunk.at_coord(local(below)) == Blocks::AIR as BlockID {
+                        chunk.place_block(local(below), foundation);
+                        below[1] -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// A piece fits if it is in the world, not too far from the start and
+    /// doesn't overlap another piece. On the surface pieces are at different
+    /// heights, so they may not overlap even when one is above the other, and
+    /// they can't stand in water.
+    fn fits(&self, map: &Map, definition: &StructureDefinition, piece: &PlacedPiece, center: [i64; 2]) -> bool {
+        let (min, max) = piece.bounds(structures().template(piece.template));
+
+        if min[1] < 0 || max[1] >= WORLDHEIGHT as i64 {
+            return false
+        }
+        let reach = definition.max_distance;
+        if min[0] < center[0] - reach || max[0] > center[0] + reach || min[2] < center[1] - reach || max[2] > center[1] + reach {
+            return false
+        }
+
+        let surface = definition.placement == Placement::Surface;
+        if surface {
+            let corners = [[min[0], min[2]], [min[0], max[2]], [max[0], min[2]], [max[0], max[2]]];
+            if corners.iter().any(|corner| map.column_at(corner[0], corner[1]).water > 0) {
+                return false
+            }
+        }
+
+        !self.pieces.iter().any(|other| {
+            let (other_min, other_max) = other.bounds(structures().template(other.template));
+            let apart = |axis: usize| max[axis] < other_min[axis] || min[axis] > other_max[axis];
+
+            !(apart(0) || apart(2) || (!surface && apart(1)))
+        })
+    }
+}
+
+/// On the ground in the middle of the piece, sunk into it as far as the template says
+fn ground(map: &Map, piece: &PlacedPiece, template: &Template) -> i64 {
+    let size = piece.size(template);
+    let x = piece.origin[0] + size[0] / 2;
+    let z = piece.origin[2] + size[2] / 2;
+
+    map.height_at(x, z) as i64 + 1 - template.sink
+}
+
+/// The connectors of a piece that pieces can be attached to
+fn open_connectors(piece: &PlacedPiece, index: usize, depth: u32) -> Vec<(usize, usize, u32)> {
+    structures().template(piece.template).connectors
+        .iter()
+        .enumerate()
+        .filter(|(_, connector)| connector.pool.is_some())
+        .map(|(connector, _)| (index, connector, depth))
+        .collect()
+}
+
+/// One of the templates, more likely the ones with more weight. None if there are none.
+fn pick(rng: &mut StructureRng, templates: &[usize]) -> Option<usize> {
+    let total: u64 = templates.iter().map(|template| structures().template(*template).weight as u64).sum();
+    if total == 0 {
+        return None
+    }
+
+    let mut roll = rng.below(total);
+    for template in templates {
+        let weight = structures().template(*template).weight as u64;
+        if roll < weight {
+            return Some(*template)
+        }
+        roll -= weight;
+    }
+
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::structure::placement::locate;
+    use crate::world::testing::setup;
+
+    const SEED: u32 = 42;
+
+    /// The closest structure of every kind that has one nearby
+    fn found(map: &Map) -> Vec<Structure> {
+        (0..structures().all().len())
+            .filter_map(|kind| locate(map, kind, ChunkPos::new(0, 0, 0), 8))
+            .collect()
+    }
+
+    #[test]
+    fn pieces_do_not_overlap() {
+        setup();
+        let map = Map::new(SEED);
+
+        let found = found(&map);
+        assert!(found.iter().any(|structure| structure.pieces.len() > 1));
+        for structure in found {
+            let surface = structures().all()[structure.kind].placement == Placement::Surface;
+            let bounds: Vec<_> = structure.pieces.iter()
+                .map(|piece| piece.bounds(structures().template(piece.template)))
+                .collect();
+
+            for (i, (min, max)) in bounds.iter().enumerate() {
+                for (other_min, other_max) in &bounds[i + 1..] {
+                    let apart = |axis: usize| max[axis] < other_min[axis] || min[axis] > other_max[axis];
+                    assert!(apart(0) || apart(2) || (!surface && apart(1)), "Pieces {} of {:?} overlap", i, structure.start);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn assembling_stops_at_the_depth_limit() {
+        setup();
+        let map = Map::new(SEED);
+
+        for structure in found(&map) {
+            let mut definition = structures().all()[structure.kind].clone();
+            let mut pieces: Vec<PlacedPiece> = vec![];
+
+            for max_depth in 0..=definition.max_depth {
+                definition.max_depth = max_depth;
+                let limited = Structure::build(&map, structure.kind, &definition, structure.start).unwrap();
+
+                if max_depth == 0 {
+                    assert_eq!(limited.pieces.len(), 1);
+                }
+                // Pieces are attached breadth first, so a deeper limit only adds pieces
+                assert!(limited.pieces.starts_with(&pieces));
+                pieces = limited.pieces;
+            }
+
+            assert_eq!(pieces, structure.pieces);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod template;
+pub mod registry;
+pub mod jigsaw;
+pub mod placement;
\ No newline at end of file
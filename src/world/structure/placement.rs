@@ -0,0 +1,216 @@
+use crate::world::{
+    chunk::{chunk::Chunk, pos::ChunkPos},
+    constants::CHUNKSIZE,
+    map::Map,
+    terrain::Column,
+    structure::{
+        jigsaw::Structure,
+        registry::{structures, Placement, StructureDefinition},
+    },
+};
+
+/// How far around the start the ground is looked at for max_slope, in blocks
+const SLOPE_RADIUS: i64 = 8;
+
+/// Random numbers that only depend on what they are made from, so every
+/// chunk and every computer gets the same structures for a seed (SplitMix64)
+pub struct StructureRng {
+    state: u64,
+}
+
+impl StructureRng {
+    pub fn new(parts: &[u64]) -> Self {
+        let mut rng = Self { state: 0x9E37_79B9_7F4A_7C15 };
+        for part in parts {
+            rng.state ^= *part;
+            rng.next_u64();
+        }
+
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// From 0 up to but not including `n`, which can't be 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// The chunk a structure could start in, for the square of `spacing` chunks
+/// it is in. None if the ground there is too steep for it or a surface
+/// structure would start in water.
+pub fn start_in_region(map: &Map, structure: &StructureDefinition, region: [i32; 2]) -> Option<ChunkPos> {
+    let mut rng = StructureRng::new(&[map.seed as u64, structure.salt, region[0] as u64, region[1] as u64]);
+    let range = (structure.spacing - structure.separation) as u64;
+    let x = region[0] * structure.spacing + rng.below(range) as i32;
+    let z = region[1] * structure.spacing + rng.below(range) as i32;
+
+    if structure.placement == Placement::Surface {
+        let center = [x as i64 * CHUNKSIZE as i64 + CHUNKSIZE as i64 / 2, z as i64 * CHUNKSIZE as i64 + CHUNKSIZE as i64 / 2];
+        let columns: Vec<Column> = [-SLOPE_RADIUS, 0, SLOPE_RADIUS].iter()
+            .flat_map(|dx| [-SLOPE_RADIUS, 0, SLOPE_RADIUS].iter().map(move |dz| (*dx, *dz)))
+            .map(|(dx, dz)| map.column_at(center[0] + dx, center[1] + dz))
+            .collect();
+
+        if columns.iter().any(|column| column.water > 0) {
+            return None
+        }
+        if let Some(max_slope) = structure.max_slope {
+            let highest = columns.iter().map(|column| column.height).max().unwrap();
+            let lowest = columns.iter().map(|column| column.height).min().unwrap();
+            if (highest - lowest) as i64 > max_slope {
+                return None
+            }
+        }
+    }
+
+    Some(ChunkPos::new(x, 0, z))
+}
+
+/// The square of `spacing` chunks a chunk is in
+fn region_of(structure: &StructureDefinition, x: i32, z: i32) -> [i32; 2] {
+    [x.div_euclid(structure.spacing), z.div_euclid(structure.spacing)]
+}
+
+/// Every structure that reaches into a chunk, assembled
+pub fn structures_near(map: &Map, pos: ChunkPos) -> Vec<Structure> {
+    let mut found = vec![];
+
+    for (kind, structure) in structures().all().iter().enumerate() {
+        let reach = structure.reach();
+        let low = region_of(structure, pos.x - reach, pos.z - reach);
+        let high = region_of(structure, pos.x + reach, pos.z + reach);
+
+        for rx in low[0]..=high[0] {
+            for rz in low[1]..=high[1] {
+                let start = match start_in_region(map, structure, [rx, rz]) {
+                    Some(start) => start,
+                    None => continue,
+                };
+                if (start.x - pos.x).abs() > reach || (start.z - pos.z).abs() > reach {
+                    continue
+                }
+                if let Some(assembled) = Structure::assemble(map, kind, start) {
+                    found.push(assembled);
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Puts the parts of every structure that reach into a freshly generated chunk in it
+pub fn place_structures(map: &Map, chunk: &mut Chunk) {
+    for structure in structures_near(map, chunk.pos) {
+        structure.place(chunk);
+    }
+}
+
+/// The structure of a kind that starts closest to a chunk, looking at most
+/// `max_regions` squares of spacing chunks away. Only the ground height is
+/// needed, no chunk is generated.
+pub fn locate(map: &Map, kind: usize, from: ChunkPos, max_regions: i32) -> Option<Structure> {
+    let structure = &structures().all()[kind];
+    let center = region_of(structure, from.x, from.z);
+    let distance = |start: ChunkPos| ((start.x - from.x) as i64).pow(2) + ((start.z - from.z) as i64).pow(2);
+
+    let mut closest: Option<Structure> = None;
+    for ring in 0..=max_regions {
+        for rx in center[0] - ring..=center[0] + ring {
+            for rz in center[1] - ring..=center[1] + ring {
+                if (rx - center[0]).abs() != ring && (rz - center[1]).abs() != ring {
+                    continue
+                }
+
+                let start = match start_in_region(map, structure, [rx, rz]) {
+                    Some(start) => start,
+                    None => continue,
+                };
+                let closer = closest.as_ref().map_or(true, |closest| distance(start) < distance(closest.start));
+                if closer {
+                    if let Some(assembled) = Structure::assemble(map, kind, start) {
+                        closest = Some(assembled);
+                    }
+                }
+            }
+        }
+
+        // A start in the next ring can still be closer than one in this ring,
+        // but not one in the ring after it
+        if let Some(found) = &closest {
+            let ring_distance = (ring as i64 * structure.spacing as i64).pow(2);
+            if distance(found.start) <= ring_distance {
+                break
+            }
+        }
+    }
+
+    closest
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::testing::setup;
+
+    const SEED: u32 = 42;
+
+    #[test]
+    fn starts_depend_on_the_seed_only() {
+        setup();
+        let (map, again) = (Map::new(SEED), Map::new(SEED));
+
+        for structure in structures().all() {
+            for region in [[0, 0], [-3, 7], [12, -40]] {
+                assert_eq!(start_in_region(&map, structure, region), start_in_region(&again, structure, region));
+            }
+        }
+    }
+
+    #[test]
+    fn starts_keep_their_distance() {
+        setup();
+        let map = Map::new(SEED);
+
+        for structure in structures().all() {
+            for rx in -4..4 {
+                for rz in -4..4 {
+                    let start = match start_in_region(&map, structure, [rx, rz]) {
+                        Some(start) => start,
+                        None => continue,
+                    };
+                    assert_eq!(region_of(structure, start.x, start.z), [rx, rz]);
+
+                    for neighbor in [[rx + 1, rz], [rx, rz + 1]] {
+                        if let Some(other) = start_in_region(&map, structure, neighbor) {
+                            let apart = (other.x - start.x).abs().max((other.z - start.z).abs());
+                            assert!(apart > structure.separation, "{} starts {} chunks apart", structure.name, apart);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn locate_is_the_same_every_time() {
+        setup();
+        let (map, again) = (Map::new(SEED), Map::new(SEED));
+
+        for kind in 0..structures().all().len() {
+            let from = ChunkPos::new(5, 0, -9);
+            let first = locate(&map, kind, from, 8).map(|structure| (structure.start, structure.pieces));
+            let second = locate(&again, kind, from, 8).map(|structure| (structure.start, structure.pieces));
+            assert_eq!(first, second);
+        }
+    }
+}
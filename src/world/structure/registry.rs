@@ -0,0 +1,384 @@
+use anyhow::{Context, Result, anyhow, bail};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::resources::resourcemanager::ResourceManager;
+use crate::world::block::blocks::BlockID;
+use crate::world::block::registry::registry;
+use crate::world::constants::{CHUNKSIZE, WORLDHEIGHT};
+use crate::world::edit::schematic;
+use crate::world::structure::template::{Connector, Facing, Template};
+
+/// Where the structures and their pieces are found in a resource pack
+pub const STRUCTURES_ASSET: &str = "structures.toml";
+
+/// Pieces can't be larger than this on any side
+const MAX_PIECE_SIZE: i64 = 48;
+
+static STRUCTURES: OnceCell<StructureRegistry> = OnceCell::new();
+
+/// The structures that were installed, or the built in ones if none were
+pub fn structures() -> &'static StructureRegistry {
+    STRUCTURES.get_or_init(StructureRegistry::builtin)
+}
+
+#[derive(Deserialize)]
+struct StructureFile {
+    #[serde(default)]
+    structure: Vec<StructureDefinition>,
+    #[serde(default)]
+    piece: Vec<PieceDefinition>,
+}
+
+/// How a structure is fitted to the terrain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    /// Every piece stands on the ground where it is
+    Surface,
+    /// All pieces are at the height their connectors give them, the start
+    /// piece `depth` blocks below the ground
+    Underground,
+}
+
+/// A structure as it is written in structures.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct StructureDefinition {
+    pub name: String,
+    /// The pool the first piece comes from
+    pub start_pool: String,
+    pub placement: Placement,
+    #[serde(default)]
+    pub depth: i64,
+    /// The world is divided in squares of this many chunks, each has at most one start
+    pub spacing: i32,
+    /// Starts of neighboring squares are at least this many chunks apart
+    pub separation: i32,
+    /// Makes the starts of structures with the same spacing differ
+    pub salt: u64,
+    /// How many pieces away from the start pieces can be
+    pub max_depth: u32,
+    /// How far from the start, in blocks, pieces can be
+    pub max_distance: i64,
+    /// The largest difference in ground height around the start, so
+    /// surface structures don't start on a cliff
+    pub max_slope: Option<i64>,
+}
+
+impl StructureDefinition {
+    /// How many chunks from its start chunk a structure can reach
+    pub fn reach(&self) -> i32 {
+        (self.max_distance / CHUNKSIZE as i64) as i32 + 1
+    }
+}
+
+/// A piece as it is written in structures.toml
+#[derive(Deserialize)]
+struct PieceDefinition {
+    name: String,
+    pools: Vec<String>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    #[serde(default)]
+    sink: i64,
+    foundation: Option<String>,
+    /// A character for every block name
+    #[serde(default)]
+    palette: HashMap<String, String>,
+    /// From the bottom up. Rows go south, characters east. A space keeps the
+    /// block that is there and `.` is air.
+    #[serde(default)]
+    layers: Vec<Vec<String>>,
+    /// A schematic in the resource packs to take the blocks from instead of
+    /// the layers, see edit::schematic. Its air is placed as air.
+    schematic: Option<String>,
+    #[serde(default)]
+    connector: Vec<ConnectorDefinition>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+struct ConnectorDefinition {
+    at: [i64; 3],
+    facing: Facing,
+    pool: Option<String>,
+}
+
+impl PieceDefinition {
+    fn into_template(self, resources: Option<&ResourceManager>) -> Result<Template> {
+        let (size, blocks) = match (&self.schematic, resources) {
+            (Some(_), _) if !self.layers.is_empty() => bail!("A piece has either layers or a schematic"),
+            (Some(path), Some(resources)) => {
+                let clipboard = schematic::decode(&resources.read(path)?)
+                    .with_context(|| format!("{} is not a valid schematic", path))?
+                    .clipboard;
+                (clipboard.size(), clipboard.block_ids().iter().map(|block| Some(*block)).collect())
+            }
+            (Some(_), None) => bail!("Only structures in a resource pack can use schematics"),
+            (None, _) => self.layer_blocks()?,
+        };
+
+        let [sx, sy, sz] = size;
+        if sx > MAX_PIECE_SIZE || sy > MAX_PIECE_SIZE || sz > MAX_PIECE_SIZE {
+            bail!("The piece is larger than {} blocks", MAX_PIECE_SIZE);
+        }
+
+        let connectors = self.connector
+            .into_iter()
+            .map(|connector| {
+                let [x, y, z] = connector.at;
+                let inside = x >= 0 && y >= 0 && z >= 0 && x < sx && y < sy && z < sz;
+                // A connector looks out of the piece, so it is on the side it faces
+                let on_side = match connector.facing {
+                    Facing::North => z == 0,
+                    Facing::East => x == sx - 1,
+                    Facing::South => z == sz - 1,
+                    Facing::West => x == 0,
+                };
+                if !inside || !on_side {
+                    bail!("The connector at {:?} is not on the {:?} side of the piece", connector.at, connector.facing);
+                }
+
+                Ok(Connector {
+                    at: connector.at,
+                    facing: connector.facing,
+                    pool: connector.pool,
+                })
+            })
+            .collect::<Result<Vec<Connector>>>()?;
+
+        let foundation = match &self.foundation {
+            Some(name) => Some(registry().id(name).ok_or_else(|| anyhow!("Unknown foundation block '{}'", name))?),
+            None => None,
+        };
+
+        Ok(Template {
+            name: self.name,
+            size,
+            blocks,
+            connectors,
+            sink: self.sink,
+            foundation,
+            weight: self.weight.max(1),
+        })
+    }
+
+    /// The size and the blocks of the layers, through the palette
+    fn layer_blocks(&self) -> Result<([i64; 3], Vec<Option<BlockID>>)> {
+        let mut palette = HashMap::new();
+        for (key, name) in &self.palette {
+            let mut chars = key.chars();
+            let c = match (chars.next(), chars.next()) {
+                (Some(c), None) if c != ' ' && c != '.' => c,
+                _ => bail!("Palette key '{}' has to be a single character other than space and .", key),
+            };
+            let block = registry().id(name).ok_or_else(|| anyhow!("Unknown block '{}'", name))?;
+            palette.insert(c, block);
+        }
+        palette.insert('.', registry().id("air").unwrap());
+
+        let sy = self.layers.len() as i64;
+        let sz = self.layers.first().map(|layer| layer.len()).unwrap_or(0) as i64;
+        let sx = self.layers.first().and_then(|layer| layer.first()).map(|row| row.chars().count()).unwrap_or(0) as i64;
+        if sx == 0 || sy == 0 || sz == 0 {
+            bail!("The piece is empty");
+        }
+
+        let mut blocks = Vec::with_capacity((sx * sy * sz) as usize);
+        for (y, layer) in self.layers.iter().enumerate() {
+            if layer.len() as i64 != sz {
+                bail!("Layer {} has {} rows instead of {}", y, layer.len(), sz);
+            }
+            for (z, row) in layer.iter().enumerate() {
+                if row.chars().count() as i64 != sx {
+                    bail!("Row {} of layer {} is not {} blocks long", z, y, sx);
+                }
+                for c in row.chars() {
+                    blocks.push(match c {
+                        ' ' => None,
+                        c => Some(*palette.get(&c).ok_or_else(|| anyhow!("'{}' is not in the palette", c))?),
+                    });
+                }
+            }
+        }
+
+        Ok(([sx, sy, sz], blocks))
+    }
+}
+
+/// All structures and the pieces they are made of
+pub struct StructureRegistry {
+    structures: Vec<StructureDefinition>,
+    templates: Vec<Template>,
+    /// The templates in every pool, by index
+    pools: HashMap<String, Vec<usize>>,
+}
+
+impl StructureRegistry {
+    /// The structures of the base pack, compiled into the game
+    pub fn builtin() -> Self {
+        StructureRegistry::from_toml(include_str!("../../../assets/structures.toml"), None)
+            .expect("Built in structures are invalid")
+    }
+
+    pub fn load(resources: &ResourceManager) -> Result<Self> {
+        StructureRegistry::from_toml(&resources.read_string(STRUCTURES_ASSET)?, Some(resources))
+            .with_context(|| format!("Invalid structures in {}", STRUCTURES_ASSET))
+    }
+
+    /// Block names are looked up in the block registry, so it has to be installed
+    /// first. Schematics of pieces are read from the resources.
+    pub fn from_toml(source: &str, resources: Option<&ResourceManager>) -> Result<Self> {
+        let file: StructureFile = toml::from_str(source)?;
+
+        let mut templates = vec![];
+        let mut pools: HashMap<String, Vec<usize>> = HashMap::new();
+        for piece in file.piece {
+            let name = piece.name.clone();
+            if templates.iter().any(|template: &Template| template.name == name) {
+                bail!("Piece '{}' is defined twice", name);
+            }
+
+            for pool in &piece.pools {
+                pools.entry(pool.clone()).or_default().push(templates.len());
+            }
+            templates.push(piece.into_template(resources).with_context(|| format!("Invalid piece '{}'", name))?);
+        }
+
+        for template in &templates {
+            for pool in template.connectors.iter().filter_map(|connector| connector.pool.as_ref()) {
+                if !pools.contains_key(pool) {
+                    bail!("Piece '{}' connects to pool '{}', which has no pieces", template.name, pool);
+                }
+            }
+        }
+
+        let mut structures: Vec<StructureDefinition> = vec![];
+        for structure in file.structure {
+            if structures.iter().any(|known| known.name == structure.name) {
+                bail!("Structure '{}' is defined twice", structure.name);
+            }
+            if !pools.contains_key(&structure.start_pool) {
+                bail!("Structure '{}' starts from pool '{}', which has no pieces", structure.name, structure.start_pool);
+            }
+            if structure.spacing <= 0 || structure.separation < 0 || structure.separation >= structure.spacing {
+                bail!("Structure '{}' needs a spacing larger than its separation", structure.name);
+            }
+            if structure.depth < 0 || structure.depth >= WORLDHEIGHT as i64 {
+                bail!("Structure '{}' is deeper than the world", structure.name);
+            }
+            structures.push(structure);
+        }
+
+        Ok(Self {
+            structures,
+            templates,
+            pools,
+        })
+    }
+
+    /// Makes this the registry returned by structures(). Can only be done once.
+    pub fn install(self) -> Result<()> {
+        STRUCTURES
+            .set(self)
+            .map_err(|_| anyhow!("A structure registry was already installed"))
+    }
+
+    pub fn all(&self) -> &[StructureDefinition] {
+        &self.structures
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StructureDefinition> {
+        self.structures.iter().find(|structure| structure.name == name)
+    }
+
+    /// Panics if the index does not exist
+    pub fn template(&self, index: usize) -> &Template {
+        &self.templates[index]
+    }
+
+    /// The templates in a pool, by index. Empty for pools that don't exist.
+    pub fn pool(&self, name: &str) -> &[usize] {
+        self.pools.get(name).map(|pool| pool.as_slice()).unwrap_or(&[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use crate::resources::resourcemanager::BASE_PACK;
+    use crate::world::block::blocks::Blocks;
+    use crate::world::edit::clipboard::Clipboard;
+    use crate::world::testing::setup;
+
+    const TOWER: &str = r#"
+        [[piece]]
+        name = "tower"
+        pools = ["towers"]
+        schematic = "schematics/tower.lwschem"
+
+        [[piece.connector]]
+        at = [0, 0, 0]
+        facing = "west"
+    "#;
+
+    /// A base pack with the structures and a schematic
+    fn pack(structures: &str, size: [i64; 3]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        let base = root.path().join(BASE_PACK);
+        fs::create_dir_all(base.join("schematics")).unwrap();
+
+        let blocks = vec![Blocks::STONE as BlockID; size.iter().product::<i64>() as usize];
+        let clipboard = Clipboard::from_blocks([0, 0, 0], size, blocks).unwrap();
+        fs::write(base.join("schematics/tower.lwschem"), schematic::encode(&clipboard)).unwrap();
+        fs::write(base.join(STRUCTURES_ASSET), structures).unwrap();
+
+        root
+    }
+
+    fn resources(root: &tempfile::TempDir) -> ResourceManager {
+        ResourceManager::new(root.path().to_path_buf(), vec![]).unwrap()
+    }
+
+    #[test]
+    fn pieces_can_be_schematics() {
+        setup();
+        let root = pack(TOWER, [3, 5, 2]);
+
+        let registry = StructureRegistry::load(&resources(&root)).unwrap();
+
+        let tower = registry.template(registry.pool("towers")[0]);
+        assert_eq!(tower.size, [3, 5, 2]);
+        assert!(tower.blocks.iter().all(|block| *block == Some(Blocks::STONE as BlockID)));
+    }
+
+    #[test]
+    fn schematics_need_a_resource_pack() {
+        setup();
+        assert!(StructureRegistry::from_toml(TOWER, None).is_err());
+    }
+
+    #[test]
+    fn schematics_can_not_be_too_large() {
+        setup();
+        let root = pack(TOWER, [3, MAX_PIECE_SIZE + 1, 2]);
+
+        assert!(StructureRegistry::load(&resources(&root)).is_err());
+    }
+
+    #[test]
+    fn builtin_structures_are_valid() {
+        setup();
+        let registry = StructureRegistry::builtin();
+
+        for structure in registry.all() {
+            assert!(!registry.pool(&structure.start_pool).is_empty(), "{} has no start", structure.name);
+        }
+    }
+}
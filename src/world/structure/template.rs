@@ -0,0 +1,132 @@
+use crate::world::block::blocks::BlockID;
+
+/// A horizontal direction. Turning clockwise as seen from above goes
+/// north, east, south, west, the same way Clipboard::rotate turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Facing {
+    const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
+    /// Turned clockwise a number of quarter turns
+    pub fn rotated(self, quarter_turns: u8) -> Self {
+        Facing::ALL[(self as usize + quarter_turns as usize) % 4]
+    }
+
+    pub fn opposite(self) -> Self {
+        self.rotated(2)
+    }
+
+    /// One block in this direction, north is -z
+    pub fn offset(self) -> [i64; 3] {
+        match self {
+            Facing::North => [0, 0, -1],
+            Facing::East => [1, 0, 0],
+            Facing::South => [0, 0, 1],
+            Facing::West => [-1, 0, 0],
+        }
+    }
+}
+
+/// Where pieces join. Another piece is attached with one of its connectors
+/// in the block in front of this one, facing back.
+#[derive(Debug, Clone)]
+pub struct Connector {
+    /// From the lowest corner of the piece
+    pub at: [i64; 3],
+    pub facing: Facing,
+    /// The pool that pieces attached here come from. Without one, pieces can
+    /// only be attached to the piece with this connector, not the other way around.
+    pub pool: Option<String>,
+}
+
+/// A piece structures are assembled from
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    /// x, y, z
+    pub size: [i64; 3],
+    /// None keeps the block that is there, x + z * size x + y * size x * size z
+    pub blocks: Vec<Option<BlockID>>,
+    pub connectors: Vec<Connector>,
+    /// How far the piece is sunk into the ground when it is placed on the surface
+    pub sink: i64,
+    /// Fills the gap below the piece down to the ground on uneven terrain
+    pub foundation: Option<BlockID>,
+    /// How likely the piece is picked from its pools compared to the other pieces
+    pub weight: u32,
+}
+
+impl Template {
+    pub fn block(&self, local: [i64; 3]) -> Option<BlockID> {
+        self.blocks[(local[0] + local[2] * self.size[0] + local[1] * self.size[0] * self.size[2]) as usize]
+    }
+}
+
+/// A template placed in the world, turned clockwise a number of quarter turns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacedPiece {
+    /// The index of the template in the registry
+    pub template: usize,
+    pub rotation: u8,
+    /// The lowest corner
+    pub origin: [i64; 3],
+}
+
+impl PlacedPiece {
+    /// Size after turning
+    pub fn size(&self, template: &Template) -> [i64; 3] {
+        let [x, y, z] = template.size;
+        if self.rotation % 2 == 0 { [x, y, z] } else { [z, y, x] }
+    }
+
+    /// The lowest and the highest corner, both inside the piece
+    pub fn bounds(&self, template: &Template) -> ([i64; 3], [i64; 3]) {
+        let size = self.size(template);
+        let max = [self.origin[0] + size[0] - 1, self.origin[1] + size[1] - 1, self.origin[2] + size[2] - 1];
+
+        (self.origin, max)
+    }
+
+    /// Where a position in the template ends up relative to the origin
+    pub fn turn(&self, template: &Template, local: [i64; 3]) -> [i64; 3] {
+        let [mut x, y, mut z] = local;
+        let (mut sx, mut sz) = (template.size[0], template.size[2]);
+
+        for _ in 0..self.rotation % 4 {
+            let turned = [sz - 1 - z, x];
+            x = turned[0];
+            z = turned[1];
+            std::mem::swap(&mut sx, &mut sz);
+        }
+
+        [x, y, z]
+    }
+
+    /// The connectors in world coordinates, facing where they face after turning
+    pub fn connectors<'a>(&'a self, template: &'a Template) -> impl Iterator<Item = (usize, [i64; 3], Facing)> + 'a {
+        template.connectors.iter().enumerate().map(move |(i, connector)| {
+            let turned = self.turn(template, connector.at);
+            let at = [self.origin[0] + turned[0], self.origin[1] + turned[1], self.origin[2] + turned[2]];
+            (i, at, connector.facing.rotated(self.rotation))
+        })
+    }
+
+    /// Every block that is not kept, in world coordinates
+    pub fn blocks<'a>(&'a self, template: &'a Template) -> impl Iterator<Item = ([i64; 3], BlockID)> + 'a {
+        let [sx, sy, sz] = template.size;
+
+        (0..sy).flat_map(move |y| (0..sz).flat_map(move |z| (0..sx).map(move |x| [x, y, z])))
+            .filter_map(move |local| {
+                let block = template.block(local)?;
+                let turned = self.turn(template, local);
+                Some(([self.origin[0] + turned[0], self.origin[1] + turned[1], self.origin[2] + turned[2]], block))
+            })
+    }
+}
@@ -0,0 +1,279 @@
+use crate::helper::{math::smoothstep, noise::NoiseGenerator};
+use crate::world::block::blocks::Blocks;
+use crate::world::constants::{SEA_LEVEL, WORLDHEIGHT};
+
+/// The ground is never higher than this, so structures fit on top of it
+const MAX_HEIGHT: f64 = (WORLDHEIGHT - 10) as f64;
+/// Ground at least this high is bare stone
+const STONE_HEIGHT: u32 = 46;
+/// Continentalness below this is coast, where the land along the sea is beach
+const COAST: f64 = -0.1;
+/// How far from the middle of a river, in river noise, its valley reaches
+const RIVER_VALLEY: f64 = 0.12;
+
+/// A curve through points, straight between them and flat beyond the first and the last
+pub struct Spline {
+    /// Sorted by x
+    points: &'static [(f64, f64)],
+}
+
+impl Spline {
+    pub const fn new(points: &'static [(f64, f64)]) -> Self {
+        Self { points }
+    }
+
+    pub fn at(&self, x: f64) -> f64 {
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        if x <= first.0 {
+            return first.1
+        }
+
+        for pair in self.points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            if x <= x1 {
+                return y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+            }
+        }
+
+        last.1
+    }
+}
+
+/// Continentalness to the height of the ground: ocean floor, coast, then inland
+const CONTINENTS: Spline = Spline::new(&[
+    (-1.0, 6.0),
+    (-0.6, 9.0),
+    (-0.35, 18.0),
+    (-0.22, 23.0),
+    (-0.15, 25.0),
+    (0.1, 28.0),
+    (0.5, 34.0),
+    (1.0, 38.0),
+]);
+
+/// Erosion to how much of the peaks and valleys is left. Eroded land is flat.
+const EROSION: Spline = Spline::new(&[
+    (-1.0, 1.2),
+    (-0.3, 0.9),
+    (0.1, 0.35),
+    (0.5, 0.1),
+    (1.0, 0.05),
+]);
+
+/// Peaks and valleys to how far the ground goes up or down
+const PEAKS: Spline = Spline::new(&[
+    (-1.0, -8.0),
+    (-0.4, -2.0),
+    (0.0, 0.0),
+    (0.4, 8.0),
+    (0.75, 20.0),
+    (1.0, 30.0),
+]);
+
+/// Distance to the middle of a river, as part of RIVER_VALLEY, to the height
+/// of the ground. The river bed is below the sea, its banks just above it.
+const RIVER_BANKS: Spline = Spline::new(&[
+    (0.0, SEA_LEVEL as f64 - 3.0),
+    (0.2, SEA_LEVEL as f64 - 1.0),
+    (0.35, SEA_LEVEL as f64 + 1.0),
+    (1.0, SEA_LEVEL as f64 + 2.0),
+]);
+
+/// What the top of a column is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Surface {
+    Grass,
+    /// Beaches, river banks and shallow water
+    Sand,
+    /// Deep water
+    Gravel,
+    /// High peaks
+    Stone,
+}
+
+impl Surface {
+    /// The top block and the blocks below it
+    pub fn blocks(self) -> (Blocks, Blocks) {
+        match self {
+            Surface::Grass => (Blocks::GRASS, Blocks::DIRT),
+            Surface::Sand => (Blocks::SAND, Blocks::SAND),
+            Surface::Gravel => (Blocks::GRAVEL, Blocks::GRAVEL),
+            Surface::Stone => (Blocks::STONE, Blocks::STONE),
+        }
+    }
+}
+
+/// A column of the world as it is generated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Column {
+    /// The highest block of the ground
+    pub height: u32,
+    pub surface: Surface,
+    /// How many blocks of the surface are below the top block, above the stone
+    pub soil: u32,
+    /// Water fills the column from above the ground up to SEA_LEVEL, 0 on land
+    pub water: u32,
+    /// From 0 far from a river to 1 in the middle of one
+    pub river: f64,
+}
+
+/// Shapes the terrain by combining a few kinds of noise through splines.
+/// Continentalness decides where the land and the sea are, erosion how
+/// flat the land is and peaks and valleys how high its hills are. Rivers
+/// are carved where the river noise is close to zero.
+pub struct Terrain {
+    seed: u32,
+    continents: NoiseGenerator,
+    erosion: NoiseGenerator,
+    weirdness: NoiseGenerator,
+    rivers: NoiseGenerator,
+    detail: NoiseGenerator,
+}
+
+impl Terrain {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            continents: NoiseGenerator::with(seed.wrapping_add(1), 5, 0.002),
+            erosion: NoiseGenerator::with(seed.wrapping_add(2), 4, 0.004),
+            weirdness: NoiseGenerator::with(seed.wrapping_add(3), 4, 0.008),
+            rivers: NoiseGenerator::with(seed.wrapping_add(4), 3, 0.003),
+            detail: NoiseGenerator::new(seed),
+        }
+    }
+
+    /// Only depends on the seed and the position
+    pub fn column(&self, x: i64, z: i64) -> Column {
+        let (fx, fz) = (x as f64, z as f64);
+
+        let continentalness = self.continents.get(fx, fz) * 1.6;
+        let erosion = self.erosion.get(fx, fz) * 1.6;
+        let weirdness = self.weirdness.get(fx, fz) * 1.6;
+        // Folds weirdness so both its highs and lows become peaks, with valleys in between
+        let peaks = 1.0 - (3.0 * weirdness.abs() - 2.0).abs();
+        // No hills in the sea, they grow in over the coast
+        let inland = ((continentalness + 0.25) / 0.3).clamp(0.0, 1.0);
+
+        let mut height = CONTINENTS.at(continentalness)
+            + PEAKS.at(peaks) * EROSION.at(erosion) * inland
+            + self.detail.get(fx, fz) * 3.0;
+
+        // The valley of a river only lowers the ground, so rivers flow into the sea
+        let distance = (self.rivers.get(fx, fz).abs() / RIVER_VALLEY).min(1.0);
+        let river = 1.0 - smoothstep(0.0, 1.0, distance as f32) as f64;
+        let banks = RIVER_BANKS.at(distance);
+        if height > banks {
+            height += (banks - height) * river;
+        }
+
+        let height = height.clamp(1.0, MAX_HEIGHT) as u32;
+        let water = (SEA_LEVEL as u32).saturating_sub(height);
+
+        let surface = if water > 3 {
+            Surface::Gravel
+        } else if water > 0 || (height <= SEA_LEVEL as u32 + 1 && (continentalness < COAST || river > 0.4)) {
+            Surface::Sand
+        } else if height >= STONE_HEIGHT {
+            Surface::Stone
+        } else {
+            Surface::Grass
+        };
+
+        Column {
+            height,
+            surface,
+            soil: 1 + self.hash(x, z) % 3,
+            water,
+            river,
+        }
+    }
+
+    /// A number that looks random but is the same for a position every time
+    fn hash(&self, x: i64, z: i64) -> u32 {
+        let mut h = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ self.seed as u64;
+        h ^= h >> 29;
+        h = h.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+
+        (h >> 32) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::blocks::BlockID;
+    use crate::world::chunk::{chunk::Chunk, pos::{ChunkCoord, ChunkPos}};
+    use crate::world::constants::CHUNKSIZE;
+
+    const SEED: u32 = 42;
+    /// Columns from -AREA to AREA are looked at
+    const AREA: i64 = 512;
+
+    fn columns(terrain: &Terrain) -> impl Iterator<Item = (i64, i64, Column)> + '_ {
+        (-AREA..AREA).step_by(3).flat_map(move |x| (-AREA..AREA).step_by(3).map(move |z| (x, z, terrain.column(x, z))))
+    }
+
+    #[test]
+    fn columns_depend_on_the_seed_only() {
+        let (terrain, again, other) = (Terrain::new(SEED), Terrain::new(SEED), Terrain::new(SEED + 1));
+
+        assert!(columns(&terrain).zip(columns(&again)).all(|(a, b)| a == b));
+        assert!(columns(&terrain).zip(columns(&other)).any(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn splines_are_monotonic_and_flat_beyond_their_ends() {
+        for spline in [&CONTINENTS, &EROSION, &PEAKS, &RIVER_BANKS] {
+            let (first, last) = (spline.points[0], spline.points[spline.points.len() - 1]);
+            let rising = last.1 > first.1;
+
+            let samples: Vec<f64> = (0..=400).map(|i| spline.at(first.0 + (last.0 - first.0) * i as f64 / 400.0)).collect();
+            assert!(samples.windows(2).all(|pair| if rising { pair[0] <= pair[1] } else { pair[0] >= pair[1] }));
+
+            assert_eq!(spline.at(first.0 - 10.0), first.1);
+            assert_eq!(spline.at(last.0 + 10.0), last.1);
+        }
+    }
+
+    #[test]
+    fn oceans_are_water_up_to_sea_level() {
+        let terrain = Terrain::new(SEED);
+        let (x, z, column) = columns(&terrain).find(|(_, _, column)| column.water > 3).unwrap();
+        assert_eq!(column.height + column.water, SEA_LEVEL as u32);
+
+        let pos = ChunkPos::new(x.div_euclid(CHUNKSIZE as i64) as i32, 0, z.div_euclid(CHUNKSIZE as i64) as i32);
+        let columns: Vec<Column> = (0..CHUNKSIZE as i64)
+            .flat_map(|local_z| (0..CHUNKSIZE as i64).map(move |local_x| (local_x, local_z)))
+            .map(|(local_x, local_z)| terrain.column(pos.x as i64 * CHUNKSIZE as i64 + local_x, pos.z as i64 * CHUNKSIZE as i64 + local_z))
+            .collect();
+        let mut chunk = Chunk::new(pos);
+        chunk.generate(&columns);
+
+        let at = |y: u32| chunk.at_coord(ChunkCoord {
+            x: x.rem_euclid(CHUNKSIZE as i64) as i16,
+            y: y as i16,
+            z: z.rem_euclid(CHUNKSIZE as i64) as i16,
+        });
+        assert_eq!(at(column.height), Blocks::GRAVEL as BlockID);
+        assert!((column.height + 1..=SEA_LEVEL as u32).all(|y| at(y) == Blocks::WATER as BlockID));
+        assert_eq!(at(SEA_LEVEL as u32 + 1), Blocks::AIR as BlockID);
+    }
+
+    #[test]
+    fn coasts_are_sandy() {
+        let terrain = Terrain::new(SEED);
+        let wet = |x: i64, z: i64| terrain.column(x, z).water > 0;
+
+        // Land next to water
+        let coast: Vec<Column> = columns(&terrain)
+            .filter(|(x, z, column)| column.water == 0 && (wet(x + 1, *z) || wet(x - 1, *z) || wet(*x, z + 1) || wet(*x, z - 1)))
+            .map(|(_, _, column)| column)
+            .collect();
+        let sand = coast.iter().filter(|column| column.surface == Surface::Sand).count();
+
+        assert!(!coast.is_empty());
+        assert!(sand * 2 > coast.len(), "{} of {} coast columns are sand", sand, coast.len());
+    }
+}
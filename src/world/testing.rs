@@ -0,0 +1,15 @@
+use std::sync::Once;
+
+use crate::resources::resourcemanager::ResourceManager;
+use crate::world::{block::registry::BlockRegistry, structure::registry::StructureRegistry};
+
+static SETUP: Once = Once::new();
+
+/// Installs the blocks and structures of the assets directory, once for all tests
+pub fn setup() {
+    SETUP.call_once(|| {
+        let resources = ResourceManager::from_env().unwrap();
+        BlockRegistry::load(&resources).unwrap().install().unwrap();
+        StructureRegistry::load(&resources).unwrap().install().unwrap();
+    });
+}
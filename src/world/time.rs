@@ -0,0 +1,74 @@
+use std::f32::consts::PI;
+
+use crate::helper::math::smoothstep;
+
+/// Length of a full day in ticks, 20 minutes at 20 ticks per second
+pub const DAY_LENGTH: u64 = 24000;
+
+/// The time in the world. Advances one tick every world tick and
+/// is saved with the world. A day starts at sunrise, noon is at 6000
+/// ticks, sunset at 12000 and midnight at 18000.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WorldTime {
+    pub ticks: u64,
+}
+
+impl WorldTime {
+    pub fn new(ticks: u64) -> Self {
+        Self {
+            ticks,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    /// The number of days that have passed
+    pub fn day(&self) -> u64 {
+        self.ticks / DAY_LENGTH
+    }
+
+    /// Ticks since the start of the current day
+    pub fn time_of_day(&self) -> u64 {
+        self.ticks % DAY_LENGTH
+    }
+
+    /// Jumps to the given time of the current day. Never goes back in
+    /// time, if that time already passed today it moves to tomorrow.
+    pub fn set_time_of_day(&mut self, time: u64) {
+        let time = time % DAY_LENGTH;
+        let mut day = self.day();
+
+        if time < self.time_of_day() {
+            day += 1;
+        }
+
+        self.ticks = day * DAY_LENGTH + time;
+    }
+
+    /// Angle of the sun in radians, 0 at sunrise and PI at sunset.
+    /// `partial_tick` smooths the movement between ticks.
+    pub fn sun_angle(&self, partial_tick: f32) -> f32 {
+        (self.time_of_day() as f32 + partial_tick) / DAY_LENGTH as f32 * 2.0 * PI
+    }
+
+    /// Direction towards the sun. It rises in the east (+x) and sets in the west,
+    /// slightly tilted so it doesn't pass straight overhead.
+    pub fn sun_direction(&self, partial_tick: f32) -> [f32; 3] {
+        let angle = self.sun_angle(partial_tick);
+        let (sin, cos) = angle.sin_cos();
+
+        let direction = [cos, sin * 0.95, sin * 0.3];
+        let length = (direction[0] * direction[0] + direction[1] * direction[1] + direction[2] * direction[2]).sqrt();
+
+        [direction[0] / length, direction[1] / length, direction[2] / length]
+    }
+
+    /// How much sky light reaches the world, from 0.15 at night to 1 during the day
+    pub fn sky_light(&self, partial_tick: f32) -> f32 {
+        let height = self.sun_angle(partial_tick).sin();
+
+        0.15 + 0.85 * smoothstep(-0.2, 0.25, height)
+    }
+}
@@ -0,0 +1,159 @@
+use image::{Rgba, RgbaImage};
+
+use crate::world::{
+    block::{blocks::{BlockID, Blocks}, colors::BlockColors},
+    chunk::{chunk::Chunk, chunkstore::ChunkStore, pos::{ChunkCoord, ChunkPos, WorldCoord}},
+    constants::WORLDHEIGHT,
+    map::Map,
+};
+
+/// Pixels of chunks that are not loaded
+const UNLOADED: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+/// The highest block of a column that is not air
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Surface {
+    pub block: BlockID,
+    pub height: i64,
+}
+
+impl Surface {
+    /// None if the column is only air
+    pub fn of_column(chunk: &Chunk, x: i16, z: i16) -> Option<Self> {
+        (0..WORLDHEIGHT as i16)
+            .rev()
+            .map(|y| (y, chunk.at_coord(ChunkCoord { x, y, z })))
+            .find(|(_, block)| *block != Blocks::AIR as BlockID)
+            .map(|(y, block)| Self { block, height: y as i64 })
+    }
+
+    /// None if the chunk is not loaded or the column is only air
+    pub fn at(chunks: &ChunkStore, x: i64, z: i64) -> Option<Self> {
+        let at = WorldCoord { x, y: 0, z };
+        let local = at.to_chunk_local();
+
+        chunks.get(at.to_chunk_coord()).and_then(|chunk| Surface::of_column(chunk, local.x, local.z))
+    }
+
+    /// The color of the block, brighter the higher it is. Slopes that face
+    /// north are lit and those that face south are in shadow, like a hill
+    /// lit from the north, so the shape of the land can be seen.
+    pub fn color(&self, colors: &BlockColors, north: Option<Surface>) -> [u8; 3] {
+        let height = self.height as f32 / (WORLDHEIGHT - 1) as f32;
+        let slope = match north {
+            Some(north) => (self.height - north.height).clamp(-3, 3) as f32,
+            None => 0.0,
+        };
+        let light = 0.65 + 0.35 * height + 0.08 * slope;
+
+        let [r, g, b] = colors.get(self.block);
+        let shade = |channel: u8| (channel as f32 * light).round().clamp(0.0, 255.0) as u8;
+        [shade(r), shade(g), shade(b)]
+    }
+}
+
+/// A map of the loaded chunks seen from above, one pixel per block. The top
+/// left pixel is `corner`, x goes to the right and z down, so north is up.
+/// Chunks that are not loaded are transparent.
+pub fn render_map(chunks: &ChunkStore, colors: &BlockColors, corner: [i64; 2], size: [u32; 2]) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(size[0], size[1], UNLOADED);
+
+    // Every column is looked up once, each row needs the row north of it
+    let mut north: Vec<Option<Surface>> = (0..size[0] as i64)
+        .map(|x| Surface::at(chunks, corner[0] + x, corner[1] - 1))
+        .collect();
+
+    for z in 0..size[1] {
+        for x in 0..size[0] {
+            let surface = Surface::at(chunks, corner[0] + x as i64, corner[1] + z as i64);
+
+            if let Some(surface) = surface {
+                let [r, g, b] = surface.color(colors, north[x as usize]);
+                image.put_pixel(x, z, Rgba([r, g, b, 255]));
+            }
+            north[x as usize] = surface;
+        }
+    }
+
+    image
+}
+
+/// Generates the world of a seed around `center` on the CPU and renders its
+/// map, `radius` blocks on every side
+pub fn render_seed(seed: u32, colors: &BlockColors, center: [i64; 2], radius: u32) -> RgbaImage {
+    let map = Map::new(seed);
+    let mut chunks = ChunkStore::new();
+    let radius = radius as i64;
+    let corner = [center[0] - radius, center[1] - radius];
+
+    // One more row to the north for the shading of the first row
+    let first = WorldCoord { x: corner[0], y: 0, z: corner[1] - 1 }.to_chunk_coord();
+    let last = WorldCoord { x: center[0] + radius - 1, y: 0, z: center[1] + radius - 1 }.to_chunk_coord();
+
+    for x in first.x..=last.x {
+        for z in first.z..=last.z {
+            chunks.insert(map.generate_chunk(ChunkPos::new(x, 0, z)));
+        }
+    }
+
+    let size = (radius * 2) as u32;
+    render_map(&chunks, colors, corner, [size, size])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::testing::setup;
+
+    const SEED: u32 = 42;
+
+    #[test]
+    fn maps_depend_on_the_seed_only() {
+        setup();
+        let colors = BlockColors::builtin();
+
+        let map = render_seed(SEED, &colors, [100, -40], 24);
+        assert_eq!(map, render_seed(SEED, &colors, [100, -40], 24));
+        assert_ne!(map, render_seed(SEED + 1, &colors, [100, -40], 24));
+    }
+
+    #[test]
+    fn maps_have_the_size_of_their_radius() {
+        setup();
+        let colors = BlockColors::builtin();
+
+        for (center, radius) in [([0, 0], 1), ([-35, 70], 20), ([5, 5], 33)] {
+            let map = render_seed(SEED, &colors, center, radius);
+
+            assert_eq!(map.dimensions(), (radius * 2, radius * 2));
+            // Every chunk it shows was generated
+            assert!(map.pixels().all(|pixel| *pixel != UNLOADED));
+        }
+    }
+
+    #[test]
+    fn higher_ground_is_brighter() {
+        setup();
+        let colors = BlockColors::builtin();
+        let low = Surface { block: Blocks::GRASS as BlockID, height: 10 };
+        let high = Surface { block: Blocks::GRASS as BlockID, height: 60 };
+
+        let brightness = |color: [u8; 3]| color.iter().map(|channel| *channel as u32).sum::<u32>();
+        assert!(brightness(high.color(&colors, None)) > brightness(low.color(&colors, None)));
+
+        // Lit when it rises to the south, in shadow when it falls
+        let flat = low.color(&colors, Some(low));
+        assert!(brightness(low.color(&colors, Some(Surface { height: 8, ..low }))) > brightness(flat));
+        assert!(brightness(low.color(&colors, Some(Surface { height: 12, ..low }))) < brightness(flat));
+    }
+
+    #[test]
+    fn surfaces_are_the_highest_block() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0, 0));
+        assert_eq!(Surface::of_column(&chunk, 3, 4), None);
+
+        chunk.place_block(ChunkCoord { x: 3, y: 5, z: 4 }, Blocks::STONE as BlockID);
+        chunk.place_block(ChunkCoord { x: 3, y: 9, z: 4 }, Blocks::DIRT as BlockID);
+        assert_eq!(Surface::of_column(&chunk, 3, 4), Some(Surface { block: Blocks::DIRT as BlockID, height: 9 }));
+    }
+}
@@ -1,49 +1,274 @@
+use anyhow::Result;
+use cgmath::Point3;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
 use crate::world::{
     map::Map,
+    time::WorldTime,
+    level::LevelData,
+    entity::entitymanager::EntityManager,
+    edit::{history::BlockChange, session::EditSession},
     chunk::{
-        chunkmanager::ChunkManager,
+        chunk::Chunk,
+        chunkstore::ChunkStore,
+        codec::ChunkData,
+        storage,
         pos::*,
     },
     block::blocks::*,
+    constants::{CHUNKSIZE, WORLDHEIGHT},
 };
 
 use crate::game::{
     player::player::Player,
 };
 
+/// Items this close to the player are picked up, in blocks
+const PICKUP_RADIUS: f32 = 2.0;
+/// How many chunks from the middle of the world the spawn point is looked for on land
+const SPAWN_SEARCH_RADIUS: i64 = 16;
+
+/// Everything that is simulated: the chunks, the time, the players and the
+/// entities. The server owns the world, clients only get to see parts of it.
 pub struct World {
-    pub chunk_manager: ChunkManager,
+    pub chunks: ChunkStore,
     pub seed: u32,
-    pub player: Player,
+    /// The players that are in the world, by name
+    pub players: HashMap<String, Player>,
+    pub entities: EntityManager,
+    /// What every player is building with, see the edit commands
+    pub edits: HashMap<String, EditSession>,
+    /// Loaded chunks that differ from how they are generated, these are saved
+    edited: HashSet<ChunkPos>,
 
     pub map: Map,
+    pub time: WorldTime,
+
+    /// Where the world is saved, None for worlds that are never saved
+    pub directory: Option<PathBuf>,
 }
 
 impl World {
     pub fn new(seed: u32) -> Self {
-        let chunk_manager = ChunkManager::new(4);
         let map = Map::new(seed);
 
-        let player = Player::null_player();
-
         Self {
-            chunk_manager,
+            chunks: ChunkStore::new(),
             seed,
             map,
-            player,
+            players: HashMap::new(),
+            entities: EntityManager::new(),
+            edits: HashMap::new(),
+            edited: HashSet::new(),
+            time: WorldTime::new(0),
+
+            directory: None,
         }
     }
 
-    pub fn place_block(&mut self, at: WorldCoord, block: BlockID) {
-        match self.chunk_manager.get_chunk_mut_option(at.to_chunk_coord()) {
-            None => {println!("Could not place block because chunk was not loaded.");},
-            Some(chunk) => {
-                chunk.place_block(at.to_chunk_local(), block)
+    /// Loads the world saved in directory, or creates a new one with the given seed
+    pub fn open(directory: PathBuf, seed: u32) -> Result<Self> {
+        let level = LevelData::load(&directory)?;
+
+        let mut world = match &level {
+            Some(level) => World::new(level.seed),
+            None => World::new(seed),
+        };
+
+        if let Some(level) = level {
+            world.time = WorldTime::new(level.time);
+        }
+
+        world.directory = Some(directory);
+
+        Ok(world)
+    }
+
+    /// Writes the level data, the players and the loaded chunks to the world directory
+    pub fn save(&self) -> Result<()> {
+        if let Some(directory) = &self.directory {
+            let level = LevelData {
+                seed: self.seed,
+                time: self.time.ticks,
+            };
+
+            level.save(directory)?;
+            for (name, player) in &self.players {
+                player.save(directory, name)?;
+            }
+            for pos in self.chunks.positions() {
+                self.save_chunk(pos)?;
             }
         }
+
+        Ok(())
     }
 
-    pub fn remove_block(&mut self, at: WorldCoord) {
-        self.place_block(at, Blocks::AIR as BlockID);
+    /// Where new players start, just above the ground in the middle of the
+    /// first chunk, or of the closest chunk that is on land
+    pub fn spawn_point(&self) -> Point3<f32> {
+        let middle = CHUNKSIZE as i64 / 2;
+        let (x, z) = (0..=SPAWN_SEARCH_RADIUS)
+            .flat_map(|ring| {
+                (-ring..=ring).flat_map(move |x| (-ring..=ring).map(move |z| (x, z)))
+                    .filter(move |(x, z)| x.abs() == ring || z.abs() == ring)
+            })
+            .map(|(x, z)| (x * CHUNKSIZE as i64 + middle, z * CHUNKSIZE as i64 + middle))
+            .find(|(x, z)| self.map.column_at(*x, *z).water == 0)
+            .unwrap_or((middle, middle));
+        let ground = self.map.height_at(x, z) as f32;
+
+        Point3::new(x as f32 + 0.5, ground + 2.0, z as f32 + 0.5)
+    }
+
+    /// Puts a player in the world where it was left, or at the spawn point if
+    /// it never joined before
+    pub fn join(&mut self, name: &str) -> Result<&mut Player> {
+        let player = match &self.directory {
+            Some(directory) => Player::load(directory, name)?,
+            None => None,
+        };
+        let player = player.unwrap_or_else(|| Player::new(self.spawn_point()));
+
+        Ok(self.players.entry(name.to_string()).or_insert(player))
+    }
+
+    /// Saves a player and takes it out of the world
+    pub fn leave(&mut self, name: &str) -> Result<()> {
+        self.edits.remove(name);
+        if let (Some(player), Some(directory)) = (self.players.remove(name), &self.directory) {
+            player.save(directory, name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Advances the time and the entities by one tick
+    pub fn tick(&mut self) {
+        self.time.tick();
+
+        self.entities.tick(&self.chunks);
+        for player in self.players.values_mut() {
+            self.entities.spawn_wanderers(&self.chunks, player.position);
+            self.entities.collect_items(player.position, PICKUP_RADIUS, |stack| player.pick_up(stack));
+        }
+    }
+
+    /// Sets the time of day in ticks, see WorldTime
+    pub fn set_time(&mut self, time: u64) {
+        self.time.set_time_of_day(time);
+    }
+
+    /// Loads a chunk with its entities, or generates it if it was never saved
+    pub fn load_chunk(&mut self, pos: ChunkPos) -> &Chunk {
+        let saved = match &self.directory {
+            Some(directory) => storage::load(directory, pos).unwrap_or_else(|e| {
+                println!("Could not load chunk {} {}, it is generated again: {:?}", pos.x, pos.z, e);
+                None
+            }),
+            None => None,
+        };
+
+        let (chunk, entities) = match saved {
+            Some(data) => {
+                self.edited.insert(pos);
+                (data.chunk, data.entities)
+            }
+            None => {
+                let chunk = self.map.generate_chunk(pos);
+
+                let entities = match &self.directory {
+                    Some(directory) => storage::load_legacy_entities(directory, pos).unwrap_or_else(|e| {
+                        println!("Could not load the entities of chunk {} {}: {:?}", pos.x, pos.z, e);
+                        vec![]
+                    }),
+                    None => vec![],
+                };
+                (chunk, entities)
+            }
+        };
+
+        self.chunks.insert(chunk);
+        self.entities.load_chunk(pos, entities);
+
+        self.chunks.get(pos).unwrap()
+    }
+
+    /// Generates a loaded chunk again, undoing every change to its blocks. Its entities stay.
+    /// Returns false if the chunk is not loaded.
+    pub fn regenerate_chunk(&mut self, pos: ChunkPos) -> bool {
+        if self.chunks.get(pos).is_none() {
+            return false
+        }
+
+        self.chunks.insert(self.map.generate_chunk(pos));
+        self.edited.remove(&pos);
+
+        true
+    }
+
+    /// Saves a chunk and forgets it
+    pub fn unload_chunk(&mut self, pos: ChunkPos) {
+        if let Err(e) = self.save_chunk(pos) {
+            println!("Could not save chunk {} {}: {:?}", pos.x, pos.z, e);
+        }
+
+        self.chunks.remove(&pos);
+        self.entities.unload_chunk(pos);
+        self.edited.remove(&pos);
+    }
+
+    /// Chunks that were not changed and have no entities don't need a file
+    fn save_chunk(&self, pos: ChunkPos) -> Result<()> {
+        let (directory, chunk) = match (&self.directory, self.chunks.get(pos)) {
+            (Some(directory), Some(chunk)) => (directory, chunk),
+            _ => return Ok(()),
+        };
+
+        let entities = self.entities.in_chunk(pos);
+        if !self.edited.contains(&pos) && entities.is_empty() {
+            return storage::remove(directory, pos)
+        }
+
+        let mut data = ChunkData::new(chunk.clone());
+        data.entities = entities.to_vec();
+        storage::save(directory, &data)
+    }
+
+    pub fn get_block(&self, at: WorldCoord) -> Option<BlockID> {
+        self.chunks.get_block_at_coord(at)
+    }
+
+    /// Returns false if the chunk is not loaded
+    pub fn set_block(&mut self, at: WorldCoord, block: BlockID) -> bool {
+        let changed = self.chunks.set_block(at, block);
+        if changed {
+            self.edited.insert(at.to_chunk_coord());
+        }
+
+        changed
+    }
+
+    /// Changes many blocks at once, in order. Blocks outside the world or in
+    /// chunks that are not loaded are skipped. Returns the blocks that changed.
+    pub fn set_blocks(&mut self, blocks: impl IntoIterator<Item = (WorldCoord, BlockID)>) -> Vec<BlockChange> {
+        let mut changes = vec![];
+
+        for (at, new) in blocks {
+            if at.y < 0 || at.y >= WORLDHEIGHT as i64 {
+                continue
+            }
+
+            match self.get_block(at) {
+                Some(old) if old != new => {
+                    self.set_block(at, new);
+                    changes.push(BlockChange { at, old, new });
+                }
+                _ => {}
+            }
+        }
+
+        changes
     }
 }
\ No newline at end of file
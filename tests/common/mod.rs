@@ -0,0 +1,19 @@
+use std::sync::Once;
+
+use ludwig_world_3d::resources::resourcemanager::ResourceManager;
+use ludwig_world_3d::world::{
+    block::{registry::BlockRegistry, colors::BlockColors},
+    structure::registry::StructureRegistry,
+};
+
+static SETUP: Once = Once::new();
+
+/// Installs the blocks and structures of the assets directory, once for all tests
+pub fn setup() {
+    SETUP.call_once(|| {
+        let resources = ResourceManager::from_env().unwrap();
+        BlockRegistry::load(&resources).unwrap().install().unwrap();
+        BlockColors::load(&resources).unwrap().install().unwrap();
+        StructureRegistry::load(&resources).unwrap().install().unwrap();
+    });
+}
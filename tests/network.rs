@@ -0,0 +1,306 @@
+mod common;
+
+use cgmath::Point3;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::sync::mpsc::{channel, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use ludwig_world_3d::game::{
+    breaking::ticks_to_break,
+    clock::TICKS_PER_SECOND,
+    player::{inventory::GameMode, player::InventoryAction},
+};
+use ludwig_world_3d::server::{
+    connection::Connection,
+    network::{connect, Listener},
+    protocol::{ClientMessage, ServerMessage, PROTOCOL_VERSION},
+    server::{Server, ServerKind},
+};
+use ludwig_world_3d::world::{
+    block::{blocks::{get_block, BlockID, Blocks}, registry::registry},
+    chunk::{chunk::Chunk, codec::decode, pos::WorldCoord},
+    world::World,
+};
+
+/// How long a test waits for something to arrive before it fails
+const TIMEOUT: Duration = Duration::from_secs(20);
+const CLIENTS: usize = 3;
+
+/// A dedicated server on a free port, stopped from its console
+struct TestServer {
+    port: u16,
+    console: Sender<String>,
+    thread: JoinHandle<()>,
+    _listener: Listener,
+}
+
+impl TestServer {
+    fn start() -> Self {
+        common::setup();
+
+        let (listener, connections) = Listener::bind(0).unwrap();
+        let (console, lines) = channel();
+        let thread = std::thread::spawn(move || {
+            let mut server = Server::new(World::new(42), connections, ServerKind::Dedicated);
+            server.set_console(lines);
+            server.run();
+        });
+
+        Self {
+            port: listener.port,
+            console,
+            thread,
+            _listener: listener,
+        }
+    }
+
+    fn connect(&self) -> Client {
+        Inbox::new(connect(&format!("127.0.0.1:{}", self.port)).unwrap())
+    }
+
+    fn stop(self) {
+        self.console.send("stop".to_string()).unwrap();
+        self.thread.join().unwrap();
+    }
+}
+
+/// A connection that keeps the messages that arrived after the one that was waited for
+struct Inbox<S, R> {
+    connection: Connection<S, R>,
+    pending: VecDeque<R>,
+}
+
+type Client = Inbox<ClientMessage, ServerMessage>;
+
+impl<S, R> Inbox<S, R> {
+    fn new(connection: Connection<S, R>) -> Self {
+        Self {
+            connection,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn send(&mut self, message: S) {
+        self.connection.send(message);
+    }
+
+    /// Skips messages until one of them gives something, fails after TIMEOUT
+    fn wait_for<T>(&mut self, what: &str, mut found: impl FnMut(R) -> Option<T>) -> T {
+        let start = Instant::now();
+
+        while start.elapsed() < TIMEOUT {
+            self.pending.extend(self.connection.receive());
+            while let Some(message) = self.pending.pop_front() {
+                if let Some(value) = found(message) {
+                    return value
+                }
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        panic!("No {} arrived in {:?}", what, TIMEOUT)
+    }
+}
+
+/// Joins and waits for the welcome, returns where the player is
+fn join(client: &mut Client, name: &str) -> Point3<f32> {
+    client.send(ClientMessage::Join { name: name.to_string(), view_distance: 2 });
+
+    client.wait_for("welcome", |message| match message {
+        ServerMessage::Welcome { position, .. } => Some(position),
+        ServerMessage::Disconnect(reason) => panic!("{} could not join: {}", name, reason),
+        _ => None,
+    })
+}
+
+/// Waits for the chunk that has a coord in it
+fn chunk_with(client: &mut Client, coord: WorldCoord) -> Chunk {
+    client.wait_for("chunk", |message| match message {
+        ServerMessage::Chunk(bytes) => Some(decode(&bytes).unwrap().chunk).filter(|chunk| chunk.pos == coord.to_chunk_coord()),
+        _ => None,
+    })
+}
+
+/// The highest solid block below a coord
+fn ground_below(chunk: &Chunk, coord: WorldCoord) -> WorldCoord {
+    (0..=coord.y)
+        .rev()
+        .map(|y| WorldCoord { y, ..coord })
+        .find(|below| get_block(chunk.at_coord(below.to_chunk_local())).solid)
+        .unwrap()
+}
+
+/// The first frame of the handshake, the way the game writes it
+fn handshake(magic: [u8; 4], version: u32) -> Vec<u8> {
+    let bytes = bincode::serialize(&(magic, version, registry().fingerprint(), 0u64)).unwrap();
+
+    let mut frame = (bytes.len() as u32).to_le_bytes().to_vec();
+    frame.extend_from_slice(&bytes);
+    frame
+}
+
+/// Sends a handshake and reads everything until the listener closes the stream
+fn refused(magic: [u8; 4], version: u32) {
+    common::setup();
+    let (listener, connections) = Listener::bind(0).unwrap();
+
+    let mut stream = TcpStream::connect(("127.0.0.1", listener.port)).unwrap();
+    stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+    stream.write_all(&handshake(magic, version)).unwrap();
+
+    // The listener answers with its own handshake, so the client can tell why, and then hangs up
+    let mut answer = vec![];
+    stream.read_to_end(&mut answer).unwrap();
+    assert!(!answer.is_empty());
+
+    assert!(connections.recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn clients_join_and_receive_chunks() {
+    let server = TestServer::start();
+    let mut clients: Vec<Client> = (0..CLIENTS).map(|_| server.connect()).collect();
+
+    for (i, client) in clients.iter_mut().enumerate() {
+        let position = join(client, &format!("player{}", i));
+        let chunk = chunk_with(client, WorldCoord::from_point(position));
+
+        assert!(chunk.blocks().iter().any(|&block| block != Blocks::AIR as BlockID));
+    }
+
+    server.stop();
+}
+
+#[test]
+fn names_are_taken_once() {
+    let server = TestServer::start();
+    let (mut first, mut second) = (server.connect(), server.connect());
+
+    join(&mut first, "same");
+    second.send(ClientMessage::Join { name: "same".to_string(), view_distance: 2 });
+    let reason = second.wait_for("disconnect", |message| match message {
+        ServerMessage::Disconnect(reason) => Some(reason),
+        _ => None,
+    });
+
+    assert!(reason.contains("already playing"));
+    server.stop();
+}
+
+#[test]
+fn block_changes_reach_every_client() {
+    let server = TestServer::start();
+    let mut clients: Vec<Client> = (0..CLIENTS).map(|_| server.connect()).collect();
+
+    // Every player starts at the spawn point, so every client has its chunk
+    let mut position = Point3::new(0.0, 0.0, 0.0);
+    let mut chunk = None;
+    for (i, client) in clients.iter_mut().enumerate() {
+        position = join(client, &format!("player{}", i));
+        chunk = Some(chunk_with(client, WorldCoord::from_point(position)));
+    }
+
+    let ground = ground_below(&chunk.unwrap(), WorldCoord::from_point(position));
+    // Out of reach, the server ignores it
+    clients[0].send(ClientMessage::BreakBlock(WorldCoord { y: ground.y - 12, ..ground }));
+    clients[0].send(ClientMessage::BreakBlock(ground));
+
+    for client in &mut clients {
+        let (coord, block) = client.wait_for("block change", |message| match message {
+            ServerMessage::BlockChange { coord, block } => Some((coord, block)),
+            _ => None,
+        });
+
+        assert_eq!(coord, ground);
+        assert_eq!(block, Blocks::AIR as BlockID);
+    }
+
+    server.stop();
+}
+
+#[test]
+fn breaking_takes_time_in_survival() {
+    let server = TestServer::start();
+    let mut client = server.connect();
+
+    let position = join(&mut client, "miner");
+    let chunk = chunk_with(&mut client, WorldCoord::from_point(position));
+    let ground = ground_below(&chunk, WorldCoord::from_point(position));
+    client.send(ClientMessage::Inventory(InventoryAction::SetGameMode(GameMode::Survival)));
+
+    // Broken as soon as it was started, the server ignores it. The chat line
+    // is handled after it, so any block change would arrive first.
+    client.send(ClientMessage::StartBreaking(ground));
+    client.send(ClientMessage::BreakBlock(ground));
+    client.send(ClientMessage::Chat("too early".to_string()));
+    client.wait_for("chat", |message| match message {
+        ServerMessage::BlockChange { .. } => panic!("The block broke at once"),
+        ServerMessage::Chat(line) => Some(line).filter(|line| line.contains("too early")),
+        _ => None,
+    });
+
+    let ticks = ticks_to_break(get_block(chunk.at_coord(ground.to_chunk_local())), None).unwrap();
+    client.send(ClientMessage::StartBreaking(ground));
+    std::thread::sleep(Duration::from_secs_f32((ticks + 1) as f32 / TICKS_PER_SECOND as f32));
+    client.send(ClientMessage::BreakBlock(ground));
+
+    let (coord, block) = client.wait_for("block change", |message| match message {
+        ServerMessage::BlockChange { coord, block } => Some((coord, block)),
+        _ => None,
+    });
+    assert_eq!(coord, ground);
+    assert_eq!(block, Blocks::AIR as BlockID);
+
+    server.stop();
+}
+
+#[test]
+fn players_can_not_move_too_far() {
+    let server = TestServer::start();
+    let mut client = server.connect();
+
+    let position = join(&mut client, "runner");
+    client.send(ClientMessage::Move { position: position + cgmath::Vector3::new(1000.0, 0.0, 0.0), yaw: 0.0, pitch: 0.0 });
+    let back = client.wait_for("teleport", |message| match message {
+        ServerMessage::Teleport(back) => Some(back),
+        _ => None,
+    });
+
+    assert_eq!(back, position);
+    server.stop();
+}
+
+#[test]
+fn wrong_magic_is_refused() {
+    refused(*b"HTTP", PROTOCOL_VERSION);
+}
+
+#[test]
+fn wrong_version_is_refused() {
+    refused(*b"LWLD", PROTOCOL_VERSION + 1);
+}
+
+#[test]
+fn datagrams_with_a_wrong_token_are_dropped() {
+    common::setup();
+    let (listener, connections) = Listener::bind(0).unwrap();
+    let mut client = connect(&format!("127.0.0.1:{}", listener.port)).unwrap();
+    let mut server = Inbox::new(connections.recv_timeout(TIMEOUT).unwrap());
+
+    // Tokens are never 0, see network::accept
+    let forged = bincode::serialize(&(0u64, 1u64, ClientMessage::Chat("forged".to_string()))).unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.send_to(&forged, ("127.0.0.1", listener.port)).unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    client.send(ClientMessage::Chat("real".to_string()));
+    let line = server.wait_for("chat", |message| match message {
+        ClientMessage::Chat(line) => Some(line),
+        _ => None,
+    });
+
+    assert_eq!(line, "real");
+}